@@ -0,0 +1,76 @@
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::config::CiConfig;
+use crate::timing::TimedOutput;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GhRun {
+    url: String,
+    created_at: DateTime<Utc>,
+}
+
+/// Trigger a `workflow_dispatch` run on `branch` via the GitHub CLI and return
+/// the run URL once GitHub has registered it (if it shows up in time). Runs
+/// created before the dispatch, from earlier `sesh pr`s, are skipped.
+pub fn dispatch_workflow(
+    worktree_path: &Path,
+    ci: &CiConfig,
+    workflow: &str,
+    branch: &str,
+) -> Result<Option<String>> {
+    let mut cmd = Command::new("gh");
+    cmd.args(["workflow", "run", workflow, "--ref", branch])
+        .current_dir(worktree_path);
+    if let Some(input) = &ci.branch_input {
+        cmd.args(["-f", &format!("{}={}", input, branch)]);
+    }
+    for (key, value) in &ci.inputs {
+        cmd.args(["-f", &format!("{}={}", key, value)]);
+    }
+
+    // Whole seconds, since GitHub reports `createdAt` without fractions
+    let dispatched_at = DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap_or_else(Utc::now);
+    let output = cmd.timed_output().context("failed to run gh workflow run")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("gh workflow run {} failed: {}", workflow, stderr.trim());
+    }
+
+    // The run is created asynchronously; poll briefly for its URL
+    for _ in 0..5 {
+        thread::sleep(Duration::from_secs(2));
+        let output = Command::new("gh")
+            .args([
+                "run", "list",
+                "--workflow", workflow,
+                "--branch", branch,
+                "--event", "workflow_dispatch",
+                "--limit", "5",
+                "--json", "url,createdAt",
+            ])
+            .current_dir(worktree_path)
+            .timed_output()
+            .context("failed to run gh run list")?;
+        if !output.status.success() {
+            continue;
+        }
+        let runs: Vec<GhRun> = serde_json::from_slice(&output.stdout).unwrap_or_default();
+        if let Some(run) = runs
+            .into_iter()
+            .filter(|run| run.created_at >= dispatched_at)
+            .max_by_key(|run| run.created_at)
+        {
+            return Ok(Some(run.url));
+        }
+    }
+
+    Ok(None)
+}
@@ -9,6 +9,16 @@ pub struct Cli {
     #[arg(short, long, global = true)]
     pub dir: Option<PathBuf>,
 
+    /// Print how long config loading, discovery, git and API calls took
+    /// (also enabled by SESH_PROFILE=1)
+    #[arg(long, global = true)]
+    pub profile_startup: bool,
+
+    /// How many repos to work on at once in multi-repo operations
+    /// (overrides [performance] jobs; 0 = one per CPU core, 4 to 16)
+    #[arg(long, global = true, value_name = "N")]
+    pub jobs: Option<usize>,
+
     #[command(subcommand)]
     pub command: Command,
 }
@@ -42,8 +52,20 @@ pub enum Command {
         no_vscode: bool,
 
         /// Pick a branch from your Linear tickets
-        #[arg(long)]
+        #[arg(long, conflicts_with = "jira")]
         linear: bool,
+
+        /// Pick a branch from your Jira issues
+        #[arg(long)]
+        jira: bool,
+
+        /// Start from an issue: linear:ENG-123, jira:PROJ-9, github:org/repo#55, azure:123 or sentry:4567
+        #[arg(long, value_name = "PROVIDER:ID", conflicts_with_all = ["branch", "linear", "jira"])]
+        issue: Option<String>,
+
+        /// On failure, leave created worktrees in place and list them instead of rolling back
+        #[arg(long)]
+        keep_on_failure: bool,
     },
 
     /// List sessions
@@ -51,6 +73,25 @@ pub enum Command {
         /// Show only sessions with existing worktrees
         #[arg(long)]
         active: bool,
+
+        /// Show only sessions with this tag (repeat to require several)
+        #[arg(long)]
+        tag: Vec<String>,
+
+        /// Also show each session's repos, tags and metadata
+        #[arg(short, long)]
+        long: bool,
+
+        /// List sessions in every workspace sesh has seen, not just this one
+        #[arg(long, conflicts_with = "dir")]
+        global: bool,
+    },
+
+    /// Print a session's directory, finding it in any registered workspace
+    /// (e.g. `cd "$(sesh switch feat-x)"`)
+    Switch {
+        /// Session name
+        name: String,
     },
 
     /// Stop and clean up a session
@@ -61,6 +102,88 @@ pub enum Command {
         /// Keep branches after removing worktrees
         #[arg(long)]
         keep_branches: bool,
+
+        /// Also prune stale remote-tracking refs in the session's repos
+        #[arg(long)]
+        prune_remote: bool,
+
+        /// Stop even if repos have uncommitted or unpushed work, or the session is pinned
+        #[arg(short, long)]
+        force: bool,
+
+        /// Move the session's scratch/ to .sesh/archive/scratch/ instead of deleting it
+        #[arg(long)]
+        keep_scratch: bool,
+    },
+
+    /// Stop a session's services and release its locks, keeping worktrees and branches
+    Archive {
+        /// Session name (interactive if omitted)
+        name: Option<String>,
+    },
+
+    /// Protect a session from `sesh prune` and an unforced `sesh stop`
+    Pin {
+        /// Session name (interactive if omitted)
+        name: Option<String>,
+    },
+
+    /// Remove a session's pin
+    Unpin {
+        /// Session name (interactive if omitted)
+        name: Option<String>,
+    },
+
+    /// Re-run a session's setup scripts (global and per repo), replacing its background processes
+    Setup {
+        /// Session name (interactive if omitted)
+        name: Option<String>,
+    },
+
+    /// Stop a session's background processes and run its teardown scripts, keeping worktrees
+    Teardown {
+        /// Session name (interactive if omitted)
+        name: Option<String>,
+    },
+
+    /// Stop sessions whose work has landed
+    Prune {
+        /// Sessions whose PRs are all merged or closed
+        #[arg(long)]
+        merged: bool,
+
+        /// Stop every prunable session without asking
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Keep branches after removing worktrees
+        #[arg(long)]
+        keep_branches: bool,
+
+        /// Also prune stale remote-tracking refs in the session's repos
+        #[arg(long)]
+        prune_remote: bool,
+
+        /// Also stop sessions with uncommitted or unpushed work
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Show what happened in a session and when (works for stopped sessions too)
+    History {
+        /// Session name (interactive if omitted)
+        name: Option<String>,
+    },
+
+    /// Print Prometheus metrics for this workspace, or write them to a textfile
+    Metrics {
+        /// Write to this file (atomically) instead of stdout
+        #[arg(long)]
+        textfile: Option<PathBuf>,
+
+        /// Write to `[metrics] textfile` from sesh.toml
+        #[arg(long, conflicts_with = "textfile")]
+        write: bool,
     },
 
     /// Re-open VS Code windows for a session
@@ -73,6 +196,31 @@ pub enum Command {
     Status {
         /// Session name (interactive if omitted)
         name: Option<String>,
+
+        /// Show files changed (by author) since the last `--changes` review, then advance the marker
+        #[arg(long)]
+        changes: bool,
+
+        /// Keep re-rendering every SECS seconds (default 2) and when worktree files change
+        #[arg(
+            long,
+            value_name = "SECS",
+            num_args = 0..=1,
+            default_missing_value = "2",
+            value_parser = clap::value_parser!(u64).range(1..),
+            conflicts_with = "changes"
+        )]
+        watch: Option<u64>,
+    },
+
+    /// Show disk used by each session against `[session] max_disk_gb`
+    Du {
+        /// Session name (all sessions if omitted)
+        name: Option<String>,
+
+        /// Warn about sessions over quota, or pause their background processes with `over_quota = "pause"`
+        #[arg(long)]
+        enforce: bool,
     },
 
     /// Push branches and create PRs
@@ -80,30 +228,87 @@ pub enum Command {
         /// Session name (interactive if omitted)
         name: Option<String>,
 
-        /// Base branch for PRs
-        #[arg(long, default_value = "main")]
-        base: String,
+        /// Base branch for PRs (default: each repo's base branch)
+        #[arg(long)]
+        base: Option<String>,
+
+        /// Only push and create PRs for this repo (repeatable)
+        #[arg(long = "repo")]
+        repos: Vec<String>,
+
+        /// Skip repos with no commits ahead of base (default unless --repo is given)
+        #[arg(long)]
+        only_changed: bool,
+
+        /// Push and open PRs for repos without commits ahead of base too
+        #[arg(long, conflicts_with = "only_changed")]
+        all: bool,
+
+        /// Open PRs even if [verify] steps fail
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Rebase (or merge) each session branch onto its freshly fetched base
+    Sync {
+        /// Session name (interactive if omitted)
+        name: Option<String>,
+
+        /// Merge the base in instead of rebasing
+        #[arg(long, conflicts_with = "rebase")]
+        merge: bool,
+
+        /// Rebase onto the base (overrides git.sync_strategy = "merge")
+        #[arg(long)]
+        rebase: bool,
+    },
+
+    /// Run the [verify] checklist in each repo of a session
+    Verify {
+        /// Session name (interactive if omitted)
+        name: Option<String>,
     },
 
     /// Generate sesh.toml interactively
-    Init,
+    Init {
+        /// Download a team sesh.toml (http(s) URL or git repo) instead of prompting
+        #[arg(long)]
+        from_url: Option<String>,
+    },
+
+    /// Manage the team-shared sesh.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
 
     /// Detect and fix orphaned worktrees/sessions
-    Doctor,
+    Doctor {
+        /// Apply fixes without asking
+        #[arg(long)]
+        fix: bool,
+        /// Print a JSON report of issues found and fixed (never prompts)
+        #[arg(long)]
+        json: bool,
+    },
 
     /// Transfer exclusive locks to a session (runs teardown/setup scripts)
     Activate {
         /// Session name (interactive if omitted)
         name: Option<String>,
+
+        /// Show which locks would move and which scripts would run, without changing anything
+        #[arg(long)]
+        dry_run: bool,
     },
 
-    /// Configure API tokens for integrations (Linear, Sentry)
+    /// Configure API tokens for integrations (Linear, Sentry, Jira, Bitbucket, Azure DevOps)
     Auth {
         #[command(subcommand)]
         provider: AuthProvider,
     },
 
-    /// View background script logs
+    /// View script logs
     Log {
         /// Session name (interactive if omitted)
         #[arg(short, long)]
@@ -115,6 +320,42 @@ pub enum Command {
         /// Follow the log output (like tail -f)
         #[arg(short, long)]
         follow: bool,
+
+        /// Follow every background script log at once, prefixed by label (with --follow)
+        #[arg(long, requires = "follow", conflicts_with = "script")]
+        all: bool,
+
+        /// Only show lines matching this regex (searches all logs if no script is given)
+        #[arg(long, conflicts_with = "follow")]
+        grep: Option<String>,
+
+        /// Only show lines from the last duration (e.g. 30s, 10m, 2h, 1d)
+        #[arg(long, conflicts_with = "follow")]
+        since: Option<String>,
+
+        /// Copy all of the session's logs into this directory
+        #[arg(long, conflicts_with_all = ["script", "follow", "grep", "since"])]
+        export: Option<PathBuf>,
+    },
+
+    /// List background processes, marking those that died (e.g. after a reboot)
+    Ps {
+        /// Only this session (all sessions if omitted)
+        name: Option<String>,
+
+        /// Relaunch dead background setup scripts of active sessions
+        #[arg(long)]
+        restart: bool,
+    },
+
+    /// Kill and respawn one background setup script of a session
+    Restart {
+        /// Session name (interactive if omitted)
+        #[arg(short, long)]
+        session: Option<String>,
+
+        /// Script label, as shown by `sesh ps` (interactive if omitted)
+        label: Option<String>,
     },
 
     /// Run a command in each repo's worktree
@@ -123,15 +364,138 @@ pub enum Command {
         #[arg(short, long)]
         session: Option<String>,
 
-        /// Command to execute in each repo's worktree
+        /// Only run in this repo (repeatable)
+        #[arg(long = "repo")]
+        repos: Vec<String>,
+
+        /// Run one repo at a time in session order, streaming output live
+        #[arg(long)]
+        sequential: bool,
+
+        /// Stop at the first repo whose command fails
+        #[arg(long, requires = "sequential")]
+        fail_fast: bool,
+
+        /// Command to execute in each repo's worktree; {repo}, {worktree}, {branch} and {session} are filled in
         command: String,
     },
 
+    /// Run a task across several sessions and compare the results
+    Matrix {
+        #[command(subcommand)]
+        action: MatrixAction,
+    },
+
+    /// Commit in every session repo with changes, validating the message
+    Commit {
+        /// Session name (interactive if omitted)
+        #[arg(short, long)]
+        session: Option<String>,
+
+        /// Commit message
+        #[arg(short, long)]
+        message: String,
+
+        /// Stage all tracked changes before committing (like git commit -a)
+        #[arg(short, long)]
+        all: bool,
+    },
+
+    /// Check session changes against protected paths
+    Guard {
+        #[command(subcommand)]
+        action: GuardAction,
+    },
+
+    /// Rerun a command in each repo whose worktree changes
+    Watch {
+        /// Session name (interactive if omitted)
+        #[arg(short, long)]
+        session: Option<String>,
+
+        /// Command to run (overrides [watch] and per-repo config)
+        command: Option<String>,
+    },
+
+    /// Track preview/tunnel URLs for a session
+    Url {
+        #[command(subcommand)]
+        action: UrlAction,
+    },
+
+    /// Inspect the generated session context
+    Context {
+        #[command(subcommand)]
+        action: ContextAction,
+    },
+
+    /// Keep warm worktrees ready for `sesh start` to claim
+    Pool {
+        #[command(subcommand)]
+        action: PoolAction,
+    },
+
+    /// Label sessions with tags (see `sesh list --tag`)
+    Tag {
+        #[command(subcommand)]
+        action: TagAction,
+    },
+
+    /// Attach key/value metadata to a session
+    Meta {
+        #[command(subcommand)]
+        action: MetaAction,
+    },
+
+    /// Expose a session's services through cloudflared/ngrok tunnels
+    Tunnel {
+        #[command(subcommand)]
+        action: TunnelAction,
+    },
+
+    /// Watch tracked tickets and mark sessions whose ticket is closed or reassigned
+    Listen {
+        /// Poll Linear for the state of each session's ticket
+        #[arg(long)]
+        linear: bool,
+
+        /// Seconds between polls
+        #[arg(long, default_value_t = 300)]
+        interval: u64,
+
+        /// Poll once and exit (for cron)
+        #[arg(long)]
+        once: bool,
+    },
+
+    /// Compose a session from a different existing branch per repo
+    Compose {
+        /// Session name (prompted if omitted)
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Skip setup scripts
+        #[arg(long)]
+        no_setup: bool,
+
+        /// Don't open VS Code
+        #[arg(long)]
+        no_vscode: bool,
+
+        /// On failure, leave created worktrees in place and list them instead of rolling back
+        #[arg(long)]
+        keep_on_failure: bool,
+    },
+
     /// Check out an existing branch or PR into a new session
     Checkout {
-        /// Pick from local+remote branches
-        #[arg(long, conflicts_with = "pr")]
-        branch: bool,
+        /// Check out this branch, or pick from local+remote branches when no name is given
+        #[arg(long, conflicts_with = "pr", num_args = 0..=1, value_name = "NAME")]
+        branch: Option<Option<String>>,
+
+        /// Only offer branches whose name contains this text (case-insensitive)
+        #[arg(long, conflicts_with = "pr", value_name = "TEXT")]
+        branch_filter: Option<String>,
 
         /// Pick from open GitHub PRs
         #[arg(long, conflicts_with = "branch")]
@@ -152,6 +516,25 @@ pub enum Command {
         /// Don't open VS Code
         #[arg(long)]
         no_vscode: bool,
+
+        /// On failure, leave created worktrees in place and list them instead of rolling back
+        #[arg(long)]
+        keep_on_failure: bool,
+    },
+
+    /// Internal: timestamp and label background script output into a log file
+    #[command(name = "__log-prefix", hide = true)]
+    LogPrefix {
+        label: String,
+        log_file: PathBuf,
+    },
+
+    /// Internal: wait for a background script to exit and notify if it wasn't stopped by sesh
+    #[command(name = "__watch-exit", hide = true)]
+    WatchExit {
+        session_dir: PathBuf,
+        label: String,
+        pid: u32,
     },
 
     /// Generate shell completions
@@ -159,6 +542,231 @@ pub enum Command {
         /// Shell to generate completions for
         shell: clap_complete::Shell,
     },
+
+    /// List `sesh-<name>` plugins found on PATH
+    Plugins,
+
+    /// Any other subcommand runs the `sesh-<name>` plugin on PATH
+    #[command(external_subcommand)]
+    External(Vec<String>),
+}
+
+impl Command {
+    /// The session a command names explicitly, used to find its workspace
+    /// through the global registry when it isn't in the current directory.
+    pub fn session_name(&self) -> Option<&str> {
+        let name = match self {
+            Command::Stop { name, .. }
+            | Command::Archive { name }
+            | Command::Pin { name }
+            | Command::Unpin { name }
+            | Command::Setup { name }
+            | Command::Teardown { name }
+            | Command::History { name }
+            | Command::Resume { name }
+            | Command::Status { name, .. }
+            | Command::Du { name, .. }
+            | Command::Pr { name, .. }
+            | Command::Sync { name, .. }
+            | Command::Verify { name, .. }
+            | Command::Activate { name, .. }
+            | Command::Ps { name, .. } => name.as_ref(),
+            Command::Log { session, .. }
+            | Command::Restart { session, .. }
+            | Command::Exec { session, .. }
+            | Command::Commit { session, .. }
+            | Command::Watch { session, .. } => session.as_ref(),
+            Command::Switch { name } => Some(name),
+            Command::Url { action } => match action {
+                UrlAction::Add { session, .. } | UrlAction::Remove { session, .. } | UrlAction::List { session } => {
+                    session.as_ref()
+                }
+            },
+            Command::Context { action } => match action {
+                ContextAction::Show { name, .. } => name.as_ref(),
+            },
+            Command::Tag { action } => match action {
+                TagAction::Add { name, .. } | TagAction::Remove { name, .. } => Some(name),
+            },
+            Command::Meta { action } => match action {
+                MetaAction::Set { name, .. } | MetaAction::Unset { name, .. } => Some(name),
+            },
+            _ => None,
+        };
+        name.map(String::as_str)
+    }
+}
+
+#[derive(Subcommand)]
+pub enum GuardAction {
+    /// Fail if the session's diff touches a protected path
+    Check {
+        /// Session name (interactive if omitted)
+        session: Option<String>,
+
+        /// Only check this repo
+        #[arg(long)]
+        repo: Option<String>,
+
+        /// Only check staged files (used by the pre-commit hook)
+        #[arg(long)]
+        staged: bool,
+    },
+    /// Install a pre-commit hook running the check in each worktree
+    Install {
+        /// Session name (interactive if omitted)
+        session: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Pull the latest sesh.toml from [meta] config_source
+    Update,
+    /// Print the effective config (sesh.toml, sesh.local.toml and repo .sesh.toml merged, defaults included)
+    Show,
+    /// Print one effective setting, e.g. `session.base_branch`
+    Get {
+        /// Dotted key (quote names containing dots: repos."api.v2".copy)
+        key: String,
+    },
+    /// Set a value in sesh.toml, keeping its comments and layout
+    Set {
+        /// Dotted key, e.g. session.base_branch
+        key: String,
+        /// New value, read as TOML (true, 5, ["a", "b"]) or else as a string
+        value: String,
+    },
+    /// Check config files for unknown keys, missing script files and unknown preset repos
+    Validate,
+}
+
+#[derive(Subcommand)]
+pub enum MatrixAction {
+    /// Run a command in each session's repos sequentially and summarize exit codes and durations
+    Run {
+        /// Command to run in each repo's worktree
+        task: String,
+
+        /// Sessions to include (comma-separated; all sessions if omitted)
+        #[arg(long, value_delimiter = ',')]
+        sessions: Vec<String>,
+
+        /// Only run in this repo
+        #[arg(long)]
+        repo: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum UrlAction {
+    /// Register (or replace) a named URL
+    Add {
+        /// Short name, e.g. "preview" or "tunnel"
+        name: String,
+        /// The URL
+        url: String,
+        /// Session name (defaults to $SESH_SESSION, interactive otherwise)
+        #[arg(short, long)]
+        session: Option<String>,
+    },
+    /// Remove a named URL
+    Remove {
+        name: String,
+        /// Session name (defaults to $SESH_SESSION, interactive otherwise)
+        #[arg(short, long)]
+        session: Option<String>,
+    },
+    /// List a session's URLs
+    List {
+        /// Session name (defaults to $SESH_SESSION, interactive otherwise)
+        #[arg(short, long)]
+        session: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ContextAction {
+    /// Print the session's `.sesh-context.md`
+    Show {
+        /// Session name (defaults to the current session, interactive otherwise)
+        name: Option<String>,
+
+        /// Print the file's location instead
+        #[arg(long)]
+        path: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PoolAction {
+    /// Create worktrees until every repo with `pool = N` has N
+    Warm {
+        /// Only warm these repos
+        repos: Vec<String>,
+    },
+    /// Show each repo's ready and warming worktrees
+    Status,
+    /// Remove all pooled worktrees
+    Clear,
+}
+
+#[derive(Subcommand)]
+pub enum TagAction {
+    /// Add tags to a session
+    Add {
+        /// Session name
+        name: String,
+        /// Tags, e.g. "urgent"
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Remove tags from a session
+    Remove {
+        /// Session name
+        name: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MetaAction {
+    /// Set metadata fields
+    Set {
+        /// Session name
+        name: String,
+        /// Fields as key=value
+        #[arg(required = true, value_name = "KEY=VALUE")]
+        fields: Vec<String>,
+    },
+    /// Remove metadata fields
+    Unset {
+        /// Session name
+        name: String,
+        #[arg(required = true)]
+        keys: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TunnelAction {
+    /// Start tunnels for [tunnel] services (all if none given)
+    Start {
+        /// Services to expose
+        services: Vec<String>,
+        /// Session name (defaults to $SESH_SESSION, interactive otherwise)
+        #[arg(short, long)]
+        session: Option<String>,
+    },
+    /// Stop tunnels (all if none given)
+    Stop {
+        /// Services to stop
+        services: Vec<String>,
+        /// Session name (defaults to $SESH_SESSION, interactive otherwise)
+        #[arg(short, long)]
+        session: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -167,4 +775,10 @@ pub enum AuthProvider {
     Linear,
     /// Set your Sentry auth token
     Sentry,
+    /// Set your Jira API token
+    Jira,
+    /// Set your Bitbucket token (used by `checkout --pr` and `pr`)
+    Bitbucket,
+    /// Set your Azure DevOps personal access token (repos and work items)
+    Azure,
 }
@@ -3,18 +3,22 @@ use std::path::Path;
 use anyhow::{bail, Result};
 use console::style;
 
-use crate::config::SeshConfig;
+use crate::config::{ScriptEntry, SeshConfig};
+use crate::history;
 use crate::lock;
-use crate::scripts;
-use crate::session;
+use crate::scripts::{self, ProcessState};
+use crate::session::{self, BackgroundPid, SessionInfo};
 
-use super::pick_session;
+use super::stop::stop_services;
+use super::{check_setup, pick_session, record_spawn, sanitize_label};
 
-pub fn run(parent_dir: &Path, name: Option<String>) -> Result<()> {
+/// `dry_run` prints what activation would do (lock moves, teardowns, setup
+/// scripts) and changes nothing.
+pub async fn run(parent_dir: &Path, name: Option<String>, dry_run: bool) -> Result<()> {
     let config_path = parent_dir.join("sesh.toml");
     let config = SeshConfig::load(&config_path)?;
 
-    let target_session = pick_session(parent_dir, name)?;
+    let mut target_session = pick_session(parent_dir, name)?;
     let target_dir = session::session_dir(parent_dir, &target_session.name);
 
     // Find exclusive repos in the target session
@@ -31,10 +35,15 @@ pub fn run(parent_dir: &Path, name: Option<String>) -> Result<()> {
         .map(|r| r.name.as_str())
         .collect();
 
-    if exclusive_repos.is_empty() {
+    // Archived sessions are brought back even without exclusive repos
+    if exclusive_repos.is_empty() && target_session.archived_at.is_none() {
         bail!("Session '{}' has no exclusive repos to activate.", target_session.name);
     }
 
+    if dry_run {
+        return preview(parent_dir, &config, &target_session, &exclusive_repos);
+    }
+
     // For each exclusive repo, check who currently holds the lock
     let mut transfers: Vec<(String, String)> = Vec::new(); // (repo_name, old_session_name)
 
@@ -80,108 +89,88 @@ pub fn run(parent_dir: &Path, name: Option<String>) -> Result<()> {
     teardown_sessions.dedup();
 
     for old_session_name in &teardown_sessions {
-        if let Ok(old_session) = session::load_session(
-            &session::session_dir(parent_dir, old_session_name),
-        ) {
-            let old_dir = session::session_dir(parent_dir, old_session_name);
-            let repo_names: Vec<String> =
-                old_session.repos.iter().map(|r| r.name.clone()).collect();
-
-            // Kill background processes for old session
-            let bg_pids = session::load_background_pids(&old_dir);
-            if !bg_pids.is_empty() {
-                println!(
-                    "\n  {} Killing {} background process(es) for '{}'...",
-                    style("→").cyan(),
-                    bg_pids.len(),
-                    old_session_name
-                );
-                scripts::kill_background_pids(&bg_pids);
-            }
-
-            // Per-repo teardown scripts for old session
-            for repo in &old_session.repos {
-                if let Some(repo_config) = config.repos.get(&repo.name) {
-                    for entry in &repo_config.teardown {
-                        let script_path = parent_dir.join(&entry.path);
-                        if script_path.exists() {
-                            println!(
-                                "  {} Running teardown for {}: {}...",
-                                style("→").cyan(),
-                                repo.name,
-                                entry.path
-                            );
-                            if let Err(e) = scripts::run_script_entry(
-                                "teardown",
-                                entry,
-                                &script_path,
-                                &repo.worktree_path,
-                                &old_session.name,
-                                &old_session.branch,
-                                &repo_names,
-                                &[("SESH_REPO", repo.name.as_str())],
-                            ) {
-                                eprintln!(
-                                    "  {} Teardown '{}' for {} failed: {}",
-                                    style("!").yellow(),
-                                    entry.path,
-                                    repo.name,
-                                    e
-                                );
-                            }
-                        }
-                    }
-                }
-            }
-
-            // Global teardown scripts for old session
-            for entry in &config.scripts.teardown {
-                let script_path = parent_dir.join(&entry.path);
-                if script_path.exists() {
-                    println!(
-                        "\n  {} Running teardown for session '{}': {}...",
-                        style("→").cyan(),
-                        old_session_name,
-                        entry.path
-                    );
-                    if let Err(e) = scripts::run_script_entry(
-                        "teardown",
-                        entry,
-                        &script_path,
-                        &old_dir,
-                        &old_session.name,
-                        &old_session.branch,
-                        &repo_names,
-                        &[],
-                    ) {
-                        eprintln!(
-                            "  {} Teardown '{}' failed for '{}': {}",
-                            style("!").yellow(),
-                            entry.path,
-                            old_session_name,
-                            e
-                        );
-                    }
-                }
-            }
+        let old_dir = session::session_dir(parent_dir, old_session_name);
+        if let Ok(old_session) = session::load_session(&old_dir) {
+            println!(
+                "\n  {} Stopping services for '{}'...",
+                style("→").cyan(),
+                old_session_name
+            );
+            stop_services(parent_dir, &config, &old_session, &old_dir).await;
+            history::record(
+                parent_dir,
+                old_session_name,
+                history::Event::Deactivated {
+                    to_session: target_session.name.clone(),
+                },
+            );
         }
     }
 
     // Run setup for the target session
     let repo_names: Vec<String> =
         target_session.repos.iter().map(|r| r.name.clone()).collect();
+    let log_dir = target_dir.join("logs");
+    let mut bg_pids = session::load_background_pids(&target_dir);
+    // Background scripts still running from an earlier activation are left
+    // alone; only missing ones are spawned, replacing their dead entries.
+    let states = scripts::reconcile_background_pids(&target_dir, &mut bg_pids);
+    let running: Vec<String> = bg_pids
+        .iter()
+        .zip(states)
+        .filter(|(_, state)| *state != ProcessState::Dead)
+        .map(|(bp, _)| bp.label.clone())
+        .collect();
+    let session_env = target_session.script_env();
+    let session_env: Vec<(&str, &str)> = session_env.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
 
     // Global setup scripts
     for entry in &config.scripts.setup {
         let script_path = parent_dir.join(&entry.path);
-        if script_path.exists() {
+        if entry.is_available(parent_dir) {
+            let label = format!("global-setup-{}", sanitize_label(entry.name()));
+            if entry.background && running.contains(&label) {
+                println!("  {} Already running ({})", style("·").dim(), entry.name());
+                continue;
+            }
+            if entry.background {
+                let spawned = scripts::spawn_background_script(
+                    entry,
+                    &script_path,
+                    &target_dir,
+                    &log_dir,
+                    &label,
+                    &target_session.name,
+                    &target_session.branch,
+                    &repo_names,
+                    &session_env,
+                    config.scripts.timestamp_logs,
+                );
+                record_spawn(parent_dir, &target_session.name, entry.name(), None, &spawned);
+                let Some(pid) = check_setup(entry, spawned)? else {
+                    continue;
+                };
+                println!("  {} Background PID {} ({})", style("✓").green(), pid, entry.name());
+                bg_pids.retain(|bp| bp.label != label);
+                bg_pids.push(BackgroundPid {
+                    pid,
+                    label,
+                    script: entry.name().to_string(),
+                    process_group: true,
+                    started_at: Some(chrono::Utc::now()),
+                    dead: false,
+                    paused: false,
+                    health: None,
+                });
+                continue;
+            }
             println!(
                 "\n  {} Running setup for session '{}': {}...",
                 style("→").cyan(),
                 target_session.name,
-                entry.path
+                entry.name()
             );
-            scripts::run_script_entry(
+            let result = scripts::run_script_entry(
                 "setup",
                 entry,
                 &script_path,
@@ -189,38 +178,116 @@ pub fn run(parent_dir: &Path, name: Option<String>) -> Result<()> {
                 &target_session.name,
                 &target_session.branch,
                 &repo_names,
-                &[],
-            )?;
+                &session_env,
+                Some(&scripts::ScriptLog {
+                    log_dir: &log_dir,
+                    label: &label,
+                    timestamps: config.scripts.timestamp_logs,
+                }),
+            );
+            history::record(
+                parent_dir,
+                &target_session.name,
+                history::Event::script("setup", entry.name(), None, history::outcome(&result)),
+            );
+            check_setup(entry, result)?;
         }
     }
 
     // Per-repo setup scripts
     for repo in &target_session.repos {
+        let mut repo_env = session_env.clone();
+        repo_env.push(("SESH_REPO", repo.name.as_str()));
         if let Some(repo_config) = config.repos.get(&repo.name) {
             for entry in &repo_config.setup {
                 let script_path = parent_dir.join(&entry.path);
-                if script_path.exists() {
+                if entry.is_available(parent_dir) {
+                    let label = format!("{}-setup-{}", repo.name, sanitize_label(entry.name()));
+                    if entry.background && running.contains(&label) {
+                        println!("  {} Already running ({}/{})", style("·").dim(), repo.name, entry.name());
+                        continue;
+                    }
+                    if entry.background {
+                        let spawned = scripts::spawn_background_script(
+                            entry,
+                            &script_path,
+                            &repo.worktree_path,
+                            &log_dir,
+                            &label,
+                            &target_session.name,
+                            target_session.branch_for(repo),
+                            &repo_names,
+                            &repo_env,
+                            config.scripts.timestamp_logs,
+                        );
+                        record_spawn(parent_dir, &target_session.name, entry.name(), Some(&repo.name), &spawned);
+                        let Some(pid) = check_setup(entry, spawned)? else {
+                            continue;
+                        };
+                        println!(
+                            "  {} Background PID {} ({}/{})",
+                            style("✓").green(),
+                            pid,
+                            repo.name,
+                            entry.name()
+                        );
+                        bg_pids.retain(|bp| bp.label != label);
+                        bg_pids.push(BackgroundPid {
+                            pid,
+                            label,
+                            script: entry.name().to_string(),
+                            process_group: true,
+                            started_at: Some(chrono::Utc::now()),
+                            dead: false,
+                            paused: false,
+                            health: None,
+                        });
+                        continue;
+                    }
                     println!(
                         "  {} Running setup for {}: {}...",
                         style("→").cyan(),
                         repo.name,
-                        entry.path
+                        entry.name()
                     );
-                    scripts::run_script_entry(
+                    let result = scripts::run_script_entry(
                         "setup",
                         entry,
                         &script_path,
                         &repo.worktree_path,
                         &target_session.name,
-                        &target_session.branch,
+                        target_session.branch_for(repo),
                         &repo_names,
-                        &[("SESH_REPO", repo.name.as_str())],
-                    )?;
+                        &repo_env,
+                        Some(&scripts::ScriptLog {
+                            log_dir: &log_dir,
+                            label: &label,
+                            timestamps: config.scripts.timestamp_logs,
+                        }),
+                    );
+                    history::record(
+                        parent_dir,
+                        &target_session.name,
+                        history::Event::script(
+                            "setup",
+                            entry.name(),
+                            Some(&repo.name),
+                            history::outcome(&result),
+                        ),
+                    );
+                    check_setup(entry, result)?;
                 }
             }
         }
     }
 
+    session::save_background_pids(&target_dir, &bg_pids)?;
+    if target_session.archived_at.take().is_some() {
+        session::save_session(&target_dir, &target_session)?;
+    }
+
+    history::record(parent_dir, &target_session.name, history::Event::Activated);
+
     println!(
         "\n{} Session '{}' is now active.",
         style("✔").green(),
@@ -229,3 +296,91 @@ pub fn run(parent_dir: &Path, name: Option<String>) -> Result<()> {
 
     Ok(())
 }
+
+/// Print the lock transfers, teardowns and setup scripts activating `target`
+/// would run, mirroring the steps of [`run`] without taking any of them.
+fn preview(parent_dir: &Path, config: &SeshConfig, target: &SessionInfo, exclusive_repos: &[&str]) -> Result<()> {
+    println!(
+        "{} Dry run: activating '{}' would:\n",
+        style("→").cyan().bold(),
+        target.name
+    );
+
+    let mut losing: Vec<String> = Vec::new();
+    if !exclusive_repos.is_empty() {
+        println!("  Locks:");
+    }
+    for &repo_name in exclusive_repos {
+        let change = match lock::check_lock(parent_dir, repo_name)? {
+            Some(held) if held.session == target.name => style("already held").dim().to_string(),
+            Some(held) if session::session_exists(parent_dir, &held.session) => {
+                if !losing.contains(&held.session) {
+                    losing.push(held.session.clone());
+                }
+                format!("move from '{}'", held.session)
+            }
+            Some(held) => format!("replace stale lock of '{}' (session gone)", held.session),
+            None => "acquire (free)".to_string(),
+        };
+        println!("    {}: {}", style(repo_name).cyan(), change);
+    }
+
+    losing.sort();
+    for old_name in &losing {
+        let old_dir = session::session_dir(parent_dir, old_name);
+        let Ok(old) = session::load_session(&old_dir) else {
+            continue;
+        };
+        println!("\n  Stop services of '{}':", old_name);
+        let running = session::load_background_pids(&old_dir).iter().filter(|bp| !bp.dead).count();
+        if running > 0 {
+            println!("    kill {} background process(es)", running);
+        }
+        let mut steps = Vec::new();
+        for repo in &old.repos {
+            if let Some(rc) = config.repos.get(&repo.name) {
+                steps.extend(planned(parent_dir, &rc.teardown).map(|e| (Some(repo.name.as_str()), e)));
+            }
+        }
+        steps.extend(planned(parent_dir, &config.scripts.teardown).map(|e| (None, e)));
+        print_steps("teardown", &steps);
+        if running == 0 && steps.is_empty() {
+            println!("    {}", style("(nothing to stop)").dim());
+        }
+    }
+
+    println!("\n  Set up '{}':", target.name);
+    let mut steps: Vec<(Option<&str>, &ScriptEntry)> =
+        planned(parent_dir, &config.scripts.setup).map(|e| (None, e)).collect();
+    for repo in &target.repos {
+        if let Some(rc) = config.repos.get(&repo.name) {
+            steps.extend(planned(parent_dir, &rc.setup).map(|e| (Some(repo.name.as_str()), e)));
+        }
+    }
+    print_steps("setup", &steps);
+    if steps.is_empty() {
+        println!("    {}", style("(no setup scripts)").dim());
+    }
+    if target.archived_at.is_some() {
+        println!("    un-archive the session");
+    }
+
+    println!("\n{}", style("Dry run: nothing was changed.").dim());
+    Ok(())
+}
+
+/// The entries of `scripts` that activation would run (missing files are skipped).
+fn planned<'a>(parent_dir: &'a Path, scripts: &'a [ScriptEntry]) -> impl Iterator<Item = &'a ScriptEntry> {
+    scripts.iter().filter(|e| e.is_available(parent_dir))
+}
+
+fn print_steps(kind: &str, steps: &[(Option<&str>, &ScriptEntry)]) {
+    for (repo, entry) in steps {
+        let scope = match repo {
+            Some(repo) => format!("{} {}", repo, kind),
+            None => kind.to_string(),
+        };
+        let mode = if entry.background { " (background)" } else { "" };
+        println!("    {}: {}{}", scope, entry.name(), mode);
+    }
+}
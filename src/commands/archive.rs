@@ -0,0 +1,40 @@
+use std::path::Path;
+
+use anyhow::Result;
+use console::style;
+
+use crate::config::SeshConfig;
+use crate::history;
+use crate::session;
+
+use super::pick_session;
+use super::stop::{release_locks, stop_services};
+
+pub async fn run(parent_dir: &Path, name: Option<String>) -> Result<()> {
+    let mut sess = pick_session(parent_dir, name)?;
+    if let Some(at) = sess.archived_at {
+        println!(
+            "Session '{}' was already archived on {}.",
+            sess.name,
+            at.format("%Y-%m-%d %H:%M")
+        );
+        return Ok(());
+    }
+
+    let sess_dir = session::session_dir(parent_dir, &sess.name);
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+    stop_services(parent_dir, &config, &sess, &sess_dir).await;
+    release_locks(parent_dir, &config, &sess);
+
+    sess.archived_at = Some(chrono::Utc::now());
+    session::save_session(&sess_dir, &sess)?;
+    history::record(parent_dir, &sess.name, history::Event::Archived);
+
+    println!(
+        "{} Session '{}' archived. Worktrees and branches are kept; run `sesh activate {}` to bring it back.",
+        style("✔").green(),
+        sess.name,
+        sess.name
+    );
+    Ok(())
+}
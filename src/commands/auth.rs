@@ -1,8 +1,10 @@
 use std::path::Path;
 
 use anyhow::{bail, Context, Result};
-use console::style;
-use dialoguer::Password;
+use console::{style, Term};
+use dialoguer::{Confirm, Password};
+
+use crate::integrations::{self, TokenHealth};
 
 pub fn run(parent_dir: &Path, provider: &str) -> Result<()> {
     let (filename, prompt, help) = match provider {
@@ -16,6 +18,21 @@ pub fn run(parent_dir: &Path, provider: &str) -> Result<()> {
             "Sentry auth token",
             "Get one from: Sentry → Settings → Auth Tokens",
         ),
+        "jira" => (
+            "jira_token",
+            "Jira API token",
+            "Get one from: Atlassian account → Security → API tokens (set [jira] email for Jira Cloud)",
+        ),
+        "bitbucket" => (
+            "bitbucket_token",
+            "Bitbucket token",
+            "Use an access token, or an app password / API token with [bitbucket] username set",
+        ),
+        "azure" => (
+            "azure_token",
+            "Azure DevOps personal access token",
+            "Get one from: Azure DevOps → User settings → Personal access tokens (Code: read & write, Work Items: read)",
+        ),
         _ => bail!("unknown provider: {}", provider),
     };
 
@@ -67,3 +84,36 @@ pub fn run(parent_dir: &Path, provider: &str) -> Result<()> {
 
     Ok(())
 }
+
+/// Check the `linear` or `sentry` token before a flow that depends on it. A
+/// missing or rejected token is reported up front, with an offer to run
+/// `sesh auth <provider>` inline when there is a terminal to ask on.
+pub async fn ensure_token(parent_dir: &Path, provider: &str) -> Result<()> {
+    let name = match provider {
+        "linear" => "Linear",
+        "sentry" => "Sentry",
+        _ => provider,
+    };
+    let problem = match integrations::token_health(parent_dir, provider).await {
+        TokenHealth::Valid | TokenHealth::Unknown => return Ok(()),
+        TokenHealth::Missing => format!("No {} token saved", name),
+        TokenHealth::Rejected(status) => format!("{} rejected the saved token ({})", name, status),
+    };
+    if !Term::stderr().is_term() {
+        bail!("{}. Run `sesh auth {}` to save a new one.", problem, provider);
+    }
+    println!("  {} {}.", style("✗").red(), problem);
+    let fix = Confirm::new()
+        .with_prompt(format!("Run `sesh auth {}` now?", provider))
+        .default(true)
+        .interact()
+        .context("confirmation cancelled")?;
+    if !fix {
+        bail!("{}. Run `sesh auth {}` to save a new one.", problem, provider);
+    }
+    run(parent_dir, provider)?;
+    if let TokenHealth::Rejected(status) = integrations::token_health(parent_dir, provider).await {
+        bail!("{} rejected the new token too ({})", name, status);
+    }
+    Ok(())
+}
@@ -1,88 +1,90 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
 use anyhow::{bail, Context, Result};
 use console::style;
 use dialoguer::{Confirm, FuzzySelect, MultiSelect};
-use serde::Deserialize;
 
-use crate::config::SeshConfig;
+use crate::config::{Hook, SeshConfig};
 use crate::discovery;
+use crate::forge::Forge;
+use crate::parallel;
+use crate::rollback::Rollback;
+use crate::scripting::ScriptHooks;
 use crate::session;
 use crate::worktree;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
     parent_dir: &Path,
     branch_mode: bool,
+    branch: Option<String>,
+    branch_filter: Option<String>,
     pr_mode: bool,
     all: bool,
     preset: Option<String>,
     no_setup: bool,
     no_vscode: bool,
+    keep_on_failure: bool,
 ) -> Result<()> {
     if !branch_mode && !pr_mode {
         bail!("specify either --branch or --pr");
     }
+    if branch.is_some() && branch_filter.is_some() {
+        bail!("--branch-filter only applies when picking a branch; drop it or the branch name");
+    }
 
     // Load config
     let config_path = parent_dir.join("sesh.toml");
     let config = SeshConfig::load(&config_path)?;
+    let hooks = ScriptHooks::load(parent_dir)?;
 
     // Discover repos
-    let repos = discovery::discover_repos(parent_dir)?;
+    let repos = discovery::discover_repos(parent_dir).await?;
     if repos.is_empty() {
         bail!("no git repos found in {}", parent_dir.display());
     }
 
-    // Select repos
-    let selected_repos = if all {
-        repos.clone()
+    // Select repos (presets may pin some repos read-only at their base branch)
+    let (selected_repos, pinned_repos) = if all {
+        (repos.clone(), Vec::new())
     } else if let Some(ref preset_name) = preset {
-        let preset_repos = config
+        let preset = config
             .presets
             .get(preset_name)
             .with_context(|| format!("preset '{}' not found in sesh.toml", preset_name))?;
-        repos
+        let selected: Vec<discovery::RepoInfo> = repos
             .iter()
-            .filter(|r| preset_repos.contains(&r.name))
+            .filter(|r| preset.includes(&r.name))
             .cloned()
-            .collect()
+            .collect();
+        (selected, preset.pinned().to_vec())
     } else {
-        select_repos_interactive(&repos, &config)?
+        (select_repos_interactive(&repos, &config, &hooks)?, Vec::new())
     };
 
     if selected_repos.is_empty() {
         bail!("no repos selected");
     }
 
+    let branch_repos: Vec<discovery::RepoInfo> = selected_repos
+        .iter()
+        .filter(|r| !pinned_repos.contains(&r.name))
+        .cloned()
+        .collect();
+
     // Fetch all repos for fresh branch/PR data
-    for repo in &selected_repos {
-        print!(
-            "  {} Fetching {}...",
-            style("↓").dim(),
-            repo.name
-        );
-        let output = Command::new("git")
-            .arg("-C")
-            .arg(&repo.path)
-            .args(["fetch", "--all", "--prune"])
-            .output();
-        match output {
-            Ok(o) if o.status.success() => println!(" {}", style("done").green()),
-            _ => println!(" {}", style("warning: fetch failed, continuing").yellow()),
-        }
-    }
+    super::fetch_all(&selected_repos).await;
 
     // Resolve branch name
     let branch_name = if branch_mode {
-        pick_branch(&selected_repos)?
+        pick_branch(&config, &branch_repos, branch, branch_filter.as_deref())?
     } else {
-        pick_pr_branch(&selected_repos)?
+        pick_pr_branch(parent_dir, &config, &branch_repos).await?
     };
 
     // Check for worktree conflicts
-    match check_worktree_conflicts(parent_dir, &selected_repos, &branch_name)? {
+    match check_worktree_conflicts(parent_dir, &config, &branch_repos, &branch_name)? {
         ConflictResult::OpenedExisting => return Ok(()),
         ConflictResult::NoConflict => {}
     }
@@ -98,66 +100,127 @@ pub async fn run(
         selected_repos.len()
     );
 
-    // Create worktrees with mixed strategy
-    let effective_base = &config.session.base_branch;
-    let mut created_worktrees: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let repo_names: Vec<String> = selected_repos.iter().map(|r| r.name.clone()).collect();
+    super::run_hook(parent_dir, &config, Hook::PreStart, parent_dir, &session_name, &branch_name, &repo_names, &[])?;
 
-    for repo in &selected_repos {
-        let worktree_path = sess_dir.join(&repo.name);
-        let has_local = worktree::branch_exists(&repo.path, &branch_name)?;
-        let has_remote = worktree::remote_branch_exists(&repo.path, &branch_name)?;
+    let mut rollback = Rollback::new(keep_on_failure);
+    rollback.add_session_dir(&sess_dir);
 
-        let result = if has_local || has_remote {
-            // Existing branch — check out without -b
-            worktree::checkout_existing_branch(&repo.path, &worktree_path, &branch_name)
-        } else {
-            // Branch doesn't exist in this repo — create new from base
-            let repo_config = config.repos.get(&repo.name);
-            let base_branch = repo_config
+    // Create worktrees with mixed strategy, several repos at a time
+    let effective_base = &config.session.base_branch;
+    let jobs: Vec<(String, PathBuf, PathBuf, String, [String; 2], bool)> = selected_repos
+        .iter()
+        .map(|repo| {
+            let base_branch = config
+                .repos
+                .get(&repo.name)
                 .and_then(|rc| rc.base_branch.as_deref())
                 .unwrap_or(effective_base);
-            let base_ref = format!("origin/{}", base_branch);
-            worktree::create_worktree(&repo.path, &worktree_path, &branch_name, &base_ref)
-        };
-
-        if let Err(e) = result {
-            rollback_worktrees(&created_worktrees);
-            return Err(e.context(format!("failed while setting up repo '{}'", repo.name)));
-        }
-
-        created_worktrees.push((repo.path.clone(), worktree_path.clone()));
-        println!(
-            "  {} Worktree created: {}{}",
-            style("✓").green(),
-            repo.name,
-            if has_local || has_remote {
-                ""
-            } else {
-                " (new branch)"
+            (
+                repo.name.clone(),
+                repo.path.clone(),
+                sess_dir.join(&repo.name),
+                format!("{}/{}", config.remote(&repo.name), base_branch),
+                [config.remote(&repo.name).to_string(), config.push_remote(&repo.name).to_string()],
+                pinned_repos.contains(&repo.name),
+            )
+        })
+        .collect();
+    let branch = branch_name.clone();
+    let outcomes = parallel::map_blocking(jobs, move |(name, repo_path, worktree_path, base_ref, remotes, pinned)| {
+        let result = checkout_worktree(&repo_path, &worktree_path, &branch, &base_ref, &remotes, pinned);
+        (name, repo_path, worktree_path, base_ref, pinned, result)
+    })
+    .await;
+
+    let mut failure = None;
+    for (name, repo_path, worktree_path, base_ref, pinned, result) in outcomes {
+        match result {
+            Ok(existing) => {
+                rollback.add_worktree(repo_path, worktree_path);
+                println!(
+                    "  {} Worktree created: {}{}",
+                    style("✓").green(),
+                    name,
+                    if pinned {
+                        format!(" (pinned at {})", base_ref)
+                    } else if existing {
+                        String::new()
+                    } else {
+                        " (new branch)".to_string()
+                    }
+                );
             }
-        );
+            Err(e) => {
+                println!("  {} {}", style("✗").red(), name);
+                if failure.is_none() {
+                    failure = Some(e.context(format!("failed while setting up repo '{}'", name)));
+                }
+            }
+        }
+    }
+    if let Some(e) = failure {
+        rollback.run();
+        return Err(e);
     }
 
     // Finalize session
-    super::finalize_session(
+    let finalized = super::finalize_session(
         parent_dir,
         &config,
-        &selected_repos,
-        &branch_name,
-        &session_name,
-        &sess_dir,
-        None,
-        effective_base,
-        no_setup,
-        no_vscode,
-    )?;
-
-    Ok(())
+        super::NewSession {
+            selected_repos: &selected_repos,
+            pinned_repos: &pinned_repos,
+            repo_branches: &HashMap::new(),
+            prefixed_branches: &HashMap::new(),
+            branch_name: &branch_name,
+            session_name: &session_name,
+            sess_dir: &sess_dir,
+            issue_context: None,
+            effective_base,
+            no_setup,
+            no_vscode,
+        },
+    );
+    // The branches existed before the session; never delete them
+    if let Err(e) = &finalized {
+        super::roll_back_setup(parent_dir, &config, &sess_dir, e, keep_on_failure, true).await;
+    }
+    finalized
 }
 
-fn select_repos_interactive(
+/// Create one repo's worktree for a checked-out branch: detached at the base
+/// for pinned repos, the existing branch (local or on one of `remotes`) if
+/// there is one, otherwise a new branch from the base. Returns whether the
+/// branch existed.
+fn checkout_worktree(
+    repo_path: &Path,
+    worktree_path: &Path,
+    branch_name: &str,
+    base_ref: &str,
+    remotes: &[String],
+    pinned: bool,
+) -> Result<bool> {
+    if pinned {
+        worktree::create_detached_worktree(repo_path, worktree_path, base_ref)?;
+        return Ok(false);
+    }
+    let mut existing = worktree::branch_exists(repo_path, branch_name)?;
+    for remote in remotes {
+        existing = existing || worktree::remote_branch_exists(repo_path, remote, branch_name)?;
+    }
+    if existing {
+        worktree::checkout_existing_branch(repo_path, worktree_path, branch_name)?;
+    } else {
+        worktree::create_worktree(repo_path, worktree_path, branch_name, base_ref)?;
+    }
+    Ok(existing)
+}
+
+pub fn select_repos_interactive(
     repos: &[discovery::RepoInfo],
     config: &SeshConfig,
+    hooks: &ScriptHooks,
 ) -> Result<Vec<discovery::RepoInfo>> {
     let labels: Vec<String> = repos
         .iter()
@@ -182,6 +245,8 @@ fn select_repos_interactive(
                 .unwrap_or(true)
         })
         .collect();
+    let names: Vec<String> = repos.iter().map(|r| r.name.clone()).collect();
+    let defaults = hooks.default_repos(&names, &defaults)?;
 
     let selections = MultiSelect::new()
         .with_prompt("Select repos for this session")
@@ -193,13 +258,46 @@ fn select_repos_interactive(
     Ok(selections.into_iter().map(|i| repos[i].clone()).collect())
 }
 
-fn pick_branch(repos: &[discovery::RepoInfo]) -> Result<String> {
-    let mut all_branches = BTreeSet::new();
+/// Use `name` if given (it must exist in at least one repo), otherwise pick
+/// from the branches of every repo, narrowed by `filter`. Picker labels show
+/// which repos have each branch and where only the remote has it.
+fn pick_branch(
+    config: &SeshConfig,
+    repos: &[discovery::RepoInfo],
+    name: Option<String>,
+    filter: Option<&str>,
+) -> Result<String> {
+    // Branch → (repo, has a local branch) for each repo that has it
+    let mut all_branches: BTreeMap<String, Vec<(&str, bool)>> = BTreeMap::new();
 
     for repo in repos {
-        let branches = worktree::list_all_branches(&repo.path)?;
-        for b in branches {
-            all_branches.insert(b);
+        let branches = worktree::branch_locations(&repo.path, config.remote(&repo.name))?;
+        for (b, local) in branches {
+            all_branches.entry(b).or_default().push((&repo.name, local));
+        }
+    }
+
+    if let Some(name) = name {
+        if !all_branches.contains_key(&name) {
+            bail!(
+                "branch '{}' not found locally or on the remote in any selected repo (use `sesh start -b {}` to create it)",
+                name,
+                name
+            );
+        }
+        return Ok(name);
+    }
+
+    if let Some(filter) = filter {
+        let needle = filter.to_lowercase();
+        all_branches.retain(|b, _| b.to_lowercase().contains(&needle));
+        if all_branches.is_empty() {
+            bail!("no branches matching '{}' across selected repos", filter);
+        }
+        if all_branches.len() == 1 {
+            let only = all_branches.into_keys().next().unwrap_or_default();
+            println!("{} Only '{}' matches '{}'", style("→").cyan(), only, filter);
+            return Ok(only);
         }
     }
 
@@ -207,24 +305,26 @@ fn pick_branch(repos: &[discovery::RepoInfo]) -> Result<String> {
         bail!("no branches found across selected repos");
     }
 
-    let branch_list: Vec<String> = all_branches.into_iter().collect();
+    let width = all_branches.keys().map(|b| b.len()).max().unwrap_or(0);
+    let labels: Vec<String> = all_branches
+        .iter()
+        .map(|(branch, found)| {
+            let repos: Vec<String> = found
+                .iter()
+                .map(|(repo, local)| if *local { repo.to_string() } else { format!("{} (remote only)", repo) })
+                .collect();
+            format!("{:<width$}  {}", branch, style(repos.join(", ")).dim(), width = width)
+        })
+        .collect();
 
     let selection = FuzzySelect::new()
         .with_prompt("Select a branch")
-        .items(&branch_list)
+        .items(&labels)
         .default(0)
         .interact()
         .context("branch selection cancelled")?;
 
-    Ok(branch_list[selection].clone())
-}
-
-#[derive(Debug, Deserialize)]
-struct GhPr {
-    number: u64,
-    title: String,
-    #[serde(rename = "headRefName")]
-    head_ref_name: String,
+    Ok(all_branches.into_keys().nth(selection).unwrap_or_default())
 }
 
 struct PrDisplayItem {
@@ -234,50 +334,34 @@ struct PrDisplayItem {
     branch: String,
 }
 
-fn pick_pr_branch(repos: &[discovery::RepoInfo]) -> Result<String> {
-    // Check gh is available
-    let gh_check = Command::new("which").arg("gh").output();
-    match gh_check {
-        Ok(output) if !output.status.success() => {
-            bail!("GitHub CLI (gh) not found. Install it from https://cli.github.com")
-        }
-        Err(_) => bail!("GitHub CLI (gh) not found. Install it from https://cli.github.com"),
-        _ => {}
-    }
-
+async fn pick_pr_branch(
+    parent_dir: &Path,
+    config: &SeshConfig,
+    repos: &[discovery::RepoInfo],
+) -> Result<String> {
     let mut pr_items: Vec<PrDisplayItem> = Vec::new();
 
     for repo in repos {
-        let output = Command::new("gh")
-            .args([
-                "pr", "list",
-                "--json", "number,title,headRefName",
-                "--state", "open",
-            ])
-            .current_dir(&repo.path)
-            .output()
-            .with_context(|| format!("failed to run gh pr list in {}", repo.name))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            eprintln!(
-                "  {} Failed to list PRs for {}: {}",
-                style("!").yellow(),
-                repo.name,
-                stderr.trim()
-            );
-            continue;
-        }
-
-        let prs: Vec<GhPr> = serde_json::from_slice(&output.stdout)
-            .with_context(|| format!("failed to parse PR list for {}", repo.name))?;
+        let forge = Forge::detect(&repo.path, config.remote(&repo.name));
+        let prs = match forge.list_open_prs(parent_dir, config, &repo.path).await {
+            Ok(prs) => prs,
+            Err(e) => {
+                eprintln!(
+                    "  {} Failed to list PRs for {}: {:#}",
+                    style("!").yellow(),
+                    repo.name,
+                    e
+                );
+                continue;
+            }
+        };
 
         for pr in prs {
             pr_items.push(PrDisplayItem {
                 repo_name: repo.name.clone(),
                 number: pr.number,
                 title: pr.title,
-                branch: pr.head_ref_name,
+                branch: pr.branch,
             });
         }
     }
@@ -313,6 +397,7 @@ enum ConflictResult {
 
 fn check_worktree_conflicts(
     parent_dir: &Path,
+    config: &SeshConfig,
     repos: &[discovery::RepoInfo],
     branch_name: &str,
 ) -> Result<ConflictResult> {
@@ -344,13 +429,7 @@ fn check_worktree_conflicts(
             .context("confirmation cancelled")?;
 
         if open {
-            let sess_dir = session::session_dir(parent_dir, &existing.name);
-            let paths: Vec<PathBuf> = existing
-                .repos
-                .iter()
-                .map(|r| r.worktree_path.clone())
-                .collect();
-            crate::vscode::open_session_in_vscode(&sess_dir, &paths)?;
+            crate::vscode::open_session_in_vscode(&existing, &config.vscode)?;
             println!(
                 "  {} Opened session '{}' in VS Code.",
                 style("✓").green(),
@@ -375,18 +454,3 @@ fn check_worktree_conflicts(
     );
 }
 
-fn rollback_worktrees(created: &[(PathBuf, PathBuf)]) {
-    eprintln!(
-        "\n  {} Rolling back created worktrees...",
-        style("✗").red()
-    );
-    for (repo_path, worktree_path) in created.iter().rev() {
-        if let Err(e) = worktree::remove_worktree(repo_path, worktree_path) {
-            eprintln!(
-                "    Failed to remove worktree {}: {}",
-                worktree_path.display(),
-                e
-            );
-        }
-    }
-}
@@ -0,0 +1,66 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use console::style;
+
+use crate::commit_msg;
+use crate::config::SeshConfig;
+use crate::gitstate;
+use crate::worktree;
+
+use super::pick_session;
+
+pub fn run(
+    parent_dir: &Path,
+    session_name: Option<String>,
+    message: String,
+    all: bool,
+) -> Result<()> {
+    let config_path = parent_dir.join("sesh.toml");
+    let config = SeshConfig::load(&config_path)?;
+
+    // Validate before touching any repo so a bad message commits nothing
+    if let Some(pattern) = &config.git.commit_pattern {
+        commit_msg::validate_message(&message, pattern)?;
+    }
+
+    let info = pick_session(parent_dir, session_name)?;
+    let mut committed = 0;
+
+    for repo in &info.repos {
+        if repo.pinned || !repo.worktree_path.exists() {
+            continue;
+        }
+
+        if !worktree::has_changes_to_commit(&repo.worktree_path, all)? {
+            println!("  {} {} (nothing to commit)", style("·").dim(), repo.name);
+            continue;
+        }
+
+        let mut cmd = Command::new("git");
+        cmd.arg("-C").arg(&repo.worktree_path).arg("commit");
+        if all {
+            cmd.arg("-a");
+        }
+        let output = cmd
+            .args(["-m", &message])
+            .output()
+            .context("failed to run git commit")?;
+        gitstate::invalidate();
+
+        if output.status.success() {
+            println!("  {} Committed in {}", style("✓").green(), repo.name);
+            committed += 1;
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!("git commit failed in {}: {}", repo.name, stderr.trim());
+        }
+    }
+
+    if committed == 0 {
+        println!("Nothing to commit in session '{}'.", info.name);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use console::style;
+use dialoguer::{FuzzySelect, Input};
+
+use crate::config::{Hook, SeshConfig};
+use crate::discovery;
+use crate::rollback::Rollback;
+use crate::scripting::ScriptHooks;
+use crate::session;
+use crate::worktree;
+
+use super::checkout::select_repos_interactive;
+
+/// Create a session where each repo checks out its own existing branch, e.g.
+/// api on `feat/x` and web on `feat/y`, for integration testing, or a new one
+/// from its base branch. `sesh stop` leaves the branches in place.
+pub async fn run(
+    parent_dir: &Path,
+    name: Option<String>,
+    no_setup: bool,
+    no_vscode: bool,
+    keep_on_failure: bool,
+) -> Result<()> {
+    let config_path = parent_dir.join("sesh.toml");
+    let config = SeshConfig::load(&config_path)?;
+    let hooks = ScriptHooks::load(parent_dir)?;
+
+    let repos = discovery::discover_repos(parent_dir).await?;
+    if repos.is_empty() {
+        bail!("no git repos found in {}", parent_dir.display());
+    }
+
+    let selected_repos = select_repos_interactive(&repos, &config, &hooks)?;
+    if selected_repos.is_empty() {
+        bail!("no repos selected");
+    }
+
+    // Fetch for fresh branch data
+    super::fetch_all(&selected_repos).await;
+
+    // Pick a branch per repo. Branches checked out elsewhere (including the
+    // main repo's own) can't get another worktree, so they aren't offered.
+    let mut repo_branches: HashMap<String, String> = HashMap::new();
+    // Repos getting a new branch, with the ref it starts from
+    let mut new_branches: HashMap<String, String> = HashMap::new();
+    for repo in &selected_repos {
+        let mut branches = Vec::new();
+        for branch in worktree::list_all_branches(&repo.path, config.remote(&repo.name))? {
+            if !worktree::is_branch_on_worktree(&repo.path, &branch)? {
+                branches.push(branch);
+            }
+        }
+        let base = config.base_branch(&repo.name);
+        let base_ref = format!("{}/{}", config.remote(&repo.name), base);
+        let mut items = vec![format!("+ new branch from {}", base_ref)];
+        items.extend(branches.iter().cloned());
+        let default = branches.iter().position(|b| b == base).map_or(0, |i| i + 1);
+        let selection = FuzzySelect::new()
+            .with_prompt(format!("Branch for {}", repo.name))
+            .items(&items)
+            .default(default)
+            .interact()
+            .context("branch selection cancelled")?;
+
+        let branch = match selection.checked_sub(1) {
+            Some(i) => branches[i].clone(),
+            None => {
+                let branch: String = Input::new()
+                    .with_prompt(format!("New branch for {}", repo.name))
+                    .validate_with(|name: &String| worktree::validate_branch_name(name).map_err(|e| e.to_string()))
+                    .interact_text()
+                    .context("branch name input cancelled")?;
+                if worktree::branch_exists(&repo.path, &branch)? {
+                    bail!("branch '{}' already exists in {}; pick it from the list instead", branch, repo.name);
+                }
+                new_branches.insert(repo.name.clone(), base_ref);
+                branch
+            }
+        };
+        repo_branches.insert(repo.name.clone(), branch);
+    }
+
+    let name = match name {
+        Some(n) => n,
+        None => Input::new()
+            .with_prompt("Session name")
+            .interact_text()
+            .context("session name input cancelled")?,
+    };
+    let session_name = session::sanitize_session_name(&name, parent_dir);
+    let sess_dir = session::session_dir(parent_dir, &session_name);
+
+    println!(
+        "\n{} Composing session {} with {} repo(s)...\n",
+        style("→").cyan().bold(),
+        style(&session_name).green().bold(),
+        selected_repos.len()
+    );
+
+    let repo_names: Vec<String> = selected_repos.iter().map(|r| r.name.clone()).collect();
+    super::run_hook(parent_dir, &config, Hook::PreStart, parent_dir, &session_name, &session_name, &repo_names, &[])?;
+
+    let mut rollback = Rollback::new(keep_on_failure);
+    rollback.add_session_dir(&sess_dir);
+    for repo in &selected_repos {
+        let worktree_path = sess_dir.join(&repo.name);
+        let branch = &repo_branches[&repo.name];
+        let created = match new_branches.get(&repo.name) {
+            Some(base_ref) => worktree::create_worktree(&repo.path, &worktree_path, branch, base_ref),
+            None => worktree::checkout_existing_branch(&repo.path, &worktree_path, branch),
+        };
+        if let Err(e) = created {
+            rollback.run();
+            return Err(e.context(format!("failed while setting up repo '{}'", repo.name)));
+        }
+        if new_branches.contains_key(&repo.name) {
+            let (repo_path, branch) = (repo.path.clone(), branch.clone());
+            rollback.add(format!("branch {} in {}", branch, repo.name), move || {
+                worktree::delete_branch(&repo_path, &branch)
+            });
+        }
+        rollback.add_worktree(repo.path.clone(), worktree_path);
+        println!(
+            "  {} Worktree created: {} ({})",
+            style("✓").green(),
+            repo.name,
+            style(branch).cyan()
+        );
+    }
+
+    // The session branch is only a label here; each repo records its own branch
+    let finalized = super::finalize_session(
+        parent_dir,
+        &config,
+        super::NewSession {
+            selected_repos: &selected_repos,
+            pinned_repos: &[],
+            repo_branches: &repo_branches,
+            prefixed_branches: &HashMap::new(),
+            branch_name: &session_name,
+            session_name: &session_name,
+            sess_dir: &sess_dir,
+            issue_context: None,
+            effective_base: &config.session.base_branch,
+            no_setup,
+            no_vscode,
+        },
+    );
+    if let Err(e) = &finalized {
+        super::roll_back_setup(parent_dir, &config, &sess_dir, e, keep_on_failure, true).await;
+    }
+    finalized
+}
@@ -0,0 +1,303 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use console::style;
+
+use crate::config::{self, Hook, ScriptEntry, SeshConfig, LOCAL_CONFIG_FILE};
+use crate::discovery;
+use crate::remote_config;
+
+/// Pull the canonical team config from `[meta] config_source` into sesh.toml.
+pub async fn update(parent_dir: &Path) -> Result<()> {
+    let config_path = parent_dir.join("sesh.toml");
+    let config = SeshConfig::load(&config_path)?;
+    let Some(source) = config.meta.config_source.as_deref() else {
+        bail!("no [meta] config_source in sesh.toml (use `sesh init --from-url` to set one up)");
+    };
+
+    println!("  {} Fetching {}...", style("↓").dim(), source);
+    let fetched = remote_config::fetch(
+        parent_dir,
+        source,
+        config.meta.config_path.as_deref(),
+        config.meta.config_ref.as_deref(),
+    )
+    .await?;
+    let fetched = remote_config::record_source(&fetched, source)?;
+
+    let current = std::fs::read_to_string(&config_path).unwrap_or_default();
+    if current == fetched {
+        println!("{} sesh.toml is already up to date.", style("✓").green());
+        return Ok(());
+    }
+
+    crate::state::write_atomic(&config_path, fetched.as_bytes())
+        .with_context(|| format!("failed to write {}", config_path.display()))?;
+    println!(
+        "{} sesh.toml updated from {} (personal overrides in {} are kept)",
+        style("✓").green(),
+        source,
+        LOCAL_CONFIG_FILE
+    );
+    Ok(())
+}
+
+/// Print the effective configuration, defaults included.
+pub fn show(parent_dir: &Path) -> Result<()> {
+    let config_path = parent_dir.join("sesh.toml");
+    let effective = effective(&config_path)?;
+    let files: Vec<String> = config::source_files(&config_path)
+        .iter()
+        .map(|f| f.strip_prefix(parent_dir).unwrap_or(f).display().to_string())
+        .collect();
+    if files.is_empty() {
+        println!("# No config files found; showing defaults");
+    } else {
+        println!("# Effective config from {} (defaults included)", files.join(", "));
+    }
+    print!("{}", toml::to_string_pretty(&effective)?);
+    Ok(())
+}
+
+/// Print one effective setting: strings bare, tables as TOML.
+pub fn get(parent_dir: &Path, key: &str) -> Result<()> {
+    let effective = effective(&parent_dir.join("sesh.toml"))?;
+    let mut value = &effective;
+    for part in key_path(key)? {
+        value = value.get(part.as_str()).with_context(|| {
+            format!("'{}' is not set (see `sesh config show` for every setting)", key)
+        })?;
+    }
+    match value {
+        toml::Value::String(s) => println!("{}", s),
+        toml::Value::Table(t) => print!("{}", toml::to_string_pretty(t)?),
+        other => println!("{}", other),
+    }
+    Ok(())
+}
+
+/// Set `key` to `value` in sesh.toml, editing the file in place so comments
+/// and layout survive. `value` is read as TOML (`true`, `5`, `["a", "b"]`),
+/// falling back to a plain string.
+pub fn set(parent_dir: &Path, key: &str, value: &str) -> Result<()> {
+    let config_path = parent_dir.join("sesh.toml");
+    if !config_path.exists() {
+        bail!("no sesh.toml in {} (run `sesh init` first)", parent_dir.display());
+    }
+    let path = key_path(key)?;
+    let contents = std::fs::read_to_string(&config_path)
+        .with_context(|| format!("failed to read {}", config_path.display()))?;
+    let mut doc: toml_edit::DocumentMut = contents
+        .parse()
+        .with_context(|| format!("failed to parse {}", config_path.display()))?;
+
+    let (last, parents) = path.split_last().context("empty key")?;
+    let mut table: &mut dyn toml_edit::TableLike = doc.as_table_mut();
+    for (i, part) in parents.iter().enumerate() {
+        table = table
+            .entry(part)
+            .or_insert_with(|| {
+                // Let `[repos.api]` stand without an empty `[repos]` above it
+                let mut t = toml_edit::Table::new();
+                t.set_implicit(true);
+                toml_edit::Item::Table(t)
+            })
+            .as_table_like_mut()
+            .with_context(|| format!("'{}' is not a table", parents[..=i].join(".")))?;
+    }
+    let mut new_value = value
+        .parse::<toml_edit::Value>()
+        .unwrap_or_else(|_| toml_edit::Value::from(value));
+    match table.get_mut(last).and_then(|item| item.as_value_mut()) {
+        Some(old) => {
+            // Keep the old value's spacing and trailing comment
+            *new_value.decor_mut() = old.decor().clone();
+            *old = new_value;
+        }
+        None => {
+            new_value.decor_mut().clear();
+            table.insert(last, toml_edit::Item::Value(new_value));
+        }
+    }
+
+    // Refuse to write a file sesh can't load, or a setting it would ignore
+    let updated = doc.to_string();
+    let raw: toml::Table = toml::from_str(&updated).context("edited sesh.toml no longer parses")?;
+    let unknown = config::unknown_keys(&raw)
+        .with_context(|| format!("refusing to set '{}' to {}", key, value))?;
+    let dotted = path.iter().map(|p| config::toml_key(p)).collect::<Vec<_>>().join(".");
+    if unknown
+        .iter()
+        .any(|u| *u == dotted || dotted.starts_with(&format!("{}.", u)))
+    {
+        bail!("unknown setting '{}' (see `sesh config show` for every setting)", key);
+    }
+
+    crate::state::write_atomic(&config_path, updated.as_bytes())
+        .with_context(|| format!("failed to write {}", config_path.display()))?;
+    println!("{} {} = {} in sesh.toml", style("✓").green(), key, value);
+
+    let local_path = parent_dir.join(LOCAL_CONFIG_FILE);
+    if let Ok(local) = std::fs::read_to_string(&local_path)
+        && let Ok(local) = toml::from_str::<toml::Value>(&local)
+        && path.iter().try_fold(&local, |v, part| v.get(part.as_str())).is_some()
+    {
+        println!(
+            "  {} {} also sets '{}' and takes precedence",
+            style("note:").yellow(),
+            LOCAL_CONFIG_FILE,
+            key
+        );
+    }
+    Ok(())
+}
+
+/// Check the config files for unknown keys, missing script files, templates
+/// without `.tmpl` and preset repos that don't exist.
+pub fn validate(parent_dir: &Path) -> Result<()> {
+    let config_path = parent_dir.join("sesh.toml");
+    let mut problems = Vec::new();
+
+    for file in [config_path.clone(), parent_dir.join(LOCAL_CONFIG_FILE)] {
+        if !file.exists() {
+            continue;
+        }
+        let name = file.strip_prefix(parent_dir).unwrap_or(&file).display().to_string();
+        let raw = std::fs::read_to_string(&file)
+            .with_context(|| format!("failed to read {}", file.display()))?;
+        let unknown = toml::from_str::<toml::Table>(&raw)
+            .map_err(anyhow::Error::from)
+            .and_then(|t| config::unknown_keys(&t));
+        match unknown {
+            Ok(keys) => {
+                problems.extend(keys.into_iter().map(|k| format!("{}: unknown key `{}`", name, k)));
+            }
+            Err(e) => problems.push(format!("{}: {:#}", name, e)),
+        }
+    }
+
+    match SeshConfig::load(&config_path) {
+        Ok(config) => {
+            let mut scripts: Vec<(String, &ScriptEntry)> = Vec::new();
+            let global = [("setup", &config.scripts.setup), ("teardown", &config.scripts.teardown)];
+            for (section, entries) in global {
+                scripts.extend(entries.iter().map(|e| (format!("[scripts] {}", section), e)));
+            }
+            for hook in Hook::ALL {
+                for entry in config.scripts.hook(hook) {
+                    let section = format!("[scripts] {}", hook.name());
+                    if entry.background || entry.group.is_some() || !entry.depends_on.is_empty() {
+                        problems.push(format!(
+                            "{}: hooks run in the foreground, in order; drop `background`, `group` and `depends_on`",
+                            section
+                        ));
+                    }
+                    scripts.push((section, entry));
+                }
+            }
+            let mut repo_names: Vec<&String> = config.repos.keys().collect();
+            repo_names.sort();
+            for name in repo_names {
+                let rc = &config.repos[name];
+                for item in rc.template.iter().filter(|t| !t.ends_with(".tmpl")) {
+                    problems.push(format!(
+                        "[repos.{}] template: `{}` must end in `.tmpl` (the rendered file drops it)",
+                        config::toml_key(name),
+                        item
+                    ));
+                }
+                for (section, entries) in [("setup", &rc.setup), ("teardown", &rc.teardown)] {
+                    scripts.extend(
+                        entries
+                            .iter()
+                            .map(|e| (format!("[repos.{}] {}", config::toml_key(name), section), e)),
+                    );
+                }
+            }
+            let groups: Vec<&String> = scripts
+                .iter()
+                .filter(|(section, _)| section.ends_with("setup"))
+                .filter_map(|(_, e)| e.group.as_ref())
+                .collect();
+            for (section, entry) in scripts {
+                for group in &entry.depends_on {
+                    if !groups.contains(&group) {
+                        problems.push(format!("{}: `depends_on` names unknown group `{}`", section, group));
+                    }
+                }
+                if entry.command.is_some() != entry.path.is_empty() {
+                    problems.push(format!("{}: each entry needs exactly one of `path` or `command`", section));
+                } else if entry.command.is_none() && !parent_dir.join(&entry.path).is_file() {
+                    problems.push(format!("{}: script file `{}` not found", section, entry.path));
+                }
+                if let Some(check) = &entry.health_check {
+                    if !entry.background || !section.ends_with("setup") {
+                        problems.push(format!("{}: `health_check` only applies to background setup scripts", section));
+                    }
+                    if check.url.is_some() == check.command.is_some() {
+                        problems.push(format!("{}: `health_check` needs exactly one of `url` or `command`", section));
+                    } else if check
+                        .url
+                        .as_ref()
+                        .is_some_and(|url| !url.starts_with("http://") && !url.starts_with("https://"))
+                    {
+                        problems.push(format!("{}: `health_check` URLs must be http:// or https://", section));
+                    }
+                }
+            }
+
+            let repos: Vec<String> = discovery::repo_dirs(parent_dir)?
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
+            let mut presets: Vec<_> = config.presets.iter().collect();
+            presets.sort_by(|a, b| a.0.cmp(b.0));
+            for (preset, p) in presets {
+                for repo in p.repos().iter().chain(p.pinned()) {
+                    if !repos.contains(repo) {
+                        problems.push(format!(
+                            "preset '{}': no repo named '{}' in this directory",
+                            preset, repo
+                        ));
+                    }
+                }
+            }
+        }
+        Err(e) => problems.push(format!("{:#}", e)),
+    }
+
+    if problems.is_empty() {
+        println!("{} Config is valid.", style("✓").green());
+        return Ok(());
+    }
+    println!("{} Found {} problem(s):\n", style("!").yellow(), problems.len());
+    for problem in &problems {
+        println!("  • {}", problem);
+    }
+    println!();
+    bail!("invalid sesh config");
+}
+
+fn effective(config_path: &Path) -> Result<toml::Value> {
+    let config = SeshConfig::load(config_path)?;
+    toml::Value::try_from(&config).context("failed to serialize config")
+}
+
+/// Split a dotted key like `repos."api.v2".copy` into its parts.
+fn key_path(key: &str) -> Result<Vec<String>> {
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut quoted = false;
+    for c in key.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '.' if !quoted => parts.push(std::mem::take(&mut part)),
+            c => part.push(c),
+        }
+    }
+    parts.push(part);
+    if quoted || parts.iter().any(|p| p.is_empty()) {
+        bail!("invalid key '{}': expected dotted names like session.base_branch", key);
+    }
+    Ok(parts)
+}
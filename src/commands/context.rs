@@ -0,0 +1,31 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+use crate::context;
+use crate::session;
+
+use super::pick_session;
+
+/// Print the session's generated context (or, with `path`, where it lives) for
+/// piping into an agent prompt. Without a name, the current session is used.
+pub fn show(parent_dir: &Path, name: Option<String>, path: bool) -> Result<()> {
+    let info = match name {
+        Some(name) => pick_session(parent_dir, Some(name))?,
+        None => match session::current_session(parent_dir) {
+            Some(info) => info,
+            None => pick_session(parent_dir, None)?,
+        },
+    };
+    let file = context::context_file(&session::session_dir(parent_dir, &info.name));
+    if !file.exists() {
+        bail!("session '{}' has no generated context ({} is missing)", info.name, file.display());
+    }
+    if path {
+        println!("{}", file.display());
+        return Ok(());
+    }
+    let content = std::fs::read_to_string(&file).with_context(|| format!("failed to read {}", file.display()))?;
+    print!("{}", content);
+    Ok(())
+}
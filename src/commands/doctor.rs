@@ -1,26 +1,69 @@
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
-use console::style;
-use dialoguer::Confirm;
+use anyhow::{bail, Result};
+use console::{style, Term};
+use dialoguer::MultiSelect;
+use serde::Serialize;
 
+use crate::config::SeshConfig;
 use crate::discovery;
+use crate::gitstate;
+use crate::index;
 use crate::lock;
-use crate::session;
+use crate::scripts::{self, ProcessState};
+use crate::session::{self, SessionInfo, SessionRepo};
+use crate::state;
 use crate::worktree;
 
-pub fn run(parent_dir: &Path) -> Result<()> {
-    println!("{} Running diagnostics...\n", style("🔍").bold());
+/// Machine-readable result of a doctor run (`--json`).
+#[derive(Debug, Default, Serialize)]
+struct Report {
+    issues: Vec<String>,
+    fixed: Vec<String>,
+    failed: Vec<String>,
+}
+
+impl Report {
+    fn fixed(&mut self, json: bool, msg: String) {
+        if !json {
+            println!("  {}", msg);
+        }
+        self.fixed.push(msg);
+    }
+
+    fn failed(&mut self, json: bool, msg: String) {
+        if !json {
+            eprintln!("  Warning: {}", msg);
+        }
+        self.failed.push(msg);
+    }
+}
+
+/// `fix` applies repairs without asking; `json` prints a [`Report`] instead
+/// of text and never prompts.
+pub async fn run(parent_dir: &Path, fix: bool, json: bool) -> Result<()> {
+    if !json {
+        println!("{} Running diagnostics...\n", style("🔍").bold());
+    }
 
     let mut issues = Vec::new();
+    let mut fixes = Vec::new();
 
     // Check sessions
     let sessions = session::list_sessions(parent_dir)?;
-    println!("  Sessions found: {}", sessions.len());
+    if !json {
+        println!("  Sessions found: {}", sessions.len());
+    }
 
     for sess in &sessions {
         for repo in &sess.repos {
-            if !repo.worktree_path.exists() {
+            if let Err(e) = session::ensure_managed_path(parent_dir, &repo.worktree_path) {
+                issues.push(format!(
+                    "Session '{}': worktree path for '{}' is unsafe, `sesh stop` will refuse to remove it ({:#})",
+                    sess.name, repo.name, e
+                ));
+            } else if !repo.worktree_path.exists() {
                 issues.push(format!(
                     "Session '{}': worktree for '{}' missing at {}",
                     sess.name,
@@ -31,15 +74,62 @@ pub fn run(parent_dir: &Path) -> Result<()> {
         }
     }
 
+    // Check for session files written in an older schema
+    for sess in &sessions {
+        let dir = session::session_dir(parent_dir, &sess.name);
+        if let Ok((_, on_disk)) = session::load_session_versioned(&dir)
+            && on_disk < session::SESSION_SCHEMA_VERSION
+        {
+            issues.push(format!(
+                "Session '{}': session.json uses schema v{} (current v{})",
+                sess.name,
+                on_disk,
+                session::SESSION_SCHEMA_VERSION
+            ));
+            fixes.push(Fix::UpgradeSession { dir, from: on_disk });
+        }
+    }
+
+    // Check background_pids.json against what is actually running, e.g. after a reboot
+    for sess in &sessions {
+        let dir = session::session_dir(parent_dir, &sess.name);
+        let mut pids = session::load_background_pids(&dir);
+        let was_dead: Vec<bool> = pids.iter().map(|bp| bp.dead).collect();
+        let states = scripts::reconcile_background_pids(&dir, &mut pids);
+        let adopted = states.iter().filter(|s| **s == ProcessState::Adopted).count();
+        let died = states
+            .iter()
+            .zip(&was_dead)
+            .filter(|(s, was)| **s == ProcessState::Dead && !**was)
+            .count();
+        let mut changes = Vec::new();
+        if died > 0 {
+            changes.push(format!("{} background process(es) no longer running", died));
+        }
+        if adopted > 0 {
+            changes.push(format!("{} replaced by the PID in their pidfile", adopted));
+        }
+        if !changes.is_empty() {
+            issues.push(format!("Session '{}': {} (see `sesh ps`)", sess.name, changes.join(", ")));
+            fixes.push(Fix::ReconcileProcesses(dir));
+        }
+    }
+
     // Check for orphaned worktrees in discovered repos
-    let repos = discovery::discover_repos(parent_dir).unwrap_or_default();
+    let repos = discovery::discover_repos(parent_dir).await.unwrap_or_default();
+    let repo_paths: Vec<PathBuf> = repos.iter().map(|r| r.path.clone()).collect();
+    gitstate::prefetch_worktrees(&repo_paths).await;
     let sesh_dir = parent_dir.join(".sesh");
 
+    let mut orphaned_in = Vec::new();
+    #[allow(clippy::needless_borrows_for_generic_args)]
     for repo in &repos {
         if let Ok(worktrees) = worktree::get_worktree_list(&repo.path) {
             for wt_path in &worktrees {
                 // If worktree is under .sesh/ but no session owns it
-                if wt_path.starts_with(&sesh_dir.to_string_lossy().as_ref()) {
+                if wt_path.starts_with(&sesh_dir.to_string_lossy().as_ref())
+                    && !wt_path.starts_with(crate::pool::pool_dir(parent_dir).to_string_lossy().as_ref())
+                {
                     let owned = sessions.iter().any(|s| {
                         s.repos.iter().any(|r| r.worktree_path.to_string_lossy() == *wt_path)
                     });
@@ -48,30 +138,86 @@ pub fn run(parent_dir: &Path) -> Result<()> {
                             "Orphaned worktree for '{}': {}",
                             repo.name, wt_path
                         ));
+                        if !orphaned_in.contains(&repo.name) {
+                            orphaned_in.push(repo.name.clone());
+                        }
                     }
                 }
             }
         }
     }
+    if !orphaned_in.is_empty() {
+        fixes.push(Fix::PruneWorktrees(orphaned_in));
+    }
 
-    // Check for stale session dirs (no session.json)
+    // Check for stale session dirs (no session.json). Dirs whose session.json
+    // was quarantined as corrupt still hold worktrees, so they get rebuilt
+    // rather than removed.
     let sessions_dir = parent_dir.join(".sesh/sessions");
+    #[allow(clippy::collapsible_if)]
     if sessions_dir.exists() {
         if let Ok(entries) = std::fs::read_dir(&sessions_dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if path.is_dir() && !path.join("session.json").exists() {
+                if !path.is_dir() {
+                    continue;
+                }
+                let quarantined = state::quarantined_files(&path);
+                for file in &quarantined {
+                    issues.push(format!("Quarantined corrupt state file: {}", file.display()));
+                }
+                if path.join("session.json").exists() {
+                    if let Err(e) = session::load_session(&path)
+                        && e.downcast_ref::<state::CorruptStateFile>().is_some()
+                    {
+                        issues.push(format!(
+                            "Corrupt session.json (can be moved aside and rebuilt from its worktrees): {}",
+                            path.display()
+                        ));
+                        fixes.push(Fix::RebuildSession(path));
+                    }
+                    continue;
+                }
+                if quarantined.iter().any(|f| is_quarantined_session_file(f)) {
+                    issues.push(format!(
+                        "Session directory without a valid session.json (can be rebuilt from its worktrees): {}",
+                        path.display()
+                    ));
+                    fixes.push(Fix::RebuildSession(path));
+                } else {
                     issues.push(format!(
                         "Stale session directory (no session.json): {}",
                         path.display()
                     ));
+                    fixes.push(Fix::RemoveStaleDir(path));
                 }
             }
         }
     }
+    for file in state::quarantined_files(&parent_dir.join(".sesh/locks")) {
+        issues.push(format!("Quarantined corrupt state file: {}", file.display()));
+    }
+
+    // Check the optional session index against `[index] enabled`
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+    let index_exists = index::exists(parent_dir);
+    if config.index.enabled && !index_exists {
+        issues.push("Session index not built ([index] enabled = true)".to_string());
+        fixes.push(Fix::RebuildIndex);
+    } else if config.index.enabled
+        && let Err(e) = index::check(parent_dir)
+    {
+        issues.push(format!("Session index is unusable: {:#}", e));
+        fixes.push(Fix::RebuildIndex);
+    } else if !config.index.enabled && index_exists {
+        issues.push(format!(
+            "Session index present but [index] enabled = false: {}",
+            index::index_path(parent_dir).display()
+        ));
+        fixes.push(Fix::RemoveIndex);
+    }
 
     // Check for stale locks (pointing to sessions that no longer exist)
-    let mut stale_locks = Vec::new();
     if let Ok(locks) = lock::list_locks(parent_dir) {
         for (repo_name, lock_info) in &locks {
             if !session::session_exists(parent_dir, &lock_info.session) {
@@ -79,63 +225,291 @@ pub fn run(parent_dir: &Path) -> Result<()> {
                     "Stale lock for repo '{}' (session '{}' no longer exists)",
                     repo_name, lock_info.session
                 ));
-                stale_locks.push(repo_name.clone());
+                fixes.push(Fix::ReleaseLock {
+                    repo: repo_name.clone(),
+                    session: lock_info.session.clone(),
+                });
+            }
+        }
+    }
+
+    let mut report = Report { issues, ..Default::default() };
+    if json {
+        if fix {
+            for f in &fixes {
+                f.apply(parent_dir, &repos, &mut report, json);
             }
         }
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
     }
 
-    if issues.is_empty() {
+    if report.issues.is_empty() {
         println!("\n  {} No issues found. Everything looks good!", style("✔").green());
         return Ok(());
     }
 
-    println!("\n  {} Found {} issue(s):\n", style("!").yellow(), issues.len());
-    for (i, issue) in issues.iter().enumerate() {
+    println!("\n  {} Found {} issue(s):\n", style("!").yellow(), report.issues.len());
+    for (i, issue) in report.issues.iter().enumerate() {
         println!("  {}. {}", i + 1, issue);
     }
 
-    let fix = Confirm::new()
-        .with_prompt("\nAttempt to fix issues?")
-        .default(false)
-        .interact()?;
+    if fixes.is_empty() {
+        println!("\n  No automatic fixes available.");
+        return Ok(());
+    }
+
+    let plans: Vec<String> = fixes.iter().map(|f| f.describe(parent_dir)).collect();
+    let selected: Vec<usize> = if fix {
+        (0..fixes.len()).collect()
+    } else {
+        if !Term::stderr().is_term() {
+            println!("\n  Run `sesh doctor --fix` to apply all fixes.");
+            return Ok(());
+        }
+        println!();
+        MultiSelect::new()
+            .with_prompt("Fixes to apply (space to toggle, enter to confirm)")
+            .items(&plans)
+            .defaults(&vec![true; plans.len()])
+            .interact()?
+    };
 
-    if !fix {
+    if selected.is_empty() {
+        println!("\n  No fixes selected.");
         return Ok(());
     }
 
-    // Fix: prune worktrees for all repos
-    for repo in &repos {
-        if let Err(e) = worktree::prune_worktrees(&repo.path) {
-            eprintln!("  Warning: failed to prune worktrees for {}: {}", repo.name, e);
+    println!("\n  {} Applying {} fix(es):", style("→").cyan().bold(), selected.len());
+    for &i in &selected {
+        println!("    - {}", plans[i]);
+    }
+    println!();
+    for &i in &selected {
+        fixes[i].apply(parent_dir, &repos, &mut report, json);
+    }
+
+    println!("\n  {} Cleanup complete.", style("✔").green());
+
+    Ok(())
+}
+
+/// A repair for one detected issue. Each can be described before it runs,
+/// so the user can pick which ones to apply.
+enum Fix {
+    /// `git worktree prune` in the named repos, which have orphaned worktrees
+    PruneWorktrees(Vec<String>),
+    /// Recreate session.json for a dir whose file was quarantined
+    RebuildSession(PathBuf),
+    /// Delete a session dir with no session.json
+    RemoveStaleDir(PathBuf),
+    UpgradeSession { dir: PathBuf, from: u32 },
+    RebuildIndex,
+    RemoveIndex,
+    ReleaseLock { repo: String, session: String },
+    /// Mark dead background processes and adopt pidfile PIDs in a session dir
+    ReconcileProcesses(PathBuf),
+}
+
+impl Fix {
+    fn describe(&self, parent_dir: &Path) -> String {
+        match self {
+            Fix::PruneWorktrees(names) => format!(
+                "Drop worktree records whose directories are gone (`git worktree prune` in {})",
+                names.join(", ")
+            ),
+            Fix::RebuildSession(dir) => format!(
+                "Rebuild {} from the worktrees in {}",
+                dir.join("session.json").display(),
+                dir.display()
+            ),
+            Fix::RemoveStaleDir(path) => {
+                format!("Delete {} and everything in it", path.display())
+            }
+            Fix::UpgradeSession { dir, from } => format!(
+                "Rewrite {} from schema v{} to v{}",
+                dir.join("session.json").display(),
+                from,
+                session::SESSION_SCHEMA_VERSION
+            ),
+            Fix::RebuildIndex => format!(
+                "Rebuild the session index at {}",
+                index::index_path(parent_dir).display()
+            ),
+            Fix::RemoveIndex => format!(
+                "Delete the session index at {}",
+                index::index_path(parent_dir).display()
+            ),
+            Fix::ReleaseLock { repo, session } => format!(
+                "Release the lock on '{}' held by missing session '{}'",
+                repo, session
+            ),
+            Fix::ReconcileProcesses(dir) => format!(
+                "Update {} to match the processes actually running",
+                dir.join("background_pids.json").display()
+            ),
         }
     }
 
-    // Fix: remove stale session dirs
-    if sessions_dir.exists() {
-        if let Ok(entries) = std::fs::read_dir(&sessions_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() && !path.join("session.json").exists() {
-                    if let Err(e) = std::fs::remove_dir_all(&path) {
-                        eprintln!("  Warning: failed to remove {}: {}", path.display(), e);
-                    } else {
-                        println!("  Removed stale dir: {}", path.display());
+    fn apply(&self, parent_dir: &Path, repos: &[discovery::RepoInfo], report: &mut Report, json: bool) {
+        match self {
+            Fix::PruneWorktrees(names) => {
+                for repo in repos.iter().filter(|r| names.contains(&r.name)) {
+                    if let Err(e) = worktree::prune_worktrees(&repo.path) {
+                        report.failed(json, format!("failed to prune worktrees for {}: {}", repo.name, e));
                     }
                 }
             }
+            Fix::RebuildSession(dir) => match rebuild_session(parent_dir, dir) {
+                Ok(info) => report.fixed(json, format!(
+                    "Rebuilt {} ({} repo(s) on '{}')",
+                    dir.join("session.json").display(),
+                    info.repos.len(),
+                    info.branch
+                )),
+                Err(e) => report.failed(json, format!("failed to rebuild {}: {}", dir.display(), e)),
+            },
+            Fix::RemoveStaleDir(path) => {
+                if let Err(e) = session::ensure_managed_path(parent_dir, path) {
+                    report.failed(json, format!("refusing to remove {}: {:#}", path.display(), e));
+                } else if let Err(e) = std::fs::remove_dir_all(path) {
+                    report.failed(json, format!("failed to remove {}: {}", path.display(), e));
+                } else {
+                    report.fixed(json, format!("Removed stale dir: {}", path.display()));
+                }
+            }
+            Fix::UpgradeSession { dir, .. } => match session::upgrade_session_file(dir) {
+                Ok(Some(from)) => report.fixed(json, format!(
+                    "Upgraded {} from schema v{}",
+                    dir.join("session.json").display(),
+                    from
+                )),
+                Ok(None) => {}
+                Err(e) => report.failed(json, format!("failed to upgrade {}: {}", dir.display(), e)),
+            },
+            Fix::RebuildIndex => match index::rebuild(parent_dir) {
+                Ok(n) => report.fixed(json, format!("Rebuilt session index ({} session(s))", n)),
+                Err(e) => report.failed(json, format!("failed to rebuild session index: {}", e)),
+            },
+            Fix::RemoveIndex => match index::remove(parent_dir) {
+                Ok(()) => report.fixed(json, "Removed session index".to_string()),
+                Err(e) => report.failed(json, format!("failed to remove session index: {}", e)),
+            },
+            Fix::ReleaseLock { repo, .. } => {
+                if let Err(e) = lock::release_lock(parent_dir, repo) {
+                    report.failed(json, format!("failed to remove stale lock for {}: {}", repo, e));
+                } else {
+                    report.fixed(json, format!("Removed stale lock: {}", repo));
+                }
+            }
+            Fix::ReconcileProcesses(dir) => {
+                let mut pids = session::load_background_pids(dir);
+                scripts::reconcile_background_pids(dir, &mut pids);
+                match session::save_background_pids(dir, &pids) {
+                    Ok(()) => report.fixed(json, format!(
+                        "Reconciled background processes in {}",
+                        dir.display()
+                    )),
+                    Err(e) => report.failed(json, format!("{:#}", e)),
+                }
+            }
+        }
+    }
+}
+
+fn is_quarantined_session_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with("session.json."))
+}
+
+/// Recreate a minimal session.json from the worktrees left in a session dir,
+/// quarantining a corrupt one first. Issue context, URLs and CI history are
+/// lost; the quarantined file keeps them.
+fn rebuild_session(parent_dir: &Path, dir: &Path) -> Result<SessionInfo> {
+    let existing = dir.join("session.json");
+    if existing.exists() {
+        state::quarantine(&existing)?;
+    }
+    let name = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut found: Vec<(String, PathBuf, PathBuf, Option<String>)> = Vec::new();
+    for entry in std::fs::read_dir(dir)?.flatten() {
+        let wt = entry.path();
+        // Linked worktrees have a `.git` file rather than a directory
+        if !wt.join(".git").is_file() {
+            continue;
         }
+        let Ok(original) = worktree::main_repo_path(&wt) else {
+            continue;
+        };
+        let repo_name = entry.file_name().to_string_lossy().to_string();
+        found.push((repo_name, wt.clone(), original, worktree::current_branch(&wt)));
     }
+    if found.is_empty() {
+        bail!("no worktrees found in {}", dir.display());
+    }
+    found.sort_by(|a, b| a.0.cmp(&b.0));
 
-    // Fix: remove stale locks
-    for repo_name in &stale_locks {
-        if let Err(e) = lock::release_lock(parent_dir, repo_name) {
-            eprintln!("  Warning: failed to remove stale lock for {}: {}", repo_name, e);
-        } else {
-            println!("  Removed stale lock: {}", repo_name);
+    // The session branch is the one most worktrees are on
+    let mut counts: std::collections::BTreeMap<&str, usize> = std::collections::BTreeMap::new();
+    for (_, _, _, branch) in &found {
+        if let Some(b) = branch {
+            *counts.entry(b.as_str()).or_default() += 1;
         }
     }
+    let Some(branch) = counts.iter().max_by_key(|(_, n)| **n).map(|(b, _)| b.to_string()) else {
+        bail!("every worktree in {} is detached; cannot tell the session branch", dir.display());
+    };
 
-    println!("\n  {} Cleanup complete.", style("✔").green());
+    let repos = found
+        .iter()
+        .map(|(repo_name, wt, original, repo_branch)| SessionRepo {
+            name: repo_name.clone(),
+            worktree_path: wt.clone(),
+            original_repo_path: original.clone(),
+            pinned: repo_branch.is_none(),
+            branch: repo_branch.clone().filter(|b| *b != branch),
+            prefixed_branch: None,
+        })
+        .collect();
 
-    Ok(())
+    let info = SessionInfo {
+        version: session::SESSION_SCHEMA_VERSION,
+        name,
+        branch,
+        repos,
+        created_at: chrono::Utc::now(),
+        parent_dir: parent_dir.to_path_buf(),
+        issue: None,
+        base_branch: None,
+        ci_runs: Vec::new(),
+        urls: Default::default(),
+        cleanup: None,
+        archived_at: None,
+        tags: Vec::new(),
+        metadata: Default::default(),
+        pinned_at: None,
+        ports: salvaged_ports(dir).unwrap_or_default(),
+        pull_requests: Default::default(),
+    };
+    session::save_session(dir, &info)?;
+    Ok(info)
+}
+
+/// The `ports` object of the newest quarantined session file, if it survived,
+/// so a rebuilt session keeps the ports its templates and services already use.
+fn salvaged_ports(dir: &Path) -> Option<BTreeMap<String, u16>> {
+    let newest = state::quarantined_files(dir)
+        .into_iter()
+        .filter(|f| is_quarantined_session_file(f))
+        .max()?;
+    let text = std::fs::read_to_string(newest).ok()?;
+    let rest = &text[text.find("\"ports\"")?..];
+    let open = rest.find('{')?;
+    let close = open + rest[open..].find('}')?;
+    serde_json::from_str(&rest[open..=close]).ok()
 }
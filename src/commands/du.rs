@@ -0,0 +1,149 @@
+use std::path::Path;
+
+use anyhow::Result;
+use console::style;
+
+use crate::config::{QuotaAction, SeshConfig};
+use crate::scripts;
+use crate::session::{self, SessionInfo};
+
+use super::pick_session;
+
+const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Print the disk each session directory uses (worktrees, logs and scratch
+/// included), largest first, flagging sessions over `[session] max_disk_gb`.
+/// With `enforce`, over-quota sessions are reported on stderr and, with
+/// `over_quota = "pause"`, their background processes are paused; paused
+/// sessions back under quota are resumed.
+pub fn run(parent_dir: &Path, name: Option<String>, enforce: bool) -> Result<()> {
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+    let sessions: Vec<SessionInfo> = match name {
+        Some(name) => vec![pick_session(parent_dir, Some(name))?],
+        None => session::list_sessions(parent_dir)?,
+    };
+    if sessions.is_empty() {
+        println!("No sessions found.");
+        return Ok(());
+    }
+
+    let quota = config.session.max_disk_gb;
+    let mut usage: Vec<(SessionInfo, u64)> = sessions
+        .into_iter()
+        .map(|s| {
+            let bytes = session::disk_usage(&session::session_dir(parent_dir, &s.name));
+            (s, bytes)
+        })
+        .collect();
+    usage.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+
+    let width = usage.iter().map(|(s, _)| s.name.len()).max().unwrap_or(0);
+    for (s, bytes) in &usage {
+        let over = quota.is_some_and(|q| *bytes as f64 > q * GB);
+        let note = match quota {
+            Some(q) if over => style(format!("  over the {} GB quota", q)).red().to_string(),
+            _ => String::new(),
+        };
+        println!("  {:<width$}  {:>9}{}", style(&s.name).cyan(), format_size(*bytes), note, width = width);
+        if enforce {
+            enforce_quota(parent_dir, &config, &s.name, over);
+        }
+    }
+    if quota.is_none() && enforce {
+        println!("\nNo quota set; add `max_disk_gb` under [session] to enforce one.");
+    }
+    Ok(())
+}
+
+/// Report an over-quota session and pause its background processes if
+/// configured to, or resume them once it's back under.
+fn enforce_quota(parent_dir: &Path, config: &SeshConfig, name: &str, over: bool) {
+    let sess_dir = session::session_dir(parent_dir, name);
+    let mut pids = session::load_background_pids(&sess_dir);
+    let pause = over && config.session.over_quota == QuotaAction::Pause;
+    if over {
+        eprintln!("  {} Session '{}' is over its disk quota", style("!").yellow(), name);
+    }
+    let changed = scripts::set_paused(&mut pids, pause);
+    if changed == 0 {
+        return;
+    }
+    if let Err(e) = session::save_background_pids(&sess_dir, &pids) {
+        eprintln!("  Warning: {}", e);
+    }
+    println!(
+        "  {} {} {} background process(es) of '{}'",
+        style("→").cyan(),
+        if pause { "Paused" } else { "Resumed" },
+        changed,
+        name
+    );
+}
+
+fn format_size(bytes: u64) -> String {
+    let b = bytes as f64;
+    if b >= GB {
+        format!("{:.2} GB", b / GB)
+    } else if b >= 1024.0 * 1024.0 {
+        format!("{:.1} MB", b / (1024.0 * 1024.0))
+    } else if b >= 1024.0 {
+        format!("{:.1} KB", b / 1024.0)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::BackgroundPid;
+
+    #[test]
+    fn sizes_use_the_largest_fitting_unit() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024 / 2), "1.50 GB");
+    }
+
+    #[test]
+    fn enforcing_pauses_over_quota_sessions_and_resumes_them() {
+        let parent = std::env::temp_dir().join(format!("sesh-du-test-{}", std::process::id()));
+        let sess_dir = session::session_dir(&parent, "big");
+        std::fs::create_dir_all(&sess_dir).unwrap();
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let bp = BackgroundPid {
+            pid: child.id(),
+            label: "dev".to_string(),
+            script: "dev".to_string(),
+            process_group: false,
+            started_at: None,
+            dead: false,
+            paused: false,
+            health: None,
+        };
+        session::save_background_pids(&sess_dir, &[bp]).unwrap();
+        let state = || {
+            let stat = std::fs::read_to_string(format!("/proc/{}/stat", child.id())).unwrap();
+            stat.rsplit(") ").next().unwrap().chars().next().unwrap()
+        };
+
+        // "warn" only reports
+        let mut config = SeshConfig::default();
+        enforce_quota(&parent, &config, "big", true);
+        assert!(!session::load_background_pids(&sess_dir)[0].paused);
+
+        config.session.over_quota = QuotaAction::Pause;
+        enforce_quota(&parent, &config, "big", true);
+        assert!(session::load_background_pids(&sess_dir)[0].paused);
+        assert_eq!(state(), 'T');
+
+        enforce_quota(&parent, &config, "big", false);
+        assert!(!session::load_background_pids(&sess_dir)[0].paused);
+        assert_ne!(state(), 'T');
+
+        let _ = child.kill();
+        let _ = child.wait();
+        let _ = std::fs::remove_dir_all(&parent);
+    }
+}
@@ -1,17 +1,35 @@
 use std::path::Path;
-use std::process::Command;
 
 use anyhow::{bail, Result};
 use console::style;
+use tokio::process::Command;
+
+use crate::hooks;
+use crate::parallel;
+use crate::session::{self, SessionInfo, SessionRepo};
 
 use super::pick_session;
 
-pub fn run(parent_dir: &Path, session_name: Option<String>, command: &str) -> Result<()> {
+pub async fn run(
+    parent_dir: &Path,
+    session_name: Option<String>,
+    only_repos: &[String],
+    sequential: bool,
+    fail_fast: bool,
+    command: &str,
+) -> Result<()> {
     let info = pick_session(parent_dir, session_name)?;
 
+    for name in only_repos {
+        if !info.repos.iter().any(|r| &r.name == name) {
+            bail!("repo '{}' is not part of session '{}'", name, info.name);
+        }
+    }
+
     let repos: Vec<_> = info
         .repos
         .iter()
+        .filter(|r| only_repos.is_empty() || only_repos.contains(&r.name))
         .filter(|r| r.worktree_path.exists())
         .collect();
 
@@ -19,30 +37,35 @@ pub fn run(parent_dir: &Path, session_name: Option<String>, command: &str) -> Re
         bail!("no worktrees found on disk for session '{}'", info.name);
     }
 
-    // Spawn all commands in parallel
-    let handles: Vec<_> = repos
+    // Sessions from before scratch directories get one on first use
+    let scratch = session::ensure_scratch_dir(&session::session_dir(parent_dir, &info.name))?;
+    if sequential {
+        return run_sequential(&info, &repos, fail_fast, command, &scratch).await;
+    }
+
+    // Run in parallel (bounded), buffering each repo's output
+    let jobs = repos
         .iter()
         .map(|repo| {
-            let name = repo.name.clone();
-            let cwd = repo.worktree_path.clone();
-            let cmd = command.to_string();
-
-            std::thread::spawn(move || {
-                let output = Command::new("sh")
-                    .args(["-c", &cmd])
-                    .current_dir(&cwd)
-                    .output();
-
-                (name, output)
-            })
+            let cmd = render_command(command, &info, repo);
+            (repo.name.clone(), repo.worktree_path.clone(), cmd, scratch.clone())
         })
         .collect();
-
-    // Collect results and print sequentially
+    let results = parallel::map_bounded(jobs, |(name, cwd, cmd, scratch)| async move {
+        let output = Command::new("sh")
+            .args(["-c", &cmd])
+            .current_dir(&cwd)
+            .env("SESH_SCRATCH", &scratch)
+            .output()
+            .await;
+        (name, output)
+    })
+    .await;
+
+    // Print results in session order
     let mut any_failed = false;
 
-    for handle in handles {
-        let (name, result) = handle.join().expect("thread panicked");
+    for (name, result) in results {
         match result {
             Ok(output) => {
                 println!("{}", style(format!("── {} ──", name)).cyan().bold());
@@ -83,3 +106,107 @@ pub fn run(parent_dir: &Path, session_name: Option<String>, command: &str) -> Re
 
     Ok(())
 }
+
+/// Run in one repo at a time with inherited stdio, so output streams as it is
+/// produced instead of being buffered per repo.
+async fn run_sequential(
+    info: &SessionInfo,
+    repos: &[&SessionRepo],
+    fail_fast: bool,
+    command: &str,
+    scratch: &Path,
+) -> Result<()> {
+    let mut failed = Vec::new();
+
+    for (i, repo) in repos.iter().enumerate() {
+        println!("{}", style(format!("── {} ──", repo.name)).cyan().bold());
+        let status = Command::new("sh")
+            .args(["-c", &render_command(command, info, repo)])
+            .current_dir(&repo.worktree_path)
+            .env("SESH_SCRATCH", scratch)
+            .status()
+            .await;
+
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                println!("{} exited with {}", style(&repo.name).red(), status);
+                failed.push(repo.name.clone());
+            }
+            Err(e) => {
+                println!("{} failed to execute: {}", style(&repo.name).red(), e);
+                failed.push(repo.name.clone());
+            }
+        }
+        println!();
+
+        if fail_fast && !failed.is_empty() {
+            let skipped: Vec<&str> = repos[i + 1..].iter().map(|r| r.name.as_str()).collect();
+            if !skipped.is_empty() {
+                println!("{} {}", style("Skipped (--fail-fast):").yellow(), skipped.join(", "));
+            }
+            break;
+        }
+    }
+
+    if !failed.is_empty() {
+        bail!("command failed in: {}", failed.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Substitute `{repo}`, `{worktree}`, `{branch}` and `{session}` for `repo`,
+/// each shell-quoted. Anything else in braces is left for the shell.
+fn render_command(command: &str, info: &SessionInfo, repo: &SessionRepo) -> String {
+    let worktree = repo.worktree_path.to_string_lossy();
+    fill_placeholders(
+        command,
+        &[
+            ("repo", &repo.name),
+            ("worktree", &worktree),
+            ("branch", info.branch_for(repo)),
+            ("session", &info.name),
+        ],
+    )
+}
+
+/// Replace each `{name}` in one pass, so values containing braces are never
+/// substituted into again.
+fn fill_placeholders(command: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(command.len());
+    let mut rest = command;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start..];
+        let found = values.iter().find(|(name, _)| {
+            after[1..].strip_prefix(name).is_some_and(|tail| tail.starts_with('}'))
+        });
+        match found {
+            Some((name, value)) => {
+                out.push_str(&hooks::shell_quote(value));
+                rest = &after[name.len() + 2..];
+            }
+            None => {
+                out.push('{');
+                rest = &after[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_quoted_once() {
+        let values = [("repo", "api"), ("branch", "x; rm -rf ~"), ("worktree", "/tmp/my {repo}")];
+        assert_eq!(
+            fill_placeholders("cd {worktree} && git push origin {branch} # {repo} ${HOME} {other}", &values),
+            "cd '/tmp/my {repo}' && git push origin 'x; rm -rf ~' # 'api' ${HOME} {other}"
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use console::style;
+
+use crate::config::SeshConfig;
+use crate::guard;
+use crate::session;
+use crate::worktree;
+
+use super::{pick_session, repo_base};
+
+/// Fail if the session's changes touch any `[guard] protected` path.
+pub fn check(
+    parent_dir: &Path,
+    session_name: Option<String>,
+    only_repo: Option<String>,
+    staged: bool,
+) -> Result<()> {
+    let config_path = parent_dir.join("sesh.toml");
+    let config = SeshConfig::load(&config_path)?;
+    if config.guard.protected.is_empty() {
+        println!("No protected paths configured.");
+        return Ok(());
+    }
+    let matcher = guard::build_matcher(&config.guard.protected)?;
+
+    let info = pick_session(parent_dir, session_name)?;
+    let mut total = 0;
+
+    for repo in &info.repos {
+        if only_repo.as_ref().is_some_and(|r| r != &repo.name) {
+            continue;
+        }
+        if repo.pinned || !repo.worktree_path.exists() {
+            continue;
+        }
+
+        let base_ref = format!("{}/{}", config.remote(&repo.name), repo_base(&config, &info, &repo.name));
+        let files = worktree::changed_files(&repo.worktree_path, &base_ref, staged)?;
+        let hits = guard::violations(&matcher, &files);
+        if hits.is_empty() {
+            continue;
+        }
+
+        eprintln!(
+            "{} {} touches protected paths:",
+            style("✗").red(),
+            style(&repo.name).cyan()
+        );
+        for file in &hits {
+            eprintln!("    {}", file);
+        }
+        total += hits.len();
+    }
+
+    if total > 0 {
+        bail!("{} protected file(s) modified", total);
+    }
+
+    println!("{} No protected paths touched.", style("✓").green());
+    Ok(())
+}
+
+/// Install the guard pre-commit hook in each of the session's worktrees.
+pub fn install(parent_dir: &Path, session_name: Option<String>) -> Result<()> {
+    let info = pick_session(parent_dir, session_name)?;
+    let sess_dir = session::session_dir(parent_dir, &info.name);
+
+    for repo in info.repos.iter().filter(|r| !r.pinned && r.worktree_path.exists()) {
+        guard::install_guard_hook(
+            parent_dir,
+            &sess_dir,
+            &info.name,
+            &repo.name,
+            &repo.original_repo_path,
+            &repo.worktree_path,
+        )?;
+        println!("  {} Guard hook installed: {}", style("✓").green(), repo.name);
+    }
+
+    Ok(())
+}
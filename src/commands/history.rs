@@ -0,0 +1,42 @@
+use std::path::Path;
+
+use anyhow::Result;
+use console::style;
+
+use crate::history::{self, Event};
+use crate::session;
+
+use super::pick_session;
+
+pub fn run(parent_dir: &Path, name: Option<String>) -> Result<()> {
+    // Stopped sessions keep their history, so an explicit name needn't exist
+    let name = match name {
+        Some(n) if !session::session_exists(parent_dir, &n) => n,
+        other => pick_session(parent_dir, other)?.name,
+    };
+
+    let (entries, skipped) = history::load(parent_dir, &name)?;
+    println!("History for session '{}':\n", style(&name).cyan());
+    for entry in &entries {
+        let line = entry.describe();
+        let line = match &entry.event {
+            Event::Script { outcome, .. } if outcome.starts_with("failed") => style(line).red(),
+            Event::Created { .. } | Event::Stopped => style(line).bold(),
+            _ => style(line),
+        };
+        println!(
+            "  {}  {:<10} {}",
+            style(entry.at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S")).dim(),
+            entry.user.as_deref().unwrap_or("-"),
+            line
+        );
+    }
+    if skipped > 0 {
+        println!(
+            "\n  {} {} unreadable line(s) skipped",
+            style("!").yellow(),
+            skipped
+        );
+    }
+    Ok(())
+}
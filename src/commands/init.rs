@@ -1,12 +1,83 @@
+use std::collections::BTreeMap;
 use std::path::Path;
+use std::process::Command;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use console::style;
 use dialoguer::{Confirm, Input, MultiSelect};
 
+use crate::config::{McpServer, McpTransport, LOCAL_CONFIG_FILE};
 use crate::discovery;
+use crate::remote_config;
 
-pub fn run(parent_dir: &Path) -> Result<()> {
+const COMPOSE_FILES: &[&str] = &["compose.yaml", "compose.yml", "docker-compose.yaml", "docker-compose.yml"];
+const ENV_EXAMPLES: &[&str] = &[".env.example", ".env.sample", ".env.template"];
+const MCP_FILES: &[&str] = &[".mcp.json", ".cursor/mcp.json"];
+/// Makefile targets that install or prepare things, and ones that start a
+/// dev server, in order of preference.
+const MAKE_SETUP: &[&str] = &["setup", "bootstrap", "install", "deps"];
+const MAKE_DEV: &[&str] = &["dev", "serve", "start", "run"];
+
+/// An entry `sesh init` proposes for a repo from what's in its checkout.
+enum Suggestion {
+    Copy(String),
+    Symlink(String),
+    Setup { command: String, background: bool },
+    Teardown(String),
+    Mcp(McpServer),
+}
+
+struct Found {
+    repo: String,
+    suggestion: Suggestion,
+    /// What it was derived from, shown in the picker.
+    source: String,
+}
+
+impl Found {
+    fn label(&self) -> String {
+        let what = match &self.suggestion {
+            Suggestion::Copy(file) => format!("copy {}", file),
+            Suggestion::Symlink(file) => format!("symlink {}", file),
+            Suggestion::Setup { command, background: true } => format!("background setup `{}`", command),
+            Suggestion::Setup { command, .. } => format!("setup `{}`", command),
+            Suggestion::Teardown(command) => format!("teardown `{}`", command),
+            Suggestion::Mcp(server) => format!("MCP server {}", server.name),
+        };
+        format!("{}: {} (from {})", self.repo, what, self.source)
+    }
+}
+
+/// Create sesh.toml from a team-shared config and remember its source.
+pub async fn from_url(parent_dir: &Path, source: &str) -> Result<()> {
+    let config_path = parent_dir.join("sesh.toml");
+
+    if config_path.exists() {
+        let overwrite = Confirm::new()
+            .with_prompt("sesh.toml already exists. Overwrite?")
+            .default(false)
+            .interact()?;
+        if !overwrite {
+            println!("Aborted.");
+            return Ok(());
+        }
+    }
+
+    println!("  {} Fetching {}...", style("↓").dim(), source);
+    let contents = remote_config::fetch(parent_dir, source, None, None).await?;
+    let contents = remote_config::record_source(&contents, source)?;
+    std::fs::write(&config_path, &contents)?;
+
+    println!(
+        "{} Wrote {} from {}. Run `sesh config update` to pull changes; put personal tweaks in sesh.local.toml.",
+        style("✓").green(),
+        config_path.display(),
+        source
+    );
+    ignore_local_config(parent_dir)
+}
+
+pub async fn run(parent_dir: &Path) -> Result<()> {
     let config_path = parent_dir.join("sesh.toml");
 
     if config_path.exists() {
@@ -21,7 +92,7 @@ pub fn run(parent_dir: &Path) -> Result<()> {
     }
 
     // Discover repos
-    let repos = discovery::discover_repos(parent_dir)?;
+    let repos = discovery::discover_repos(parent_dir).await?;
     if repos.is_empty() {
         println!("No git repos found in {}", parent_dir.display());
         return Ok(());
@@ -46,8 +117,26 @@ pub fn run(parent_dir: &Path) -> Result<()> {
         .items(&mcp_options)
         .interact()?;
 
+    // Offer what the repos already have: env files, compose, make targets, MCP configs
+    let found: Vec<Found> = repos.iter().flat_map(|r| scan_repo(&r.name, &r.path)).collect();
+    let found: Vec<Found> = if found.is_empty() {
+        found
+    } else {
+        let labels: Vec<String> = found.iter().map(Found::label).collect();
+        let picked = MultiSelect::new()
+            .with_prompt("Found in your repos; include (space to toggle, enter to confirm)")
+            .items(&labels)
+            .defaults(&vec![true; labels.len()])
+            .interact()?;
+        found.into_iter().enumerate().filter(|(i, _)| picked.contains(i)).map(|(_, f)| f).collect()
+    };
+
     // Build TOML content
     let mut toml = String::new();
+    toml.push_str(&format!(
+        "# Personal overrides go in {} (layered on top, keep it out of git)\n\n",
+        LOCAL_CONFIG_FILE
+    ));
 
     // [session]
     toml.push_str("[session]\n");
@@ -80,13 +169,53 @@ pub fn run(parent_dir: &Path) -> Result<()> {
         }
         toml.push('\n');
     }
+    let mut mcp_names: Vec<&str> = mcp_selected.iter().map(|&i| mcp_options[i]).collect();
+    for f in &found {
+        if let Suggestion::Mcp(server) = &f.suggestion
+            && !mcp_names.contains(&server.name.as_str())
+        {
+            mcp_names.push(&server.name);
+            toml.push_str(&mcp_server_toml(server));
+        }
+    }
 
     // [repos.*]
     for repo in &repos {
-        toml.push_str(&format!("[repos.{}]\n", repo.name));
-        toml.push_str("copy = []\n");
-        toml.push_str("symlink = []\n");
+        let key = crate::config::toml_key(&repo.name);
+        let mine: Vec<&Suggestion> = found.iter().filter(|f| f.repo == repo.name).map(|f| &f.suggestion).collect();
+        let files = |symlink: bool| -> Vec<String> {
+            mine.iter()
+                .filter_map(|s| match s {
+                    Suggestion::Copy(f) if !symlink => Some(f.clone()),
+                    Suggestion::Symlink(f) if symlink => Some(f.clone()),
+                    _ => None,
+                })
+                .collect()
+        };
+        toml.push_str(&format!("[repos.{}]\n", key));
+        toml.push_str(&format!("copy = {}\n", toml_strings(&files(false))));
+        toml.push_str(&format!("symlink = {}\n", toml_strings(&files(true))));
         toml.push('\n');
+        // Setup entries first, then teardown, whatever order they were found in
+        let (setup, teardown): (Vec<&Suggestion>, Vec<&Suggestion>) =
+            mine.iter().partition(|s| !matches!(s, Suggestion::Teardown(_)));
+        for s in setup.into_iter().chain(teardown) {
+            match s {
+                Suggestion::Setup { command, background } => {
+                    toml.push_str(&format!("[[repos.{}.setup]]\n", key));
+                    toml.push_str(&format!("command = {}\n", toml_string(command)));
+                    if *background {
+                        toml.push_str("background = true\n");
+                    }
+                    toml.push('\n');
+                }
+                Suggestion::Teardown(command) => {
+                    toml.push_str(&format!("[[repos.{}.teardown]]\n", key));
+                    toml.push_str(&format!("command = {}\n\n", toml_string(command)));
+                }
+                _ => {}
+            }
+        }
     }
 
     std::fs::write(&config_path, &toml)?;
@@ -96,5 +225,210 @@ pub fn run(parent_dir: &Path) -> Result<()> {
         config_path.display(),
     );
 
+    ignore_local_config(parent_dir)
+}
+
+/// Look through a repo's checkout for things sesh.toml usually needs: local
+/// env files to copy, an untracked `.cursor/` to share, compose and make
+/// targets to run as setup, and MCP servers configured for other clients.
+fn scan_repo(repo: &str, path: &Path) -> Vec<Found> {
+    let mut found = Vec::new();
+    let mut add = |suggestion: Suggestion, source: &str| {
+        found.push(Found { repo: repo.to_string(), suggestion, source: source.to_string() });
+    };
+
+    if let Some(example) = ENV_EXAMPLES.iter().find(|f| path.join(f).is_file()) {
+        if path.join(".env").is_file() {
+            add(Suggestion::Copy(".env".to_string()), example);
+        } else {
+            let command = format!("cp -n {} .env", example);
+            add(Suggestion::Setup { command, background: false }, example);
+        }
+    } else if path.join(".env").is_file() && !is_tracked(path, ".env") {
+        add(Suggestion::Copy(".env".to_string()), ".env");
+    }
+    if path.join(".cursor").is_dir() && !is_tracked(path, ".cursor") {
+        add(Suggestion::Symlink(".cursor".to_string()), ".cursor/");
+    }
+
+    if let Some(compose) = COMPOSE_FILES.iter().find(|f| path.join(f).is_file()) {
+        // One compose project per session and repo, so sessions don't share containers
+        let project = r#"docker compose -p "$SESH_SESSION-$SESH_REPO""#;
+        add(Suggestion::Setup { command: format!("{} up -d", project), background: false }, compose);
+        add(Suggestion::Teardown(format!("{} down", project)), compose);
+    }
+
+    if let Ok(makefile) = std::fs::read_to_string(path.join("Makefile")) {
+        let targets = make_targets(&makefile);
+        for (wanted, background) in [(MAKE_SETUP, false), (MAKE_DEV, true)] {
+            if let Some(target) = wanted.iter().find(|t| targets.iter().any(|have| have == *t)) {
+                add(Suggestion::Setup { command: format!("make {}", target), background }, "Makefile");
+            }
+        }
+    }
+
+    for file in MCP_FILES {
+        let Ok(raw) = std::fs::read_to_string(path.join(file)) else {
+            continue;
+        };
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(&raw) else {
+            continue;
+        };
+        let Some(servers) = json.get("mcpServers").and_then(|s| s.as_object()) else {
+            continue;
+        };
+        for (name, server) in servers {
+            if let Some(server) = mcp_server(name, server) {
+                add(Suggestion::Mcp(server), file);
+            }
+        }
+    }
+    found
+}
+
+/// Targets defined in a Makefile, skipping special (`.PHONY`) targets and
+/// variable assignments.
+fn make_targets(makefile: &str) -> Vec<String> {
+    makefile
+        .lines()
+        .filter(|line| !line.starts_with([' ', '\t', '#', '.']))
+        .filter_map(|line| {
+            let (names, rest) = line.split_once(':')?;
+            if rest.starts_with('=') || names.contains('=') {
+                return None;
+            }
+            Some(names.split_whitespace().map(String::from).collect::<Vec<_>>())
+        })
+        .flatten()
+        .collect()
+}
+
+/// An `mcpServers` entry from a `.mcp.json`-style file, as sesh config.
+fn mcp_server(name: &str, server: &serde_json::Value) -> Option<McpServer> {
+    let text = |key: &str| server.get(key).and_then(|v| v.as_str()).map(String::from);
+    let transport = match (text("url"), text("command")) {
+        (Some(url), _) if text("type").as_deref() == Some("sse") => McpTransport::Sse { url },
+        (Some(url), _) => McpTransport::Http { url },
+        (None, Some(command)) => McpTransport::Stdio {
+            command,
+            args: server
+                .get("args")
+                .and_then(|a| serde_json::from_value(a.clone()).ok())
+                .unwrap_or_default(),
+            env: server
+                .get("env")
+                .and_then(|e| serde_json::from_value::<BTreeMap<String, String>>(e.clone()).ok())
+                .unwrap_or_default(),
+        },
+        (None, None) => return None,
+    };
+    Some(McpServer { name: name.to_string(), transport })
+}
+
+fn mcp_server_toml(server: &McpServer) -> String {
+    let mut toml = format!("[[mcp.servers]]\nname = {}\n", toml_string(&server.name));
+    match &server.transport {
+        McpTransport::Http { url } => toml.push_str(&format!("type = \"http\"\nurl = {}\n", toml_string(url))),
+        McpTransport::Sse { url } => toml.push_str(&format!("type = \"sse\"\nurl = {}\n", toml_string(url))),
+        McpTransport::Stdio { command, args, env } => {
+            toml.push_str(&format!("type = \"stdio\"\ncommand = {}\n", toml_string(command)));
+            if !args.is_empty() {
+                toml.push_str(&format!("args = {}\n", toml_strings(args)));
+            }
+            if !env.is_empty() {
+                let table: toml::Table = env.iter().map(|(k, v)| (k.clone(), toml::Value::String(v.clone()))).collect();
+                toml.push_str(&format!("env = {}\n", toml::Value::Table(table)));
+            }
+        }
+    }
+    toml.push('\n');
+    toml
+}
+
+fn toml_string(s: &str) -> String {
+    toml::Value::String(s.to_string()).to_string()
+}
+
+fn toml_strings(items: &[String]) -> String {
+    toml::Value::Array(items.iter().cloned().map(toml::Value::String).collect()).to_string()
+}
+
+/// Whether `file` is tracked in the repo at `path`.
+fn is_tracked(path: &Path, file: &str) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(["ls-files", "--error-unmatch", file])
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+/// When the workspace is itself a git checkout (say, a repo holding the team
+/// config), add sesh.local.toml to its .gitignore so overrides stay personal.
+fn ignore_local_config(parent_dir: &Path) -> Result<()> {
+    if !parent_dir.join(".git").exists() {
+        return Ok(());
+    }
+    let ignored = Command::new("git")
+        .arg("-C")
+        .arg(parent_dir)
+        .args(["check-ignore", "-q", LOCAL_CONFIG_FILE])
+        .status()
+        .is_ok_and(|s| s.success());
+    if ignored {
+        return Ok(());
+    }
+
+    let gitignore = parent_dir.join(".gitignore");
+    let mut contents = std::fs::read_to_string(&gitignore).unwrap_or_default();
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    contents.push_str(LOCAL_CONFIG_FILE);
+    contents.push('\n');
+    std::fs::write(&gitignore, contents)
+        .with_context(|| format!("failed to write {}", gitignore.display()))?;
+    println!("{} Added {} to .gitignore", style("✔").green(), LOCAL_CONFIG_FILE);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn make_targets_skips_special_targets_and_assignments() {
+        let makefile = ".PHONY: dev setup\nCC := gcc\nVERSION=1\nsetup install: deps\n\tnpm ci\n# dev: commented\ndev:\n\tnpm run dev\n";
+        assert_eq!(make_targets(makefile), vec!["setup", "install", "dev"]);
+    }
+
+    #[test]
+    fn scan_repo_picks_up_every_mcp_transport() {
+        let path = std::env::temp_dir().join(format!("sesh-init-test-{}", std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        let mcp = r#"{"mcpServers": {
+            "bare": {"command": "my-mcp"},
+            "local": {"command": "npx", "args": ["-y", "server"], "env": {"DEBUG": "1"}},
+            "remote": {"type": "http", "url": "https://mcp.example.com/mcp"},
+            "events": {"type": "sse", "url": "https://mcp.example.com/sse"},
+            "broken": {"args": ["nothing to run"]}
+        }}"#;
+        std::fs::write(path.join(".mcp.json"), mcp).unwrap();
+
+        let servers: Vec<String> = scan_repo("api", &path)
+            .into_iter()
+            .filter_map(|f| match f.suggestion {
+                Suggestion::Mcp(server) => Some(mcp_server_toml(&server)),
+                _ => None,
+            })
+            .collect();
+        let _ = std::fs::remove_dir_all(&path);
+
+        assert_eq!(servers.len(), 4);
+        let server = |name: &str| servers.iter().find(|s| s.contains(&format!("name = \"{}\"", name))).unwrap();
+        assert_eq!(server("bare"), "[[mcp.servers]]\nname = \"bare\"\ntype = \"stdio\"\ncommand = \"my-mcp\"\n\n");
+        assert!(server("local").contains("args = [\"-y\", \"server\"]\nenv = { DEBUG = \"1\" }\n"));
+        assert!(server("remote").contains("type = \"http\"\nurl = \"https://mcp.example.com/mcp\""));
+        assert!(server("events").contains("type = \"sse\"\nurl = \"https://mcp.example.com/sse\""));
+    }
+}
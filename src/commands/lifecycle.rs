@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use console::style;
+
+use crate::config::SeshConfig;
+use crate::lock;
+use crate::scripts;
+use crate::session;
+
+use super::{pick_session, run_setup, stop};
+
+/// Re-run a session's setup scripts without touching its worktrees, e.g. after
+/// a failed step or `--no-setup`. Background processes from an earlier run are
+/// stopped first so they aren't started twice.
+pub fn setup(parent_dir: &Path, name: Option<String>) -> Result<()> {
+    let info = pick_session(parent_dir, name)?;
+    if info.archived_at.is_some() {
+        bail!("session '{}' is archived; `sesh activate {}` brings it back", info.name, info.name);
+    }
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+    let sess_dir = session::session_dir(parent_dir, &info.name);
+
+    let mut pids = session::load_background_pids(&sess_dir);
+    scripts::reconcile_background_pids(&sess_dir, &mut pids);
+    let running = pids.iter().filter(|bp| !bp.dead).count();
+    if running > 0 {
+        println!("Stopping {} background process(es) from the last setup...", running);
+        scripts::kill_background_pids(&pids);
+    }
+    session::save_background_pids(&sess_dir, &[])?;
+
+    // Exclusive repos held by another session keep their services there
+    let mut exclusive_skipped = Vec::new();
+    for repo in &info.repos {
+        if !config.repos.get(&repo.name).is_some_and(|rc| rc.exclusive) {
+            continue;
+        }
+        if let Some(held) = lock::check_lock(parent_dir, &repo.name)?
+            && held.session != info.name
+            && session::session_exists(parent_dir, &held.session)
+        {
+            println!(
+                "  {} Exclusive repo '{}' is locked by session '{}' — skipping services",
+                style("!").yellow(),
+                repo.name,
+                held.session
+            );
+            exclusive_skipped.push(repo.name.clone());
+        }
+    }
+
+    session::ensure_scratch_dir(&sess_dir)?;
+    run_setup(parent_dir, &config, &info, &exclusive_skipped)?;
+    println!("{} Setup finished for '{}'.", style("✔").green(), info.name);
+    Ok(())
+}
+
+/// Stop a session's background processes and run its teardown scripts, as
+/// `sesh stop` does, but keep its worktrees, branches and locks.
+pub async fn teardown(parent_dir: &Path, name: Option<String>) -> Result<()> {
+    let info = pick_session(parent_dir, name)?;
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+    let sess_dir = session::session_dir(parent_dir, &info.name);
+    stop::stop_services(parent_dir, &config, &info, &sess_dir).await;
+    println!(
+        "{} Teardown finished for '{}'; `sesh setup {}` starts it again.",
+        style("✔").green(),
+        info.name,
+        info.name
+    );
+    Ok(())
+}
@@ -1,24 +1,80 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use console::style;
 
-use crate::session;
+use crate::parallel;
+use crate::registry;
+use crate::session::{self, SessionInfo};
 
-pub fn run(parent_dir: &Path, active: bool) -> Result<()> {
+pub async fn run(parent_dir: &Path, active: bool, tags: Vec<String>, long: bool, global: bool) -> Result<()> {
+    if !global {
+        let sessions = load(parent_dir, active, &tags).await?;
+        if sessions.is_empty() {
+            println!("No sessions found.");
+        } else {
+            print_table(&sessions, long);
+        }
+        return Ok(());
+    }
+
+    let mut dirs = registry::parent_dirs();
+    let here: PathBuf = parent_dir.canonicalize().unwrap_or_else(|_| parent_dir.to_path_buf());
+    if !dirs.contains(&here) && here.join(".sesh/sessions").is_dir() {
+        dirs.push(here);
+    }
+    let mut any = false;
+    for dir in dirs {
+        let sessions = match load(&dir, active, &tags).await {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                eprintln!("  {} {}: {:#}", style("Skipping").yellow(), dir.display(), e);
+                continue;
+            }
+        };
+        if sessions.is_empty() {
+            continue;
+        }
+        if any {
+            println!();
+        }
+        any = true;
+        println!("{}", style(dir.display()).bold());
+        print_table(&sessions, long);
+    }
+    if !any {
+        println!("No sessions found.");
+    }
+    Ok(())
+}
+
+/// Sessions in `parent_dir`, narrowed by `--active` and `--tag`.
+async fn load(parent_dir: &Path, active: bool, tags: &[String]) -> Result<Vec<SessionInfo>> {
     let mut sessions = session::list_sessions(parent_dir)?;
+    sessions.retain(|s| tags.iter().all(|t| s.tags.contains(t)));
 
     if active {
-        sessions.retain(|s| {
-            s.repos.iter().any(|r| r.worktree_path.exists())
-        });
+        let worktrees = sessions
+            .iter()
+            .map(|s| s.repos.iter().map(|r| r.worktree_path.clone()).collect::<Vec<_>>())
+            .collect();
+        let mut on_disk = parallel::map_bounded(worktrees, |paths| async move {
+            for path in paths {
+                if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+                    return true;
+                }
+            }
+            false
+        })
+        .await
+        .into_iter();
+        sessions.retain(|_| on_disk.next().unwrap_or(false));
     }
 
-    if sessions.is_empty() {
-        println!("No sessions found.");
-        return Ok(());
-    }
+    Ok(sessions)
+}
 
+fn print_table(sessions: &[SessionInfo], long: bool) {
     // Print table header
     println!(
         "{:<20} {:<25} {:<6} {}",
@@ -28,16 +84,46 @@ pub fn run(parent_dir: &Path, active: bool) -> Result<()> {
         style("Created").bold().underlined(),
     );
 
-    for session in &sessions {
+    for session in sessions {
         let created = session.created_at.format("%Y-%m-%d %H:%M");
+        let cleanup = match &session.cleanup {
+            Some(mark) => format!("  {}", style(format!("cleanup: {}", mark.reason)).yellow()),
+            None => String::new(),
+        };
+        let archived = if session.archived_at.is_some() {
+            format!("  {}", style("archived").dim())
+        } else {
+            String::new()
+        };
+        let pinned = if session.pinned_at.is_some() {
+            format!("  {}", style("pinned").cyan())
+        } else {
+            String::new()
+        };
         println!(
-            "{:<20} {:<25} {:<6} {}",
+            "{:<20} {:<25} {:<6} {}{}{}{}",
             session.name,
             session.branch,
             session.repos.len(),
             created,
+            cleanup,
+            archived,
+            pinned,
         );
+        if long {
+            print_details(session);
+        }
     }
+}
 
-    Ok(())
+/// The `--long` lines under a session's row.
+fn print_details(session: &SessionInfo) {
+    let repos: Vec<&str> = session.repos.iter().map(|r| r.name.as_str()).collect();
+    println!("  {} {}", style("repos:").dim(), repos.join(", "));
+    if !session.tags.is_empty() {
+        println!("  {} {}", style("tags:").dim(), session.tags.join(", "));
+    }
+    for (key, value) in &session.metadata {
+        println!("  {} {}", style(format!("{}:", key)).dim(), value);
+    }
 }
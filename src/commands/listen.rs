@@ -0,0 +1,85 @@
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use console::style;
+
+use crate::config::SeshConfig;
+use crate::integrations;
+use crate::notify;
+use crate::session::{self, CleanupMark};
+
+pub async fn run(parent_dir: &Path, linear: bool, interval: u64, once: bool) -> Result<()> {
+    if !linear {
+        bail!("specify what to listen to (currently only --linear is supported)");
+    }
+
+    if !once {
+        println!(
+            "{} Watching Linear tickets every {}s (Ctrl-C to stop)",
+            style("→").cyan().bold(),
+            interval
+        );
+    }
+
+    loop {
+        poll_linear(parent_dir).await?;
+        if once {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+/// Check each session's Linear ticket once, marking and announcing sessions
+/// whose ticket was closed or moved off the current user.
+async fn poll_linear(parent_dir: &Path) -> Result<()> {
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+
+    for mut info in session::list_sessions(parent_dir)? {
+        if info.cleanup.is_some() {
+            continue;
+        }
+        let Some(issue) = info.issue.as_ref().filter(|i| i.provider == "linear") else {
+            continue;
+        };
+
+        let (status, viewer_id) = match integrations::linear_issue_status(parent_dir, &issue.identifier).await {
+            Ok(r) => r,
+            Err(e) => {
+                eprintln!("  {} {}: {}", style("!").yellow(), issue.identifier, e);
+                continue;
+            }
+        };
+        let Some(status) = status else {
+            continue;
+        };
+
+        let reason = match status.state_type.as_deref() {
+            Some(state @ ("completed" | "canceled")) => format!("{} is {}", issue.identifier, state),
+            _ if status.assignee_id.is_none() => format!("{} was unassigned", issue.identifier),
+            _ if viewer_id.is_some() && status.assignee_id != viewer_id => {
+                format!("{} was reassigned", issue.identifier)
+            }
+            _ => continue,
+        };
+
+        let message = format!(
+            "sesh: session '{}' can be cleaned up ({}). Run `sesh stop {}`.",
+            info.name, reason, info.name
+        );
+        info.cleanup = Some(CleanupMark {
+            reason,
+            marked_at: Utc::now(),
+        });
+        session::save_session(&session::session_dir(parent_dir, &info.name), &info)?;
+
+        println!("  {} {}", style("⚑").yellow(), message);
+        if let Err(e) = notify::send(&config.notify, &message).await {
+            eprintln!("  {} {}", style("Notification failed:").red(), e);
+        }
+    }
+
+    Ok(())
+}
@@ -1,18 +1,60 @@
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
 
-use anyhow::{bail, Result};
-use console::style;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use console::{style, Color};
+use notify::{RecursiveMode, Watcher};
+use regex::Regex;
 
 use crate::session;
 
 use super::pick_session;
 
+/// Line filters for `sesh log` (`--grep`, `--since`).
+#[derive(Default)]
+pub struct LogFilter {
+    pub grep: Option<String>,
+    pub since: Option<String>,
+}
+
+struct CompiledFilter {
+    pattern: Option<Regex>,
+    cutoff: Option<DateTime<Utc>>,
+}
+
+impl LogFilter {
+    fn is_empty(&self) -> bool {
+        self.grep.is_none() && self.since.is_none()
+    }
+
+    fn compile(&self) -> Result<CompiledFilter> {
+        let pattern = self
+            .grep
+            .as_deref()
+            .map(|g| Regex::new(g).with_context(|| format!("invalid --grep pattern: {}", g)))
+            .transpose()?;
+        let cutoff = self
+            .since
+            .as_deref()
+            .map(|s| parse_duration(s).map(|d| Utc::now() - d))
+            .transpose()?;
+        Ok(CompiledFilter { pattern, cutoff })
+    }
+}
+
 pub fn run(
     parent_dir: &Path,
     session_name: Option<String>,
     script: Option<String>,
     follow: bool,
+    all: bool,
+    filter: LogFilter,
+    export: Option<PathBuf>,
 ) -> Result<()> {
     let info = pick_session(parent_dir, session_name)?;
     let sess_dir = session::session_dir(parent_dir, &info.name);
@@ -22,10 +64,61 @@ pub fn run(
         bail!("no logs directory for session '{}'", info.name);
     }
 
+    if let Some(dest) = export {
+        return export_logs(&log_dir, &dest);
+    }
+
+    if all {
+        return follow_logs(&log_dir, None);
+    }
+
     match script {
-        None => list_logs(&sess_dir, &log_dir),
-        Some(label) => view_log(&log_dir, &label, follow),
+        None if filter.is_empty() => list_logs(&sess_dir, &log_dir),
+        None => search_logs(&log_dir, &filter.compile()?),
+        Some(label) => view_log(&log_dir, &label, follow, &filter),
+    }
+}
+
+fn log_files(log_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(log_dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "log"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn export_logs(log_dir: &Path, dest: &Path) -> Result<()> {
+    std::fs::create_dir_all(dest)
+        .with_context(|| format!("failed to create {}", dest.display()))?;
+
+    let files = log_files(log_dir)?;
+    for src in &files {
+        let target = dest.join(src.file_name().unwrap());
+        std::fs::copy(src, &target)
+            .with_context(|| format!("failed to copy {} → {}", src.display(), target.display()))?;
+    }
+
+    println!(
+        "{} Exported {} log file(s) to {}",
+        style("✔").green(),
+        files.len(),
+        dest.display()
+    );
+    Ok(())
+}
+
+/// Apply `--grep`/`--since` across every log, prefixing matches with their label.
+fn search_logs(log_dir: &Path, filter: &CompiledFilter) -> Result<()> {
+    for path in log_files(log_dir)? {
+        let label = path.file_stem().unwrap().to_string_lossy().to_string();
+        let content = std::fs::read_to_string(&path)?;
+        for line in filter_lines(&content, filter, file_mtime(&path)) {
+            println!("{} {}", style(format!("[{}]", label)).cyan(), line);
+        }
     }
+    Ok(())
 }
 
 fn list_logs(sess_dir: &Path, log_dir: &Path) -> Result<()> {
@@ -43,7 +136,7 @@ fn list_logs(sess_dir: &Path, log_dir: &Path) -> Result<()> {
 
     entries.sort_by_key(|e| e.file_name());
 
-    println!("{}", style("Background script logs:").bold());
+    println!("{}", style("Script logs:").bold());
     println!();
 
     for entry in &entries {
@@ -56,13 +149,13 @@ fn list_logs(sess_dir: &Path, log_dir: &Path) -> Result<()> {
 
         let status = match pid_entry {
             Some(p) => {
-                if is_process_running(p.pid) {
+                if !p.dead && is_process_running(p.pid) {
                     style("running").green().to_string()
                 } else {
                     style("stopped").red().to_string()
                 }
             }
-            None => style("unknown").dim().to_string(),
+            None => style("foreground").dim().to_string(),
         };
 
         let script_path = pid_entry
@@ -89,7 +182,7 @@ fn list_logs(sess_dir: &Path, log_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-fn view_log(log_dir: &Path, label: &str, follow: bool) -> Result<()> {
+fn view_log(log_dir: &Path, label: &str, follow: bool, filter: &LogFilter) -> Result<()> {
     // Try exact match first
     let exact = log_dir.join(format!("{}.log", label));
     let log_path = if exact.exists() {
@@ -122,21 +215,222 @@ fn view_log(log_dir: &Path, label: &str, follow: bool) -> Result<()> {
     };
 
     if follow {
-        let status = Command::new("tail")
-            .args(["-f", &log_path.to_string_lossy()])
-            .status()?;
-
-        if !status.success() {
-            bail!("tail exited with {}", status);
-        }
-    } else {
+        follow_logs(log_dir, Some(log_path))?;
+    } else if filter.is_empty() {
         let content = std::fs::read_to_string(&log_path)?;
         print!("{}", content);
+    } else {
+        let filter = filter.compile()?;
+        let content = std::fs::read_to_string(&log_path)?;
+        for line in filter_lines(&content, &filter, file_mtime(&log_path)) {
+            println!("{}", line);
+        }
     }
 
     Ok(())
 }
 
+/// Lines of history shown for each log before following it, like `tail -f`.
+const FOLLOW_BACKLOG: usize = 10;
+
+/// How often logs are re-checked even without a change notification, for
+/// filesystems where events are missed.
+const FOLLOW_POLL: Duration = Duration::from_millis(500);
+
+const LABEL_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Blue,
+    Color::Red,
+];
+
+/// A log file being followed from a byte offset.
+struct FollowedLog {
+    path: PathBuf,
+    /// Colored `[label]` prefix, when following several logs.
+    prefix: Option<String>,
+    offset: u64,
+    /// Bytes after the last newline, held until the line is complete.
+    partial: Vec<u8>,
+}
+
+impl FollowedLog {
+    fn new(path: PathBuf, prefix: Option<String>) -> Self {
+        FollowedLog { path, prefix, offset: 0, partial: Vec::new() }
+    }
+
+    /// Print the last `backlog` lines and move to the end of the file.
+    fn print_backlog(&mut self, backlog: usize) -> Result<()> {
+        let content = std::fs::read(&self.path)
+            .with_context(|| format!("failed to read {}", self.path.display()))?;
+        self.offset = content.len() as u64;
+        let text = String::from_utf8_lossy(&content);
+        let lines: Vec<&str> = text.lines().collect();
+        for line in &lines[lines.len().saturating_sub(backlog)..] {
+            self.print_line(line);
+        }
+        Ok(())
+    }
+
+    /// Print complete lines appended since the last poll. A file that shrank
+    /// was truncated or replaced, so it is read again from the start.
+    fn poll(&mut self) -> Result<()> {
+        let Ok(mut file) = std::fs::File::open(&self.path) else {
+            return Ok(());
+        };
+        let len = file.metadata()?.len();
+        if len < self.offset {
+            self.offset = 0;
+            self.partial.clear();
+        }
+        if len == self.offset {
+            return Ok(());
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buf = Vec::new();
+        file.take(len - self.offset).read_to_end(&mut buf)?;
+        self.offset += buf.len() as u64;
+        self.partial.extend_from_slice(&buf);
+
+        if let Some(end) = self.partial.iter().rposition(|&b| b == b'\n') {
+            let complete: Vec<u8> = self.partial.drain(..=end).collect();
+            for line in String::from_utf8_lossy(&complete).lines() {
+                self.print_line(line);
+            }
+        }
+        Ok(())
+    }
+
+    fn print_line(&self, line: &str) {
+        match &self.prefix {
+            Some(prefix) => println!("{} {}", prefix, line),
+            None => println!("{}", line),
+        }
+    }
+}
+
+/// Follow one log, or (with `path` unset) every log in `log_dir` interleaved
+/// with a colored per-label prefix, picking up logs created while following.
+/// Runs until interrupted.
+fn follow_logs(log_dir: &Path, path: Option<PathBuf>) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::RecommendedWatcher::new(tx, notify::Config::default())
+        .context("failed to start file watcher")?;
+    watcher
+        .watch(log_dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("failed to watch {}", log_dir.display()))?;
+
+    let mut logs: BTreeMap<PathBuf, FollowedLog> = BTreeMap::new();
+    let single = path.is_some();
+    let add_new_logs = |logs: &mut BTreeMap<PathBuf, FollowedLog>| -> Result<Vec<PathBuf>> {
+        let files = match &path {
+            Some(p) => vec![p.clone()],
+            None => log_files(log_dir)?,
+        };
+        let mut added = Vec::new();
+        for file in files {
+            if logs.contains_key(&file) {
+                continue;
+            }
+            let prefix = (!single).then(|| {
+                let label = file.file_stem().unwrap_or_default().to_string_lossy();
+                let color = LABEL_COLORS[logs.len() % LABEL_COLORS.len()];
+                style(format!("[{}]", label)).fg(color).to_string()
+            });
+            logs.insert(file.clone(), FollowedLog::new(file.clone(), prefix));
+            added.push(file);
+        }
+        Ok(added)
+    };
+
+    for file in add_new_logs(&mut logs)? {
+        if let Some(log) = logs.get_mut(&file) {
+            log.print_backlog(FOLLOW_BACKLOG)?;
+        }
+    }
+    if logs.is_empty() {
+        println!("{}", style("No log files yet, waiting...").dim());
+    }
+
+    loop {
+        match rx.recv_timeout(FOLLOW_POLL) {
+            Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        // Drain the burst; every log is polled anyway
+        while rx.try_recv().is_ok() {}
+
+        // Logs that appear mid-follow are shown from their first line
+        add_new_logs(&mut logs)?;
+        for log in logs.values_mut() {
+            log.poll()?;
+        }
+    }
+}
+
+/// Keep lines matching the filter. Lines are timed by a leading RFC 3339
+/// timestamp when present (untimed lines inherit the previous one), falling
+/// back to the file's modification time.
+fn filter_lines<'a>(
+    content: &'a str,
+    filter: &CompiledFilter,
+    fallback: Option<DateTime<Utc>>,
+) -> Vec<&'a str> {
+    let mut current: Option<DateTime<Utc>> = None;
+    content
+        .lines()
+        .filter(|line| {
+            if let Some(ts) = line_timestamp(line) {
+                current = Some(ts);
+            }
+            if let (Some(cutoff), Some(ts)) = (filter.cutoff, current.or(fallback))
+                && ts < cutoff
+            {
+                return false;
+            }
+            filter.pattern.as_ref().is_none_or(|re| re.is_match(line))
+        })
+        .collect()
+}
+
+fn line_timestamp(line: &str) -> Option<DateTime<Utc>> {
+    let token = line.split_whitespace().next()?;
+    DateTime::parse_from_rfc3339(token)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn file_mtime(path: &Path) -> Option<DateTime<Utc>> {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .map(DateTime::<Utc>::from)
+}
+
+/// Parse a short duration like `30s`, `10m`, `2h` or `1d`.
+fn parse_duration(input: &str) -> Result<chrono::Duration> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (num, unit) = input.split_at(split);
+    let n: i64 = num
+        .parse()
+        .with_context(|| format!("invalid duration '{}' (expected e.g. 10m)", input))?;
+
+    let duration = match unit {
+        "s" => chrono::Duration::seconds(n),
+        "m" | "" => chrono::Duration::minutes(n),
+        "h" => chrono::Duration::hours(n),
+        "d" => chrono::Duration::days(n),
+        _ => bail!("invalid duration unit '{}' (use s, m, h or d)", unit),
+    };
+    Ok(duration)
+}
+
 fn is_process_running(pid: u32) -> bool {
     Command::new("kill")
         .args(["-0", &pid.to_string()])
@@ -155,3 +449,43 @@ fn format_size(bytes: u64) -> String {
         format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s").unwrap(), chrono::Duration::seconds(30));
+        assert_eq!(parse_duration("10m").unwrap(), chrono::Duration::minutes(10));
+        assert_eq!(parse_duration("2h").unwrap(), chrono::Duration::hours(2));
+        assert_eq!(parse_duration("1d").unwrap(), chrono::Duration::days(1));
+        assert!(parse_duration("10x").is_err());
+        assert!(parse_duration("m").is_err());
+    }
+
+    #[test]
+    fn test_filter_lines() {
+        let content = "2020-01-01T00:00:00Z old line\n\
+                       continuation\n\
+                       2099-01-01T00:00:00Z new error\n\
+                       2099-01-01T00:00:01Z new ok\n";
+        let filter = CompiledFilter {
+            pattern: None,
+            cutoff: Some(Utc::now()),
+        };
+        assert_eq!(
+            filter_lines(content, &filter, None),
+            vec!["2099-01-01T00:00:00Z new error", "2099-01-01T00:00:01Z new ok"]
+        );
+
+        let filter = CompiledFilter {
+            pattern: Some(Regex::new("error|continuation").unwrap()),
+            cutoff: None,
+        };
+        assert_eq!(
+            filter_lines(content, &filter, None),
+            vec!["continuation", "2099-01-01T00:00:00Z new error"]
+        );
+    }
+}
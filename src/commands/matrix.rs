@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use console::style;
+
+use crate::session;
+
+/// Outcome of one task run: exit code (`None` if it could not start or was
+/// killed by a signal) and wall time.
+struct Outcome {
+    code: Option<i32>,
+    duration: Duration,
+}
+
+/// Run `task` in every repo of each selected session, one at a time, then print
+/// a repo × session table of exit codes and durations.
+pub fn run(parent_dir: &Path, task: &str, session_names: Vec<String>, only_repo: Option<String>) -> Result<()> {
+    let mut sessions = session::list_sessions(parent_dir)?;
+    if !session_names.is_empty() {
+        for name in &session_names {
+            if !sessions.iter().any(|s| &s.name == name) {
+                bail!("Session '{}' not found.", name);
+            }
+        }
+        sessions.retain(|s| session_names.contains(&s.name));
+        sessions.sort_by_key(|s| session_names.iter().position(|n| n == &s.name));
+    }
+    if sessions.is_empty() {
+        bail!("No sessions found.");
+    }
+
+    // repo -> session -> outcome
+    let mut results: BTreeMap<String, BTreeMap<String, Outcome>> = BTreeMap::new();
+
+    for info in &sessions {
+        for repo in &info.repos {
+            if only_repo.as_ref().is_some_and(|r| r != &repo.name) || !repo.worktree_path.exists() {
+                continue;
+            }
+
+            println!(
+                "{}",
+                style(format!("── {} / {} ──", info.name, repo.name)).cyan().bold()
+            );
+            let start = Instant::now();
+            let status = Command::new("sh")
+                .args(["-c", task])
+                .current_dir(&repo.worktree_path)
+                .status();
+            let outcome = Outcome {
+                code: status.ok().and_then(|s| s.code()),
+                duration: start.elapsed(),
+            };
+            println!();
+
+            results
+                .entry(repo.name.clone())
+                .or_default()
+                .insert(info.name.clone(), outcome);
+        }
+    }
+
+    if results.is_empty() {
+        bail!("no matching worktrees found in the selected sessions");
+    }
+
+    // Summary table
+    println!("{}", style(format!("Matrix: {}", task)).bold());
+    print!("  {:<16}", "");
+    for info in &sessions {
+        print!(" {:<20}", style(&info.name).bold());
+    }
+    println!();
+
+    let mut any_failed = false;
+    for (repo, by_session) in &results {
+        print!("  {:<16}", repo);
+        for info in &sessions {
+            let cell = match by_session.get(&info.name) {
+                Some(o) => {
+                    let secs = format!("{:.1}s", o.duration.as_secs_f64());
+                    match o.code {
+                        Some(0) => style(format!("✓ {}", secs)).green(),
+                        Some(c) => style(format!("✗ {} ({})", secs, c)).red(),
+                        None => style(format!("✗ {} (error)", secs)).red(),
+                    }
+                }
+                None => style("-".to_string()).dim(),
+            };
+            print!(" {:<20}", cell);
+        }
+
+        let codes: Vec<Option<i32>> = by_session.values().map(|o| o.code).collect();
+        if codes.iter().any(|c| *c != Some(0)) {
+            any_failed = true;
+        }
+        if codes.windows(2).any(|w| w[0] != w[1]) {
+            print!(" {}", style("differs").yellow());
+        }
+        println!();
+    }
+    println!();
+
+    if any_failed {
+        bail!("task failed in one or more sessions");
+    }
+    Ok(())
+}
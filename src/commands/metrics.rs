@@ -0,0 +1,34 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+
+use crate::config::{MetricsConfig, SeshConfig};
+use crate::metrics;
+use crate::state;
+
+pub fn run(parent_dir: &Path, textfile: Option<PathBuf>, write: bool) -> Result<()> {
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+    let disk_ttl = config.metrics.disk_ttl_secs.unwrap_or(MetricsConfig::DEFAULT_DISK_TTL_SECS);
+    let text = metrics::render(parent_dir, disk_ttl)?;
+
+    let target = match textfile {
+        Some(path) => Some(path),
+        None if write => {
+            let Some(path) = config.metrics.textfile else {
+                bail!("--write needs `[metrics] textfile` in sesh.toml (or pass --textfile <path>)");
+            };
+            Some(parent_dir.join(path))
+        }
+        None => None,
+    };
+
+    match target {
+        // Written atomically so the node_exporter textfile collector never
+        // reads a half-written file
+        Some(path) => state::write_atomic(&path, text.as_bytes()),
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
@@ -1,32 +1,67 @@
 pub mod activate;
+pub mod archive;
 pub mod auth;
 pub mod checkout;
+pub mod commit;
 pub mod completions;
+pub mod compose;
+pub mod config;
+pub mod context;
 pub mod doctor;
+pub mod du;
 pub mod exec;
+pub mod guard;
+pub mod history;
+pub mod lifecycle;
 pub mod init;
 pub mod list;
+pub mod listen;
 pub mod log;
+pub mod matrix;
+pub mod metrics;
+pub mod pin;
+pub mod plugins;
+pub mod pool;
 pub mod pr;
+pub mod prune;
+pub mod ps;
 pub mod resume;
 pub mod start;
 pub mod status;
 pub mod stop;
+pub mod switch;
+pub mod sync;
+pub mod tag;
+pub mod tunnel;
+pub mod url;
+pub mod verify;
+pub mod watch;
 
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use anyhow::{bail, Context, Result};
 use chrono::Utc;
-use console::style;
-use dialoguer::Select;
+use console::{style, Term};
+use dialoguer::{Confirm, Select};
 
-use crate::config::SeshConfig;
-use crate::context;
+use crate::commit_msg;
+use crate::config::{FailurePolicy, HealthCheck, Hook, ScriptEntry, SeshConfig};
 use crate::discovery;
+use crate::fileset::{self, FileSet};
+use crate::gitstate;
+use crate::install;
 use crate::lock;
+use crate::manifest;
 use crate::mcp;
+use crate::parallel;
+use crate::scripting::ScriptHooks;
 use crate::scripts;
-use crate::session::{self, BackgroundPid, IssueContext, SessionInfo, SessionRepo};
+use crate::session::{self, BackgroundPid, Health, IssueContext, SessionInfo, SessionRepo};
+use crate::state;
+use crate::template;
+use crate::timing::TimedOutput;
 use crate::vscode;
 
 /// Pick a session by name, or interactively if name is None.
@@ -55,23 +90,55 @@ pub fn pick_session(parent_dir: &Path, name: Option<String>) -> Result<SessionIn
     }
 }
 
+/// The branch `repo` in `session` is based on. A session started with --from
+/// records that base; otherwise the repo's `base_branch` overrides the session
+/// default, as in `sesh start`.
+pub fn repo_base<'a>(config: &'a SeshConfig, session: &'a SessionInfo, repo: &str) -> &'a str {
+    match session.base_branch.as_deref() {
+        Some(base) if base != config.session.base_branch => base,
+        _ => config.base_branch(repo),
+    }
+}
+
+/// A session whose worktrees `start`, `checkout` or `compose` just created,
+/// for [`finalize_session`] to set up.
+pub struct NewSession<'a> {
+    pub selected_repos: &'a [discovery::RepoInfo],
+    /// Repos checked out detached at their base branch.
+    pub pinned_repos: &'a [String],
+    /// Per-repo branches when they differ from the session branch (`compose`).
+    pub repo_branches: &'a HashMap<String, String>,
+    /// Per-repo branches after `branch_prefix`/`branch_template`.
+    pub prefixed_branches: &'a HashMap<String, String>,
+    pub branch_name: &'a str,
+    pub session_name: &'a str,
+    pub sess_dir: &'a Path,
+    pub issue_context: Option<IssueContext>,
+    pub effective_base: &'a str,
+    pub no_setup: bool,
+    pub no_vscode: bool,
+}
+
 /// Shared session finalization: save session, copy/symlink files, MCP config,
 /// context generation, parent-dir copies, exclusive locks, setup scripts,
 /// VS Code launch, and summary output.
-pub fn finalize_session(
-    parent_dir: &Path,
-    config: &SeshConfig,
-    selected_repos: &[discovery::RepoInfo],
-    branch_name: &str,
-    session_name: &str,
-    sess_dir: &Path,
-    issue_context: Option<IssueContext>,
-    effective_base: &str,
-    no_setup: bool,
-    no_vscode: bool,
-) -> Result<()> {
+pub fn finalize_session(parent_dir: &Path, config: &SeshConfig, new: NewSession) -> Result<()> {
+    let NewSession {
+        selected_repos,
+        pinned_repos,
+        repo_branches,
+        prefixed_branches,
+        branch_name,
+        session_name,
+        sess_dir,
+        issue_context,
+        effective_base,
+        no_setup,
+        no_vscode,
+    } = new;
     // Save session early so `sesh stop` can always find it for cleanup
     let session_info = SessionInfo {
+        version: session::SESSION_SCHEMA_VERSION,
         name: session_name.to_string(),
         branch: branch_name.to_string(),
         repos: selected_repos
@@ -80,61 +147,142 @@ pub fn finalize_session(
                 name: r.name.clone(),
                 worktree_path: sess_dir.join(&r.name),
                 original_repo_path: r.path.clone(),
+                pinned: pinned_repos.contains(&r.name),
+                branch: repo_branches.get(&r.name).cloned(),
+                prefixed_branch: prefixed_branches.get(&r.name).cloned(),
             })
             .collect(),
         created_at: Utc::now(),
         parent_dir: parent_dir.to_path_buf(),
         issue: issue_context,
         base_branch: Some(effective_base.to_string()),
+        ci_runs: Vec::new(),
+        urls: BTreeMap::new(),
+        cleanup: None,
+        archived_at: None,
+        tags: Vec::new(),
+        metadata: BTreeMap::new(),
+        pinned_at: None,
+        ports: session::allocate_ports(parent_dir, &config.session.ports),
+        pull_requests: BTreeMap::new(),
     };
 
     session::save_session(sess_dir, &session_info)?;
+    session::ensure_scratch_dir(sess_dir)?;
+    // In single-repo mode the sessions live inside the repo itself
+    for repo in selected_repos.iter().filter(|r| r.path == parent_dir) {
+        if let Err(e) = manifest::add_to_git_exclude(&repo.path, "/.sesh/") {
+            eprintln!("  {} Failed to exclude .sesh/ in {}: {}", style("!").yellow(), repo.name, e);
+        }
+    }
+    crate::history::record(
+        parent_dir,
+        session_name,
+        crate::history::Event::Created {
+            branch: branch_name.to_string(),
+            repos: session_info.repos.iter().map(|r| r.name.clone()).collect(),
+        },
+    );
 
     // Copy/symlink per-repo files
     for repo in selected_repos {
         if let Some(repo_config) = config.repos.get(&repo.name) {
             let worktree_path = sess_dir.join(&repo.name);
 
-            // Copy files
-            for file in &repo_config.copy {
-                let src = repo.path.join(file);
-                let dst = worktree_path.join(file);
-                if src.exists() {
+            // Copy files and directories
+            let copies = FileSet::new(&repo_config.copy)?;
+            for file in matched(&copies, &repo.path, &repo.name)? {
+                if let Err(e) = copies.copy(&repo.path, &worktree_path, &file) {
+                    eprintln!(
+                        "  {} Failed to copy {} in {}: {}",
+                        style("!").yellow(),
+                        file,
+                        repo.name,
+                        e
+                    );
+                } else {
+                    manifest::record(sess_dir, &repo.name, &repo.path, &file)?;
+                    println!("  {} Copied {} → {}", style("·").dim(), file, repo.name);
+                }
+            }
+
+            // Symlink files/dirs
+            for item in matched(&FileSet::new(&repo_config.symlink)?, &repo.path, &repo.name)? {
+                let src = repo.path.join(&item);
+                let dst = worktree_path.join(&item);
+                if dst.symlink_metadata().is_err() {
                     if let Some(parent) = dst.parent() {
                         std::fs::create_dir_all(parent).ok();
                     }
-                    if let Err(e) = std::fs::copy(&src, &dst) {
+                    if let Err(e) = std::os::unix::fs::symlink(&src, &dst) {
                         eprintln!(
-                            "  {} Failed to copy {} in {}: {}",
+                            "  {} Failed to symlink {} in {}: {}",
                             style("!").yellow(),
-                            file,
+                            item,
                             repo.name,
                             e
                         );
                     } else {
-                        println!("  {} Copied {} → {}", style("·").dim(), file, repo.name);
+                        manifest::record(sess_dir, &repo.name, &repo.path, &item)?;
+                        println!("  {} Symlinked {} → {}", style("·").dim(), item, repo.name);
                     }
                 }
             }
 
-            // Symlink files/dirs
-            for item in &repo_config.symlink {
-                let src = repo.path.join(item);
-                let dst = worktree_path.join(item);
-                if src.exists() && !dst.exists() {
-                    if let Err(e) = std::os::unix::fs::symlink(&src, &dst) {
+            // Copy-on-write clones of heavy dirs (node_modules, target, ...)
+            for item in matched(&FileSet::new(&repo_config.clone)?, &repo.path, &repo.name)? {
+                let src = repo.path.join(&item);
+                let dst = worktree_path.join(&item);
+                if !dst.exists() {
+                    if let Err(e) = clone_path(&src, &dst) {
                         eprintln!(
-                            "  {} Failed to symlink {} in {}: {}",
+                            "  {} Failed to clone {} in {}: {:#}",
                             style("!").yellow(),
                             item,
                             repo.name,
                             e
                         );
                     } else {
-                        println!("  {} Symlinked {} → {}", style("·").dim(), item, repo.name);
+                        manifest::record(sess_dir, &repo.name, &repo.path, &item)?;
+                        println!("  {} Cloned {} → {}", style("·").dim(), item, repo.name);
                     }
                 }
             }
+
+            // Render templates with per-session values
+            for item in matched(&FileSet::new(&repo_config.template)?, &repo.path, &repo.name)? {
+                // Without the suffix the output would overwrite the tracked template
+                let Some(target) = item.strip_suffix(".tmpl") else {
+                    eprintln!(
+                        "  {} Skipping template {} in {}: its name must end in .tmpl",
+                        style("!").yellow(),
+                        item,
+                        repo.name
+                    );
+                    continue;
+                };
+                let dst = worktree_path.join(target);
+                let rendered = std::fs::read_to_string(repo.path.join(&item))
+                    .map(|t| template::render(&t, &template_vars(&session_info, &repo.name)))
+                    .and_then(|out| {
+                        if let Some(parent) = dst.parent() {
+                            std::fs::create_dir_all(parent)?;
+                        }
+                        std::fs::write(&dst, out)
+                    });
+                if let Err(e) = rendered {
+                    eprintln!(
+                        "  {} Failed to render {} in {}: {}",
+                        style("!").yellow(),
+                        item,
+                        repo.name,
+                        e
+                    );
+                } else {
+                    manifest::record(sess_dir, &repo.name, &repo.path, target)?;
+                    println!("  {} Rendered {} → {}", style("·").dim(), target, repo.name);
+                }
+            }
         }
     }
 
@@ -143,7 +291,7 @@ pub fn finalize_session(
     if !servers.is_empty() {
         for repo in selected_repos {
             let worktree_path = sess_dir.join(&repo.name);
-            mcp::write_mcp_config(&worktree_path, &repo.path, servers)
+            mcp::write_mcp_config(sess_dir, &repo.name, &worktree_path, &repo.path, servers)
                 .with_context(|| format!("failed to write .mcp.json for {}", repo.name))?;
         }
         println!(
@@ -153,13 +301,100 @@ pub fn finalize_session(
         );
     }
 
+    // Write per-repo VS Code settings
+    for repo in selected_repos {
+        if let Some(rc) = config.repos.get(&repo.name).filter(|rc| !rc.vscode_settings.is_empty()) {
+            let worktree_path = sess_dir.join(&repo.name);
+            match vscode::write_repo_settings(sess_dir, &repo.name, &worktree_path, &repo.path, &rc.vscode_settings) {
+                Ok(true) => println!("  {} VS Code settings written for {}", style("✓").green(), repo.name),
+                Ok(false) => println!(
+                    "  {} {} already has .vscode/settings.json; skipped vscode_settings",
+                    style("!").yellow(),
+                    repo.name
+                ),
+                Err(e) => eprintln!("  Warning: failed to write VS Code settings for {}: {}", repo.name, e),
+            }
+        }
+    }
+
+    // Install commit template per worktree
+    if let Some(template) = &config.git.commit_template {
+        let rendered = commit_msg::render_template(
+            template,
+            session_info.issue.as_ref(),
+            branch_name,
+            session_name,
+        );
+        let worktrees: Vec<(PathBuf, PathBuf)> = session_info
+            .repos
+            .iter()
+            .filter(|r| !r.pinned)
+            .map(|r| (r.original_repo_path.clone(), r.worktree_path.clone()))
+            .collect();
+        match commit_msg::install_commit_template(sess_dir, &worktrees, &rendered) {
+            Ok(_) => println!("  {} Commit template installed", style("✓").green()),
+            Err(e) => eprintln!(
+                "  {} Failed to install commit template: {}",
+                style("!").yellow(),
+                e
+            ),
+        }
+    }
+
+    // Link every commit to the ticket via a prepare-commit-msg trailer hook
+    if let Some(issue) = session_info.issue.as_ref().filter(|_| config.git.ticket_trailer) {
+        for repo in session_info.repos.iter().filter(|r| !r.pinned) {
+            if let Err(e) = commit_msg::install_ticket_hook(
+                sess_dir,
+                &repo.name,
+                &repo.original_repo_path,
+                &repo.worktree_path,
+                &issue.identifier,
+            ) {
+                eprintln!(
+                    "  {} Failed to install ticket hook in {}: {}",
+                    style("!").yellow(),
+                    repo.name,
+                    e
+                );
+            }
+        }
+        println!(
+            "  {} Commits will reference {}",
+            style("✓").green(),
+            issue.identifier
+        );
+    }
+
+    // Block commits touching protected paths
+    if config.guard.install_hook && !config.guard.protected.is_empty() {
+        for repo in session_info.repos.iter().filter(|r| !r.pinned) {
+            if let Err(e) = crate::guard::install_guard_hook(
+                parent_dir,
+                sess_dir,
+                session_name,
+                &repo.name,
+                &repo.original_repo_path,
+                &repo.worktree_path,
+            ) {
+                eprintln!(
+                    "  {} Failed to install guard hook in {}: {}",
+                    style("!").yellow(),
+                    repo.name,
+                    e
+                );
+            }
+        }
+        println!("  {} Guard hooks installed", style("✓").green());
+    }
+
     // Generate context
     let repo_pairs: Vec<(String, PathBuf)> = selected_repos
         .iter()
         .map(|r| (r.name.clone(), sess_dir.join(&r.name)))
         .collect();
 
-    context::generate_context(
+    crate::context::generate_context(
         sess_dir,
         branch_name,
         &repo_pairs,
@@ -168,7 +403,20 @@ pub fn finalize_session(
         session_info.issue.as_ref(),
         Some(effective_base),
     )?;
+    let hooks = ScriptHooks::load(parent_dir)?;
+    let context_file = crate::context::context_file(sess_dir);
+    let content = std::fs::read_to_string(&context_file)
+        .with_context(|| format!("failed to read {}", context_file.display()))?;
+    let repo_names: Vec<String> = repo_pairs.iter().map(|(name, _)| name.clone()).collect();
+    let hooked = hooks.context(&content, session_name, branch_name, &repo_names)?;
+    if hooked != content {
+        std::fs::write(&context_file, &hooked)
+            .with_context(|| format!("failed to write {}", context_file.display()))?;
+    }
     println!("  {} Session context generated", style("✓").green());
+    if let Err(e) = crate::landing::write(&session_info, config) {
+        eprintln!("  {} Failed to write {}: {:#}", style("!").yellow(), crate::landing::README_FILE, e);
+    }
 
     // Copy parent-dir files into session directory
     if !config.session.copy.is_empty() {
@@ -180,7 +428,7 @@ pub fn finalize_session(
                     std::fs::create_dir_all(parent).ok();
                 }
                 if src.is_dir() {
-                    if let Err(e) = copy_dir_recursive(&src, &dst) {
+                    if let Err(e) = copy_preserving(&src, &dst) {
                         eprintln!(
                             "  {} Failed to copy dir {} to session: {}",
                             style("!").yellow(),
@@ -249,152 +497,38 @@ pub fn finalize_session(
 
     // Run setup scripts
     if !no_setup {
-        let repo_names: Vec<String> = selected_repos.iter().map(|r| r.name.clone()).collect();
-        let mut bg_pids: Vec<BackgroundPid> = Vec::new();
-        let log_dir = sess_dir.join("logs");
-
-        let exclusive_skip_csv = exclusive_skipped.join(",");
-
-        // Global setup scripts
-        for entry in &config.scripts.setup {
-            let script_path = parent_dir.join(&entry.path);
-            let extra_env: Vec<(&str, &str)> = if !exclusive_skipped.is_empty() {
-                vec![("SESH_EXCLUSIVE_SKIP", exclusive_skip_csv.as_str())]
-            } else {
-                vec![]
-            };
-
-            if entry.background {
-                let label = format!("global-setup-{}", sanitize_label(&entry.path));
-                println!(
-                    "  {} Spawning background: {}...",
-                    style("→").cyan(),
-                    entry.path
-                );
-                let pid = scripts::spawn_background_script(
-                    entry,
-                    &script_path,
-                    sess_dir,
-                    &log_dir,
-                    &label,
-                    session_name,
-                    branch_name,
-                    &repo_names,
-                    &extra_env,
-                )?;
-                bg_pids.push(BackgroundPid {
-                    pid,
-                    label: label.clone(),
-                    script: entry.path.clone(),
-                });
-                println!(
-                    "  {} Background PID {} ({})",
-                    style("✓").green(),
-                    pid,
-                    entry.path
-                );
-            } else {
-                println!(
-                    "\n  {} Running setup: {}...",
-                    style("→").cyan(),
-                    entry.path
-                );
-                scripts::run_script_entry(
-                    "setup",
-                    entry,
-                    &script_path,
-                    sess_dir,
-                    session_name,
-                    branch_name,
-                    &repo_names,
-                    &extra_env,
-                )?;
-            }
-        }
+        run_setup(parent_dir, config, &session_info, &exclusive_skipped)?;
+    }
 
-        // Per-repo setup scripts
-        for repo in selected_repos {
-            if let Some(repo_config) = config.repos.get(&repo.name) {
-                let worktree_path = sess_dir.join(&repo.name);
-                let repo_env_name = repo.name.clone();
-
-                for entry in &repo_config.setup {
-                    let script_path = parent_dir.join(&entry.path);
-                    let extra_env: Vec<(&str, &str)> =
-                        vec![("SESH_REPO", repo_env_name.as_str())];
-
-                    if entry.background {
-                        let label =
-                            format!("{}-setup-{}", repo.name, sanitize_label(&entry.path));
-                        println!(
-                            "  {} Spawning background for {}: {}...",
-                            style("→").cyan(),
-                            repo.name,
-                            entry.path
-                        );
-                        let pid = scripts::spawn_background_script(
-                            entry,
-                            &script_path,
-                            &worktree_path,
-                            &log_dir,
-                            &label,
-                            session_name,
-                            branch_name,
-                            &repo_names,
-                            &extra_env,
-                        )?;
-                        bg_pids.push(BackgroundPid {
-                            pid,
-                            label: label.clone(),
-                            script: entry.path.clone(),
-                        });
-                        println!(
-                            "  {} Background PID {} ({}/{})",
-                            style("✓").green(),
-                            pid,
-                            repo.name,
-                            entry.path
-                        );
-                    } else {
-                        println!(
-                            "  {} Running setup for {}: {}...",
-                            style("→").cyan(),
-                            repo.name,
-                            entry.path
-                        );
-                        scripts::run_script_entry(
-                            "setup",
-                            entry,
-                            &script_path,
-                            &worktree_path,
-                            session_name,
-                            branch_name,
-                            &repo_names,
-                            &extra_env,
-                        )?;
-                    }
-                }
-            }
-        }
+    let setup_time = Utc::now().signed_duration_since(session_info.created_at);
+    crate::history::record(
+        parent_dir,
+        session_name,
+        crate::history::Event::Ready {
+            duration_secs: setup_time.num_milliseconds() as f64 / 1000.0,
+        },
+    );
+    let secs = setup_time.num_seconds();
+    crate::notify::desktop_after(
+        setup_time.to_std().unwrap_or_default(),
+        &format!("Session {} is ready", session_name),
+        &format!("{} repo(s) set up in {}m {}s", selected_repos.len(), secs / 60, secs % 60),
+    );
 
-        // Save background PIDs
-        if !bg_pids.is_empty() {
-            session::save_background_pids(sess_dir, &bg_pids)?;
-            println!(
-                "  {} {} background process(es) started",
-                style("✓").green(),
-                bg_pids.len()
-            );
-        }
-    }
+    run_hook(
+        parent_dir,
+        config,
+        Hook::PostStart,
+        sess_dir,
+        session_name,
+        branch_name,
+        &repo_names,
+        &session_info.script_env(),
+    )?;
 
     // Open VS Code
     if !no_vscode {
-        let paths: Vec<PathBuf> = selected_repos
-            .iter()
-            .map(|r| sess_dir.join(&r.name))
-            .collect();
-        vscode::open_session_in_vscode(sess_dir, &paths)?;
+        vscode::open_session_in_vscode(&session_info, &config.vscode)?;
     }
 
     // Summary
@@ -408,11 +542,13 @@ pub fn finalize_session(
         style("Session:").bold(),
         session_name
     );
-    println!(
-        "  {:<16} {}",
-        style("Branch:").bold(),
-        branch_name
-    );
+    if repo_branches.is_empty() {
+        println!(
+            "  {:<16} {}",
+            style("Branch:").bold(),
+            branch_name
+        );
+    }
     println!(
         "  {:<16} {}",
         style("Location:").bold(),
@@ -420,11 +556,19 @@ pub fn finalize_session(
     );
     println!();
     for repo in selected_repos {
+        let pinned = if pinned_repos.contains(&repo.name) {
+            format!(" {}", style("(pinned)").dim())
+        } else if let Some(branch) = repo_branches.get(&repo.name).or_else(|| prefixed_branches.get(&repo.name)) {
+            format!(" {}", style(format!("({})", branch)).dim())
+        } else {
+            String::new()
+        };
         println!(
-            "  {} {} → {}",
+            "  {} {} → {}{}",
             style("•").dim(),
             style(&repo.name).cyan(),
-            sess_dir.join(&repo.name).display()
+            sess_dir.join(&repo.name).display(),
+            pinned
         );
     }
     println!();
@@ -432,25 +576,589 @@ pub fn finalize_session(
     Ok(())
 }
 
-fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
-    std::fs::create_dir_all(dst)?;
-    for entry in std::fs::read_dir(src)? {
-        let entry = entry?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
-        if src_path.is_dir() {
-            copy_dir_recursive(&src_path, &dst_path)?;
-        } else {
-            std::fs::copy(&src_path, &dst_path)?;
+/// `git fetch --all --prune` in every repo, several at a time, printing one
+/// line per repo in order. Failures only warn.
+pub async fn fetch_all(repos: &[discovery::RepoInfo]) {
+    let paths = repos.iter().map(|r| r.path.clone()).collect();
+    let results = parallel::map_bounded(paths, |path: PathBuf| async move {
+        gitstate::git_async(&path, &["fetch", "--all", "--prune"]).await
+    })
+    .await;
+    gitstate::invalidate();
+    for (repo, result) in repos.iter().zip(results) {
+        match result {
+            Ok(_) => println!("  {} Fetching {}... {}", style("↓").dim(), repo.name, style("done").green()),
+            Err(_) => println!(
+                "  {} Fetching {}... {}",
+                style("↓").dim(),
+                repo.name,
+                style("warning: fetch failed, continuing").yellow()
+            ),
+        }
+    }
+}
+
+/// Run a session's setup scripts, global then per repo, in `depends_on` order:
+/// foreground scripts to completion, background ones spawned (and tracked in
+/// `background_pids.json`). `exclusive_skipped` lists exclusive repos locked
+/// by another session, passed on as `SESH_EXCLUSIVE_SKIP`.
+pub fn run_setup(
+    parent_dir: &Path,
+    config: &SeshConfig,
+    session_info: &SessionInfo,
+    exclusive_skipped: &[String],
+) -> Result<()> {
+    install_dependencies(parent_dir, config, session_info)?;
+
+    let sess_dir = &session::session_dir(parent_dir, &session_info.name);
+    let repo_names: Vec<String> = session_info.repos.iter().map(|r| r.name.clone()).collect();
+    let exclusive_skip_csv = exclusive_skipped.join(",");
+    let session_env = session_info.script_env();
+    let session_env: Vec<(&str, &str)> = session_env.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+
+    // Global setup scripts, then each repo's
+    let mut steps = Vec::new();
+    for entry in &config.scripts.setup {
+        let mut extra_env = session_env.clone();
+        if !exclusive_skipped.is_empty() {
+            extra_env.push(("SESH_EXCLUSIVE_SKIP", exclusive_skip_csv.as_str()));
+        }
+        steps.push(SetupStep {
+            entry,
+            repo: None,
+            cwd: sess_dir.to_path_buf(),
+            branch: &session_info.branch,
+            extra_env,
+        });
+    }
+    for repo in &session_info.repos {
+        if let Some(repo_config) = config.repos.get(&repo.name) {
+            for entry in &repo_config.setup {
+                let mut extra_env = session_env.clone();
+                extra_env.push(("SESH_REPO", repo.name.as_str()));
+                steps.push(SetupStep {
+                    entry,
+                    repo: Some(&repo.name),
+                    cwd: repo.worktree_path.clone(),
+                    branch: session_info.branch_for(repo),
+                    extra_env,
+                });
+            }
+        }
+    }
+
+    let run = SetupRun {
+        parent_dir,
+        config,
+        session_info,
+        sess_dir,
+        repo_names: &repo_names,
+        bg_pids: Mutex::new(Vec::new()),
+    };
+    let entries: Vec<(Option<&str>, &ScriptEntry)> = steps.iter().map(|s| (s.repo, s.entry)).collect();
+    scripts::run_scheduled(&entries, parallel::limit(), &|i| run.step(&steps[i]))?;
+
+    let bg_pids = run.bg_pids.into_inner().unwrap_or_else(|e| e.into_inner());
+    if !bg_pids.is_empty() {
+        println!(
+            "  {} {} background process(es) started",
+            style("✓").green(),
+            bg_pids.len()
+        );
+    }
+    Ok(())
+}
+
+/// Run the `[[scripts.<hook>]]` entries one at a time from `cwd`, with
+/// `SESH_HOOK` set to the hook's name. A failed `pre_*` hook stops the
+/// command unless its `on_failure` is `continue`; other failures only warn.
+#[allow(clippy::too_many_arguments)]
+pub fn run_hook(
+    parent_dir: &Path,
+    config: &SeshConfig,
+    hook: Hook,
+    cwd: &Path,
+    session_name: &str,
+    branch: &str,
+    repo_names: &[String],
+    extra_env: &[(String, String)],
+) -> Result<()> {
+    let mut env: Vec<(&str, &str)> = vec![("SESH_HOOK", hook.name())];
+    env.extend(extra_env.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+    for entry in config.scripts.hook(hook).iter().filter(|e| e.is_available(parent_dir)) {
+        println!("  {} Running {} hook: {}...", style("→").cyan(), hook.name(), entry.name());
+        let result = scripts::run_script_entry(
+            hook.name(),
+            entry,
+            &parent_dir.join(&entry.path),
+            cwd,
+            session_name,
+            branch,
+            repo_names,
+            &env,
+            None,
+        );
+        crate::history::record(
+            parent_dir,
+            session_name,
+            crate::history::Event::script(hook.name(), entry.name(), None, crate::history::outcome(&result)),
+        );
+        if let Err(e) = result {
+            if hook.is_pre() && entry.on_failure != FailurePolicy::Continue {
+                return Err(e);
+            }
+            eprintln!("  {} {:#}", style("!").yellow(), e);
         }
     }
     Ok(())
 }
 
-fn sanitize_label(path: &str) -> String {
-    path.replace('/', "-")
-        .replace('\\', "-")
-        .trim_start_matches(['.', '-'])
-        .trim_end_matches(".sh")
-        .to_string()
+/// Run the install command for each repo's lockfiles (see [`install::detect`])
+/// in its worktree, when `[setup] auto_install` is true or the user agrees.
+/// Repos with setup scripts of their own are left to them. A failed install
+/// is reported but doesn't stop setup.
+fn install_dependencies(parent_dir: &Path, config: &SeshConfig, info: &SessionInfo) -> Result<()> {
+    if config.setup.auto_install == Some(false) {
+        return Ok(());
+    }
+    // Installs that ran (or were declined) are remembered, so `sesh setup`
+    // doesn't ask again or reinstall
+    let done_path = session::session_dir(parent_dir, &info.name).join("installs.json");
+    let mut done: BTreeSet<String> = std::fs::read_to_string(&done_path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default();
+    let key = |repo: &SessionRepo, command: &str| format!("{}: {}", repo.name, command);
+    let installs: Vec<(&SessionRepo, &str)> = info
+        .repos
+        .iter()
+        .filter(|r| config.repos.get(&r.name).is_none_or(|rc| rc.setup.is_empty()))
+        .flat_map(|r| install::detect(&r.worktree_path).into_iter().map(move |c| (r, c)))
+        .filter(|(r, c)| !done.contains(&key(r, c)))
+        .collect();
+    if installs.is_empty() {
+        return Ok(());
+    }
+
+    if config.setup.auto_install.is_none() {
+        if !Term::stderr().is_term() {
+            println!(
+                "  {} Dependency installs detected; set [setup] auto_install = true to run them",
+                style("·").dim()
+            );
+            return Ok(());
+        }
+        println!("  {} Detected dependency installs:", style("·").dim());
+        for (repo, command) in &installs {
+            println!("    {}: {}", repo.name, command);
+        }
+        let run = Confirm::new()
+            .with_prompt("Run them now? (set [setup] auto_install to stop asking)")
+            .default(true)
+            .interact()
+            .context("confirmation cancelled")?;
+        if !run {
+            done.extend(installs.iter().map(|(r, c)| key(r, c)));
+            return state::write_json(&done_path, &done);
+        }
+    }
+
+    for (repo, command) in installs {
+        if !install::is_available(command) {
+            eprintln!(
+                "  {} Skipping `{}` for {}: not found on PATH",
+                style("!").yellow(),
+                command,
+                repo.name
+            );
+            continue;
+        }
+        println!("\n  {} Installing dependencies for {}: {}...", style("→").cyan(), repo.name, command);
+        let result = match scripts::shell_command(
+            command,
+            &repo.worktree_path,
+            &info.name,
+            info.branch_for(repo),
+            &info.repos.iter().map(|r| r.name.clone()).collect::<Vec<_>>(),
+        )
+        .status()
+        {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(anyhow::anyhow!("`{}` exited with {}", command, status)),
+            Err(e) => Err(anyhow::anyhow!("failed to run `{}`: {}", command, e)),
+        };
+        crate::history::record(
+            parent_dir,
+            &info.name,
+            crate::history::Event::script("setup", command, Some(&repo.name), crate::history::outcome(&result)),
+        );
+        match result {
+            Ok(()) => {
+                done.insert(key(repo, command));
+            }
+            Err(e) => eprintln!("  {} Install failed for {}, continuing: {:#}", style("!").yellow(), repo.name, e),
+        }
+    }
+    state::write_json(&done_path, &done)
+}
+
+/// One setup script entry, global or for a repo, with where and how it runs.
+struct SetupStep<'a> {
+    entry: &'a ScriptEntry,
+    repo: Option<&'a str>,
+    cwd: PathBuf,
+    branch: &'a str,
+    extra_env: Vec<(&'a str, &'a str)>,
+}
+
+/// What every setup step of a new session shares. Steps may run on several
+/// threads at once, so background PIDs are collected behind a lock.
+struct SetupRun<'a> {
+    parent_dir: &'a Path,
+    config: &'a SeshConfig,
+    session_info: &'a SessionInfo,
+    sess_dir: &'a Path,
+    repo_names: &'a [String],
+    bg_pids: Mutex<Vec<BackgroundPid>>,
+}
+
+impl SetupRun<'_> {
+    /// Spawn a background entry (waiting for its health check) or run a
+    /// foreground one, applying its `on_failure` policy.
+    fn step(&self, step: &SetupStep) -> Result<()> {
+        let entry = step.entry;
+        let session_name = self.session_info.name.as_str();
+        let script_path = self.parent_dir.join(&entry.path);
+        let shown = match step.repo {
+            Some(repo) => format!("{}/{}", repo, entry.name()),
+            None => entry.name().to_string(),
+        };
+
+        let label = format!(
+            "{}-setup-{}",
+            step.repo.unwrap_or("global"),
+            sanitize_label(entry.name())
+        );
+        if !entry.background {
+            println!("\n  {} Running setup: {}...", style("→").cyan(), shown);
+            let log = scripts::ScriptLog {
+                log_dir: &self.sess_dir.join("logs"),
+                label: &label,
+                timestamps: self.config.scripts.timestamp_logs,
+            };
+            let result = scripts::run_script_entry(
+                "setup",
+                entry,
+                &script_path,
+                &step.cwd,
+                session_name,
+                step.branch,
+                self.repo_names,
+                &step.extra_env,
+                Some(&log),
+            );
+            crate::history::record(
+                self.parent_dir,
+                session_name,
+                crate::history::Event::script("setup", entry.name(), step.repo, crate::history::outcome(&result)),
+            );
+            check_setup(entry, result)?;
+            return Ok(());
+        }
+
+        println!("  {} Spawning background: {}...", style("→").cyan(), shown);
+        let spawned = scripts::spawn_background_script(
+            entry,
+            &script_path,
+            &step.cwd,
+            &self.sess_dir.join("logs"),
+            &label,
+            session_name,
+            step.branch,
+            self.repo_names,
+            &step.extra_env,
+            self.config.scripts.timestamp_logs,
+        );
+        record_spawn(self.parent_dir, session_name, entry.name(), step.repo, &spawned);
+        let Some(pid) = check_setup(entry, spawned)? else {
+            return Ok(());
+        };
+        self.update_pids(|pids| {
+            pids.push(BackgroundPid {
+                pid,
+                label: label.clone(),
+                script: entry.name().to_string(),
+                process_group: true,
+                started_at: Some(Utc::now()),
+                dead: false,
+                paused: false,
+                health: None,
+            })
+        })?;
+        println!("  {} Background PID {} ({})", style("✓").green(), pid, shown);
+
+        let Some(check) = health_check(entry, self.session_info, step.repo.unwrap_or("")) else {
+            return Ok(());
+        };
+        let health = scripts::wait_healthy(
+            &check,
+            entry,
+            &step.cwd,
+            session_name,
+            step.branch,
+            self.repo_names,
+            &step.extra_env,
+        );
+        let state = if health.is_ok() { Health::Healthy } else { Health::Unhealthy };
+        self.update_pids(|pids| {
+            if let Some(bp) = pids.iter_mut().find(|bp| bp.label == label) {
+                bp.health = Some(state);
+            }
+        })?;
+        if check_setup(entry, health)?.is_some() {
+            println!("  {} {} is healthy", style("✓").green(), shown);
+        }
+        Ok(())
+    }
+
+    /// Change the tracked background PIDs and save them right away, so a
+    /// later failure can still stop what was started.
+    fn update_pids(&self, change: impl FnOnce(&mut Vec<BackgroundPid>)) -> Result<()> {
+        let mut pids = self.bg_pids.lock().unwrap_or_else(|e| e.into_inner());
+        change(&mut pids);
+        session::save_background_pids(self.sess_dir, &pids)
+    }
+}
+
+/// Marks a setup failure from a script with `on_failure = "rollback"`, so the
+/// command creating the session tears it down again.
+#[derive(Debug, thiserror::Error)]
+#[error("setup step with on_failure = \"rollback\" failed")]
+pub struct SetupRollback;
+
+/// Apply `entry`'s `on_failure` policy to the outcome of a setup step: the
+/// value on success, `None` when the failure is reported and skipped.
+fn check_setup<T>(entry: &ScriptEntry, result: Result<T>) -> Result<Option<T>> {
+    let e = match result {
+        Ok(value) => return Ok(Some(value)),
+        Err(e) => e,
+    };
+    match entry.on_failure {
+        FailurePolicy::Continue => {
+            eprintln!(
+                "  {} {} failed, continuing: {:#}",
+                style("!").yellow(),
+                entry.name(),
+                e
+            );
+            Ok(None)
+        }
+        FailurePolicy::Abort => Err(e),
+        FailurePolicy::Rollback => Err(e.context(SetupRollback)),
+    }
+}
+
+/// `entry`'s health check with template variables in its URL filled in,
+/// announcing the wait that follows.
+fn health_check(entry: &ScriptEntry, info: &SessionInfo, repo_name: &str) -> Option<HealthCheck> {
+    let mut check = entry.health_check.clone()?;
+    check.url = check.url.map(|url| template::render(&url, &template_vars(info, repo_name)));
+    if let Some(target) = check.url.as_ref().or(check.command.as_ref()) {
+        println!("  {} Waiting for {} to be healthy ({})...", style("…").dim(), entry.name(), target);
+    }
+    Some(check)
+}
+
+/// After `finalize_session` failed with [`SetupRollback`], tear the session
+/// down (stopping whatever setup started), unless `keep_on_failure`.
+/// `keep_branches` protects branches the session didn't create.
+pub async fn roll_back_setup(
+    parent_dir: &Path,
+    config: &SeshConfig,
+    sess_dir: &Path,
+    error: &anyhow::Error,
+    keep_on_failure: bool,
+    keep_branches: bool,
+) {
+    if error.downcast_ref::<SetupRollback>().is_none() {
+        return;
+    }
+    if keep_on_failure {
+        eprintln!(
+            "\n  {} Left in place (--keep-on-failure): session {}",
+            style("!").yellow(),
+            sess_dir.display()
+        );
+        return;
+    }
+    eprintln!("\n  {} Rolling back...", style("✗").red());
+    let torn_down = match session::load_session(sess_dir) {
+        Ok(info) => stop::teardown(parent_dir, config, &info, keep_branches, false, false).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = torn_down {
+        eprintln!("    Failed to roll back {}: {:#}", sess_dir.display(), e);
+    }
+}
+
+/// Variables for `template` files: `session`, `branch`, `base_branch`,
+/// `repo`, `session_dir`, `worktree` and `port.<name>` per allocated port.
+fn template_vars(info: &SessionInfo, repo_name: &str) -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+    vars.insert("session".to_string(), info.name.clone());
+    vars.insert("repo".to_string(), repo_name.to_string());
+    vars.insert("base_branch".to_string(), info.base_branch.clone().unwrap_or_default());
+    vars.insert(
+        "session_dir".to_string(),
+        session::session_dir(&info.parent_dir, &info.name).display().to_string(),
+    );
+    if let Some(repo) = info.repos.iter().find(|r| r.name == repo_name) {
+        vars.insert("branch".to_string(), info.branch_for(repo).to_string());
+        vars.insert("worktree".to_string(), repo.worktree_path.display().to_string());
+    }
+    for (name, port) in &info.ports {
+        vars.insert(format!("port.{}", name), port.to_string());
+    }
+    vars
+}
+
+/// Expand a repo's file patterns against its original checkout, noting globs
+/// that matched nothing so a typo doesn't go unnoticed.
+fn matched(set: &FileSet, repo_path: &Path, repo_name: &str) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    for (pattern, matches) in set.expand(repo_path)? {
+        if matches.is_empty() && fileset::is_glob(pattern) {
+            println!("  {} {} matched nothing in {}", style("·").dim(), pattern, repo_name);
+        }
+        paths.extend(matches);
+    }
+    Ok(paths)
+}
+
+/// Duplicate `src` (a file or directory tree) to `dst` as a copy-on-write
+/// clone where the filesystem supports it (APFS `clonefile` through `cp -c`,
+/// btrfs/XFS reflinks through `cp --reflink=auto`), else as a plain copy.
+/// Modes and timestamps are kept so build tools don't rebuild everything.
+fn clone_path(src: &Path, dst: &Path) -> Result<()> {
+    if let Some(parent) = dst.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let flags: &[&str] = if cfg!(target_os = "macos") {
+        &["-c", "-Rp"]
+    } else {
+        &["-Rp", "--reflink=auto"]
+    };
+    let cloned = std::process::Command::new("cp")
+        .args(flags)
+        .arg(src)
+        .arg(dst)
+        .timed_output()
+        .is_ok_and(|o| o.status.success());
+    if cloned {
+        return Ok(());
+    }
+
+    // Filesystem (or cp) without clone support: start over with a plain copy
+    if dst.is_dir() {
+        std::fs::remove_dir_all(dst)?;
+    } else if dst.exists() {
+        std::fs::remove_file(dst)?;
+    }
+    copy_preserving(src, dst)
+}
+
+/// Copy `src` to `dst` the way `cp -Rp` would: symlinks are recreated rather
+/// than followed, and modification times are kept.
+fn copy_preserving(src: &Path, dst: &Path) -> Result<()> {
+    let meta = std::fs::symlink_metadata(src)?;
+    if meta.file_type().is_symlink() {
+        std::os::unix::fs::symlink(std::fs::read_link(src)?, dst)?;
+        return Ok(());
+    }
+    if meta.is_dir() {
+        std::fs::create_dir_all(dst)?;
+        for entry in std::fs::read_dir(src)? {
+            let entry = entry?;
+            copy_preserving(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+    } else {
+        std::fs::copy(src, dst)?;
+    }
+    // After the contents, since copying into a directory bumps its mtime
+    std::fs::File::open(dst)?.set_modified(meta.modified()?)?;
+    Ok(())
+}
+
+/// Record a background setup script spawn in the session's history.
+fn record_spawn(parent_dir: &Path, session_name: &str, script: &str, repo: Option<&str>, spawned: &Result<u32>) {
+    let outcome = match spawned {
+        Ok(pid) => format!("started in background (pid {})", pid),
+        Err(e) => format!("failed: {}", e),
+    };
+    crate::history::record(
+        parent_dir,
+        session_name,
+        crate::history::Event::script("setup", script, repo, outcome),
+    );
+}
+
+/// A log-file-safe label from a script path or inline command: runs of
+/// anything but letters, digits, `.` and `_` become `-`. Labels over 48 chars
+/// are cut short and end in a hash of the full name, so similar long commands
+/// still get their own log files.
+fn sanitize_label(name: &str) -> String {
+    const MAX: usize = 48;
+    let label = name
+        .split(|c: char| !(c.is_alphanumeric() || c == '.' || c == '_' || c == '-'))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let label = label.trim_start_matches(['.', '-']).trim_end_matches(".sh");
+    if label.chars().count() <= MAX {
+        return label.to_string();
+    }
+    let head: String = label.chars().take(MAX - 9).collect();
+    format!("{}-{:08x}", head.trim_end_matches('-'), lock::fnv1a(name.as_bytes()) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_labels_stay_distinct() {
+        assert_eq!(sanitize_label("scripts/dev server.sh"), "scripts-dev-server");
+
+        let a = sanitize_label("docker compose -f docker-compose.yml -f docker-compose.dev.yml up api");
+        let b = sanitize_label("docker compose -f docker-compose.yml -f docker-compose.dev.yml up web");
+        assert_ne!(a, b);
+        assert!(a.len() <= 48 && b.len() <= 48);
+        assert!(a.starts_with("docker-compose-"));
+    }
+
+    #[test]
+    fn plain_copy_keeps_symlinks_and_mtimes() {
+        let root = std::env::temp_dir().join(format!("sesh-copy-test-{}", std::process::id()));
+        let src = root.join("src");
+        std::fs::create_dir_all(src.join("nested")).unwrap();
+        std::fs::write(src.join("nested/file.txt"), "hi").unwrap();
+        std::os::unix::fs::symlink("nested/file.txt", src.join("link")).unwrap();
+        let old = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+        std::fs::File::options()
+            .write(true)
+            .open(src.join("nested/file.txt"))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        let dst = root.join("dst");
+        copy_preserving(&src, &dst).unwrap();
+
+        assert_eq!(std::fs::read_link(dst.join("link")).unwrap(), Path::new("nested/file.txt"));
+        assert_eq!(std::fs::read_to_string(dst.join("link")).unwrap(), "hi");
+        let copied = std::fs::metadata(dst.join("nested/file.txt")).unwrap();
+        assert_eq!(copied.modified().unwrap(), old);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
 }
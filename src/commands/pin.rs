@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use anyhow::Result;
+use console::style;
+
+use crate::session;
+
+use super::pick_session;
+
+/// Protect a session from `sesh prune` and an unforced `sesh stop`, or lift
+/// that protection with `pin = false`.
+pub fn run(parent_dir: &Path, name: Option<String>, pin: bool) -> Result<()> {
+    let mut sess = pick_session(parent_dir, name)?;
+    if sess.pinned_at.is_some() == pin {
+        println!(
+            "Session '{}' is {} pinned.",
+            sess.name,
+            if pin { "already" } else { "not" }
+        );
+        return Ok(());
+    }
+
+    sess.pinned_at = pin.then(chrono::Utc::now);
+    session::save_session(&session::session_dir(parent_dir, &sess.name), &sess)?;
+
+    if pin {
+        println!(
+            "{} Session '{}' pinned: `sesh prune` skips it and `sesh stop` needs --force.",
+            style("✔").green(),
+            sess.name
+        );
+    } else {
+        println!("{} Session '{}' unpinned.", style("✔").green(), sess.name);
+    }
+    Ok(())
+}
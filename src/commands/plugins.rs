@@ -0,0 +1,16 @@
+use anyhow::Result;
+use console::style;
+
+use crate::plugins;
+
+pub fn list() -> Result<()> {
+    let found = plugins::discover();
+    if found.is_empty() {
+        println!("No plugins found. Put an executable named `sesh-<name>` on PATH to add `sesh <name>`.");
+        return Ok(());
+    }
+    for (name, path) in &found {
+        println!("  {:<16} {}", style(name).cyan(), style(path.display()).dim());
+    }
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use std::fs::File;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+use console::style;
+
+use crate::config::SeshConfig;
+use crate::discovery;
+use crate::pool;
+use crate::worktree;
+
+/// Repos with `pool = N` set, as (name, path, N).
+fn pooled_repos(parent_dir: &Path, config: &SeshConfig) -> Result<Vec<(String, PathBuf, usize)>> {
+    Ok(discovery::repo_dirs(parent_dir)?
+        .into_iter()
+        .filter_map(|(name, path)| {
+            let size = config.repos.get(&name).map_or(0, |rc| rc.pool);
+            (size > 0).then_some((name, path, size))
+        })
+        .collect())
+}
+
+/// `<remote>/<base branch>` for `repo`, the ref `sesh start` branches from.
+fn base_ref(config: &SeshConfig, repo: &str) -> (String, String) {
+    let base = config
+        .repos
+        .get(repo)
+        .and_then(|rc| rc.base_branch.as_deref())
+        .unwrap_or(&config.session.base_branch);
+    (config.remote(repo).to_string(), base.to_string())
+}
+
+/// Create pooled worktrees until each repo with `pool = N` has N, fetching
+/// its base branch first. Dependency installs run in each when `[setup]
+/// auto_install = true`.
+pub fn warm(parent_dir: &Path, only: Vec<String>) -> Result<()> {
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+    let repos = pooled_repos(parent_dir, &config)?;
+    for name in &only {
+        if !repos.iter().any(|(repo, _, _)| repo == name) {
+            bail!("repo '{}' has no `pool` size set in sesh.toml", name);
+        }
+    }
+    if repos.is_empty() {
+        println!("No repos have a pool; set `pool = 2` under [repos.<name>] to keep worktrees warm.");
+        return Ok(());
+    }
+
+    for (name, path, size) in repos.iter().filter(|(name, _, _)| only.is_empty() || only.contains(name)) {
+        let have = pool::entries(parent_dir, name).len();
+        if have >= *size {
+            println!("  {} {}: {} of {} warm", style("✓").green(), style(name).cyan(), have, size);
+            continue;
+        }
+        let (remote, base) = base_ref(&config, name);
+        if let Err(e) = worktree::fetch_branch(path, &remote, &base) {
+            eprintln!("  {} fetch failed for {}, continuing: {:#}", style("!").yellow(), name, e);
+        }
+        let base_ref = format!("{}/{}", remote, base);
+        for _ in have..*size {
+            print!("  {} Warming {} at {}...", style("→").cyan(), style(name).cyan(), base_ref);
+            match pool::warm_one(parent_dir, name, path, &base_ref, config.setup.auto_install == Some(true)) {
+                Ok(_) => println!(" {}", style("done").green()),
+                Err(e) => {
+                    println!(" {}", style("failed").red());
+                    eprintln!("    {:#}", e);
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print each pooled repo's ready and warming worktrees against its size.
+pub fn status(parent_dir: &Path) -> Result<()> {
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+    let repos = pooled_repos(parent_dir, &config)?;
+    if repos.is_empty() {
+        println!("No repos have a pool.");
+        return Ok(());
+    }
+    for (name, _, size) in &repos {
+        let entries = pool::entries(parent_dir, name);
+        let ready: Vec<&str> = entries.iter().filter_map(|e| e.base_ref.as_deref()).collect();
+        let warming = entries.len() - ready.len();
+        let mut line = format!("{} of {} ready", ready.len(), size);
+        if warming > 0 {
+            line.push_str(&format!(", {} warming", warming));
+        }
+        if let Some(base_ref) = ready.first() {
+            line.push_str(&format!(" (at {})", base_ref));
+        }
+        println!("  {} {}: {}", style("•").dim(), style(name).cyan(), line);
+    }
+    Ok(())
+}
+
+/// Remove every pooled worktree, e.g. after changing a repo's base branch.
+pub fn clear(parent_dir: &Path) -> Result<()> {
+    let mut removed = 0;
+    for (name, path) in discovery::repo_dirs(parent_dir)? {
+        removed += pool::clear(parent_dir, &name, &path)?;
+    }
+    println!("{} Removed {} pooled worktree(s).", style("✔").green(), removed);
+    Ok(())
+}
+
+/// Top the pool up again after `sesh start` claimed from it, as a detached
+/// `sesh pool warm` logging to `.sesh/pool/warm.log`.
+pub fn refill_in_background(parent_dir: &Path) -> Result<()> {
+    let dir = pool::pool_dir(parent_dir);
+    std::fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let log_path = dir.join("warm.log");
+    let log = File::create(&log_path).with_context(|| format!("failed to create {}", log_path.display()))?;
+    let exe = std::env::current_exe().context("failed to locate sesh executable")?;
+    Command::new(exe)
+        .args(["pool", "warm", "--dir"])
+        .arg(parent_dir)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .process_group(0)
+        .spawn()
+        .context("failed to start `sesh pool warm`")?;
+    Ok(())
+}
@@ -1,38 +1,149 @@
+use std::collections::HashMap;
 use std::path::Path;
 use std::process::Command;
+use std::time::Instant;
 
 use anyhow::{bail, Context, Result};
+use chrono::Utc;
 use console::style;
 
-use super::pick_session;
+use crate::ci;
+use crate::config::{Hook, SeshConfig};
+use crate::forge::{self, Forge};
+use crate::gitstate;
+use crate::integrations;
+use crate::manifest;
+use crate::notify;
+use crate::session::{self, CiRun, SessionInfo};
+use crate::template;
+use crate::timing::TimedOutput;
+use crate::worktree;
 
-pub fn run(parent_dir: &Path, name: Option<String>, base: String) -> Result<()> {
-    let session = pick_session(parent_dir, name)?;
+use super::{pick_session, repo_base};
 
-    // Check gh is available
-    let gh_check = Command::new("which").arg("gh").output();
-    match gh_check {
-        Ok(output) if !output.status.success() => bail!("GitHub CLI (gh) not found. Install it from https://cli.github.com"),
-        Err(_) => bail!("GitHub CLI (gh) not found. Install it from https://cli.github.com"),
-        _ => {}
+pub async fn run(
+    parent_dir: &Path,
+    name: Option<String>,
+    base: Option<String>,
+    only_repos: Vec<String>,
+    only_changed: bool,
+    all: bool,
+    force: bool,
+) -> Result<()> {
+    let started = Instant::now();
+    let mut session = pick_session(parent_dir, name)?;
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+    let sess_dir = session::session_dir(parent_dir, &session.name);
+    let mut dispatched: Vec<CiRun> = Vec::new();
+    let mut opened: Vec<(String, String)> = Vec::new();
+
+    for name in &only_repos {
+        if !session.repos.iter().any(|r| &r.name == name) {
+            bail!("repo '{}' is not part of session '{}'", name, session.name);
+        }
+    }
+
+    if config.verify.before_pr {
+        println!("{}", style("Running verification before opening PRs...").bold());
+        println!();
+        if !super::verify::run_verification(&config, &session)? {
+            if !force {
+                bail!("verification failed — fix the failures or pass --force");
+            }
+            println!("  {} Verification failed, continuing (--force)", style("!").yellow());
+        }
+    }
+
+    let generated = manifest::load(&sess_dir);
+
+    // Without an explicit repo list, unchanged repos are skipped unless --all
+    let skip_unchanged = !all && (only_changed || only_repos.is_empty());
+    let mut skipped_unchanged = 0;
+
+    // Repos hosted on GitHub need gh; check before pushing anything
+    let needs_gh = session.repos.iter().any(|r| {
+        (only_repos.is_empty() || only_repos.contains(&r.name))
+            && !r.pinned
+            && Forge::detect(&r.worktree_path, config.remote(&r.name)) == Forge::GitHub
+    });
+    if needs_gh {
+        forge::require_gh()?;
     }
 
     for repo in &session.repos {
+        if !only_repos.is_empty() && !only_repos.contains(&repo.name) {
+            continue;
+        }
+
         println!("{}", style(format!("── {} ──", repo.name)).bold());
 
+        if repo.pinned {
+            println!("  {}", style("(pinned, skipping)").dim());
+            continue;
+        }
+
         if !repo.worktree_path.exists() {
             println!("  {}", style("(worktree missing, skipping)").red());
             continue;
         }
 
-        let wt = repo.worktree_path.to_string_lossy();
+        let remote = config.remote(&repo.name);
+        let push_remote = config.push_remote(&repo.name);
+        let forge = Forge::detect(&repo.worktree_path, remote);
+        if push_remote != remote && forge != Forge::GitHub {
+            eprintln!(
+                "  {}: push_remote '{}' (a fork) is only supported for GitHub repos",
+                style("Skipping").red(),
+                push_remote
+            );
+            println!();
+            continue;
+        }
+        let base = base.as_deref().unwrap_or_else(|| repo_base(&config, &session, &repo.name));
+        let base_ref = format!("{}/{}", remote, base);
+
+        if skip_unchanged {
+            match worktree::commits_ahead(&repo.worktree_path, &base_ref) {
+                Ok(0) => {
+                    println!("  {}", style(format!("(no commits ahead of {}, skipping)", base_ref)).dim());
+                    println!();
+                    skipped_unchanged += 1;
+                    continue;
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!(
+                    "  {} Could not compare with {}: {}",
+                    style("!").yellow(),
+                    base_ref,
+                    e
+                ),
+            }
+        }
+
+        if let Some(files) = generated.get(&repo.name) {
+            let leaked = manifest::leaked_files(&repo.worktree_path, files, &base_ref)?;
+            if !leaked.is_empty() {
+                eprintln!(
+                    "  {}: sesh-generated file(s) staged or committed: {}",
+                    style("Refusing to push").red(),
+                    leaked.join(", ")
+                );
+                println!();
+                continue;
+            }
+        }
+
+        let branch = session.branch_for(repo);
 
         // Push branch
-        println!("  Pushing branch '{}'...", session.branch);
+        println!("  Pushing branch '{}' to {}...", branch, push_remote);
         let push_output = Command::new("git")
-            .args(["-C", &wt, "push", "-u", "origin", &session.branch])
-            .output()
+            .arg("-C")
+            .arg(&repo.worktree_path)
+            .args(["push", "-u", push_remote, branch])
+            .timed_output()
             .context("Failed to run git push")?;
+        gitstate::invalidate();
 
         if !push_output.status.success() {
             let stderr = String::from_utf8_lossy(&push_output.stderr);
@@ -42,28 +153,256 @@ pub fn run(parent_dir: &Path, name: Option<String>, base: String) -> Result<()>
 
         // Create PR
         println!("  Creating PR...");
-        let pr_output = Command::new("gh")
-            .args([
-                "pr", "create",
-                "--base", &base,
-                "--head", &session.branch,
-                "--title", &session.branch,
-                "--fill",
-            ])
-            .current_dir(&repo.worktree_path)
-            .output()
-            .context("Failed to run gh pr create")?;
-
-        if pr_output.status.success() {
-            let url = String::from_utf8_lossy(&pr_output.stdout);
-            println!("  {} {}", style("PR:").green(), url.trim());
-        } else {
-            let stderr = String::from_utf8_lossy(&pr_output.stderr);
-            eprintln!("  {}: {}", style("PR creation failed").red(), stderr.trim());
+        let body = pr_body(&config, &session, &repo.worktree_path, &repo.name, &base_ref, base);
+        match forge
+            .create_pr(
+                parent_dir,
+                &config,
+                &repo.worktree_path,
+                remote,
+                push_remote,
+                base,
+                branch,
+                body.as_deref(),
+            )
+            .await
+        {
+            Ok(url) => {
+                println!("  {} {}", style("PR:").green(), url);
+                opened.push((repo.name.clone(), url));
+            }
+            Err(e) => {
+                eprintln!("  {}: {:#}", style("PR creation failed").red(), e);
+                println!();
+                continue;
+            }
+        }
+
+        let workflow = config
+            .repos
+            .get(&repo.name)
+            .and_then(|rc| rc.ci_workflow.as_ref())
+            .or(config.ci.workflow.as_ref());
+        if let Some(workflow) = workflow {
+            println!("  Dispatching workflow '{}'...", workflow);
+            match ci::dispatch_workflow(&repo.worktree_path, &config.ci, workflow, branch) {
+                Ok(url) => {
+                    match &url {
+                        Some(u) => println!("  {} {}", style("Run:").green(), u),
+                        None => println!("  {} dispatched (run URL not available yet)", style("Run:").green()),
+                    }
+                    dispatched.push(CiRun {
+                        repo: repo.name.clone(),
+                        workflow: workflow.clone(),
+                        url,
+                        dispatched_at: Utc::now(),
+                    });
+                }
+                Err(e) => eprintln!("  {}: {}", style("Workflow dispatch failed").red(), e),
+            }
         }
 
         println!();
     }
 
+    if skipped_unchanged > 0 {
+        println!(
+            "{}",
+            style(format!(
+                "Skipped {} repo(s) with no commits ahead of their base; pass --all to open PRs for them anyway.",
+                skipped_unchanged
+            ))
+            .dim()
+        );
+    }
+
+    if let Some(issue) = session.issue.as_ref().filter(|i| i.provider == "linear")
+        && config.pr.link_linear
+        && !opened.is_empty()
+    {
+        link_linear_issue(parent_dir, &issue.identifier, &opened).await;
+    }
+
+    if !dispatched.is_empty() || !opened.is_empty() {
+        session.ci_runs.extend(dispatched);
+        session.pull_requests.extend(opened.clone());
+        session::save_session(&sess_dir, &session)?;
+    }
+
+    let body = match opened.len() {
+        0 => "No PRs opened".to_string(),
+        1 => opened[0].1.clone(),
+        n => format!("{} PRs opened", n),
+    };
+    notify::desktop_after(started.elapsed(), &format!("sesh pr finished for {}", session.name), &body);
+
+    let repo_names: Vec<String> = session.repos.iter().map(|r| r.name.clone()).collect();
+    for (repo, url) in opened {
+        let Some(worktree) = session.repos.iter().find(|r| r.name == repo).map(|r| &r.worktree_path) else {
+            continue;
+        };
+        let mut env = session.script_env();
+        env.push(("SESH_REPO".to_string(), repo));
+        env.push(("SESH_PR_URL".to_string(), url));
+        super::run_hook(parent_dir, &config, Hook::PostPr, worktree, &session.name, &session.branch, &repo_names, &env)?;
+    }
+
     Ok(())
 }
+
+/// Attach each opened PR to the session's Linear issue. Failures are only
+/// reported: the PRs are already open.
+async fn link_linear_issue(parent_dir: &Path, identifier: &str, opened: &[(String, String)]) {
+    for (repo, url) in opened {
+        let title = format!("{} pull request", repo);
+        match integrations::link_linear_attachment(parent_dir, identifier, url, &title).await {
+            Ok(()) => println!("{} Linked {} PR to {}", style("✓").green(), repo, identifier),
+            Err(e) => eprintln!(
+                "  {}: {:#}",
+                style(format!("Failed to link {} PR to {}", repo, identifier)).yellow(),
+                e
+            ),
+        }
+    }
+}
+
+/// Used when `[pr] body_template` is unset.
+const DEFAULT_BODY_TEMPLATE: &str = "\
+{{#issue}}
+## {{ issue.identifier }}: {{ issue.title }}
+
+{{ fixes }}
+
+{{/issue}}
+{{#commits}}
+### Commits
+
+{{ commits }}
+
+{{/commits}}
+{{#diffstat}}
+### Changes
+
+```
+{{ diffstat }}
+```
+{{/diffstat}}
+";
+
+/// Headings for conventional-commit types, in the order they are listed.
+const COMMIT_TYPES: &[(&str, &str)] = &[
+    ("feat", "Features"),
+    ("fix", "Fixes"),
+    ("perf", "Performance"),
+    ("refactor", "Refactoring"),
+    ("docs", "Documentation"),
+    ("test", "Tests"),
+    ("build", "Build & CI"),
+    ("ci", "Build & CI"),
+    ("chore", "Chores"),
+    ("revert", "Reverts"),
+];
+
+/// Render the PR description, or `None` to let the forge fill it (no template
+/// configured, no linked issue and `[pr] summary = false`).
+fn pr_body(
+    config: &SeshConfig,
+    session: &SessionInfo,
+    worktree_path: &Path,
+    repo: &str,
+    base_ref: &str,
+    base: &str,
+) -> Option<String> {
+    let template = match (&config.pr.body_template, &session.issue) {
+        (Some(t), _) => t.as_str(),
+        (None, Some(_)) => DEFAULT_BODY_TEMPLATE,
+        (None, None) if config.pr.summary => DEFAULT_BODY_TEMPLATE,
+        (None, None) => return None,
+    };
+
+    let subjects = worktree::commit_subjects(worktree_path, &format!("{}..HEAD", base_ref)).unwrap_or_default();
+    let commits = if config.pr.group_commits {
+        group_by_type(&subjects)
+    } else {
+        subjects.iter().map(|s| format!("- {}", s)).collect::<Vec<_>>().join("\n")
+    };
+    let diffstat = worktree::diff_stat(worktree_path, &format!("{}...HEAD", base_ref)).unwrap_or_default();
+    let mut vars = HashMap::from([
+        ("session", session.name.clone()),
+        ("branch", session.branch.clone()),
+        ("base", base.to_string()),
+        ("repo", repo.to_string()),
+        ("commits", commits),
+        ("diffstat", diffstat),
+    ]);
+    if let Some(issue) = &session.issue {
+        vars.insert("issue", issue.identifier.clone());
+        vars.insert("issue.identifier", issue.identifier.clone());
+        vars.insert("issue.title", issue.title.clone());
+        vars.insert("issue.provider", issue.provider.clone());
+        vars.insert("issue.state", issue.state.clone().unwrap_or_default());
+        vars.insert("issue.labels", issue.labels.join(", "));
+        vars.insert("fixes", format!("{} {}", config.pr.closing_keyword, issue.identifier));
+    }
+    Some(template::render(template, &vars).trim().to_string())
+}
+
+/// List commit subjects under a heading per conventional-commit type
+/// (`feat(api): add login` becomes `- **api:** add login` under Features).
+/// Subjects that don't follow the convention go under Other.
+fn group_by_type(subjects: &[String]) -> String {
+    let pattern = regex::Regex::new(r"^(\w+)(?:\(([^)]*)\))?(!)?:\s*(.+)$").expect("valid regex");
+    let mut groups: Vec<(&str, Vec<String>)> = Vec::new();
+    for subject in subjects {
+        let (heading, line) = match pattern.captures(subject) {
+            Some(caps) => match COMMIT_TYPES.iter().find(|(t, _)| caps[1].eq_ignore_ascii_case(t)) {
+                Some((_, heading)) => {
+                    let mut line = match caps.get(2) {
+                        Some(scope) => format!("**{}:** {}", scope.as_str(), &caps[4]),
+                        None => caps[4].to_string(),
+                    };
+                    if caps.get(3).is_some() {
+                        line.push_str(" (breaking)");
+                    }
+                    (*heading, line)
+                }
+                None => ("Other", subject.clone()),
+            },
+            None => ("Other", subject.clone()),
+        };
+        match groups.iter_mut().find(|(h, _)| *h == heading) {
+            Some((_, lines)) => lines.push(line),
+            None => groups.push((heading, vec![line])),
+        }
+    }
+
+    let rank = |heading: &str| {
+        COMMIT_TYPES.iter().position(|(_, h)| *h == heading).unwrap_or(COMMIT_TYPES.len())
+    };
+    groups.sort_by_key(|(heading, _)| rank(heading));
+    groups
+        .iter()
+        .map(|(heading, lines)| {
+            let items: Vec<String> = lines.iter().map(|l| format!("- {}", l)).collect();
+            format!("#### {}\n\n{}", heading, items.join("\n"))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_commits_by_conventional_type() {
+        let subjects = ["fix: handle empty cart", "feat(api): add login", "Update README", "feat!: drop v1"]
+            .map(String::from);
+        assert_eq!(
+            group_by_type(&subjects),
+            "#### Features\n\n- **api:** add login\n- drop v1 (breaking)\n\n\
+             #### Fixes\n\n- handle empty cart\n\n\
+             #### Other\n\n- Update README"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use console::{style, Term};
+use dialoguer::MultiSelect;
+
+use crate::config::SeshConfig;
+use crate::forge::{Forge, PrState};
+use crate::parallel;
+use crate::session::{self, SessionInfo};
+
+use super::stop::{deletes_branch, teardown, work_at_risk};
+
+/// A session whose PRs are all merged or closed, with each repo's PR state.
+struct Candidate {
+    session: SessionInfo,
+    states: Vec<(String, Option<PrState>)>,
+}
+
+impl Candidate {
+    fn merged(&self, repo: &str) -> bool {
+        self.states
+            .iter()
+            .any(|(name, state)| name == repo && *state == Some(PrState::Merged))
+    }
+
+    fn summary(&self) -> String {
+        self.states
+            .iter()
+            .map(|(repo, state)| format!("{} {}", repo, state.map_or("no PR", PrState::label)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+pub async fn run(
+    parent_dir: &Path,
+    merged: bool,
+    yes: bool,
+    keep_branches: bool,
+    prune_remote: bool,
+    force: bool,
+) -> Result<()> {
+    if !merged {
+        bail!("specify what to prune: --merged");
+    }
+
+    let config = Arc::new(SeshConfig::load(&parent_dir.join("sesh.toml"))?);
+    let (pinned, sessions): (Vec<_>, Vec<_>) = session::list_sessions(parent_dir)?
+        .into_iter()
+        .partition(|s| s.pinned_at.is_some());
+    if !pinned.is_empty() {
+        let names: Vec<&str> = pinned.iter().map(|s| s.name.as_str()).collect();
+        println!("{}", style(format!("Skipping pinned: {}", names.join(", "))).dim());
+    }
+    if sessions.is_empty() {
+        println!("No sessions found.");
+        return Ok(());
+    }
+
+    println!(
+        "{} Checking PRs for {} session(s)...\n",
+        style("→").cyan().bold(),
+        sessions.len()
+    );
+
+    // One lookup per session repo with a branch of its own, all at once
+    let lookups: Vec<(usize, String, PathBuf, String)> = sessions
+        .iter()
+        .enumerate()
+        .flat_map(|(i, s)| {
+            s.repos
+                .iter()
+                .filter(|r| !r.pinned && r.worktree_path.exists())
+                .map(move |r| (i, r.name.clone(), r.worktree_path.clone(), s.branch_for(r).to_string()))
+        })
+        .collect();
+    let results = parallel::map_bounded(lookups, |(i, repo, worktree_path, branch)| {
+        let parent_dir = parent_dir.to_path_buf();
+        let config = config.clone();
+        async move {
+            let forge = Forge::detect(&worktree_path, config.remote(&repo));
+            let state = forge.pr_state(&parent_dir, &config, &worktree_path, &branch).await;
+            (i, repo, state)
+        }
+    })
+    .await;
+
+    let mut states: Vec<Vec<(String, Option<PrState>)>> = vec![Vec::new(); sessions.len()];
+    let mut failed = vec![false; sessions.len()];
+    for (i, repo, state) in results {
+        match state {
+            Ok(state) => states[i].push((repo, state)),
+            Err(e) => {
+                eprintln!(
+                    "  {} {}/{}: {:#}",
+                    style("Could not check PR").yellow(),
+                    sessions[i].name,
+                    repo,
+                    e
+                );
+                failed[i] = true;
+            }
+        }
+    }
+
+    // Prunable: at least one PR, none still open, and every lookup answered
+    let candidates: Vec<Candidate> = sessions
+        .into_iter()
+        .zip(states)
+        .zip(failed)
+        .filter(|((_, states), failed)| {
+            !failed
+                && states.iter().any(|(_, s)| s.is_some())
+                && !states.iter().any(|(_, s)| *s == Some(PrState::Open))
+        })
+        .map(|((session, states), _)| Candidate { session, states })
+        .collect();
+
+    if candidates.is_empty() {
+        println!("No sessions with merged or closed PRs.");
+        return Ok(());
+    }
+
+    // Uncommitted work is lost either way; unpushed commits only matter where
+    // the PR wasn't merged
+    let mut prunable = Vec::new();
+    for candidate in candidates {
+        let at_risk = if force {
+            Vec::new()
+        } else {
+            work_at_risk(&candidate.session, |repo| {
+                deletes_branch(repo, keep_branches) && !candidate.merged(&repo.name)
+            })
+        };
+        if at_risk.is_empty() {
+            println!("  {} {}", style(&candidate.session.name).green(), style(candidate.summary()).dim());
+            prunable.push(candidate);
+            continue;
+        }
+        println!(
+            "  {} {} (skipped, would lose work: {})",
+            style(&candidate.session.name).yellow(),
+            style(candidate.summary()).dim(),
+            at_risk
+                .iter()
+                .map(|(repo, problems)| format!("{} {}", repo, problems.join(", ")))
+                .collect::<Vec<_>>()
+                .join("; ")
+        );
+    }
+    println!();
+
+    if prunable.is_empty() {
+        println!("Nothing to prune; pass --force to stop sessions with unsaved work anyway.");
+        return Ok(());
+    }
+
+    let selected: Vec<usize> = if yes {
+        (0..prunable.len()).collect()
+    } else {
+        if !Term::stderr().is_term() {
+            bail!("refusing to prune without confirmation; pass --yes");
+        }
+        let names: Vec<&str> = prunable.iter().map(|c| c.session.name.as_str()).collect();
+        MultiSelect::new()
+            .with_prompt("Stop these sessions (space to select, enter to confirm)")
+            .items(&names)
+            .defaults(&vec![true; names.len()])
+            .interact()?
+    };
+
+    let mut stopped = 0;
+    for i in selected {
+        let session = &prunable[i].session;
+        println!("\n{} Stopping '{}'...", style("→").cyan().bold(), session.name);
+        match teardown(parent_dir, &config, session, keep_branches, prune_remote, false).await {
+            Ok(()) => stopped += 1,
+            Err(e) => eprintln!("  {}: {:#}", style("Failed to stop").red(), e),
+        }
+    }
+
+    println!("\n{} Pruned {} session(s).", style("✔").green(), stopped);
+    Ok(())
+}
@@ -0,0 +1,236 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use console::{style, Term};
+use dialoguer::Select;
+
+use crate::config::{ScriptEntry, SeshConfig};
+use crate::scripts::{self, ProcessState};
+use crate::session::{self, BackgroundPid, SessionInfo, SessionRepo};
+
+use super::{pick_session, record_spawn, sanitize_label};
+
+/// List background processes per session, reconciling `background_pids.json`
+/// against what is actually running (in memory only). With `restart`, relaunch
+/// dead setup scripts of active sessions and record the new PIDs.
+pub fn run(parent_dir: &Path, name: Option<String>, restart: bool) -> Result<()> {
+    let mut sessions = session::list_sessions(parent_dir)?;
+    if let Some(name) = &name {
+        sessions.retain(|s| &s.name == name);
+        if sessions.is_empty() {
+            bail!("Session '{}' not found.", name);
+        }
+    }
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+
+    let mut shown = 0;
+    let mut restartable = false;
+    for info in &sessions {
+        let sess_dir = session::session_dir(parent_dir, &info.name);
+        let mut pids = session::load_background_pids(&sess_dir);
+        if pids.is_empty() {
+            continue;
+        }
+        let mut states: Vec<Option<ProcessState>> =
+            scripts::reconcile_background_pids(&sess_dir, &mut pids).into_iter().map(Some).collect();
+
+        if restart && info.archived_at.is_none() {
+            for (bp, state) in pids.iter_mut().zip(states.iter_mut()) {
+                if *state != Some(ProcessState::Dead) {
+                    continue;
+                }
+                match relaunch(parent_dir, &config, info, &sess_dir, &bp.label) {
+                    Ok(Some(new)) => {
+                        *bp = new;
+                        // Freshly restarted
+                        *state = None;
+                    }
+                    Ok(None) => {}
+                    Err(e) => eprintln!("  {} {}: {:#}", style("✗").red(), bp.label, e),
+                }
+            }
+            session::save_background_pids(&sess_dir, &pids)?;
+        }
+        restartable |= info.archived_at.is_none() && states.contains(&Some(ProcessState::Dead));
+
+        if shown > 0 {
+            println!();
+        }
+        shown += 1;
+        println!("{}", style(&info.name).bold());
+        for (bp, state) in pids.iter().zip(&states) {
+            let status = match state {
+                Some(ProcessState::Running | ProcessState::Adopted) if bp.paused => {
+                    style(format!("{:<9}", "paused")).yellow()
+                }
+                Some(ProcessState::Running) => style(format!("{:<9}", "running")).green(),
+                Some(ProcessState::Adopted) => style(format!("{:<9}", "adopted")).cyan(),
+                Some(ProcessState::Dead) => style(format!("{:<9}", "dead")).red(),
+                None => style(format!("{:<9}", "restarted")).green(),
+            };
+            let mut details = Vec::new();
+            if !bp.dead {
+                if let Some(port) = scripts::recorded_port(&sess_dir, &bp.label) {
+                    details.push(format!("port {}", port));
+                }
+                if let Some(started) = bp.started_at {
+                    details.push(format!("up {}", uptime(Utc::now() - started)));
+                }
+            }
+            println!(
+                "  {:<32} {} {:>7}  {}",
+                bp.label,
+                status,
+                bp.pid,
+                style(details.join(", ")).dim()
+            );
+        }
+    }
+
+    if shown == 0 {
+        println!("No background processes.");
+    } else if restartable && !restart {
+        println!(
+            "\n{}",
+            style("Run `sesh ps --restart` to relaunch dead setup scripts.").dim()
+        );
+    }
+    Ok(())
+}
+
+/// Kill one background process of a session and respawn the setup script
+/// behind it, with the same environment and log file.
+pub fn restart(parent_dir: &Path, session: Option<String>, label: Option<String>) -> Result<()> {
+    let info = pick_session(parent_dir, session)?;
+    if info.archived_at.is_some() {
+        bail!("session '{}' is archived; `sesh activate {}` brings it back", info.name, info.name);
+    }
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+    let sess_dir = session::session_dir(parent_dir, &info.name);
+    let mut pids = session::load_background_pids(&sess_dir);
+    if pids.is_empty() {
+        bail!("session '{}' has no background processes", info.name);
+    }
+    scripts::reconcile_background_pids(&sess_dir, &mut pids);
+
+    let labels: Vec<&str> = pids.iter().map(|bp| bp.label.as_str()).collect();
+    let label = match label {
+        Some(label) => label,
+        None if Term::stderr().is_term() => {
+            let selection = Select::new()
+                .with_prompt("Restart which process?")
+                .items(&labels)
+                .default(0)
+                .interact()?;
+            labels[selection].to_string()
+        }
+        None => bail!("pass a label: {}", labels.join(", ")),
+    };
+    let Some(index) = pids.iter().position(|bp| bp.label == label) else {
+        bail!("no background process '{}' in session '{}' (see `sesh ps {}`)", label, info.name, info.name);
+    };
+    if setup_entry(&config, &info, &label).is_none() {
+        bail!("'{}' isn't a background setup script in sesh.toml, so it can't be restarted", label);
+    }
+
+    if !pids[index].dead {
+        println!("Stopping {} (PID {})...", label, pids[index].pid);
+        scripts::kill_background_pids(std::slice::from_ref(&pids[index]));
+    }
+    pids[index].dead = true;
+    let relaunched = relaunch(parent_dir, &config, &info, &sess_dir, &label);
+    if let Ok(Some(new)) = &relaunched {
+        pids[index] = new.clone();
+    }
+    session::save_background_pids(&sess_dir, &pids)?;
+    if let Some(new) = relaunched? {
+        println!("{} Restarted {} (PID {})", style("✔").green(), label, new.pid);
+    }
+    Ok(())
+}
+
+/// The background setup entry (and its repo, unless global) that `label` was spawned from.
+fn setup_entry<'a>(
+    config: &'a SeshConfig,
+    info: &'a SessionInfo,
+    label: &str,
+) -> Option<(&'a ScriptEntry, Option<&'a SessionRepo>)> {
+    let background = |entry: &&ScriptEntry| entry.background;
+    let global = config
+        .scripts
+        .setup
+        .iter()
+        .filter(background)
+        .find(|e| format!("global-setup-{}", sanitize_label(e.name())) == label)
+        .map(|e| (e, None));
+    global.or_else(|| {
+        info.repos.iter().find_map(|repo| {
+            config
+                .repos
+                .get(&repo.name)?
+                .setup
+                .iter()
+                .filter(background)
+                .find(|e| format!("{}-setup-{}", repo.name, sanitize_label(e.name())) == label)
+                .map(|e| (e, Some(repo)))
+        })
+    })
+}
+
+/// Respawn the setup script behind a dead entry, if it is still configured.
+fn relaunch(
+    parent_dir: &Path,
+    config: &SeshConfig,
+    info: &SessionInfo,
+    sess_dir: &Path,
+    label: &str,
+) -> Result<Option<BackgroundPid>> {
+    let Some((entry, repo)) = setup_entry(config, info, label) else {
+        return Ok(None);
+    };
+
+    let session_env = info.script_env();
+    let mut env: Vec<(&str, &str)> = session_env.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    let (cwd, branch) = match repo {
+        Some(repo) => {
+            env.push(("SESH_REPO", repo.name.as_str()));
+            (repo.worktree_path.clone(), info.branch_for(repo))
+        }
+        None => (sess_dir.to_path_buf(), info.branch.as_str()),
+    };
+    let repo_names: Vec<String> = info.repos.iter().map(|r| r.name.clone()).collect();
+
+    let spawned = scripts::spawn_background_script(
+        entry,
+        &parent_dir.join(&entry.path),
+        &cwd,
+        &sess_dir.join("logs"),
+        label,
+        &info.name,
+        branch,
+        &repo_names,
+        &env,
+        config.scripts.timestamp_logs,
+    );
+    record_spawn(parent_dir, &info.name, entry.name(), repo.map(|r| r.name.as_str()), &spawned);
+    Ok(Some(BackgroundPid {
+        pid: spawned?,
+        label: label.to_string(),
+        script: entry.name().to_string(),
+        process_group: true,
+        started_at: Some(Utc::now()),
+        dead: false,
+        paused: false,
+        health: None,
+    }))
+}
+
+pub(super) fn uptime(elapsed: chrono::Duration) -> String {
+    let mins = elapsed.num_minutes().max(0);
+    match (mins / 1440, mins / 60 % 24, mins % 60) {
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
+    }
+}
@@ -3,7 +3,7 @@ use std::path::Path;
 use anyhow::Result;
 use console::style;
 
-use crate::session;
+use crate::config::SeshConfig;
 use crate::vscode;
 
 use super::pick_session;
@@ -18,13 +18,20 @@ pub fn run(parent_dir: &Path, name: Option<String>) -> Result<()> {
         return Ok(());
     }
 
-    let sess_dir = session::session_dir(parent_dir, &sess.name);
-    vscode::open_session_in_vscode(&sess_dir, &paths)?;
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+    vscode::open_session_in_vscode(&sess, &config.vscode)?;
 
     println!("Opened VS Code for session '{}':", style(&sess.name).cyan());
     for repo in &sess.repos {
         println!("  {} -> {}", style(&repo.name).green(), repo.worktree_path.display());
     }
+    if sess.archived_at.is_some() {
+        println!(
+            "{} Session is archived; run `sesh activate {}` to restart its services.",
+            style("note:").yellow(),
+            sess.name
+        );
+    }
 
     Ok(())
 }
@@ -1,15 +1,21 @@
-use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::path::Path;
 
 use anyhow::{bail, Context, Result};
-use console::style;
-use dialoguer::{FuzzySelect, Input, MultiSelect};
+use console::{style, Term};
+use dialoguer::{Confirm, FuzzySelect, Input, MultiSelect};
 
-use crate::config::SeshConfig;
+use crate::config::{Hook, SeshConfig};
 use crate::discovery;
 use crate::integrations;
+use crate::parallel;
+use crate::pool;
+use crate::rollback::Rollback;
+use crate::scripting::ScriptHooks;
 use crate::session::{self, IssueContext};
 use crate::worktree;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
     parent_dir: &Path,
     branch: Option<String>,
@@ -19,42 +25,64 @@ pub async fn run(
     no_setup: bool,
     no_vscode: bool,
     linear: bool,
+    jira: bool,
+    issue: Option<String>,
+    keep_on_failure: bool,
 ) -> Result<()> {
     // 1. Load config
     let config_path = parent_dir.join("sesh.toml");
     let config = SeshConfig::load(&config_path)?;
+    let hooks = ScriptHooks::load(parent_dir)?;
 
     // 2. Discover repos
-    let repos = discovery::discover_repos(parent_dir)?;
+    let repos = discovery::discover_repos(parent_dir).await?;
     if repos.is_empty() {
         bail!("no git repos found in {}", parent_dir.display());
     }
 
-    // 3. Select repos
-    let selected_repos = if all {
-        repos.clone()
+    // 3. Select repos (presets may pin some repos read-only at their base branch)
+    let single_repo = discovery::is_single_repo(parent_dir, &repos);
+    if single_repo {
+        println!(
+            "{}",
+            style(format!("Single-repo mode: sessions are worktrees of {}", repos[0].name)).dim()
+        );
+    }
+    let (selected_repos, pinned_repos) = if all || single_repo {
+        (repos.clone(), Vec::new())
     } else if let Some(ref preset_name) = preset {
-        let preset_repos = config.presets.get(preset_name)
+        let preset = config.presets.get(preset_name)
             .with_context(|| format!("preset '{}' not found in sesh.toml", preset_name))?;
-        repos.iter()
-            .filter(|r| preset_repos.contains(&r.name))
+        let selected: Vec<discovery::RepoInfo> = repos.iter()
+            .filter(|r| preset.includes(&r.name))
             .cloned()
-            .collect()
+            .collect();
+        (selected, preset.pinned().to_vec())
     } else {
-        select_repos_interactive(&repos, &config)?
+        (select_repos_interactive(&repos, &config, &hooks)?, Vec::new())
     };
 
     if selected_repos.is_empty() {
         bail!("no repos selected");
     }
 
+    // Pinned repos never get the session branch, so exclude them from conflict checks
+    let branch_repos: Vec<discovery::RepoInfo> = selected_repos
+        .iter()
+        .filter(|r| !pinned_repos.contains(&r.name))
+        .cloned()
+        .collect();
+
     // 4. Get branch name (resolves Linear/Sentry inputs, validates, checks for conflicts)
-    let (branch_name, issue_context) = resolve_branch_name(
+    let (branch_name, issue_context, use_remote) = resolve_branch_name(
         branch.as_deref(),
         parent_dir,
-        &selected_repos,
+        &branch_repos,
         &config,
+        &hooks,
         linear,
+        jira,
+        issue.as_deref(),
     )
     .await?;
 
@@ -72,57 +100,181 @@ pub async fn run(
         selected_repos.len()
     );
 
-    // 5. Per-repo: validate base branch, fetch, create worktree
-    let mut created_worktrees: Vec<(PathBuf, PathBuf)> = Vec::new(); // (repo_path, worktree_path)
+    let repo_names: Vec<String> = selected_repos.iter().map(|r| r.name.clone()).collect();
+    super::run_hook(parent_dir, &config, Hook::PreStart, parent_dir, &session_name, &branch_name, &repo_names, &[])?;
 
-    for repo in &selected_repos {
-        let repo_config = config.repos.get(&repo.name);
-        let base_branch = from.as_deref()
-            .or_else(|| repo_config.and_then(|rc| rc.base_branch.as_deref()))
-            .unwrap_or(&config.session.base_branch);
+    let mut rollback = Rollback::new(keep_on_failure);
+    rollback.add_session_dir(&sess_dir);
 
-        let worktree_path = sess_dir.join(&repo.name);
-        let base_ref = format!("origin/{}", base_branch);
+    // 5. Per-repo: fetch and create worktrees, several repos at a time
+    let jobs: Vec<_> = selected_repos
+        .iter()
+        .map(|repo| {
+            let base_branch = from
+                .as_deref()
+                .or_else(|| config.repos.get(&repo.name).and_then(|rc| rc.base_branch.as_deref()))
+                .unwrap_or(&config.session.base_branch);
+            (
+                repo.name.clone(),
+                repo.path.clone(),
+                sess_dir.join(&repo.name),
+                config.repo_branch(&repo.name, &branch_name),
+                base_branch.to_string(),
+                [config.remote(&repo.name).to_string(), config.push_remote(&repo.name).to_string()],
+                pinned_repos.contains(&repo.name),
+                config.repos.get(&repo.name).is_some_and(|rc| rc.pool > 0),
+            )
+        })
+        .collect();
+    let parent = parent_dir.to_path_buf();
+    let outcomes = parallel::map_blocking(
+        jobs,
+        move |(name, repo_path, worktree_path, branch, base_branch, [remote, push_remote], pinned, pooled)| {
+            let fetched = worktree::fetch_branch(&repo_path, &remote, &base_branch);
+            let base_ref = format!("{}/{}", remote, base_branch);
+            let result = create_session_worktree(
+                &parent,
+                pooled.then_some(name.as_str()),
+                &repo_path,
+                &worktree_path,
+                &branch,
+                &base_ref,
+                &push_remote,
+                pinned,
+                use_remote,
+            );
+            (name, repo_path, worktree_path, base_ref, pinned, fetched, result)
+        },
+    )
+    .await;
 
-        // Fetch
-        print!("  {} Fetching {}/{}...", style("↓").dim(), repo.name, base_branch);
-        if let Err(e) = worktree::fetch_branch(&repo.path, "origin", base_branch) {
+    let mut failure = None;
+    let mut claimed = false;
+    for (name, repo_path, worktree_path, base_ref, pinned, fetched, result) in outcomes {
+        print!("  {} Fetching {} {}...", style("↓").dim(), name, base_ref);
+        if let Err(e) = fetched {
             println!(" {}", style("warning: fetch failed, continuing").yellow());
             eprintln!("    {}", e);
         } else {
             println!(" {}", style("done").green());
         }
 
-        // Create worktree with new branch (branch guaranteed not to exist after resolve_branch_name)
-        if let Err(e) = worktree::create_worktree(&repo.path, &worktree_path, &branch_name, &base_ref) {
-            rollback_worktrees(&created_worktrees);
-            return Err(e.context(format!("failed while setting up repo '{}'", repo.name)));
+        match result {
+            Ok(source) => {
+                rollback.add_worktree(repo_path, worktree_path);
+                claimed |= source == WorktreeSource::Pool;
+                println!(
+                    "  {} Worktree created: {}{}",
+                    style("✓").green(),
+                    name,
+                    match source {
+                        _ if pinned => format!(" (pinned at {})", base_ref),
+                        WorktreeSource::Remote => format!(
+                            " (from {}/{})",
+                            config.push_remote(&name),
+                            config.repo_branch(&name, &branch_name)
+                        ),
+                        WorktreeSource::Pool => " (from the warm pool)".to_string(),
+                        WorktreeSource::Base => String::new(),
+                    }
+                );
+            }
+            Err(e) => {
+                println!("  {} {}", style("✗").red(), name);
+                if failure.is_none() {
+                    failure = Some(e.context(format!("failed while setting up repo '{}'", name)));
+                }
+            }
         }
-
-        created_worktrees.push((repo.path.clone(), worktree_path.clone()));
-        println!("  {} Worktree created: {}", style("✓").green(), repo.name);
+    }
+    if let Some(e) = failure {
+        rollback.run();
+        return Err(e);
     }
 
     // 6. Finalize session (save, copy files, MCP, context, locks, scripts, VS Code, summary)
-    super::finalize_session(
+    let prefixed_branches: HashMap<String, String> = branch_repos
+        .iter()
+        .map(|r| (r.name.clone(), config.repo_branch(&r.name, &branch_name)))
+        .filter(|(_, b)| *b != branch_name)
+        .collect();
+    let finalized = super::finalize_session(
         parent_dir,
         &config,
-        &selected_repos,
-        &branch_name,
-        &session_name,
-        &sess_dir,
-        issue_context,
-        effective_base,
-        no_setup,
-        no_vscode,
-    )?;
+        super::NewSession {
+            selected_repos: &selected_repos,
+            pinned_repos: &pinned_repos,
+            repo_branches: &HashMap::new(),
+            prefixed_branches: &prefixed_branches,
+            branch_name: &branch_name,
+            session_name: &session_name,
+            sess_dir: &sess_dir,
+            issue_context,
+            effective_base,
+            no_setup,
+            no_vscode,
+        },
+    );
+    if let Err(e) = &finalized {
+        super::roll_back_setup(parent_dir, &config, &sess_dir, e, keep_on_failure, false).await;
+    }
+    if claimed && let Err(e) = super::pool::refill_in_background(parent_dir) {
+        eprintln!("  {} Failed to refill the worktree pool: {:#}", style("!").yellow(), e);
+    }
+    finalized
+}
 
-    Ok(())
+/// Where a new session's worktree came from.
+#[derive(PartialEq, Eq)]
+enum WorktreeSource {
+    Base,
+    Remote,
+    Pool,
+}
+
+/// Create one repo's worktree for a new session. Pinned repos get a detached
+/// worktree; others a new branch, or the branch already on the push remote when the
+/// user chose to base the session on it (no local branch exists after
+/// `resolve_branch_name`). A new branch is created in a ready worktree from
+/// the repo's warm pool when `pooled` names the repo and one is available.
+#[allow(clippy::too_many_arguments)]
+fn create_session_worktree(
+    parent_dir: &Path,
+    pooled: Option<&str>,
+    repo_path: &Path,
+    worktree_path: &Path,
+    branch_name: &str,
+    base_ref: &str,
+    push_remote: &str,
+    pinned: bool,
+    use_remote: bool,
+) -> Result<WorktreeSource> {
+    if pinned {
+        worktree::create_detached_worktree(repo_path, worktree_path, base_ref)?;
+        return Ok(WorktreeSource::Base);
+    }
+    let from_remote = use_remote
+        && worktree::fetch_branch(repo_path, push_remote, branch_name).is_ok()
+        && worktree::remote_branch_exists(repo_path, push_remote, branch_name)?;
+    if from_remote {
+        worktree::checkout_existing_branch(repo_path, worktree_path, branch_name)?;
+        return Ok(WorktreeSource::Remote);
+    }
+    if let Some(repo) = pooled {
+        match pool::claim(parent_dir, repo, repo_path, base_ref, worktree_path, branch_name) {
+            Ok(true) => return Ok(WorktreeSource::Pool),
+            Ok(false) => {}
+            Err(e) => eprintln!("  {} {:#}; creating a fresh worktree", style("!").yellow(), e),
+        }
+    }
+    worktree::create_worktree(repo_path, worktree_path, branch_name, base_ref)?;
+    Ok(WorktreeSource::Base)
 }
 
 fn select_repos_interactive(
     repos: &[discovery::RepoInfo],
     config: &SeshConfig,
+    hooks: &ScriptHooks,
 ) -> Result<Vec<discovery::RepoInfo>> {
     let labels: Vec<String> = repos
         .iter()
@@ -148,6 +300,8 @@ fn select_repos_interactive(
                 .unwrap_or(true)
         })
         .collect();
+    let names: Vec<String> = repos.iter().map(|r| r.name.clone()).collect();
+    let defaults = hooks.default_repos(&names, &defaults)?;
 
     let selections = MultiSelect::new()
         .with_prompt("Select repos for this session")
@@ -168,30 +322,46 @@ fn prompt_branch_name() -> Result<String> {
     Ok(name.trim().to_string())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn resolve_branch_name(
     flag_branch: Option<&str>,
     parent_dir: &Path,
     selected_repos: &[discovery::RepoInfo],
     config: &SeshConfig,
+    hooks: &ScriptHooks,
     linear: bool,
-) -> Result<(String, Option<IssueContext>)> {
-    let is_interactive = flag_branch.is_none() && !linear;
-
-    // --linear: pick from assigned tickets (re-prompt on conflict)
-    if linear {
-        println!("  {} Fetching Linear tickets...", style("↓").dim());
-        let issues = integrations::list_linear_issues(parent_dir).await?;
-        if issues.is_empty() {
-            bail!("no assigned Linear issues found");
-        }
+    jira: bool,
+    issue: Option<&str>,
+) -> Result<(String, Option<IssueContext>, bool)> {
+    let is_interactive = flag_branch.is_none() && issue.is_none() && !linear && !jira;
+
+    // --linear / --jira: pick from assigned tickets (re-prompt on conflict)
+    if linear || jira {
+        let tickets = if linear {
+            super::auth::ensure_token(parent_dir, "linear").await?;
+            println!("  {} Fetching Linear tickets...", style("↓").dim());
+            let issues = integrations::list_linear_issues(parent_dir).await?;
+            if issues.is_empty() {
+                bail!("no assigned Linear issues found");
+            }
+            linear_tickets(&issues)
+        } else {
+            println!("  {} Fetching Jira issues...", style("↓").dim());
+            let issues = integrations::list_jira_issues(config, parent_dir).await?;
+            if issues.is_empty() {
+                bail!("no assigned Jira issues found");
+            }
+            jira_tickets(&issues)
+        };
+        let prompt = if linear { "Select a Linear ticket" } else { "Select a Jira issue" };
 
         loop {
-            let (candidate, issue_ctx) = pick_linear_ticket(&issues)?;
-            let resolved = apply_prefix(config, &candidate);
+            let (candidate, issue_ctx) = pick_ticket(prompt, &tickets)?;
+            let resolved = hooks.branch_name(&apply_branch_template(config, &candidate, Some(&issue_ctx))?, Some(&issue_ctx))?;
 
-            if let Err(e) = worktree::validate_branch_name(&resolved) {
+            if let Err(e) = validate_branch_names(config, selected_repos, &resolved) {
                 println!(
-                    "  {} '{}' is not a valid git branch name: {}",
+                    "  {} '{}' is not a valid git branch name: {:#}",
                     style("✗").red(), resolved, e
                 );
                 continue;
@@ -205,7 +375,7 @@ async fn resolve_branch_name(
             }
             let mut conflicts = Vec::new();
             for repo in selected_repos {
-                if worktree::branch_exists(&repo.path, &resolved)? {
+                if worktree::branch_exists(&repo.path, &config.repo_branch(&repo.name, &resolved))? {
                     conflicts.push(repo.name.clone());
                 }
             }
@@ -216,35 +386,57 @@ async fn resolve_branch_name(
                 );
                 continue;
             }
-            return Ok((resolved, Some(issue_ctx)));
+            let remote = remote_conflicts(selected_repos, config, &resolved)?;
+            if !remote.is_empty() {
+                if offer_remote_branch(&resolved, &remote)? {
+                    return Ok((resolved, Some(issue_ctx), true));
+                }
+                continue;
+            }
+            return Ok((resolved, Some(issue_ctx), false));
         }
     }
 
     loop {
-        // 1. Get candidate
-        let candidate = match flag_branch {
-            Some(b) => b.to_string(),
-            None => prompt_branch_name()?,
+        // 1-2. Get candidate and resolve Linear/Sentry → branch name + optional
+        // issue context (an explicit --issue skips the guessing)
+        let resolution = match issue {
+            Some(spec) => {
+                if let Some(provider) = integrations::token_for_issue(spec) {
+                    super::auth::ensure_token(parent_dir, provider).await?;
+                }
+                integrations::resolve_issue(spec, config, parent_dir).await?
+            }
+            None => {
+                let candidate = match flag_branch {
+                    Some(b) => b.to_string(),
+                    None => prompt_branch_name()?,
+                };
+                if let Some(provider) = integrations::token_for_input(&candidate, config, parent_dir) {
+                    super::auth::ensure_token(parent_dir, provider).await?;
+                }
+                integrations::resolve_branch_input(&candidate, config, parent_dir).await?
+            }
         };
 
-        // 2. Resolve Linear/Sentry → branch name + optional issue context
-        let resolution = integrations::resolve_branch_input(&candidate, config, parent_dir).await?;
-
-        // 3. Apply branch prefix
-        let branch_name = apply_prefix(config, &resolution.branch);
+        // 3. Apply the branch template (or prefix) and branch_name script hooks
+        let branch_name = hooks.branch_name(
+            &apply_branch_template(config, &resolution.branch, resolution.issue.as_ref())?,
+            resolution.issue.as_ref(),
+        )?;
 
         // 4. Validate git branch name
-        if let Err(e) = worktree::validate_branch_name(&branch_name) {
+        if let Err(e) = validate_branch_names(config, selected_repos, &branch_name) {
             if is_interactive {
                 println!(
-                    "  {} '{}' is not a valid git branch name: {}",
+                    "  {} '{}' is not a valid git branch name: {:#}",
                     style("✗").red(),
                     branch_name,
                     e
                 );
                 continue;
             }
-            bail!("'{}' is not a valid git branch name: {}", branch_name, e);
+            bail!("'{}' is not a valid git branch name: {:#}", branch_name, e);
         }
 
         // 5. Check session-level duplicate
@@ -267,7 +459,7 @@ async fn resolve_branch_name(
         // 6. Check branch existence in ALL selected repos
         let mut conflicts = Vec::new();
         for repo in selected_repos {
-            if worktree::branch_exists(&repo.path, &branch_name)? {
+            if worktree::branch_exists(&repo.path, &config.repo_branch(&repo.name, &branch_name))? {
                 conflicts.push(repo.name.clone());
             }
         }
@@ -289,12 +481,83 @@ async fn resolve_branch_name(
             );
         }
 
-        return Ok((branch_name, resolution.issue));
+        // 7. Check the push remote: pushing a new branch over an existing remote one would fail later
+        let remote = remote_conflicts(selected_repos, config, &branch_name)?;
+        if !remote.is_empty() {
+            if offer_remote_branch(&branch_name, &remote)? {
+                return Ok((branch_name, resolution.issue, true));
+            }
+            if is_interactive {
+                continue;
+            }
+            bail!(
+                "branch '{}' already exists on the remote in: {}. Use `sesh checkout --branch` to work on it, or choose a different branch.",
+                branch_name,
+                remote.join(", ")
+            );
+        }
+
+        return Ok((branch_name, resolution.issue, false));
+    }
+}
+
+/// Validate `branch` and the name it takes in each repo with its own
+/// `branch_template` or `branch_prefix`.
+fn validate_branch_names(config: &SeshConfig, repos: &[discovery::RepoInfo], branch: &str) -> Result<()> {
+    worktree::validate_branch_name(branch)?;
+    for repo in repos {
+        let repo_branch = config.repo_branch(&repo.name, branch);
+        if repo_branch != branch {
+            worktree::validate_branch_name(&repo_branch)
+                .with_context(|| format!("in repo '{}'", repo.name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Repos whose push remote already has `branch` (under the repo's prefix or template).
+/// The remote is queried directly so branches pushed since the last fetch count;
+/// when it can't be reached, the remote-tracking refs are used instead.
+fn remote_conflicts(repos: &[discovery::RepoInfo], config: &SeshConfig, branch: &str) -> Result<Vec<String>> {
+    let mut conflicts = Vec::new();
+    for repo in repos {
+        let repo_branch = config.repo_branch(&repo.name, branch);
+        let remote = config.push_remote(&repo.name);
+        let exists = match worktree::remote_has_branch(&repo.path, remote, &repo_branch) {
+            Ok(exists) => exists,
+            Err(_) => worktree::remote_branch_exists(&repo.path, remote, &repo_branch)?,
+        };
+        if exists {
+            conflicts.push(repo.name.clone());
+        }
+    }
+    Ok(conflicts)
+}
+
+/// Offer to base the session on a branch that already exists on the remote, as
+/// `sesh checkout` would. Without a terminal to ask on, the answer is no.
+fn offer_remote_branch(branch: &str, repos: &[String]) -> Result<bool> {
+    println!(
+        "  {} Branch '{}' already exists on the remote in: {}.",
+        style("!").yellow(),
+        branch,
+        repos.join(", ")
+    );
+    if !Term::stderr().is_term() {
+        return Ok(false);
     }
+    Confirm::new()
+        .with_prompt("Base the session on the remote branch instead?")
+        .default(true)
+        .interact()
+        .context("confirmation cancelled")
 }
 
-fn pick_linear_ticket(issues: &[integrations::LinearIssueSummary]) -> Result<(String, IssueContext)> {
-    let labels: Vec<String> = issues
+/// A pickable ticket: display label, branch name, and issue context.
+type Ticket = (String, String, IssueContext);
+
+fn linear_tickets(issues: &[integrations::LinearIssueSummary]) -> Vec<Ticket> {
+    issues
         .iter()
         .map(|i| {
             let state_colored = integrations::color_text(
@@ -309,20 +572,87 @@ fn pick_linear_ticket(issues: &[integrations::LinearIssueSummary]) -> Result<(St
                     .collect();
                 format!(" [{}]", colored_labels.join(", "))
             };
-            format!("{} {} — {}{}", i.identifier, state_colored, i.title, label_str)
+            (
+                format!("{} {} — {}{}", i.identifier, state_colored, i.title, label_str),
+                integrations::branch_name_from_linear_issue(i),
+                integrations::issue_context_from_linear_summary(i),
+            )
         })
-        .collect();
+        .collect()
+}
+
+fn jira_tickets(issues: &[integrations::JiraIssueSummary]) -> Vec<Ticket> {
+    issues
+        .iter()
+        .map(|i| {
+            let label_str = if i.labels.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", i.labels.join(", "))
+            };
+            (
+                format!("{} {} — {}{}", i.key, style(&i.status).dim(), i.title, label_str),
+                integrations::branch_name_from_jira_issue(i),
+                integrations::issue_context_from_jira_summary(i),
+            )
+        })
+        .collect()
+}
+
+fn pick_ticket(prompt: &str, tickets: &[Ticket]) -> Result<(String, IssueContext)> {
+    let labels: Vec<&str> = tickets.iter().map(|(label, _, _)| label.as_str()).collect();
 
     let selection = FuzzySelect::new()
-        .with_prompt("Select a Linear ticket")
+        .with_prompt(prompt)
         .items(&labels)
         .default(0)
         .interact()
         .context("ticket selection cancelled")?;
 
-    let branch = integrations::branch_name_from_linear_issue(&issues[selection]);
-    let issue_ctx = integrations::issue_context_from_linear_summary(&issues[selection]);
-    Ok((branch, issue_ctx))
+    let (_, branch, issue_ctx) = &tickets[selection];
+    Ok((branch.clone(), issue_ctx.clone()))
+}
+
+/// The branch for `name` (what the input or ticket resolved to) under
+/// `[session] branch_template`, falling back to `branch_prefix`. Typed names
+/// that already contain a `/` are taken as full branch names.
+fn apply_branch_template(config: &SeshConfig, name: &str, issue: Option<&IssueContext>) -> Result<String> {
+    let Some(template) = &config.session.branch_template else {
+        return Ok(apply_prefix(config, name));
+    };
+    let (ticket, slug) = match issue {
+        Some(issue) => (
+            integrations::slugify(&issue.identifier),
+            integrations::truncate(&integrations::slugify(&issue.title), 50),
+        ),
+        None if name.contains('/') => return Ok(name.to_string()),
+        None => (String::new(), integrations::slugify(name)),
+    };
+    let user = if template.contains("{user}") { branch_user(config)? } else { String::new() };
+    Ok(integrations::render_branch_template(
+        template,
+        &[("user", &user), ("ticket", &ticket), ("slug", &slug), ("name", name)],
+    ))
+}
+
+/// `{user}` for branch templates: `[session] branch_user`, else the slugified
+/// `git config user.name`.
+fn branch_user(config: &SeshConfig) -> Result<String> {
+    let user = match &config.session.branch_user {
+        Some(user) => user.clone(),
+        None => {
+            let output = std::process::Command::new("git")
+                .args(["config", "user.name"])
+                .output()
+                .context("failed to run git config user.name")?;
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        }
+    };
+    let user = integrations::slugify(&user);
+    if user.is_empty() {
+        bail!("branch_template uses {{user}} but git config user.name is empty; set [session] branch_user");
+    }
+    Ok(user)
 }
 
 fn apply_prefix(config: &SeshConfig, branch: &str) -> String {
@@ -332,12 +662,3 @@ fn apply_prefix(config: &SeshConfig, branch: &str) -> String {
     }
 }
 
-fn rollback_worktrees(created: &[(PathBuf, PathBuf)]) {
-    eprintln!("\n  {} Rolling back created worktrees...", style("✗").red());
-    for (repo_path, worktree_path) in created.iter().rev() {
-        if let Err(e) = worktree::remove_worktree(repo_path, worktree_path) {
-            eprintln!("    Failed to remove worktree {}: {}", worktree_path.display(), e);
-        }
-    }
-}
-
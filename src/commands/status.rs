@@ -1,24 +1,159 @@
-use std::path::Path;
-use std::process::Command;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
-use console::style;
+use anyhow::{Context, Result};
+use chrono::{Local, Utc};
+use console::{style, Term};
+use notify::{EventKind, RecursiveMode, Watcher};
 
-use super::pick_session;
+use crate::config::SeshConfig;
+use crate::gitstate;
+use crate::manifest;
+use crate::parallel;
+use crate::scripts::{self, ProcessState};
+use crate::session::{self, Health, SessionInfo, SessionRepo};
+use crate::worktree;
 
-pub fn run(parent_dir: &Path, name: Option<String>) -> Result<()> {
+use super::{pick_session, repo_base};
+use super::ps::uptime;
+
+/// Lines of each background script's log shown under it.
+const LOG_TAIL: usize = 3;
+
+pub async fn run(parent_dir: &Path, name: Option<String>, changes: bool, watch: Option<u64>) -> Result<()> {
     let session = pick_session(parent_dir, name)?;
+    match watch {
+        Some(interval) => watch_status(parent_dir, session, Duration::from_secs(interval)).await,
+        None => render(parent_dir, &session, changes).await,
+    }
+}
+
+/// Clear the terminal and re-render the status every `interval`, or sooner
+/// when files in the session's worktrees change, until interrupted.
+async fn watch_status(parent_dir: &Path, mut session: SessionInfo, interval: Duration) -> Result<()> {
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::RecommendedWatcher::new(tx, notify::Config::default().with_follow_symlinks(false))
+        .context("failed to start file watcher")?;
+    let mut watched = Vec::new();
+    for repo in session.repos.iter().filter(|r| r.worktree_path.exists()) {
+        // Too many files for the OS watch limit still refreshes on the interval
+        if watcher.watch(&repo.worktree_path, RecursiveMode::Recursive).is_ok() {
+            watched.push(repo.worktree_path.clone());
+        }
+    }
+
+    let term = Term::stdout();
+    loop {
+        term.clear_screen()?;
+        println!(
+            "{}",
+            style(format!(
+                "Every {}s and on file changes · {}  (Ctrl-C to exit)",
+                interval.as_secs(),
+                Local::now().format("%H:%M:%S")
+            ))
+            .dim()
+        );
+        gitstate::invalidate();
+        render(parent_dir, &session, false).await?;
+
+        let debounce = Duration::from_millis(config.watch.debounce_ms);
+        let ignore = &config.watch.ignore;
+        tokio::task::block_in_place(|| wait_for_change(&rx, &watched, ignore, interval, debounce));
+        // Picks up URLs, pins and removal of the session since the last render
+        session = pick_session(parent_dir, Some(session.name.clone()))?;
+    }
+}
+
+/// Block until `interval` passes or a file under `watched` changes (outside
+/// the `[watch] ignore` components), then let a burst of changes settle.
+fn wait_for_change(
+    rx: &Receiver<notify::Result<notify::Event>>,
+    watched: &[PathBuf],
+    ignore: &[String],
+    interval: Duration,
+    debounce: Duration,
+) {
+    let relevant = |event: &notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return false;
+        };
+        matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_))
+            && event.paths.iter().any(|path| {
+                watched.iter().any(|root| {
+                    path.strip_prefix(root).is_ok_and(|rel| {
+                        !rel.components().any(|c| ignore.iter().any(|i| c.as_os_str() == i.as_str()))
+                    })
+                })
+            })
+    };
+
+    let deadline = Instant::now() + interval;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(left) {
+            Ok(event) if relevant(&event) => break,
+            Ok(_) => {}
+            Err(RecvTimeoutError::Timeout) => return,
+            Err(RecvTimeoutError::Disconnected) => {
+                std::thread::sleep(left);
+                return;
+            }
+        }
+    }
+    // A process that never stops writing still gets a refresh every interval
+    let settle = Instant::now() + interval;
+    while Instant::now() < settle && rx.recv_timeout(debounce).is_ok() {}
+}
+
+async fn render(parent_dir: &Path, session: &SessionInfo, changes: bool) -> Result<()> {
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+    let sess_dir = session::session_dir(parent_dir, &session.name);
+    let mut markers = session::load_review_markers(&sess_dir);
+    let generated = manifest::load(&sess_dir);
+
+    // Query every worktree up front, concurrently; the loop below then reads
+    // the results from the cache
+    let paths: Vec<PathBuf> = session
+        .repos
+        .iter()
+        .map(|r| r.worktree_path.clone())
+        .filter(|p| p.exists())
+        .collect();
+    gitstate::prefetch_status(&paths).await;
+    gitstate::prefetch_remote_refs(&paths).await;
+    let logs = parallel::map_bounded(paths.clone(), |path| async move {
+        let log = gitstate::git_async(&path, &["log", "--oneline", "-5"]).await;
+        (path, log)
+    })
+    .await;
 
     println!(
         "Session: {}  Branch: {}",
         style(&session.name).cyan().bold(),
         style(&session.branch).green(),
     );
+    if !session.urls.is_empty() {
+        println!("URLs:");
+        for (label, url) in &session.urls {
+            println!("  {}  {}", style(label).cyan(), url);
+        }
+    }
+    print_background(&sess_dir);
     println!();
 
     for repo in &session.repos {
         println!("{}", style(format!("── {} ──", repo.name)).bold());
         println!("  Path: {}", repo.worktree_path.display());
+        if let Some(branch) = &repo.branch {
+            println!("  Branch: {}", style(branch).green());
+        }
+        if repo.pinned {
+            println!("  {}", style("Pinned (read-only, detached at base)").dim());
+        }
 
         if !repo.worktree_path.exists() {
             println!("  {}", style("(worktree missing)").red());
@@ -26,46 +161,207 @@ pub fn run(parent_dir: &Path, name: Option<String>) -> Result<()> {
             continue;
         }
 
-        // git status --short
-        let wt = repo.worktree_path.to_string_lossy();
-        let status_output = Command::new("git")
-            .args(["-C", &wt, "status", "--short"])
-            .output();
-
-        match status_output {
-            Ok(output) => {
-                let text = String::from_utf8_lossy(&output.stdout);
-                if text.trim().is_empty() {
-                    println!("  {}", style("Clean working tree").dim());
-                } else {
-                    for line in text.lines() {
-                        println!("  {}", line);
-                    }
+        if !repo.pinned {
+            print_tracking(&config, session, repo);
+        }
+
+        match gitstate::status(&repo.worktree_path) {
+            Ok(status) if !status.is_dirty() => println!("  {}", style("Clean working tree").dim()),
+            Ok(status) => {
+                for line in &status.changes {
+                    println!("  {}", line);
                 }
             }
             Err(e) => println!("  {}", style(format!("Failed to get status: {}", e)).red()),
         }
 
-        // git log --oneline -5
-        let log_output = Command::new("git")
-            .args(["-C", &wt, "log", "--oneline", "-5"])
-            .output();
-
-        match log_output {
-            Ok(output) => {
-                let text = String::from_utf8_lossy(&output.stdout);
-                if !text.trim().is_empty() {
-                    println!("  {}", style("Recent commits:").dim());
-                    for line in text.lines() {
-                        println!("    {}", line);
-                    }
+        match logs.iter().find(|(path, _)| *path == repo.worktree_path).map(|(_, log)| log) {
+            Some(Ok(text)) if !text.trim().is_empty() => {
+                println!("  {}", style("Recent commits:").dim());
+                for line in text.lines() {
+                    println!("    {}", line);
                 }
             }
-            Err(e) => println!("  {}", style(format!("Failed to get log: {}", e)).red()),
+            Some(Err(e)) => println!("  {}", style(format!("Failed to get log: {}", e)).red()),
+            _ => {}
+        }
+
+        if let Some(files) = generated.get(&repo.name) {
+            let base_ref = base_ref(&config, session, repo);
+            if let Ok(leaked) = manifest::leaked_files(&repo.worktree_path, files, &base_ref)
+                && !leaked.is_empty()
+            {
+                println!(
+                    "  {} sesh-generated file(s) staged or committed: {}",
+                    style("!").yellow(),
+                    leaked.join(", ")
+                );
+            }
+        }
+
+        if changes {
+            match show_changes_since_review(&config, session, repo, markers.get(&repo.name)) {
+                Ok(head) => {
+                    markers.insert(repo.name.clone(), head);
+                }
+                Err(e) => println!("  {}", style(format!("Failed to get changes: {}", e)).red()),
+            }
         }
 
         println!();
     }
 
+    if changes {
+        session::save_review_markers(&sess_dir, &markers)?;
+    }
+
     Ok(())
 }
+
+/// Background processes with their PID, whether they're still running (and
+/// for how long), how their `health_check` went at startup, and the end of
+/// their log. Processes found gone are shown as dead, as `sesh ps` does; the
+/// file itself is left alone.
+fn print_background(sess_dir: &Path) {
+    let mut pids = session::load_background_pids(sess_dir);
+    if pids.is_empty() {
+        return;
+    }
+    let states = scripts::reconcile_background_pids(sess_dir, &mut pids);
+    println!("Background:");
+    for (bp, state) in pids.iter().zip(states) {
+        let running = match state {
+            ProcessState::Dead => style("dead").red(),
+            _ if bp.paused => style("paused").yellow(),
+            _ => style("running").green(),
+        };
+        let health = match bp.health {
+            Some(Health::Healthy) => style(", healthy").green(),
+            Some(Health::Unhealthy) => style(", unhealthy").red(),
+            None => style(""),
+        };
+        let mut details = vec![format!("pid {}", bp.pid)];
+        if state != ProcessState::Dead {
+            if let Some(port) = scripts::recorded_port(sess_dir, &bp.label) {
+                details.push(format!("port {}", port));
+            }
+            if let Some(started) = bp.started_at {
+                details.push(format!("up {}", uptime(Utc::now() - started)));
+            }
+        }
+        println!(
+            "  {}  {}{}  {}",
+            style(&bp.label).cyan(),
+            running,
+            health,
+            style(details.join(", ")).dim()
+        );
+        for line in log_tail(&sess_dir.join("logs").join(format!("{}.log", bp.label)), LOG_TAIL) {
+            println!("    {}", style(format!("│ {}", line)).dim());
+        }
+    }
+}
+
+/// The last `n` non-empty lines of a log, reading only its end.
+fn log_tail(path: &Path, n: usize) -> Vec<String> {
+    let Ok(mut file) = std::fs::File::open(path) else {
+        return Vec::new();
+    };
+    let len = file.metadata().map_or(0, |m| m.len());
+    let start = len.saturating_sub(16 * 1024);
+    let mut buf = Vec::new();
+    if file.seek(SeekFrom::Start(start)).is_err() || file.read_to_end(&mut buf).is_err() {
+        return Vec::new();
+    }
+    let text = String::from_utf8_lossy(&buf);
+    let mut lines: Vec<String> = text
+        .lines()
+        // The first line may be cut off when reading from the middle
+        .skip(usize::from(start > 0))
+        .filter(|l| !l.trim().is_empty())
+        .map(String::from)
+        .collect();
+    lines.drain(..lines.len().saturating_sub(n));
+    lines
+}
+
+/// The remote-tracking ref of the session's base branch in `repo`.
+fn base_ref(config: &SeshConfig, session: &SessionInfo, repo: &SessionRepo) -> String {
+    format!("{}/{}", config.remote(&repo.name), repo_base(config, session, &repo.name))
+}
+
+/// Print commits ahead/behind the base branch and the pushed branch, from the
+/// local remote-tracking refs (as of the last fetch).
+fn print_tracking(config: &SeshConfig, session: &SessionInfo, repo: &SessionRepo) {
+    let base_ref = base_ref(config, session, repo);
+    match gitstate::ahead_behind(&repo.worktree_path, &base_ref) {
+        Ok((ahead, behind)) => println!("  Base ({}): {}", base_ref, describe_counts(ahead, behind)),
+        Err(e) => println!("  {}", style(format!("Failed to compare with {}: {}", base_ref, e)).red()),
+    }
+
+    let branch = session.branch_for(repo);
+    let remote_ref = format!("{}/{}", config.push_remote(&repo.name), branch);
+    match gitstate::has_remote_ref(&repo.worktree_path, &remote_ref) {
+        Ok(true) => match gitstate::ahead_behind(&repo.worktree_path, &remote_ref) {
+            Ok((ahead, behind)) => {
+                println!("  Remote ({}): {}", remote_ref, describe_counts(ahead, behind))
+            }
+            Err(e) => println!("  {}", style(format!("Failed to compare with {}: {}", remote_ref, e)).red()),
+        },
+        Ok(false) => println!("  Remote: {}", style("not pushed").yellow()),
+        Err(e) => println!("  {}", style(format!("Failed to check {}: {}", remote_ref, e)).red()),
+    }
+}
+
+fn describe_counts(ahead: u32, behind: u32) -> String {
+    match (ahead, behind) {
+        (0, 0) => style("up to date").dim().to_string(),
+        _ => format!(
+            "{} ahead, {} behind",
+            style(ahead).green(),
+            if behind > 0 { style(behind).yellow() } else { style(behind) }
+        ),
+    }
+}
+
+/// Print files changed since the repo's review marker (or since the base branch
+/// on first review), grouped by author. Returns the new marker (HEAD).
+fn show_changes_since_review(
+    config: &SeshConfig,
+    session: &SessionInfo,
+    repo: &SessionRepo,
+    marker: Option<&String>,
+) -> Result<String> {
+    let head = worktree::head_sha(&repo.worktree_path)?;
+    let since = match marker {
+        Some(sha) => sha.clone(),
+        None => {
+            let base = base_ref(config, session, repo);
+            worktree::merge_base(&repo.worktree_path, &base, "HEAD")?
+        }
+    };
+
+    let short = &since[..since.len().min(7)];
+    let label = if marker.is_some() { "last review" } else { "base" };
+    println!("  {}", style(format!("Changes since {} ({}):", label, short)).dim());
+
+    let groups = worktree::changed_files_by_author(&repo.worktree_path, &format!("{}..HEAD", since))?;
+    if groups.is_empty() {
+        println!("    {}", style("No new commits").dim());
+    }
+
+    let me = worktree::user_email(&repo.worktree_path);
+    for (name, email, files) in &groups {
+        let who = if me.as_deref() == Some(email.as_str()) {
+            style(format!("you ({})", email)).green()
+        } else {
+            style(format!("{} ({})", name, email)).yellow()
+        };
+        println!("    {}", who);
+        for file in files {
+            println!("      {}", file);
+        }
+    }
+
+    Ok(head)
+}
@@ -1,132 +1,420 @@
 use std::path::Path;
 
-use anyhow::Result;
-use console::style;
+use anyhow::{bail, Context, Result};
+use console::{style, Term};
+use dialoguer::Confirm;
 
-use crate::config::SeshConfig;
+use crate::config::{Hook, LinearOnStop, ScriptEntry, SeshConfig};
+use crate::history;
+use crate::hooks;
+use crate::integrations;
 use crate::lock;
+use crate::parallel;
 use crate::scripts;
-use crate::session;
+use crate::session::{self, SessionInfo, SessionRepo};
 use crate::worktree;
 
 use super::pick_session;
 
-pub fn run(parent_dir: &Path, name: Option<String>, keep_branches: bool) -> Result<()> {
+pub async fn run(
+    parent_dir: &Path,
+    name: Option<String>,
+    keep_branches: bool,
+    prune_remote: bool,
+    force: bool,
+    keep_scratch: bool,
+) -> Result<()> {
     let session = pick_session(parent_dir, name)?;
+    if session.pinned_at.is_some() && !force {
+        bail!(
+            "session '{}' is pinned; pass --force to stop it anyway, or `sesh unpin {}` first",
+            session.name,
+            session.name
+        );
+    }
+
+    if !force {
+        let at_risk = work_at_risk(&session, |repo| deletes_branch(repo, keep_branches));
+        if !at_risk.is_empty() {
+            println!(
+                "{} Stopping '{}' would lose work:\n",
+                style("!").yellow(),
+                session.name
+            );
+            for (repo, problems) in &at_risk {
+                println!("  {} {}", style(repo).cyan(), problems.join(", "));
+            }
+            println!();
+            if !Term::stderr().is_term() {
+                bail!("refusing to stop '{}'; commit/push first or pass --force", session.name);
+            }
+            let proceed = Confirm::new()
+                .with_prompt("Stop anyway?")
+                .default(false)
+                .interact()?;
+            if !proceed {
+                return Ok(());
+            }
+        }
+    }
+
+    let config_path = parent_dir.join("sesh.toml");
+    let config = SeshConfig::load(&config_path)?;
+    let repo_names: Vec<String> = session.repos.iter().map(|r| r.name.clone()).collect();
+    let session_env = session.script_env();
+    super::run_hook(
+        parent_dir,
+        &config,
+        Hook::PreStop,
+        &session::session_dir(parent_dir, &session.name),
+        &session.name,
+        &session.branch,
+        &repo_names,
+        &session_env,
+    )?;
+    teardown(parent_dir, &config, &session, keep_branches, prune_remote, keep_scratch).await?;
+    super::run_hook(
+        parent_dir,
+        &config,
+        Hook::PostStop,
+        parent_dir,
+        &session.name,
+        &session.branch,
+        &repo_names,
+        &session_env,
+    )?;
+
+    if config.linear.on_stop == LinearOnStop::Comment
+        && let Some(issue) = session.issue.as_ref().filter(|i| i.provider == "linear")
+    {
+        let body = stop_comment(&session);
+        match integrations::comment_on_linear_issue(parent_dir, &issue.identifier, &body).await {
+            Ok(()) => println!("{} Commented on {}", style("✓").green(), issue.identifier),
+            Err(e) => eprintln!(
+                "  {}: {:#}",
+                style(format!("Failed to comment on {}", issue.identifier)).yellow(),
+                e
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// The `[linear] on_stop = "comment"` note: finished if `sesh pr` opened PRs
+/// (linked), paused otherwise.
+fn stop_comment(session: &SessionInfo) -> String {
+    if session.pull_requests.is_empty() {
+        return format!(
+            "Work paused: sesh session `{}` (branch `{}`) was stopped without opening a PR.",
+            session.name, session.branch
+        );
+    }
+    let mut body = format!(
+        "Work finished in sesh session `{}` (branch `{}`).\n\nPull requests:\n",
+        session.name, session.branch
+    );
+    for (repo, url) in &session.pull_requests {
+        body.push_str(&format!("- {}: {}\n", repo, url));
+    }
+    body
+}
+
+/// Stop `session`'s services, remove its worktrees, branches (unless
+/// `keep_branches`) and locks, and delete the session directory. With
+/// `prune_remote` (or `[session] prune_remote_on_stop`), stale remote-tracking
+/// refs in its repos are pruned too. With `keep_scratch` its `scratch/` is
+/// archived instead of deleted. Repos are cleaned up several at a time.
+pub async fn teardown(
+    parent_dir: &Path,
+    config: &SeshConfig,
+    session: &SessionInfo,
+    keep_branches: bool,
+    prune_remote: bool,
+    keep_scratch: bool,
+) -> Result<()> {
     let session_dir = session::session_dir(parent_dir, &session.name);
+    session::ensure_managed_path(parent_dir, &session_dir)
+        .with_context(|| format!("refusing to clean up session '{}'", session.name))?;
+    stop_services(parent_dir, config, session, &session_dir).await;
 
+    // Remove worktrees and branches, several repos at a time
+    let prune = prune_remote || config.session.prune_remote_on_stop;
+    let jobs: Vec<_> = session
+        .repos
+        .iter()
+        .map(|repo| {
+            let branch = deletes_branch(repo, keep_branches).then(|| session.branch_for(repo).to_string());
+            let mut remotes = Vec::new();
+            if prune {
+                remotes.push(config.remote(&repo.name).to_string());
+                if config.push_remote(&repo.name) != remotes[0] {
+                    remotes.push(config.push_remote(&repo.name).to_string());
+                }
+            }
+            (repo.clone(), branch, remotes)
+        })
+        .collect();
+    println!("Removing {} worktree(s)...", jobs.len());
+    let (parent, sess_dir) = (parent_dir.to_path_buf(), session_dir.clone());
+    let results = parallel::map_blocking(jobs, move |(repo, branch, remotes)| {
+        remove_repo(&parent, &sess_dir, &repo, branch.as_deref(), &remotes)
+    })
+    .await;
+    for (repo, result) in session.repos.iter().zip(results) {
+        match result {
+            Ok(warnings) => {
+                println!("  {} {}", style("✓").green(), style(&repo.name).cyan());
+                for warning in warnings {
+                    eprintln!("  Warning: {}", warning);
+                }
+            }
+            // Never force-remove a directory sesh doesn't own
+            Err(e) => eprintln!(
+                "  {} refusing to remove worktree for {}: {:#}; check its session.json",
+                style("Error:").red().bold(),
+                repo.name,
+                e
+            ),
+        }
+    }
+
+    release_locks(parent_dir, config, session);
+
+    // Archive logs for post-mortems before the session directory goes away
+    if let Some(keep_days) = config.scripts.keep_logs_days.filter(|&d| d > 0) {
+        match session::archive_logs(parent_dir, &session_dir, &session.name) {
+            Ok(Some(dest)) => println!("Logs archived to {}", dest.display()),
+            Ok(None) => {}
+            Err(e) => eprintln!("  Warning: failed to archive logs: {}", e),
+        }
+        session::prune_log_archives(parent_dir, keep_days);
+    }
+    if keep_scratch {
+        match session::archive_scratch(parent_dir, &session_dir, &session.name) {
+            Ok(Some(dest)) => println!("Scratch kept at {}", dest.display()),
+            Ok(None) => {}
+            Err(e) => eprintln!("  Warning: failed to keep scratch: {}", e),
+        }
+    }
+
+    // Remove session directory
+    session::delete_session_dir(&session_dir)?;
+    history::record(parent_dir, &session.name, history::Event::Stopped);
+
+    println!(
+        "{} Session '{}' stopped and cleaned up.",
+        style("✔").green(),
+        session.name,
+    );
+
+    Ok(())
+}
+
+/// Kill a session's background processes and run its teardown scripts: each
+/// repo's in order, several repos at a time, then the global ones once every
+/// repo is done. Failures are reported but don't stop the rest.
+pub async fn stop_services(parent_dir: &Path, config: &SeshConfig, session: &SessionInfo, session_dir: &Path) {
     // Kill background processes
-    let bg_pids = session::load_background_pids(&session_dir);
+    let bg_pids = session::load_background_pids(session_dir);
     if !bg_pids.is_empty() {
         println!(
             "Killing {} background process(es)...",
             bg_pids.len()
         );
         scripts::kill_background_pids(&bg_pids);
+        if let Err(e) = session::save_background_pids(session_dir, &[]) {
+            eprintln!("  Warning: {}", e);
+        }
     }
 
-    // Run teardown scripts
-    let config_path = parent_dir.join("sesh.toml");
-    let config = SeshConfig::load(&config_path)?;
-    let repo_names: Vec<String> = session.repos.iter().map(|r| r.name.clone()).collect();
-
     // Per-repo teardown scripts (run before global teardown)
-    for repo in &session.repos {
-        if let Some(repo_config) = config.repos.get(&repo.name) {
-            for entry in &repo_config.teardown {
-                let script_path = parent_dir.join(&entry.path);
-                if script_path.exists() {
-                    println!(
-                        "Running teardown for {}: {}...",
-                        style(&repo.name).cyan(),
-                        entry.path
-                    );
-                    if let Err(e) = scripts::run_script_entry(
-                        "teardown",
-                        entry,
-                        &script_path,
-                        &repo.worktree_path,
-                        &session.name,
-                        &session.branch,
-                        &repo_names,
-                        &[("SESH_REPO", repo.name.as_str())],
-                    ) {
-                        eprintln!(
-                            "  Warning: teardown script '{}' for {} failed: {}",
-                            entry.path, repo.name, e
-                        );
-                    }
-                }
-            }
-        }
-    }
+    let jobs: Vec<(SessionRepo, Vec<ScriptEntry>)> = session
+        .repos
+        .iter()
+        .filter_map(|repo| {
+            let teardown = &config.repos.get(&repo.name)?.teardown;
+            let entries: Vec<ScriptEntry> = teardown
+                .iter()
+                .filter(|e| e.is_available(parent_dir))
+                .cloned()
+                .collect();
+            (!entries.is_empty()).then(|| (repo.clone(), entries))
+        })
+        .collect();
+    let (parent, owned) = (parent_dir.to_path_buf(), session.clone());
+    parallel::map_blocking(jobs, move |(repo, entries)| {
+        run_repo_teardown(&parent, &owned, &repo, &entries)
+    })
+    .await;
+
+    let repo_names: Vec<String> = session.repos.iter().map(|r| r.name.clone()).collect();
+    let session_env = session.script_env();
+    let session_env: Vec<(&str, &str)> = session_env.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
 
     // Global teardown scripts
     for entry in &config.scripts.teardown {
         let script_path = parent_dir.join(&entry.path);
-        if script_path.exists() {
-            println!("Running teardown: {}...", entry.path);
-            if let Err(e) = scripts::run_script_entry(
+        if entry.is_available(parent_dir) {
+            println!("Running teardown: {}...", entry.name());
+            let result = scripts::run_script_entry(
                 "teardown",
                 entry,
                 &script_path,
-                &session_dir,
+                session_dir,
                 &session.name,
                 &session.branch,
                 &repo_names,
-                &[],
-            ) {
-                eprintln!("  Warning: teardown script '{}' failed: {}", entry.path, e);
+                &session_env,
+                None,
+            );
+            history::record(
+                parent_dir,
+                &session.name,
+                history::Event::script("teardown", entry.name(), None, history::outcome(&result)),
+            );
+            if let Err(e) = result {
+                eprintln!("  Warning: teardown script '{}' failed: {}", entry.name(), e);
             }
         }
     }
+}
 
-    // Remove worktrees
-    for repo in &session.repos {
-        println!("Removing worktree for {}...", style(&repo.name).cyan());
-        if let Err(e) = worktree::remove_worktree(&repo.original_repo_path, &repo.worktree_path) {
-            eprintln!("  Warning: failed to remove worktree for {}: {}", repo.name, e);
-        }
-        if let Err(e) = worktree::prune_worktrees(&repo.original_repo_path) {
-            eprintln!("  Warning: failed to prune worktrees for {}: {}", repo.name, e);
+/// Run one repo's teardown scripts in order, reporting failures.
+fn run_repo_teardown(parent_dir: &Path, session: &SessionInfo, repo: &SessionRepo, entries: &[ScriptEntry]) {
+    let repo_names: Vec<String> = session.repos.iter().map(|r| r.name.clone()).collect();
+    let session_env = session.script_env();
+    let mut repo_env: Vec<(&str, &str)> = session_env.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    repo_env.push(("SESH_REPO", repo.name.as_str()));
+
+    for entry in entries {
+        println!(
+            "Running teardown for {}: {}...",
+            style(&repo.name).cyan(),
+            entry.name()
+        );
+        let result = scripts::run_script_entry(
+            "teardown",
+            entry,
+            &parent_dir.join(&entry.path),
+            &repo.worktree_path,
+            &session.name,
+            session.branch_for(repo),
+            &repo_names,
+            &repo_env,
+            None,
+        );
+        history::record(
+            parent_dir,
+            &session.name,
+            history::Event::script(
+                "teardown",
+                entry.name(),
+                Some(&repo.name),
+                history::outcome(&result),
+            ),
+        );
+        if let Err(e) = result {
+            eprintln!(
+                "  Warning: teardown script '{}' for {} failed: {}",
+                entry.name(), repo.name, e
+            );
         }
     }
+}
 
-    // Delete branches unless --keep-branches
-    if !keep_branches {
-        for repo in &session.repos {
-            if let Err(e) = worktree::delete_branch(&repo.original_repo_path, &session.branch) {
-                eprintln!("  Warning: failed to delete branch '{}' in {}: {}", session.branch, repo.name, e);
-            }
+/// Remove one repo's session hooks and worktree, delete `branch` if given,
+/// and prune stale remote-tracking refs for `prune_remotes`. Refuses (with an
+/// error) a worktree outside `.sesh/sessions/`; other failures come back as
+/// warnings so they print in repo order.
+fn remove_repo(
+    parent_dir: &Path,
+    session_dir: &Path,
+    repo: &SessionRepo,
+    branch: Option<&str>,
+    prune_remotes: &[String],
+) -> Result<Vec<String>> {
+    session::ensure_managed_path(parent_dir, &repo.worktree_path)?;
+
+    let mut warnings = Vec::new();
+    if hooks::session_hooks_dir(session_dir, &repo.name).exists()
+        && repo.worktree_path.exists()
+        && let Err(e) = hooks::remove_hooks(&repo.worktree_path)
+    {
+        warnings.push(format!("failed to remove session hooks for {}: {}", repo.name, e));
+    }
+    if let Err(e) = worktree::remove_worktree(&repo.original_repo_path, &repo.worktree_path) {
+        warnings.push(format!("failed to remove worktree for {}: {}", repo.name, e));
+    }
+    if let Err(e) = worktree::prune_worktrees(&repo.original_repo_path) {
+        warnings.push(format!("failed to prune worktrees for {}: {}", repo.name, e));
+    }
+    if let Some(branch) = branch
+        && let Err(e) = worktree::delete_branch(&repo.original_repo_path, branch)
+    {
+        warnings.push(format!("failed to delete branch '{}' in {}: {}", branch, repo.name, e));
+    }
+    for remote in prune_remotes {
+        if let Err(e) = worktree::prune_remote(&repo.original_repo_path, remote) {
+            warnings.push(format!("failed to prune remote-tracking refs for {}: {}", repo.name, e));
         }
     }
+    Ok(warnings)
+}
 
-    // Release exclusive locks held by this session
+/// Release the exclusive locks `session` holds.
+pub fn release_locks(parent_dir: &Path, config: &SeshConfig, session: &SessionInfo) {
     for repo in &session.repos {
         let is_exclusive = config
             .repos
             .get(&repo.name)
             .map(|rc| rc.exclusive)
             .unwrap_or(false);
-        if is_exclusive {
-            if let Ok(Some(lock_info)) = lock::check_lock(parent_dir, &repo.name) {
-                if lock_info.session == session.name {
-                    if let Err(e) = lock::release_lock(parent_dir, &repo.name) {
-                        eprintln!("  Warning: failed to release lock for {}: {}", repo.name, e);
-                    }
-                }
-            }
+        if is_exclusive
+            && let Ok(Some(lock_info)) = lock::check_lock(parent_dir, &repo.name)
+            && lock_info.session == session.name
+            && let Err(e) = lock::release_lock(parent_dir, &repo.name)
+        {
+            eprintln!("  Warning: failed to release lock for {}: {}", repo.name, e);
         }
     }
+}
 
-    // Remove session directory
-    session::delete_session_dir(&session_dir)?;
-
-    println!(
-        "{} Session '{}' stopped and cleaned up.",
-        style("✔").green(),
-        session.name,
-    );
+/// Whether stopping the session deletes `repo`'s branch: sesh only deletes
+/// the session branches it created.
+pub fn deletes_branch(repo: &SessionRepo, keep_branches: bool) -> bool {
+    !keep_branches && !repo.pinned && repo.branch.is_none()
+}
 
-    Ok(())
+/// Per-repo descriptions of work that tearing the session down would destroy:
+/// uncommitted changes (worktrees are removed with --force) and, for repos
+/// where `check_unpushed` holds, commits that only exist on the branch.
+pub fn work_at_risk(
+    session: &SessionInfo,
+    check_unpushed: impl Fn(&SessionRepo) -> bool,
+) -> Vec<(String, Vec<String>)> {
+    let mut at_risk = Vec::new();
+    for repo in &session.repos {
+        if !repo.worktree_path.exists() {
+            continue;
+        }
+        let mut problems = Vec::new();
+        match worktree::uncommitted_files(&repo.worktree_path) {
+            Ok(files) if !files.is_empty() => {
+                problems.push(format!("{} uncommitted file(s)", files.len()));
+            }
+            Ok(_) => {}
+            Err(e) => problems.push(format!("could not check for uncommitted changes: {}", e)),
+        }
+        if check_unpushed(repo) {
+            match worktree::unpushed_commits(&repo.worktree_path) {
+                Ok(0) => {}
+                Ok(n) => problems.push(format!("{} unpushed commit(s) on '{}'", n, session.branch_for(repo))),
+                Err(e) => problems.push(format!("could not check for unpushed commits: {}", e)),
+            }
+        }
+        if !problems.is_empty() {
+            at_risk.push((repo.name.clone(), problems));
+        }
+    }
+    at_risk
 }
@@ -0,0 +1,21 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use crate::session;
+
+use super::pick_session;
+
+/// Print the session directory alone on stdout, for `cd "$(sesh switch ...)"`.
+/// `main` has already moved to whichever workspace holds the session.
+pub fn run(parent_dir: &Path, name: String) -> Result<()> {
+    if !session::session_exists(parent_dir, &name) {
+        bail!(
+            "session '{}' not found here or in any registered workspace (see `sesh list --global`)",
+            name
+        );
+    }
+    let sess = pick_session(parent_dir, Some(name))?;
+    println!("{}", session::session_dir(parent_dir, &sess.name).display());
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use console::style;
+
+use crate::config::{SeshConfig, SyncStrategy};
+use crate::history;
+use crate::session::{self, SessionRepo};
+use crate::worktree;
+
+use super::{pick_session, repo_base};
+
+enum Outcome {
+    UpToDate,
+    Updated(u32),
+    Conflicts(Vec<String>),
+    Skipped(String),
+    Failed(String),
+}
+
+pub fn run(parent_dir: &Path, name: Option<String>, merge: bool, rebase: bool) -> Result<()> {
+    let config_path = parent_dir.join("sesh.toml");
+    let config = SeshConfig::load(&config_path)?;
+    let session = pick_session(parent_dir, name)?;
+
+    let merge = if merge || rebase {
+        merge
+    } else {
+        config.git.sync_strategy == SyncStrategy::Merge
+    };
+    let verb = if merge { "merge" } else { "rebase" };
+
+    let mut results: Vec<(String, String, Outcome)> = Vec::new(); // (repo, base, outcome)
+
+    for repo in &session.repos {
+        let base = repo_base(&config, &session, &repo.name);
+        let remote = config.remote(&repo.name);
+        let base_ref = format!("{}/{}", remote, base);
+
+        println!("{}", style(format!("── {} ──", repo.name)).cyan().bold());
+        let outcome = sync_repo(repo, remote, base, &base_ref, merge);
+        match &outcome {
+            Outcome::UpToDate => println!("  {}", style("Already up to date").dim()),
+            Outcome::Updated(n) => println!(
+                "  {} {} onto {} ({} new commit(s))",
+                style("✓").green(),
+                if repo.pinned { "moved" } else if merge { "merged" } else { "rebased" },
+                base_ref,
+                n
+            ),
+            Outcome::Conflicts(files) => {
+                println!("  {} conflicts, {} aborted:", style("✗").red(), verb);
+                for f in files {
+                    println!("    {}", f);
+                }
+            }
+            Outcome::Skipped(why) => println!("  {} skipped: {}", style("!").yellow(), why),
+            Outcome::Failed(e) => println!("  {} {}", style("✗").red(), e),
+        }
+        println!();
+        results.push((repo.name.clone(), base_ref, outcome));
+    }
+
+    let failed: Vec<&(String, String, Outcome)> = results
+        .iter()
+        .filter(|(_, _, o)| matches!(o, Outcome::Conflicts(_) | Outcome::Failed(_)))
+        .collect();
+    if !failed.is_empty() {
+        println!("To resolve by hand:");
+        for (repo, base_ref, _) in &failed {
+            let wt = session::session_dir(parent_dir, &session.name).join(repo);
+            println!("  cd {} && git {} {}", wt.display(), verb, base_ref);
+        }
+        let names: Vec<&str> = failed.iter().map(|(r, _, _)| r.as_str()).collect();
+        bail!("sync failed in: {}", names.join(", "));
+    }
+
+    history::record(
+        parent_dir,
+        &session.name,
+        history::Event::Synced { strategy: verb.to_string() },
+    );
+    println!("{} Session '{}' is in sync.", style("✔").green(), session.name);
+    Ok(())
+}
+
+fn sync_repo(repo: &SessionRepo, remote: &str, base: &str, base_ref: &str, merge: bool) -> Outcome {
+    if !repo.worktree_path.exists() {
+        return Outcome::Skipped("worktree missing".to_string());
+    }
+
+    print!("  {} Fetching {}...", style("↓").dim(), base_ref);
+    if let Err(e) = worktree::fetch_branch(&repo.worktree_path, remote, base) {
+        println!(" {}", style("failed").red());
+        return Outcome::Failed(e.to_string());
+    }
+    println!(" {}", style("done").green());
+
+    let behind = match worktree::commits_behind(&repo.worktree_path, base_ref) {
+        Ok(n) => n,
+        Err(e) => return Outcome::Failed(e.to_string()),
+    };
+    if behind == 0 {
+        return Outcome::UpToDate;
+    }
+
+    match worktree::has_changes_to_commit(&repo.worktree_path, true) {
+        Ok(true) => return Outcome::Skipped("uncommitted changes".to_string()),
+        Ok(false) => {}
+        Err(e) => return Outcome::Failed(e.to_string()),
+    }
+
+    if repo.pinned {
+        return match worktree::checkout_detached(&repo.worktree_path, base_ref) {
+            Ok(()) => Outcome::Updated(behind),
+            Err(e) => Outcome::Failed(e.to_string()),
+        };
+    }
+
+    match worktree::integrate_base(&repo.worktree_path, base_ref, merge) {
+        Ok(conflicts) if conflicts.is_empty() => Outcome::Updated(behind),
+        Ok(conflicts) => Outcome::Conflicts(conflicts),
+        Err(e) => Outcome::Failed(e.to_string()),
+    }
+}
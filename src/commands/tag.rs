@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use console::style;
+
+use crate::config;
+use crate::session::{self, SessionInfo};
+
+use super::pick_session;
+
+fn save(parent_dir: &Path, info: &SessionInfo) -> Result<()> {
+    session::save_session(&session::session_dir(parent_dir, &info.name), info)
+}
+
+pub fn add(parent_dir: &Path, name: String, tags: Vec<String>) -> Result<()> {
+    for tag in &tags {
+        // Tags reach scripts comma-separated in SESH_TAGS
+        if tag.is_empty() || tag.contains(',') || tag.chars().any(char::is_whitespace) {
+            bail!("invalid tag '{}': tags can't be empty or contain commas or spaces", tag);
+        }
+    }
+    let mut info = pick_session(parent_dir, Some(name))?;
+    for tag in tags {
+        if !info.tags.contains(&tag) {
+            info.tags.push(tag);
+        }
+    }
+    save(parent_dir, &info)?;
+    println!("{} '{}' tags: {}", style("✓").green(), info.name, info.tags.join(", "));
+    Ok(())
+}
+
+pub fn remove(parent_dir: &Path, name: String, tags: Vec<String>) -> Result<()> {
+    let mut info = pick_session(parent_dir, Some(name))?;
+    for tag in &tags {
+        if !info.tags.contains(tag) {
+            bail!("session '{}' has no tag '{}'", info.name, tag);
+        }
+    }
+    info.tags.retain(|t| !tags.contains(t));
+    save(parent_dir, &info)?;
+    if info.tags.is_empty() {
+        println!("{} '{}' has no tags", style("✓").green(), info.name);
+    } else {
+        println!("{} '{}' tags: {}", style("✓").green(), info.name, info.tags.join(", "));
+    }
+    Ok(())
+}
+
+/// Set `key=value` metadata fields.
+pub fn set_meta(parent_dir: &Path, name: String, fields: Vec<String>) -> Result<()> {
+    let mut parsed = Vec::new();
+    for field in &fields {
+        let Some((key, value)) = field.split_once('=') else {
+            bail!("expected key=value, got '{}'", field);
+        };
+        // Keys become SESH_META_<KEY> env vars
+        if !config::is_plain_name(key) {
+            bail!("invalid key '{}': use letters, digits, '-' and '_'", key);
+        }
+        parsed.push((key.to_string(), value.to_string()));
+    }
+    let mut info = pick_session(parent_dir, Some(name))?;
+    for (key, value) in parsed {
+        println!("  {} = {}", style(&key).cyan(), value);
+        info.metadata.insert(key, value);
+    }
+    save(parent_dir, &info)?;
+    println!("{} Metadata saved for '{}'", style("✓").green(), info.name);
+    Ok(())
+}
+
+pub fn unset_meta(parent_dir: &Path, name: String, keys: Vec<String>) -> Result<()> {
+    let mut info = pick_session(parent_dir, Some(name))?;
+    for key in &keys {
+        if info.metadata.remove(key).is_none() {
+            bail!("session '{}' has no metadata field '{}'", info.name, key);
+        }
+    }
+    save(parent_dir, &info)?;
+    println!("{} Removed {} from '{}'", style("✓").green(), keys.join(", "), info.name);
+    Ok(())
+}
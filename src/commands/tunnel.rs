@@ -0,0 +1,113 @@
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use console::style;
+
+use crate::config::SeshConfig;
+use crate::scripts;
+use crate::session::{self, BackgroundPid};
+use crate::tunnel;
+
+use super::url::{resolve_session, save_urls};
+
+const LABEL_PREFIX: &str = "tunnel-";
+
+/// Start a tunnel per requested `[tunnel] services` entry, recording each public
+/// URL under the service name in session state.
+pub fn start(parent_dir: &Path, session_name: Option<String>, services: Vec<String>) -> Result<()> {
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+    if config.tunnel.services.is_empty() {
+        bail!("no [tunnel.services] configured in sesh.toml");
+    }
+    for name in &services {
+        if !config.tunnel.services.contains_key(name) {
+            bail!("unknown tunnel service '{}' (not in [tunnel.services])", name);
+        }
+    }
+
+    let mut info = resolve_session(parent_dir, session_name)?;
+    let sess_dir = session::session_dir(parent_dir, &info.name);
+    let mut pids = session::load_background_pids(&sess_dir);
+    let tool = config.tunnel.tool;
+
+    for (name, port) in &config.tunnel.services {
+        if !services.is_empty() && !services.contains(name) {
+            continue;
+        }
+        let label = format!("{}{}", LABEL_PREFIX, name);
+        if pids
+            .iter()
+            .any(|p| p.label == label && scripts::is_process_alive(p.pid))
+        {
+            println!("  {} {} already running", style("•").dim(), name);
+            continue;
+        }
+        pids.retain(|p| p.label != label);
+
+        let log_path = sess_dir.join("logs").join(format!("{}.log", label));
+        let pid = tunnel::spawn(tool, *port, &log_path)?;
+        pids.push(BackgroundPid {
+            pid,
+            label: label.clone(),
+            script: tool.binary().to_string(),
+            process_group: true,
+            started_at: Some(chrono::Utc::now()),
+            dead: false,
+            paused: false,
+            health: None,
+        });
+        session::save_background_pids(&sess_dir, &pids)?;
+
+        match tunnel::wait_for_url(tool, &log_path, Duration::from_secs(20)) {
+            Some(url) => {
+                println!(
+                    "  {} {} (:{}) → {}",
+                    style("✓").green(),
+                    style(name).cyan(),
+                    port,
+                    url
+                );
+                info.urls.insert(name.clone(), url);
+            }
+            None => println!(
+                "  {} {} started but no URL yet; see `sesh log {} -s {}`",
+                style("!").yellow(),
+                name,
+                label,
+                info.name
+            ),
+        }
+    }
+
+    save_urls(parent_dir, &info)
+}
+
+/// Stop the session's tunnels and drop their URLs.
+pub fn stop(parent_dir: &Path, session_name: Option<String>, services: Vec<String>) -> Result<()> {
+    let mut info = resolve_session(parent_dir, session_name)?;
+    let sess_dir = session::session_dir(parent_dir, &info.name);
+    let pids = session::load_background_pids(&sess_dir);
+
+    let (stopping, keep): (Vec<BackgroundPid>, Vec<BackgroundPid>) =
+        pids.into_iter().partition(|p| {
+            p.label
+                .strip_prefix(LABEL_PREFIX)
+                .is_some_and(|name| services.is_empty() || services.iter().any(|s| s == name))
+        });
+
+    if stopping.is_empty() {
+        println!("No tunnels running for session '{}'.", info.name);
+        return Ok(());
+    }
+
+    scripts::kill_background_pids(&stopping);
+    session::save_background_pids(&sess_dir, &keep)?;
+    for p in &stopping {
+        let name = &p.label[LABEL_PREFIX.len()..];
+        info.urls.remove(name);
+        println!("  {} Stopped {}", style("✓").green(), style(name).cyan());
+    }
+
+    save_urls(parent_dir, &info)
+}
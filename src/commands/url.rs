@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use console::style;
+
+use crate::config::SeshConfig;
+use crate::context;
+use crate::landing;
+use crate::session::{self, SessionInfo};
+use crate::vscode;
+
+use super::pick_session;
+
+/// Resolve the target session: explicit name, then `$SESH_SESSION` (set for
+/// setup scripts), then an interactive pick.
+pub fn resolve_session(parent_dir: &Path, name: Option<String>) -> Result<SessionInfo> {
+    let name = name.or_else(|| std::env::var("SESH_SESSION").ok().filter(|s| !s.is_empty()));
+    pick_session(parent_dir, name)
+}
+
+/// Persist the session's URLs and refresh the context file, the session
+/// README and `URLS.md`.
+pub fn save_urls(parent_dir: &Path, info: &SessionInfo) -> Result<()> {
+    let sess_dir = session::session_dir(parent_dir, &info.name);
+    session::save_session(&sess_dir, info)?;
+    context::update_urls(&sess_dir, &info.urls)?;
+    let config = SeshConfig::load(&parent_dir.join("sesh.toml"))?;
+    landing::write(info, &config)?;
+    vscode::write_urls_file(&sess_dir, &info.urls)
+}
+
+pub fn add(parent_dir: &Path, session_name: Option<String>, name: String, url: String) -> Result<()> {
+    let mut info = resolve_session(parent_dir, session_name)?;
+    info.urls.insert(name.clone(), url.clone());
+    save_urls(parent_dir, &info)?;
+    println!(
+        "{} {} → {} (session '{}')",
+        style("✓").green(),
+        style(&name).cyan(),
+        url,
+        info.name
+    );
+    Ok(())
+}
+
+pub fn remove(parent_dir: &Path, session_name: Option<String>, name: String) -> Result<()> {
+    let mut info = resolve_session(parent_dir, session_name)?;
+    if info.urls.remove(&name).is_none() {
+        bail!("no URL named '{}' in session '{}'", name, info.name);
+    }
+    save_urls(parent_dir, &info)?;
+    println!("{} Removed {}", style("✓").green(), style(&name).cyan());
+    Ok(())
+}
+
+pub fn list(parent_dir: &Path, session_name: Option<String>) -> Result<()> {
+    let info = resolve_session(parent_dir, session_name)?;
+    if info.urls.is_empty() {
+        println!("No URLs registered for session '{}'.", info.name);
+        return Ok(());
+    }
+    for (name, url) in &info.urls {
+        println!("  {}  {}", style(name).cyan(), url);
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Result};
+use console::style;
+
+use crate::config::SeshConfig;
+use crate::session::SessionInfo;
+
+use super::pick_session;
+
+pub fn run(parent_dir: &Path, name: Option<String>) -> Result<()> {
+    let config_path = parent_dir.join("sesh.toml");
+    let config = SeshConfig::load(&config_path)?;
+    let session = pick_session(parent_dir, name)?;
+
+    if !run_verification(&config, &session)? {
+        bail!("verification failed");
+    }
+    Ok(())
+}
+
+/// Run the verify steps for every repo in the session (per-repo `verify`
+/// overrides `[verify] steps`), print a summary, and return whether all passed.
+pub fn run_verification(config: &SeshConfig, session: &SessionInfo) -> Result<bool> {
+    let mut results: Vec<(String, String, bool)> = Vec::new(); // (repo, step, passed)
+
+    for repo in &session.repos {
+        if repo.pinned || !repo.worktree_path.exists() {
+            continue;
+        }
+
+        let steps = config
+            .repos
+            .get(&repo.name)
+            .and_then(|rc| rc.verify.as_ref())
+            .unwrap_or(&config.verify.steps);
+        if steps.is_empty() {
+            continue;
+        }
+
+        println!("{}", style(format!("── {} ──", repo.name)).cyan().bold());
+        for step in steps {
+            println!("  {} {}", style("$").dim(), step);
+            let passed = Command::new("sh")
+                .args(["-c", step])
+                .current_dir(&repo.worktree_path)
+                .status()
+                .map(|s| s.success())
+                .unwrap_or(false);
+            results.push((repo.name.clone(), step.clone(), passed));
+        }
+        println!();
+    }
+
+    if results.is_empty() {
+        println!("No verify steps configured.");
+        return Ok(true);
+    }
+
+    println!("{}", style("Verification summary:").bold());
+    for (repo, step, passed) in &results {
+        let mark = if *passed {
+            style("✓").green()
+        } else {
+            style("✗").red()
+        };
+        println!("  {} {:<16} {}", mark, repo, step);
+    }
+    println!();
+
+    Ok(results.iter().all(|(_, _, passed)| *passed))
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process::Command;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use console::style;
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::config::SeshConfig;
+use crate::session::SessionRepo;
+
+use super::pick_session;
+
+pub fn run(parent_dir: &Path, session_name: Option<String>, command: Option<String>) -> Result<()> {
+    let config_path = parent_dir.join("sesh.toml");
+    let config = SeshConfig::load(&config_path)?;
+    let info = pick_session(parent_dir, session_name)?;
+
+    // Resolve the task per repo: --command > repos.<name>.watch > watch.command
+    let watched: Vec<(&SessionRepo, String)> = info
+        .repos
+        .iter()
+        .filter(|r| r.worktree_path.exists())
+        .filter_map(|r| {
+            let cmd = command
+                .clone()
+                .or_else(|| config.repos.get(&r.name).and_then(|rc| rc.watch.clone()))
+                .or_else(|| config.watch.command.clone())?;
+            Some((r, cmd))
+        })
+        .collect();
+
+    if watched.is_empty() {
+        bail!("no watch command configured — pass one or set [watch] command in sesh.toml");
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::RecommendedWatcher::new(
+        tx,
+        notify::Config::default().with_follow_symlinks(false),
+    )
+    .context("failed to start file watcher")?;
+
+    for (repo, _) in &watched {
+        watcher
+            .watch(&repo.worktree_path, RecursiveMode::Recursive)
+            .with_context(|| format!("failed to watch {}", repo.worktree_path.display()))?;
+    }
+
+    println!(
+        "{} Watching {} repo(s) in session '{}' (Ctrl-C to stop)",
+        style("👀").bold(),
+        watched.len(),
+        info.name
+    );
+    for (repo, cmd) in &watched {
+        println!("  {} {} → {}", style("•").dim(), style(&repo.name).cyan(), cmd);
+    }
+
+    let debounce = Duration::from_millis(config.watch.debounce_ms);
+
+    loop {
+        // Block for the first change, then wait for the burst to settle
+        let mut affected: BTreeSet<usize> = BTreeSet::new();
+        match rx.recv() {
+            Ok(event) => collect_affected(event, &watched, &config.watch.ignore, &mut affected),
+            Err(_) => return Ok(()),
+        }
+        loop {
+            match rx.recv_timeout(debounce) {
+                Ok(event) => collect_affected(event, &watched, &config.watch.ignore, &mut affected),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+
+        for idx in affected {
+            let (repo, cmd) = &watched[idx];
+            println!("\n{}", style(format!("── {} ──", repo.name)).cyan().bold());
+            println!("  {} {}", style("$").dim(), cmd);
+
+            let status = Command::new("sh")
+                .args(["-c", cmd])
+                .current_dir(&repo.worktree_path)
+                .status();
+
+            match status {
+                Ok(s) if s.success() => println!("{} {}", style("✓").green(), repo.name),
+                Ok(s) => println!("{} {} exited with {}", style("✗").red(), repo.name, s),
+                Err(e) => println!("{} {} failed to execute: {}", style("✗").red(), repo.name, e),
+            }
+        }
+    }
+}
+
+/// Map a watcher event to the indices of the repos it touches, ignoring
+/// access-only events and paths under ignored directories.
+fn collect_affected(
+    event: notify::Result<notify::Event>,
+    watched: &[(&SessionRepo, String)],
+    ignore: &[String],
+    affected: &mut BTreeSet<usize>,
+) {
+    let Ok(event) = event else {
+        return;
+    };
+    if !matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) {
+        return;
+    }
+
+    for path in &event.paths {
+        for (idx, (repo, _)) in watched.iter().enumerate() {
+            let Ok(rel) = path.strip_prefix(&repo.worktree_path) else {
+                continue;
+            };
+            let ignored = rel
+                .components()
+                .any(|c| ignore.iter().any(|i| c.as_os_str() == i.as_str()));
+            if !ignored {
+                affected.insert(idx);
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+
+use crate::hooks;
+use crate::session::IssueContext;
+use crate::worktree;
+
+const TICKET_HOOK: &str = "prepare-commit-msg";
+
+/// Render a commit template, substituting `{ticket}`, `{branch}` and `{session}`.
+pub fn render_template(
+    template: &str,
+    issue: Option<&IssueContext>,
+    branch: &str,
+    session_name: &str,
+) -> String {
+    let ticket = issue.map(|i| i.identifier.as_str()).unwrap_or("");
+    template
+        .replace("{ticket}", ticket)
+        .replace("{branch}", branch)
+        .replace("{session}", session_name)
+}
+
+/// Write the rendered commit template into the session directory and point each
+/// worktree's `commit.template` at it. Returns the template path.
+pub fn install_commit_template(
+    sess_dir: &Path,
+    worktrees: &[(PathBuf, PathBuf)],
+    rendered: &str,
+) -> Result<PathBuf> {
+    let path = sess_dir.join("commit_template.txt");
+    let mut contents = rendered.to_string();
+    if !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    std::fs::write(&path, contents)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    let template = path.to_string_lossy();
+    for (repo_path, worktree_path) in worktrees {
+        worktree::set_worktree_config(repo_path, worktree_path, "commit.template", &template)?;
+    }
+
+    Ok(path)
+}
+
+/// Install a `prepare-commit-msg` hook for one worktree that appends a
+/// `Refs: <ticket>` trailer, chaining to the repo's own hook if it has one.
+pub fn install_ticket_hook(
+    sess_dir: &Path,
+    repo_name: &str,
+    repo_path: &Path,
+    worktree_path: &Path,
+    ticket: &str,
+) -> Result<()> {
+    let hooks_dir = hooks::ensure_hooks_dir(sess_dir, repo_name, repo_path, worktree_path)?;
+    let original = hooks::original_hook(repo_path, TICKET_HOOK)?;
+    let script = format!(
+        "#!/bin/sh\n\
+         # Installed by sesh: links every commit to {ticket}\n\
+         ORIG={orig}\n\
+         if [ -x \"$ORIG\" ]; then \"$ORIG\" \"$@\" || exit $?; fi\n\
+         git interpret-trailers --in-place --if-exists doNothing --trailer {trailer} \"$1\"\n",
+        ticket = ticket.replace('\n', " "),
+        orig = hooks::shell_quote(&original.to_string_lossy()),
+        trailer = hooks::shell_quote(&format!("Refs: {}", ticket)),
+    );
+    hooks::write_hook(&hooks_dir, TICKET_HOOK, &script)
+}
+
+/// Validate a commit message's subject line against the configured pattern.
+pub fn validate_message(message: &str, pattern: &str) -> Result<()> {
+    let re = Regex::new(pattern)
+        .with_context(|| format!("invalid git.commit_pattern: {}", pattern))?;
+    let subject = message.lines().next().unwrap_or("").trim();
+
+    if subject.is_empty() {
+        bail!("commit message cannot be empty");
+    }
+    if !re.is_match(subject) {
+        bail!(
+            "commit message '{}' does not match the required pattern: {}",
+            subject,
+            pattern
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        let issue = IssueContext {
+            provider: "linear".to_string(),
+            identifier: "ENG-123".to_string(),
+            title: "Fix login".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_template("feat: \n\nRefs: {ticket} ({branch})", Some(&issue), "eng-123-fix", "s"),
+            "feat: \n\nRefs: ENG-123 (eng-123-fix)"
+        );
+        assert_eq!(render_template("{ticket}: ", None, "b", "s"), ": ");
+    }
+
+    #[test]
+    fn test_validate_message() {
+        let pattern = r"^(feat|fix|chore)(\(.+\))?!?: .+";
+        assert!(validate_message("feat: add login", pattern).is_ok());
+        assert!(validate_message("fix(api): handle null\n\nbody", pattern).is_ok());
+        assert!(validate_message("added stuff", pattern).is_err());
+        assert!(validate_message("", pattern).is_err());
+        assert!(validate_message("feat: x", "(").is_err());
+    }
+}
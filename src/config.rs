@@ -1,39 +1,250 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
 use anyhow::Context;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ScriptEntry {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub path: String,
+    /// Inline shell command run through `sh -c` (or `interpreter -c`) instead
+    /// of a script file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
     #[serde(default)]
     pub background: bool,
+    /// Extra environment variables for this script only.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Working directory, relative to where the script would otherwise run
+    /// (the session directory, or the repo's worktree for per-repo scripts).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Program the script is passed to (e.g. `bash`, `python3`, `uv run`),
+    /// so it needn't be executable or have a shebang.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interpreter: Option<String>,
+    /// Kill a foreground script still running after this many seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// What a failed (or timed out) setup step does to session creation.
+    #[serde(default)]
+    pub on_failure: FailurePolicy,
+    /// Readiness probe for a background setup script, waited on before the
+    /// session counts as started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_check: Option<HealthCheck>,
+    /// Setup group this entry belongs to, for other entries' `depends_on`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Groups whose setup entries must all finish before this one starts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FailurePolicy {
+    /// Stop creating the session, leaving what was set up so far.
+    #[default]
+    Abort,
+    /// Report the failure and run the remaining steps.
+    Continue,
+    /// Stop and tear the new session down again.
+    Rollback,
+}
+
+/// Either an HTTP URL that must answer with a 2xx or 3xx status, or a shell
+/// command that must exit 0. `url` may use template variables like `{{port.web}}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheck {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// How long to keep probing before the script counts as failed (default 60).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl HealthCheck {
+    pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+}
+
+impl ScriptEntry {
+    /// How the entry is shown and labelled: its inline command or its path.
+    pub fn name(&self) -> &str {
+        self.command.as_deref().unwrap_or(&self.path)
+    }
+
+    /// Inline commands can always run; scripts need their file under `parent_dir`.
+    pub fn is_available(&self, parent_dir: &Path) -> bool {
+        self.command.is_some() || (!self.path.is_empty() && parent_dir.join(&self.path).is_file())
+    }
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct SeshConfig {
+    pub meta: MetaConfig,
     pub session: SessionConfig,
     pub scripts: ScriptsConfig,
+    pub setup: SetupConfig,
     pub mcp: McpConfig,
     pub repos: HashMap<String, RepoConfig>,
-    pub presets: HashMap<String, Vec<String>>,
+    pub presets: HashMap<String, Preset>,
     pub sentry: Option<SentryConfig>,
+    pub linear: LinearConfig,
+    pub jira: Option<JiraConfig>,
+    pub bitbucket: BitbucketConfig,
+    pub azure: Option<AzureConfig>,
+    pub git: GitConfig,
+    pub watch: WatchConfig,
+    pub guard: GuardConfig,
+    pub verify: VerifyConfig,
+    pub ci: CiConfig,
+    pub pr: PrConfig,
+    pub tunnel: TunnelConfig,
+    pub reminders: RemindersConfig,
+    pub notify: NotifyConfig,
+    pub index: IndexConfig,
+    pub performance: PerformanceConfig,
+    pub metrics: MetricsConfig,
+    pub vscode: VscodeConfig,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// A preset is either a plain list of repos, or a table that additionally lists
+/// repos to pin read-only (detached) at their base branch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Preset {
+    Repos(Vec<String>),
+    Detailed {
+        #[serde(default)]
+        repos: Vec<String>,
+        #[serde(default)]
+        pinned: Vec<String>,
+    },
+}
+
+impl Preset {
+    pub fn repos(&self) -> &[String] {
+        match self {
+            Preset::Repos(repos) => repos,
+            Preset::Detailed { repos, .. } => repos,
+        }
+    }
+
+    pub fn pinned(&self) -> &[String] {
+        match self {
+            Preset::Repos(_) => &[],
+            Preset::Detailed { pinned, .. } => pinned,
+        }
+    }
+
+    /// Whether the repo is part of this preset, either as a working or pinned repo.
+    pub fn includes(&self, repo_name: &str) -> bool {
+        self.repos().iter().chain(self.pinned()).any(|r| r == repo_name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MetaConfig {
+    /// Canonical team config: an http(s) URL to a sesh.toml, or a git repo
+    /// (`git@…`, `ssh://…`, or a URL ending in `.git`). Pulled by `sesh config update`.
+    pub config_source: Option<String>,
+    /// File inside a git `config_source` (default `sesh.toml`).
+    pub config_path: Option<String>,
+    /// Branch or tag of a git `config_source` (default: the remote's HEAD).
+    pub config_ref: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SentryConfig {
     pub org: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct LinearConfig {
+    /// What `sesh stop` does to the session's Linear ticket.
+    pub on_stop: LinearOnStop,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LinearOnStop {
+    #[default]
+    None,
+    /// Post a comment saying the work was paused or finished, with PR links.
+    Comment,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct BitbucketConfig {
+    /// Bitbucket username (or Atlassian account email) for app passwords and
+    /// API tokens (basic auth). When unset the token is sent as a bearer token
+    /// (repository or workspace access token).
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AzureConfig {
+    /// Azure DevOps organization that bare `AB#123` work item IDs are looked up in.
+    pub organization: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JiraConfig {
+    /// Site URL, e.g. `https://acme.atlassian.net`.
+    pub base_url: String,
+    /// Account email for Jira Cloud API tokens (basic auth). When unset the
+    /// token is sent as a bearer token (Jira Data Center personal access token).
+    pub email: Option<String>,
+    /// Project keys resolved through Jira; bare `KEY-123` inputs for other
+    /// prefixes go to Linear. When empty, bare keys use Jira only if no Linear
+    /// token is configured.
+    #[serde(default)]
+    pub projects: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct SessionConfig {
     pub base_branch: String,
     pub branch_prefix: Option<String>,
+    /// Branch name built from `{user}`, `{ticket}`, `{slug}` and `{name}`,
+    /// replacing `branch_prefix` when set.
+    pub branch_template: Option<String>,
+    /// `{user}` in `branch_template`; defaults to `git config user.name`.
+    pub branch_user: Option<String>,
     pub shared_context: Vec<String>,
     pub copy: Vec<String>,
+    /// Drop stale remote-tracking refs in the session's repos on `sesh stop`.
+    pub prune_remote_on_stop: bool,
+    /// Remote that base branches are fetched from and session branches pushed to.
+    pub remote: String,
+    /// Service name → first port to try. Each session gets its own free port
+    /// from there, exposed as `{{port.<name>}}` and `SESH_PORT_<NAME>`.
+    pub ports: BTreeMap<String, u16>,
+    /// Disk a session directory (worktrees included) may use, checked by `sesh du`.
+    pub max_disk_gb: Option<f64>,
+    /// What `sesh du --enforce` does about a session over `max_disk_gb`.
+    pub over_quota: QuotaAction,
+}
+
+/// Response to a session using more disk than `[session] max_disk_gb`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaAction {
+    /// Report it.
+    #[default]
+    Warn,
+    /// Also pause (SIGSTOP) its background processes until it's back under.
+    Pause,
 }
 
 impl Default for SessionConfig {
@@ -41,57 +252,959 @@ impl Default for SessionConfig {
         Self {
             base_branch: "main".to_string(),
             branch_prefix: None,
+            branch_template: None,
+            branch_user: None,
             shared_context: Vec::new(),
             copy: Vec::new(),
+            prune_remote_on_stop: false,
+            remote: "origin".to_string(),
+            ports: BTreeMap::new(),
+            max_disk_gb: None,
+            over_quota: QuotaAction::Warn,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitConfig {
+    /// Commit message template installed as `commit.template` in each worktree.
+    /// Supports `{ticket}`, `{branch}` and `{session}` placeholders.
+    pub commit_template: Option<String>,
+    /// Regex the subject line must match for `sesh commit` to accept a message.
+    pub commit_pattern: Option<String>,
+    /// Append a `Refs: <ticket>` trailer to every commit in sessions linked to an issue.
+    pub ticket_trailer: bool,
+    /// How `sesh sync` brings session branches up to date with their base.
+    pub sync_strategy: SyncStrategy,
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            commit_template: None,
+            commit_pattern: None,
+            ticket_trailer: true,
+            sync_strategy: SyncStrategy::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncStrategy {
+    #[default]
+    Rebase,
+    Merge,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct CiConfig {
+    /// Workflow (file name or ID) dispatched on the session branch after `sesh pr`.
+    /// Per-repo `ci_workflow` overrides it.
+    pub workflow: Option<String>,
+    /// Name of the workflow input that receives the branch name.
+    pub branch_input: Option<String>,
+    /// Extra workflow inputs passed as `-f key=value`.
+    pub inputs: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrConfig {
+    /// PR description template rendered by `sesh pr`; see `template::render`.
+    /// Sessions linked to an issue use a built-in one when unset.
+    pub body_template: Option<String>,
+    /// Word put before the issue reference in `{{ fixes }}`.
+    pub closing_keyword: String,
+    /// Attach the opened PRs to the session's Linear issue.
+    pub link_linear: bool,
+    /// Describe PRs with their commits and diffstat even without an issue or
+    /// `body_template`, instead of letting the forge fill the description.
+    pub summary: bool,
+    /// List `{{ commits }}` under conventional-commit types (Features, Fixes, ...).
+    pub group_commits: bool,
+}
+
+impl Default for PrConfig {
+    fn default() -> Self {
+        Self {
+            body_template: None,
+            closing_keyword: "Fixes".to_string(),
+            link_linear: true,
+            summary: true,
+            group_commits: false,
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct VerifyConfig {
+    /// Commands run in each worktree by `sesh verify` (per-repo `verify` overrides them).
+    pub steps: Vec<String>,
+    /// Run verification automatically before `sesh pr`.
+    pub before_pr: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct GuardConfig {
+    /// Globs (relative to each repo) that session changes must not touch.
+    pub protected: Vec<String>,
+    /// Install the guard pre-commit hook in every new session.
+    pub install_hook: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TunnelTool {
+    #[default]
+    Cloudflared,
+    Ngrok,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct TunnelConfig {
+    pub tool: TunnelTool,
+    /// Service name → local port exposed by `sesh tunnel start`.
+    pub services: BTreeMap<String, u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// Slack-compatible incoming webhook; receives `{"text": ...}` posts.
+    pub webhook_url: Option<String>,
+    /// Show desktop notifications when session setup or `sesh pr` finishes,
+    /// and when a background script exits on its own.
+    pub desktop: bool,
+    /// Setups and PR runs quicker than this many seconds aren't announced.
+    pub desktop_min_secs: u64,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: None,
+            desktop: false,
+            desktop_min_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct VscodeConfig {
+    /// Settings written into each session's `.code-workspace` file.
+    pub settings: serde_json::Map<String, serde_json::Value>,
+    /// Extension IDs recommended by the workspace.
+    pub extensions: Vec<String>,
+    /// Which window `code` opens the session in.
+    pub window: VscodeWindow,
+    /// Files opened along with the session, relative to the session
+    /// directory (e.g. `context/.sesh-context.md`).
+    pub open_files: Vec<String>,
+    /// Launch with `--wait`, so sesh returns only once the window is closed.
+    pub wait: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VscodeWindow {
+    /// Left to VS Code's `window.openFoldersInNewWindow` setting
+    #[default]
+    Default,
+    New,
+    Reuse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct PerformanceConfig {
+    /// How many repos multi-repo operations work on at once (`--jobs` wins).
+    pub jobs: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Prometheus textfile `sesh metrics --write` updates (relative to the workspace).
+    pub textfile: Option<String>,
+    /// How long a session's measured disk usage is reused before its
+    /// directory is walked again (default 600).
+    pub disk_ttl_secs: Option<u64>,
+}
+
+impl MetricsConfig {
+    pub const DEFAULT_DISK_TTL_SECS: u64 = 600;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct IndexConfig {
+    /// Keep a SQLite index of sessions at `.sesh/index.db` (built by `sesh doctor`).
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemindersConfig {
+    /// Nudge about stale sessions when sesh commands run (opt-in).
+    pub enabled: bool,
+    /// Days without activity before a session counts as idle.
+    pub idle_days: u32,
+    /// Also flag sessions whose Linear ticket is Done or Canceled.
+    pub check_linear: bool,
+    /// Minimum hours between nudges.
+    pub interval_hours: u32,
+}
+
+impl Default for RemindersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_days: 7,
+            check_linear: true,
+            interval_hours: 24,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchConfig {
+    /// Command `sesh watch` runs in each affected repo (per-repo `watch` overrides it).
+    pub command: Option<String>,
+    pub debounce_ms: u64,
+    /// Path components whose changes never trigger a rerun.
+    pub ignore: Vec<String>,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            command: None,
+            debounce_ms: 500,
+            ignore: [".git", "node_modules", "target", "dist", "build"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct SetupConfig {
+    /// Run the install command for each repo's lockfile (`npm ci`, `cargo
+    /// fetch`, ...) during setup. Unset asks first; `false` never installs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_install: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct ScriptsConfig {
     pub setup: Vec<ScriptEntry>,
     pub teardown: Vec<ScriptEntry>,
+    /// Lifecycle hooks, see [`Hook`].
+    pub pre_start: Vec<ScriptEntry>,
+    pub post_start: Vec<ScriptEntry>,
+    pub pre_stop: Vec<ScriptEntry>,
+    pub post_stop: Vec<ScriptEntry>,
+    pub post_pr: Vec<ScriptEntry>,
+    /// Prefix every background log line with a timestamp and the script label.
+    pub timestamp_logs: bool,
+    /// Archive background logs on stop and keep them for this many days
+    /// (logs are deleted with the session when unset).
+    pub keep_logs_days: Option<u32>,
+    /// Environment scripts start from: sesh's own (`inherit`) or a minimal
+    /// baseline plus `pass` (`clean`).
+    pub env_mode: EnvMode,
+    /// Variables passed through in `clean` mode; a trailing `*` matches a prefix.
+    pub pass: Vec<String>,
+    /// Variables never passed to scripts, in either mode; wins over `pass`.
+    pub deny: Vec<String>,
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+impl ScriptsConfig {
+    pub fn hook(&self, hook: Hook) -> &[ScriptEntry] {
+        match hook {
+            Hook::PreStart => &self.pre_start,
+            Hook::PostStart => &self.post_start,
+            Hook::PreStop => &self.pre_stop,
+            Hook::PostStop => &self.post_stop,
+            Hook::PostPr => &self.post_pr,
+        }
+    }
+}
+
+/// A lifecycle moment with its own `[[scripts.<name>]]` entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    /// Before any worktree is created.
+    PreStart,
+    /// Once the session is set up.
+    PostStart,
+    /// Before `sesh stop` tears anything down.
+    PreStop,
+    /// After `sesh stop` removed the session.
+    PostStop,
+    /// After `sesh pr` opened a PR, once per PR.
+    PostPr,
+}
+
+impl Hook {
+    pub const ALL: [Hook; 5] = [Hook::PreStart, Hook::PostStart, Hook::PreStop, Hook::PostStop, Hook::PostPr];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Hook::PreStart => "pre_start",
+            Hook::PostStart => "post_start",
+            Hook::PreStop => "pre_stop",
+            Hook::PostStop => "post_stop",
+            Hook::PostPr => "post_pr",
+        }
+    }
+
+    /// Whether a failure can still stop the command it belongs to.
+    pub fn is_pre(self) -> bool {
+        matches!(self, Hook::PreStart | Hook::PreStop)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvMode {
+    #[default]
+    Inherit,
+    Clean,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct McpConfig {
     pub servers: Vec<McpServer>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpServer {
     pub name: String,
-    #[serde(rename = "type")]
-    pub kind: String,
-    pub url: String,
+    #[serde(flatten)]
+    pub transport: McpTransport,
+}
+
+/// How an MCP client reaches a server, selected by `type`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum McpTransport {
+    Http {
+        url: String,
+    },
+    Sse {
+        url: String,
+    },
+    /// A local server launched as a process that speaks MCP over stdin/stdout.
+    Stdio {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        env: BTreeMap<String, String>,
+    },
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct RepoConfig {
     pub base_branch: Option<String>,
+    /// Prepended to the session branch in this repo, e.g. `feature/` or
+    /// `users/alice/`.
+    pub branch_prefix: Option<String>,
+    /// This repo's branch name built from the session branch, e.g.
+    /// `feature/{branch}` or `{branch_slug}` for repos that forbid slashes.
+    /// Replaces `branch_prefix` when set.
+    pub branch_template: Option<String>,
+    /// Remote for this repo, replacing `[session] remote` (e.g. `upstream`).
+    pub remote: Option<String>,
+    /// Remote session branches are pushed to when it isn't `remote`, e.g. your
+    /// fork. PRs are still opened against `remote`.
+    pub push_remote: Option<String>,
     pub copy: Vec<String>,
     pub symlink: Vec<String>,
+    /// Heavy directories (`node_modules`, `target`) duplicated from the
+    /// original repo as copy-on-write clones where the filesystem allows.
+    pub clone: Vec<String>,
+    /// Files rendered with session variables (`{{session}}`, `{{branch}}`,
+    /// `{{repo}}`, `{{port.<name>}}`) into the worktree, minus any `.tmpl`.
+    pub template: Vec<String>,
     pub skip: bool,
     pub exclusive: bool,
+    /// Warm worktrees `sesh pool warm` keeps ready for `sesh start` to claim.
+    pub pool: usize,
     pub setup: Vec<ScriptEntry>,
     pub teardown: Vec<ScriptEntry>,
+    /// Command `sesh watch` reruns when this repo changes.
+    pub watch: Option<String>,
+    /// Verify steps for this repo, replacing `[verify] steps`.
+    pub verify: Option<Vec<String>>,
+    /// Workflow dispatched for this repo after `sesh pr`, replacing `[ci] workflow`.
+    pub ci_workflow: Option<String>,
+    /// VS Code settings written to the worktree's `.vscode/settings.json`
+    /// (only when the repo doesn't have one).
+    pub vscode_settings: serde_json::Map<String, serde_json::Value>,
 }
 
+/// Repo-local config file, kept in a repo's root by that repo's owners.
+pub const REPO_CONFIG_FILE: &str = ".sesh.toml";
+
+/// Personal overrides layered on top of `sesh.toml` (keep it out of version control).
+pub const LOCAL_CONFIG_FILE: &str = "sesh.local.toml";
+
 impl SeshConfig {
-    pub fn load(path: &Path) -> anyhow::Result<Self> {
-        if !path.exists() {
-            return Ok(Self::default());
+    /// The remote `repo` fetches base branches from and opens PRs against.
+    pub fn remote(&self, repo: &str) -> &str {
+        self.repos
+            .get(repo)
+            .and_then(|rc| rc.remote.as_deref())
+            .unwrap_or(&self.session.remote)
+    }
+
+    /// The branch `repo`'s sessions start from: its own `base_branch`, or the
+    /// session default.
+    pub fn base_branch(&self, repo: &str) -> &str {
+        self.repos
+            .get(repo)
+            .and_then(|rc| rc.base_branch.as_deref())
+            .unwrap_or(&self.session.base_branch)
+    }
+
+    /// The remote `repo`'s session branch is pushed to.
+    pub fn push_remote(&self, repo: &str) -> &str {
+        self.repos
+            .get(repo)
+            .and_then(|rc| rc.push_remote.as_deref())
+            .unwrap_or_else(|| self.remote(repo))
+    }
+
+    /// The branch a session on `branch` uses in `repo`, after the repo's
+    /// `branch_template` (`{branch}` as is, `{branch_slug}` slugified with no
+    /// slashes) or else its `branch_prefix`.
+    pub fn repo_branch(&self, repo: &str, branch: &str) -> String {
+        let Some(rc) = self.repos.get(repo) else {
+            return branch.to_string();
+        };
+        if let Some(template) = &rc.branch_template {
+            return crate::integrations::render_branch_template(
+                template,
+                &[("branch", branch), ("branch_slug", &crate::integrations::slugify(branch))],
+            );
         }
+        match rc.branch_prefix.as_deref() {
+            Some(prefix) if !branch.starts_with(prefix) => format!("{}{}", prefix, branch),
+            _ => branch.to_string(),
+        }
+    }
 
-        let contents = std::fs::read_to_string(path)
-            .with_context(|| format!("failed to read config file: {}", path.display()))?;
+    /// Load `sesh.toml`, layer `sesh.local.toml` over it, and merge in each
+    /// repo's `.sesh.toml`. Precedence: local override, then `sesh.toml`, then
+    /// the repo-local file.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let _t = crate::timing::timer("config", || path.display().to_string());
+        let contents = if path.exists() {
+            std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read config file: {}", path.display()))?
+        } else {
+            String::new()
+        };
 
-        let config: SeshConfig = toml::from_str(&contents)
+        let parent_dir = path.parent();
+        let local_path = parent_dir.map(|p| p.join(LOCAL_CONFIG_FILE));
+        let local = match local_path.as_deref().filter(|p| p.exists()) {
+            Some(lp) => {
+                let local_contents = std::fs::read_to_string(lp)
+                    .with_context(|| format!("failed to read {}", lp.display()))?;
+                let table: toml::Table = toml::from_str(&local_contents)
+                    .with_context(|| format!("failed to parse {}", lp.display()))?;
+                Some(table)
+            }
+            None => None,
+        };
+        let repo_configs = match parent_dir {
+            Some(dir) => load_repo_local_configs(dir)?,
+            None => Vec::new(),
+        };
+        let mut table: toml::Table = toml::from_str(&contents)
             .with_context(|| format!("failed to parse config file: {}", path.display()))?;
+        let layered = local.is_some() || !repo_configs.is_empty();
+        if let Some(local) = local {
+            merge_tables(&mut table, local);
+        }
+        if !repo_configs.is_empty() {
+            let repos = table
+                .entry("repos")
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+                .context("`repos` in sesh.toml must be a table")?;
+            for (name, local) in repo_configs {
+                merge_repo_config(&name, local, repos);
+            }
+        }
 
+        let mut table = toml::Value::Table(table);
+        for path in EXPANDED_SETTINGS {
+            expand_env(&mut table, path);
+        }
+        let config: SeshConfig = if !layered {
+            table
+                .try_into()
+                .with_context(|| format!("failed to parse config file: {}", path.display()))?
+        } else {
+            table.try_into().with_context(|| {
+                format!(
+                    "invalid config in {}, {} or a repo's {}",
+                    path.display(),
+                    LOCAL_CONFIG_FILE,
+                    REPO_CONFIG_FILE
+                )
+            })?
+        };
+        config.check_script_entries()?;
         Ok(config)
     }
+
+    /// Reject script entries with neither `path` nor `command`, which would
+    /// otherwise "run" the parent directory.
+    fn check_script_entries(&self) -> anyhow::Result<()> {
+        let mut lists: Vec<(String, &[ScriptEntry])> = vec![
+            ("[[scripts.setup]]".to_string(), &self.scripts.setup),
+            ("[[scripts.teardown]]".to_string(), &self.scripts.teardown),
+        ];
+        for hook in Hook::ALL {
+            lists.push((format!("[[scripts.{}]]", hook.name()), self.scripts.hook(hook)));
+        }
+        for (name, rc) in &self.repos {
+            lists.push((format!("[[repos.{}.setup]]", toml_key(name)), &rc.setup));
+            lists.push((format!("[[repos.{}.teardown]]", toml_key(name)), &rc.teardown));
+        }
+        for (section, entries) in lists {
+            if entries.iter().any(|e| e.command.is_none() && e.path.is_empty()) {
+                anyhow::bail!("{}: each entry needs a `path` or a `command`", section);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The files [`SeshConfig::load`] reads for `path` that exist: `sesh.toml`,
+/// `sesh.local.toml`, then each repo's `.sesh.toml`.
+pub fn source_files(path: &Path) -> Vec<std::path::PathBuf> {
+    let mut files = vec![path.to_path_buf()];
+    if let Some(parent_dir) = path.parent() {
+        files.push(parent_dir.join(LOCAL_CONFIG_FILE));
+        let mut repo_files: Vec<_> = std::fs::read_dir(parent_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+            .map(|e| e.path().join(REPO_CONFIG_FILE))
+            .collect();
+        repo_files.sort();
+        files.extend(repo_files);
+    }
+    files.retain(|f| f.is_file());
+    files
+}
+
+/// Keys in a `sesh.toml`-shaped table that no setting reads, as dotted paths.
+/// Found by round-tripping through [`SeshConfig`]: whatever doesn't come back
+/// out was ignored.
+pub fn unknown_keys(raw: &toml::Table) -> anyhow::Result<Vec<String>> {
+    let raw = toml::Value::Table(raw.clone());
+    let config: SeshConfig = raw.clone().try_into()?;
+    let known = toml::Value::try_from(&config).context("failed to serialize config")?;
+    let mut unknown = Vec::new();
+    collect_unknown(&raw, &known, "", &mut unknown);
+    Ok(unknown)
+}
+
+fn collect_unknown(raw: &toml::Value, known: &toml::Value, path: &str, out: &mut Vec<String>) {
+    match (raw, known) {
+        (toml::Value::Table(raw), toml::Value::Table(known)) => {
+            for (key, value) in raw {
+                let path = match path {
+                    "" => toml_key(key),
+                    _ => format!("{}.{}", path, toml_key(key)),
+                };
+                match known.get(key) {
+                    Some(known) => collect_unknown(value, known, &path, out),
+                    None => out.push(path),
+                }
+            }
+        }
+        (toml::Value::Array(raw), toml::Value::Array(known)) => {
+            for (i, (raw, known)) in raw.iter().zip(known).enumerate() {
+                collect_unknown(raw, known, &format!("{}[{}]", path, i), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether a repo name is safe verbatim as a bare TOML key and a file name.
+pub fn is_plain_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Render `name` as a TOML key: bare when TOML allows it, quoted otherwise
+/// (repo names like `api.v2` would otherwise become nested tables).
+pub fn toml_key(name: &str) -> String {
+    if is_plain_name(name) {
+        return name.to_string();
+    }
+    let mut quoted = String::from("\"");
+    for c in name.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Settings whose strings get `${VAR}` expansion at load time; `*` matches
+/// every key of a table or item of an array. Everything else (script
+/// commands, `[vscode] settings`, ...) is left for the tool reading it.
+const EXPANDED_SETTINGS: &[&[&str]] = &[
+    &["session", "base_branch"],
+    &["session", "copy", "*"],
+    &["repos", "*", "base_branch"],
+    &["repos", "*", "copy", "*"],
+    &["scripts", "*", "*", "path"],
+    &["repos", "*", "setup", "*", "path"],
+    &["repos", "*", "teardown", "*", "path"],
+    &["mcp", "servers", "*", "url"],
+];
+
+/// Expand `${VAR}` and `${VAR:-default}` in the strings at `path`, in place.
+fn expand_env(value: &mut toml::Value, path: &[&str]) {
+    let Some((&key, rest)) = path.split_first() else {
+        if let toml::Value::String(s) = value
+            && s.contains('$')
+        {
+            *s = expand_vars(s, |name| std::env::var(name).ok());
+        }
+        return;
+    };
+    match value {
+        toml::Value::Table(table) if key == "*" => table.iter_mut().for_each(|(_, v)| expand_env(v, rest)),
+        toml::Value::Array(items) if key == "*" => items.iter_mut().for_each(|v| expand_env(v, rest)),
+        toml::Value::Table(table) => {
+            if let Some(v) = table.get_mut(key) {
+                expand_env(v, rest);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Substitute `${VAR}` and `${VAR:-default}` (used when VAR is unset or
+/// empty) in `s`. Unset variables without a default, and anything that isn't
+/// a well-formed reference, stay as written; `$${` is a literal `${`.
+fn expand_vars(s: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start..];
+        if let Some(literal) = after.strip_prefix("$${") {
+            out.push_str("${");
+            rest = literal;
+            continue;
+        }
+        let Some((body, end)) = after.strip_prefix("${").and_then(|b| Some((b, b.find('}')?))) else {
+            out.push('$');
+            rest = &after[1..];
+            continue;
+        };
+        let (name, default) = match body[..end].split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (&body[..end], None),
+        };
+        let reference = &after[..end + 3];
+        match (is_env_name(name).then(|| lookup(name)), default) {
+            (Some(Some(v)), Some(default)) if v.is_empty() => out.push_str(default),
+            (Some(Some(v)), _) => out.push_str(&v),
+            (Some(None), Some(default)) => out.push_str(default),
+            _ => out.push_str(reference),
+        }
+        rest = &body[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn is_env_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Recursively merge `overlay` into `base`; tables merge key by key, any other
+/// value in `overlay` replaces the one in `base`.
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(b)), toml::Value::Table(o)) => merge_tables(b, o),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Read `<repo>/.sesh.toml` for every repo directory directly under `parent_dir`.
+fn load_repo_local_configs(parent_dir: &Path) -> anyhow::Result<Vec<(String, toml::Table)>> {
+    let Ok(entries) = std::fs::read_dir(parent_dir) else {
+        return Ok(Vec::new());
+    };
+
+    let mut configs = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let file = entry.path().join(REPO_CONFIG_FILE);
+        if name.starts_with('.') || !file.is_file() {
+            continue;
+        }
+        let contents = std::fs::read_to_string(&file)
+            .with_context(|| format!("failed to read {}", file.display()))?;
+        let table: toml::Table = toml::from_str(&contents)
+            .with_context(|| format!("failed to parse {}", file.display()))?;
+        configs.push((name, table));
+    }
+    configs.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(configs)
+}
+
+/// Merge a repo-local table under `repos.<name>`, keeping keys the central
+/// config already sets. Script paths in the repo-local file are relative to
+/// the repo, so they are rebased onto the parent directory.
+fn merge_repo_config(name: &str, mut local: toml::Table, repos: &mut toml::Table) {
+    for key in ["setup", "teardown"] {
+        if let Some(toml::Value::Array(entries)) = local.get_mut(key) {
+            for entry in entries {
+                if let Some(toml::Value::String(path)) = entry.get_mut("path") {
+                    *path = format!("{}/{}", name, path);
+                }
+            }
+        }
+    }
+
+    let Some(target) = repos
+        .entry(name)
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        .as_table_mut()
+    else {
+        return;
+    };
+    for (key, value) in local {
+        target.entry(key).or_insert(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toml_keys_round_trip_unusual_repo_names() {
+        for name in ["api", "api.v2", "my repo", "quote\"d", "back\\slash", "café"] {
+            let doc = format!("[repos.{}]\ncopy = []\n", toml_key(name));
+            let table: toml::Table = toml::from_str(&doc).unwrap();
+            let repos = table["repos"].as_table().unwrap();
+            assert_eq!(repos.keys().collect::<Vec<_>>(), vec![name], "{}", doc);
+        }
+        assert_eq!(toml_key("api"), "api");
+    }
+
+    #[test]
+    fn central_config_wins_and_script_paths_are_rebased() {
+        let mut repos: toml::Table = toml::from_str(
+            r#"
+            [api]
+            copy = [".env.central"]
+            "#,
+        )
+        .unwrap();
+        let local: toml::Table = toml::from_str(
+            r#"
+            copy = [".env"]
+            symlink = ["node_modules"]
+            setup = [{ path = "scripts/setup.sh", background = true }]
+            "#,
+        )
+        .unwrap();
+
+        merge_repo_config("api", local, &mut repos);
+        let config: RepoConfig = repos["api"].clone().try_into().unwrap();
+
+        assert_eq!(config.copy, vec![".env.central"]);
+        assert_eq!(config.symlink, vec!["node_modules"]);
+        assert_eq!(config.setup[0].path, "api/scripts/setup.sh");
+        assert!(config.setup[0].background);
+    }
+
+    #[test]
+    fn script_entries_need_a_path_or_command() {
+        let dir = std::env::temp_dir().join(format!("sesh-config-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("sesh.toml");
+
+        std::fs::write(&path, "[[repos.api.setup]]\nbackground = true\n").unwrap();
+        let err = SeshConfig::load(&path).unwrap_err();
+        assert!(format!("{:#}", err).contains("[[repos.api.setup]]"));
+
+        std::fs::write(&path, "[[repos.api.setup]]\ncommand = \"pnpm dev\"\n").unwrap();
+        let config = SeshConfig::load(&path).unwrap();
+        assert!(config.repos["api"].setup[0].is_available(&dir));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn expands_env_vars_with_defaults() {
+        let lookup = |name: &str| match name {
+            "USER" => Some("alice".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        };
+        let expand = |s: &str| expand_vars(s, lookup);
+
+        assert_eq!(expand("users/${USER}/"), "users/alice/");
+        assert_eq!(expand("${BASE:-main}"), "main");
+        assert_eq!(expand("${EMPTY:-dev}-${EMPTY}"), "dev-");
+        assert_eq!(expand("echo $HOME $${USER}"), "echo $HOME ${USER}");
+        assert_eq!(expand("${workspaceFolder}/.venv"), "${workspaceFolder}/.venv");
+        assert_eq!(expand("${USER"), "${USER");
+        assert_eq!(expand("${1X:-y}"), "${1X:-y}");
+    }
+
+    #[test]
+    fn expands_only_listed_settings() {
+        let mut table: toml::Value = toml::from_str(
+            r#"
+            [session]
+            base_branch = "${SESH_TEST_UNSET_BASE:-develop}"
+
+            [[repos.api.setup]]
+            path = "${SESH_TEST_UNSET_DIR:-scripts}/setup.sh"
+            command = "echo ${SESH_TEST_UNSET_DIR:-x}"
+
+            [vscode.settings]
+            "python.defaultInterpreterPath" = "${workspaceFolder}/.venv/bin/python"
+            "#,
+        )
+        .unwrap();
+        for path in EXPANDED_SETTINGS {
+            expand_env(&mut table, path);
+        }
+
+        assert_eq!(table["session"]["base_branch"].as_str(), Some("develop"));
+        let setup = &table["repos"]["api"]["setup"][0];
+        assert_eq!(setup["path"].as_str(), Some("scripts/setup.sh"));
+        assert_eq!(setup["command"].as_str(), Some("echo ${SESH_TEST_UNSET_DIR:-x}"));
+        assert_eq!(
+            table["vscode"]["settings"]["python.defaultInterpreterPath"].as_str(),
+            Some("${workspaceFolder}/.venv/bin/python")
+        );
+    }
+
+    #[test]
+    fn unknown_keys_are_reported_with_their_path() {
+        let raw: toml::Table = toml::from_str(
+            r#"
+            [session]
+            base_branch = "main"
+            base_brnch = "develop"
+
+            [[scripts.setup]]
+            path = "setup.sh"
+            backgroud = true
+
+            [repos.api]
+            copy = [".env"]
+
+            [vscode.settings]
+            "editor.tabSize" = 2
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            unknown_keys(&raw).unwrap(),
+            vec!["scripts.setup[0].backgroud", "session.base_brnch"]
+        );
+    }
+
+    #[test]
+    fn local_override_merges_tables_and_replaces_values() {
+        let mut base: toml::Table = toml::from_str(
+            r#"
+            [session]
+            base_branch = "main"
+            shared_context = ["AGENTS.md"]
+
+            [repos.api]
+            copy = [".env"]
+            exclusive = true
+            "#,
+        )
+        .unwrap();
+        let local: toml::Table = toml::from_str(
+            r#"
+            [session]
+            base_branch = "develop"
+
+            [repos.api]
+            copy = [".env.local"]
+            "#,
+        )
+        .unwrap();
+
+        merge_tables(&mut base, local);
+        let config: SeshConfig = toml::Value::Table(base).try_into().unwrap();
+
+        assert_eq!(config.session.base_branch, "develop");
+        assert_eq!(config.session.shared_context, vec!["AGENTS.md"]);
+        assert_eq!(config.repos["api"].copy, vec![".env.local"]);
+        assert!(config.repos["api"].exclusive);
+    }
+
+    #[test]
+    fn maps_session_branch_per_repo() {
+        let config: SeshConfig = toml::from_str(
+            r#"
+            [repos.web]
+            branch_prefix = "feature/"
+
+            [repos.infra]
+            branch_prefix = "ignored/"
+            branch_template = "{branch_slug}"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.repo_branch("web", "alice/eng-1-fix"), "feature/alice/eng-1-fix");
+        assert_eq!(config.repo_branch("infra", "alice/eng-1-fix"), "alice-eng-1-fix");
+        assert_eq!(config.repo_branch("api", "alice/eng-1-fix"), "alice/eng-1-fix");
+    }
 }
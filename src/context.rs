@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::os::unix::fs::symlink;
 use std::path::{Path, PathBuf};
 
@@ -5,6 +6,11 @@ use anyhow::{Context, Result};
 
 use crate::session::IssueContext;
 
+/// The generated context file inside a session directory.
+pub fn context_file(session_dir: &Path) -> PathBuf {
+    session_dir.join("context").join(".sesh-context.md")
+}
+
 pub fn generate_context(
     session_dir: &Path,
     session_name: &str,
@@ -46,7 +52,7 @@ pub fn generate_context(
         content.push_str(&format!("- **{}**: `{}`\n", name, path.display()));
     }
 
-    let context_file = context_dir.join(".sesh-context.md");
+    let context_file = context_file(session_dir);
     std::fs::write(&context_file, &content)
         .with_context(|| format!("failed to write {}", context_file.display()))?;
 
@@ -70,3 +76,29 @@ pub fn generate_context(
 
     Ok(())
 }
+
+const URLS_HEADING: &str = "\n## URLs\n";
+
+/// Rewrite the `## URLs` section of the session's context file. The section is
+/// always kept last so it can be replaced without touching the rest.
+pub fn update_urls(session_dir: &Path, urls: &BTreeMap<String, String>) -> Result<()> {
+    let context_file = context_file(session_dir);
+    let Ok(existing) = std::fs::read_to_string(&context_file) else {
+        return Ok(());
+    };
+
+    let mut content = match existing.find(URLS_HEADING) {
+        Some(idx) => existing[..idx].to_string(),
+        None => existing,
+    };
+    if !urls.is_empty() {
+        content.push_str(URLS_HEADING);
+        content.push('\n');
+        for (name, url) in urls {
+            content.push_str(&format!("- **{}**: {}\n", name, url));
+        }
+    }
+
+    std::fs::write(&context_file, &content)
+        .with_context(|| format!("failed to write {}", context_file.display()))
+}
@@ -1,8 +1,10 @@
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
 use anyhow::{Context, Result};
 
+use crate::gitstate;
+use crate::timing;
+
 #[derive(Debug, Clone)]
 pub struct RepoInfo {
     pub name: String,
@@ -11,7 +13,37 @@ pub struct RepoInfo {
     pub is_dirty: bool,
 }
 
-pub fn discover_repos(parent_dir: &Path) -> Result<Vec<RepoInfo>> {
+/// Find the git repos directly under `parent_dir`, querying their branch and
+/// dirty state concurrently.
+pub async fn discover_repos(parent_dir: &Path) -> Result<Vec<RepoInfo>> {
+    let _t = timing::timer("discovery", || parent_dir.display().to_string());
+    let candidates = repo_dirs(parent_dir)?;
+    let paths: Vec<PathBuf> = candidates.iter().map(|(_, path)| path.clone()).collect();
+    gitstate::prefetch_status(&paths).await;
+
+    let mut repos = Vec::new();
+    for (name, path) in candidates {
+        let (current_branch, is_dirty) = match gitstate::status(&path) {
+            Ok(status) => (status.branch.clone().unwrap_or_default(), status.is_dirty()),
+            Err(_) => (String::new(), false),
+        };
+
+        repos.push(RepoInfo {
+            name,
+            path,
+            current_branch,
+            is_dirty,
+        });
+    }
+
+    repos.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(repos)
+}
+
+/// Names and paths of the non-hidden directories holding a main git checkout.
+/// A `parent_dir` with none that is itself a main checkout is its own only
+/// repo (single-repo mode).
+pub fn repo_dirs(parent_dir: &Path) -> Result<Vec<(String, PathBuf)>> {
     let entries = std::fs::read_dir(parent_dir)
         .with_context(|| format!("failed to read directory: {}", parent_dir.display()))?;
 
@@ -45,35 +77,50 @@ pub fn discover_repos(parent_dir: &Path) -> Result<Vec<RepoInfo>> {
             continue;
         }
 
-        let current_branch = git_branch(&path).unwrap_or_default();
-        let is_dirty = git_is_dirty(&path).unwrap_or(false);
+        repos.push((name, path));
+    }
 
-        repos.push(RepoInfo {
-            name,
-            path,
-            current_branch,
-            is_dirty,
-        });
+    if repos.is_empty()
+        && parent_dir.join(".git").is_dir()
+        && let Some(name) = parent_dir
+            .canonicalize()
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+    {
+        repos.push((name, parent_dir.to_path_buf()));
     }
 
-    repos.sort_by(|a, b| a.name.cmp(&b.name));
     Ok(repos)
 }
 
-fn git_branch(repo_path: &Path) -> Result<String> {
-    let output = Command::new("git")
-        .args(["-C", &repo_path.to_string_lossy(), "branch", "--show-current"])
-        .output()
-        .with_context(|| format!("failed to run git branch in {}", repo_path.display()))?;
-
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+/// Whether `repos` is the single-repo fallback of [`repo_dirs`]: sessions are
+/// worktrees of the repo sesh runs in.
+pub fn is_single_repo(parent_dir: &Path, repos: &[RepoInfo]) -> bool {
+    matches!(repos, [repo] if repo.path == parent_dir)
 }
 
-fn git_is_dirty(repo_path: &Path) -> Result<bool> {
-    let output = Command::new("git")
-        .args(["-C", &repo_path.to_string_lossy(), "status", "--porcelain"])
+/// The directory to run in from `cwd`: the nearest one (`cwd` or above) with a
+/// `sesh.toml` or `.sesh/`, so sesh works from inside a repo or worktree;
+/// otherwise, when `cwd` has no repos of its own, the top of the git checkout
+/// it is in (single-repo mode); otherwise `cwd`.
+pub fn workspace_root(cwd: &Path) -> PathBuf {
+    let is_workspace = |dir: &Path| dir.join("sesh.toml").is_file() || dir.join(".sesh").is_dir();
+    if let Some(dir) = cwd.ancestors().find(|d| is_workspace(d)) {
+        return dir.to_path_buf();
+    }
+    if repo_dirs(cwd).is_ok_and(|r| !r.is_empty()) {
+        return cwd.to_path_buf();
+    }
+    let toplevel = std::process::Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .current_dir(cwd)
         .output()
-        .with_context(|| format!("failed to run git status in {}", repo_path.display()))?;
-
-    Ok(!output.stdout.is_empty())
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| PathBuf::from(String::from_utf8_lossy(&o.stdout).trim()));
+    match toplevel {
+        // Linked worktrees have a `.git` file; only a main checkout qualifies
+        Some(top) if top.join(".git").is_dir() => top,
+        _ => cwd.to_path_buf(),
+    }
 }
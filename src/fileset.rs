@@ -0,0 +1,160 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+/// A per-repo `copy` / `symlink` / `clone` list: plain paths, globs such as
+/// `.env*` or `config/*.local.json`, and `!`-prefixed patterns that exclude
+/// matches (and files inside copied directories).
+pub struct FileSet {
+    include: Vec<String>,
+    exclude: GlobSet,
+}
+
+impl FileSet {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let mut include = Vec::new();
+        let mut exclude = GlobSetBuilder::new();
+        for pattern in patterns {
+            match pattern.strip_prefix('!') {
+                Some(negated) => {
+                    exclude.add(glob(negated)?);
+                }
+                None => include.push(pattern.trim_end_matches('/').to_string()),
+            }
+        }
+        let exclude = exclude.build().context("failed to build exclude patterns")?;
+        Ok(Self { include, exclude })
+    }
+
+    /// Each include pattern with the relative paths it matches under `root`,
+    /// sorted so reporting is deterministic.
+    pub fn expand(&self, root: &Path) -> Result<Vec<(&str, Vec<String>)>> {
+        let mut expanded = Vec::new();
+        for pattern in &self.include {
+            let mut matches = if is_glob(pattern) {
+                let matcher = glob(pattern)?.compile_matcher();
+                // Only walk below the pattern's literal prefix, and no deeper than it can reach
+                let components: Vec<&str> = pattern.split('/').collect();
+                let literal = components.iter().take_while(|c| !is_glob(c)).count();
+                let base = components[..literal].join("/");
+                let max_depth = if pattern.contains("**") { usize::MAX } else { components.len() };
+                let mut found = Vec::new();
+                walk(root, &base, literal, max_depth, &mut |rel| {
+                    let hit = matcher.is_match(rel);
+                    if hit {
+                        found.push(rel.to_string());
+                    }
+                    hit
+                })?;
+                found
+            } else if root.join(pattern).symlink_metadata().is_ok() {
+                vec![pattern.clone()]
+            } else {
+                Vec::new()
+            };
+            matches.retain(|m| !self.excludes(m));
+            matches.sort();
+            expanded.push((pattern.as_str(), matches));
+        }
+        Ok(expanded)
+    }
+
+    pub fn excludes(&self, rel_path: &str) -> bool {
+        self.exclude.is_match(rel_path)
+    }
+
+    /// Copy `rel_path` (a file or directory) from `src_root` to `dst_root`,
+    /// skipping anything inside it that an exclude pattern matches.
+    pub fn copy(&self, src_root: &Path, dst_root: &Path, rel_path: &str) -> Result<()> {
+        let src = src_root.join(rel_path);
+        let dst = dst_root.join(rel_path);
+        if let Some(parent) = dst.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if !src.is_dir() {
+            std::fs::copy(&src, &dst)?;
+            return Ok(());
+        }
+        std::fs::create_dir_all(&dst)?;
+        let mut entries: Vec<_> = std::fs::read_dir(&src)?.collect::<std::io::Result<_>>()?;
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            let child = format!("{}/{}", rel_path, entry.file_name().to_string_lossy());
+            if !self.excludes(&child) {
+                self.copy(src_root, dst_root, &child)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn glob(pattern: &str) -> Result<globset::Glob> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .with_context(|| format!("invalid file pattern: {}", pattern))
+}
+
+pub fn is_glob(s: &str) -> bool {
+    s.contains(['*', '?', '[', '{'])
+}
+
+/// Visit paths below `root/rel` (relative to `root`) down to `max_depth`
+/// components, skipping `.git` and not descending into symlinks or into
+/// directories `visit` accepted.
+fn walk(
+    root: &Path,
+    rel: &str,
+    depth: usize,
+    max_depth: usize,
+    visit: &mut dyn FnMut(&str) -> bool,
+) -> Result<()> {
+    if depth >= max_depth {
+        return Ok(());
+    }
+    let Ok(entries) = std::fs::read_dir(root.join(rel)) else {
+        return Ok(());
+    };
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == ".git" {
+            continue;
+        }
+        let child = if rel.is_empty() { name } else { format!("{}/{}", rel, name) };
+        if !visit(&child) && entry.file_type()?.is_dir() {
+            walk(root, &child, depth + 1, max_depth, visit)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_globs_and_excludes() {
+        let root = std::env::temp_dir().join(format!("sesh-fileset-{}", std::process::id()));
+        for file in [".env", ".env.local", ".env.secret", "config/app.local.json", "config/deep/x.local.json"] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let patterns = [".env*", "config/*.local.json", "missing", "!.env.secret"].map(String::from);
+        let set = FileSet::new(&patterns).unwrap();
+        let expanded = set.expand(&root).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            expanded,
+            vec![
+                (".env*", vec![".env".to_string(), ".env.local".to_string()]),
+                ("config/*.local.json", vec!["config/app.local.json".to_string()]),
+                ("missing", vec![]),
+            ]
+        );
+    }
+}
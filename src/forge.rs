@@ -0,0 +1,689 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use reqwest::Client;
+use serde::Deserialize;
+
+use crate::config::{BitbucketConfig, SeshConfig};
+use crate::integrations::load_token;
+use crate::timing::{TimedOutput, TimedSend};
+
+const BITBUCKET_API: &str = "https://api.bitbucket.org/2.0";
+
+/// Where a repo's pull requests live, picked from its remote URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Forge {
+    /// Handled through the GitHub CLI (`gh`), which finds the repo itself.
+    GitHub,
+    Bitbucket { workspace: String, repo: String },
+    AzureDevOps { organization: String, project: String, repo: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct PullRequest {
+    pub number: u64,
+    pub title: String,
+    /// Head (source) branch.
+    pub branch: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrState {
+    Open,
+    Merged,
+    /// Closed (declined, abandoned) without merging.
+    Closed,
+}
+
+impl PrState {
+    pub fn label(self) -> &'static str {
+        match self {
+            PrState::Open => "open",
+            PrState::Merged => "merged",
+            PrState::Closed => "closed",
+        }
+    }
+}
+
+impl Forge {
+    /// The forge hosting `remote` (usually `origin`) of the repo at `repo_path`.
+    pub fn detect(repo_path: &Path, remote: &str) -> Forge {
+        remote_url(repo_path, remote)
+            .map(|url| Forge::from_remote_url(&url))
+            .unwrap_or(Forge::GitHub)
+    }
+
+    /// Anything that isn't a recognizable Bitbucket Cloud or Azure DevOps URL
+    /// is left to `gh`.
+    pub fn from_remote_url(url: &str) -> Forge {
+        if let Some((organization, project, repo)) = parse_azure_remote(url) {
+            return Forge::AzureDevOps { organization, project, repo };
+        }
+        let Some((_, rest)) = url.split_once("bitbucket.org") else {
+            return Forge::GitHub;
+        };
+        // `git@bitbucket.org:ws/repo.git`, `https://user@bitbucket.org/ws/repo.git`
+        let path = rest.trim_start_matches([':', '/']).trim_end_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        match path.split_once('/') {
+            Some((workspace, repo)) if !workspace.is_empty() && !repo.is_empty() && !repo.contains('/') => {
+                Forge::Bitbucket {
+                    workspace: workspace.to_string(),
+                    repo: repo.to_string(),
+                }
+            }
+            _ => Forge::GitHub,
+        }
+    }
+
+    pub async fn list_open_prs(
+        &self,
+        parent_dir: &Path,
+        config: &SeshConfig,
+        repo_path: &Path,
+    ) -> Result<Vec<PullRequest>> {
+        match self {
+            Forge::GitHub => gh_list_prs(repo_path),
+            Forge::Bitbucket { workspace, repo } => {
+                bitbucket_list_prs(parent_dir, &config.bitbucket, workspace, repo).await
+            }
+            Forge::AzureDevOps { organization, project, repo } => {
+                azure_list_prs(parent_dir, organization, project, repo).await
+            }
+        }
+    }
+
+    /// State of the most recent PR from `branch`, or `None` if it never had one.
+    pub async fn pr_state(
+        &self,
+        parent_dir: &Path,
+        config: &SeshConfig,
+        repo_path: &Path,
+        branch: &str,
+    ) -> Result<Option<PrState>> {
+        match self {
+            Forge::GitHub => gh_pr_state(repo_path, branch),
+            Forge::Bitbucket { workspace, repo } => {
+                bitbucket_pr_state(parent_dir, &config.bitbucket, workspace, repo, branch).await
+            }
+            Forge::AzureDevOps { organization, project, repo } => {
+                azure_pr_state(parent_dir, organization, project, repo, branch).await
+            }
+        }
+    }
+
+    /// Open a PR from `head` into `base` on `remote`'s repo and return its URL.
+    /// When `push_remote` differs (a fork), the head branch is taken from
+    /// there; only GitHub supports that, so callers check before pushing.
+    /// Without a `body`, GitHub PRs are filled from the commits
+    /// (`gh pr create --fill`); the others get none.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_pr(
+        &self,
+        parent_dir: &Path,
+        config: &SeshConfig,
+        repo_path: &Path,
+        remote: &str,
+        push_remote: &str,
+        base: &str,
+        head: &str,
+        body: Option<&str>,
+    ) -> Result<String> {
+        match self {
+            Forge::GitHub => gh_create_pr(repo_path, remote, push_remote, base, head, body),
+            Forge::Bitbucket { workspace, repo } => {
+                bitbucket_create_pr(parent_dir, &config.bitbucket, workspace, repo, base, head, body).await
+            }
+            Forge::AzureDevOps { organization, project, repo } => {
+                azure_create_pr(parent_dir, organization, project, repo, base, head, body).await
+            }
+        }
+    }
+}
+
+/// The URL of `remote` in the repo at `repo_path`.
+pub fn remote_url(repo_path: &Path, remote: &str) -> Option<String> {
+    Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(["remote", "get-url", remote])
+        .timed_output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+}
+
+/// The repo as `gh --repo` takes it (`OWNER/REPO`, or `HOST/OWNER/REPO` off
+/// github.com) and its owner, from a remote URL.
+fn gh_repo(url: &str) -> Option<(String, String)> {
+    let url = url.trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let rest = rest.split_once('@').map_or(rest, |(_, rest)| rest);
+    let (host, path) = rest.split_once([':', '/'])?;
+    let (owner, repo) = path.split_once('/')?;
+    if owner.is_empty() || repo.is_empty() || repo.contains('/') {
+        return None;
+    }
+    let slug = if host == "github.com" {
+        format!("{}/{}", owner, repo)
+    } else {
+        format!("{}/{}/{}", host, owner, repo)
+    };
+    Some((slug, owner.to_string()))
+}
+
+pub fn require_gh() -> Result<()> {
+    match Command::new("which").arg("gh").output() {
+        Ok(output) if output.status.success() => Ok(()),
+        _ => bail!("GitHub CLI (gh) not found. Install it from https://cli.github.com"),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GhPr {
+    number: u64,
+    title: String,
+    #[serde(rename = "headRefName")]
+    head_ref_name: String,
+}
+
+fn gh_list_prs(repo_path: &Path) -> Result<Vec<PullRequest>> {
+    require_gh()?;
+    let output = Command::new("gh")
+        .args([
+            "pr", "list",
+            "--json", "number,title,headRefName",
+            "--state", "open",
+        ])
+        .current_dir(repo_path)
+        .timed_output()
+        .context("failed to run gh pr list")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let prs: Vec<GhPr> = serde_json::from_slice(&output.stdout).context("failed to parse PR list")?;
+    Ok(prs
+        .into_iter()
+        .map(|pr| PullRequest {
+            number: pr.number,
+            title: pr.title,
+            branch: pr.head_ref_name,
+        })
+        .collect())
+}
+
+fn gh_pr_state(repo_path: &Path, branch: &str) -> Result<Option<PrState>> {
+    #[derive(Deserialize)]
+    struct View {
+        state: String,
+    }
+
+    require_gh()?;
+    let output = Command::new("gh")
+        .args(["pr", "view", branch, "--json", "state"])
+        .current_dir(repo_path)
+        .timed_output()
+        .context("failed to run gh pr view")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("no pull requests found") {
+            return Ok(None);
+        }
+        bail!("{}", stderr.trim());
+    }
+    let view: View = serde_json::from_slice(&output.stdout).context("failed to parse gh pr view output")?;
+    Ok(Some(match view.state.as_str() {
+        "MERGED" => PrState::Merged,
+        "CLOSED" => PrState::Closed,
+        _ => PrState::Open,
+    }))
+}
+
+fn gh_create_pr(
+    repo_path: &Path,
+    remote: &str,
+    push_remote: &str,
+    base: &str,
+    head: &str,
+    body: Option<&str>,
+) -> Result<String> {
+    require_gh()?;
+    let mut cmd = Command::new("gh");
+    cmd.args(["pr", "create", "--base", base, "--title", head]);
+    if remote == "origin" && push_remote == remote {
+        cmd.args(["--head", head]);
+    } else {
+        // gh would guess the target repo from the remotes; name it, and the fork's owner
+        let github = |remote: &str| {
+            remote_url(repo_path, remote)
+                .as_deref()
+                .and_then(gh_repo)
+                .with_context(|| format!("cannot tell the GitHub repo of remote '{}'", remote))
+        };
+        let (repo, _) = github(remote)?;
+        cmd.args(["--repo", &repo]);
+        if push_remote == remote {
+            cmd.args(["--head", head]);
+        } else {
+            let (_, owner) = github(push_remote)?;
+            cmd.args(["--head", &format!("{}:{}", owner, head)]);
+        }
+    }
+    match body {
+        Some(body) => cmd.args(["--body", body]),
+        None => cmd.arg("--fill"),
+    };
+    let output = cmd
+        .current_dir(repo_path)
+        .timed_output()
+        .context("Failed to run gh pr create")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn bitbucket_request(
+    parent_dir: &Path,
+    config: &BitbucketConfig,
+    method: reqwest::Method,
+    url: &str,
+) -> Result<reqwest::RequestBuilder> {
+    let token = load_token(parent_dir, "bitbucket_token")
+        .context("run `sesh auth bitbucket` to set a Bitbucket token")?;
+    let req = Client::new().request(method, url).header("Accept", "application/json");
+    Ok(match &config.username {
+        Some(username) => req.basic_auth(username, Some(token)),
+        None => req.bearer_auth(token),
+    })
+}
+
+async fn bitbucket_error(resp: reqwest::Response) -> anyhow::Error {
+    #[derive(Deserialize)]
+    struct ErrorBody {
+        error: ErrorDetail,
+    }
+    #[derive(Deserialize)]
+    struct ErrorDetail {
+        message: String,
+    }
+
+    let status = resp.status();
+    match resp.json::<ErrorBody>().await {
+        Ok(body) => anyhow::anyhow!("Bitbucket API returned status {}: {}", status, body.error.message),
+        Err(_) => anyhow::anyhow!("Bitbucket API returned status {}", status),
+    }
+}
+
+#[derive(Deserialize)]
+struct BitbucketPage {
+    values: Vec<BitbucketPr>,
+    next: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BitbucketPr {
+    id: u64,
+    title: String,
+    source: BitbucketEndpoint,
+}
+
+#[derive(Deserialize)]
+struct BitbucketEndpoint {
+    branch: BitbucketBranch,
+}
+
+#[derive(Deserialize)]
+struct BitbucketBranch {
+    name: String,
+}
+
+async fn bitbucket_list_prs(
+    parent_dir: &Path,
+    config: &BitbucketConfig,
+    workspace: &str,
+    repo: &str,
+) -> Result<Vec<PullRequest>> {
+    let mut url = Some(format!(
+        "{}/repositories/{}/{}/pullrequests?state=OPEN&pagelen=50",
+        BITBUCKET_API, workspace, repo
+    ));
+    let mut prs = Vec::new();
+    while let Some(page_url) = url {
+        let resp = bitbucket_request(parent_dir, config, reqwest::Method::GET, &page_url)?
+            .timed_send()
+            .await
+            .context("failed to call Bitbucket API")?;
+        if !resp.status().is_success() {
+            return Err(bitbucket_error(resp).await);
+        }
+        let page: BitbucketPage = resp.json().await.context("failed to parse Bitbucket response")?;
+        prs.extend(page.values.into_iter().map(|pr| PullRequest {
+            number: pr.id,
+            title: pr.title,
+            branch: pr.source.branch.name,
+        }));
+        url = page.next;
+    }
+    Ok(prs)
+}
+
+async fn bitbucket_pr_state(
+    parent_dir: &Path,
+    config: &BitbucketConfig,
+    workspace: &str,
+    repo: &str,
+    branch: &str,
+) -> Result<Option<PrState>> {
+    #[derive(Deserialize)]
+    struct Page {
+        values: Vec<StatePr>,
+    }
+    #[derive(Deserialize)]
+    struct StatePr {
+        state: String,
+    }
+
+    // Without explicit states only open PRs are returned
+    let url = reqwest::Url::parse_with_params(
+        &format!("{}/repositories/{}/{}/pullrequests", BITBUCKET_API, workspace, repo),
+        &[
+            ("q", format!("source.branch.name=\"{}\"", branch)),
+            ("state", "OPEN".to_string()),
+            ("state", "MERGED".to_string()),
+            ("state", "DECLINED".to_string()),
+            ("state", "SUPERSEDED".to_string()),
+            ("sort", "-updated_on".to_string()),
+            ("pagelen", "1".to_string()),
+        ],
+    )?;
+    let resp = bitbucket_request(parent_dir, config, reqwest::Method::GET, url.as_str())?
+        .timed_send()
+        .await
+        .context("failed to call Bitbucket API")?;
+    if !resp.status().is_success() {
+        return Err(bitbucket_error(resp).await);
+    }
+    let page: Page = resp.json().await.context("failed to parse Bitbucket response")?;
+    Ok(page.values.first().map(|pr| match pr.state.as_str() {
+        "MERGED" => PrState::Merged,
+        "OPEN" => PrState::Open,
+        _ => PrState::Closed,
+    }))
+}
+
+async fn bitbucket_create_pr(
+    parent_dir: &Path,
+    config: &BitbucketConfig,
+    workspace: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+    body: Option<&str>,
+) -> Result<String> {
+    #[derive(Deserialize)]
+    struct Created {
+        links: Links,
+    }
+    #[derive(Deserialize)]
+    struct Links {
+        html: Link,
+    }
+    #[derive(Deserialize)]
+    struct Link {
+        href: String,
+    }
+
+    let url = format!("{}/repositories/{}/{}/pullrequests", BITBUCKET_API, workspace, repo);
+    let body = serde_json::json!({
+        "title": head,
+        "description": body.unwrap_or_default(),
+        "source": { "branch": { "name": head } },
+        "destination": { "branch": { "name": base } },
+    });
+    let resp = bitbucket_request(parent_dir, config, reqwest::Method::POST, &url)?
+        .json(&body)
+        .timed_send()
+        .await
+        .context("failed to call Bitbucket API")?;
+    if !resp.status().is_success() {
+        return Err(bitbucket_error(resp).await);
+    }
+    let created: Created = resp.json().await.context("failed to parse Bitbucket response")?;
+    Ok(created.links.html.href)
+}
+
+/// `(organization, project, repo)` from an Azure DevOps remote:
+/// `https://[user@]dev.azure.com/org/project/_git/repo`,
+/// `git@ssh.dev.azure.com:v3/org/project/repo`, or the older
+/// `https://org.visualstudio.com/[DefaultCollection/]project/_git/repo`.
+fn parse_azure_remote(url: &str) -> Option<(String, String, String)> {
+    let owned = |parts: [&str; 3]| {
+        parts
+            .iter()
+            .all(|p| !p.is_empty())
+            .then(|| (parts[0].to_string(), parts[1].to_string(), parts[2].to_string()))
+    };
+
+    for ssh_host in ["ssh.dev.azure.com:v3/", "vs-ssh.visualstudio.com:v3/"] {
+        if let Some((_, path)) = url.split_once(ssh_host) {
+            let parts: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+            return match parts[..] {
+                [org, project, repo] => owned([org, project, repo]),
+                _ => None,
+            };
+        }
+    }
+
+    let rest = url.strip_prefix("https://")?;
+    let (host, path) = rest.split_once('/')?;
+    let host = host.rsplit_once('@').map_or(host, |(_, h)| h);
+    let (org, path) = if host == "dev.azure.com" {
+        path.split_once('/')?
+    } else {
+        let org = host.strip_suffix(".visualstudio.com")?;
+        (org, path.strip_prefix("DefaultCollection/").unwrap_or(path))
+    };
+    let (project, repo) = path.split_once("/_git/")?;
+    let repo = repo.trim_end_matches('/');
+    let repo = repo.strip_suffix(".git").unwrap_or(repo);
+    if repo.contains('/') {
+        return None;
+    }
+    owned([org, project, repo])
+}
+
+fn azure_request(
+    parent_dir: &Path,
+    method: reqwest::Method,
+    url: &str,
+) -> Result<reqwest::RequestBuilder> {
+    let token = load_token(parent_dir, "azure_token")
+        .context("run `sesh auth azure` to set an Azure DevOps token")?;
+    // Personal access tokens go in basic auth with an empty username
+    Ok(Client::new()
+        .request(method, url)
+        .basic_auth("", Some(token))
+        .header("Accept", "application/json"))
+}
+
+async fn azure_error(resp: reqwest::Response) -> anyhow::Error {
+    #[derive(Deserialize)]
+    struct ErrorBody {
+        message: String,
+    }
+
+    let status = resp.status();
+    match resp.json::<ErrorBody>().await {
+        Ok(body) => anyhow::anyhow!("Azure DevOps API returned status {}: {}", status, body.message),
+        Err(_) => anyhow::anyhow!("Azure DevOps API returned status {}", status),
+    }
+}
+
+fn azure_pulls_url(organization: &str, project: &str, repo: &str) -> String {
+    format!(
+        "https://dev.azure.com/{}/{}/_apis/git/repositories/{}/pullrequests",
+        organization, project, repo
+    )
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AzurePr {
+    pull_request_id: u64,
+    #[serde(default)]
+    title: String,
+    source_ref_name: String,
+}
+
+async fn azure_list_prs(
+    parent_dir: &Path,
+    organization: &str,
+    project: &str,
+    repo: &str,
+) -> Result<Vec<PullRequest>> {
+    #[derive(Deserialize)]
+    struct AzurePrList {
+        value: Vec<AzurePr>,
+    }
+
+    let url = format!(
+        "{}?searchCriteria.status=active&$top=100&api-version=7.1",
+        azure_pulls_url(organization, project, repo)
+    );
+    let resp = azure_request(parent_dir, reqwest::Method::GET, &url)?
+        .timed_send()
+        .await
+        .context("failed to call Azure DevOps API")?;
+    if !resp.status().is_success() {
+        return Err(azure_error(resp).await);
+    }
+    let list: AzurePrList = resp.json().await.context("failed to parse Azure DevOps response")?;
+    Ok(list
+        .value
+        .into_iter()
+        .map(|pr| PullRequest {
+            number: pr.pull_request_id,
+            title: pr.title,
+            branch: pr
+                .source_ref_name
+                .strip_prefix("refs/heads/")
+                .unwrap_or(&pr.source_ref_name)
+                .to_string(),
+        })
+        .collect())
+}
+
+async fn azure_pr_state(
+    parent_dir: &Path,
+    organization: &str,
+    project: &str,
+    repo: &str,
+    branch: &str,
+) -> Result<Option<PrState>> {
+    #[derive(Deserialize)]
+    struct StateList {
+        value: Vec<StatePr>,
+    }
+    #[derive(Deserialize)]
+    struct StatePr {
+        status: String,
+    }
+
+    // Results come newest first
+    let url = reqwest::Url::parse_with_params(
+        &azure_pulls_url(organization, project, repo),
+        &[
+            ("searchCriteria.sourceRefName", format!("refs/heads/{}", branch)),
+            ("searchCriteria.status", "all".to_string()),
+            ("$top", "1".to_string()),
+            ("api-version", "7.1".to_string()),
+        ],
+    )?;
+    let resp = azure_request(parent_dir, reqwest::Method::GET, url.as_str())?
+        .timed_send()
+        .await
+        .context("failed to call Azure DevOps API")?;
+    if !resp.status().is_success() {
+        return Err(azure_error(resp).await);
+    }
+    let list: StateList = resp.json().await.context("failed to parse Azure DevOps response")?;
+    Ok(list.value.first().map(|pr| match pr.status.as_str() {
+        "completed" => PrState::Merged,
+        "abandoned" => PrState::Closed,
+        _ => PrState::Open,
+    }))
+}
+
+async fn azure_create_pr(
+    parent_dir: &Path,
+    organization: &str,
+    project: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+    body: Option<&str>,
+) -> Result<String> {
+    let url = format!("{}?api-version=7.1", azure_pulls_url(organization, project, repo));
+    let body = serde_json::json!({
+        "title": head,
+        "description": body.unwrap_or_default(),
+        "sourceRefName": format!("refs/heads/{}", head),
+        "targetRefName": format!("refs/heads/{}", base),
+    });
+    let resp = azure_request(parent_dir, reqwest::Method::POST, &url)?
+        .json(&body)
+        .timed_send()
+        .await
+        .context("failed to call Azure DevOps API")?;
+    if !resp.status().is_success() {
+        return Err(azure_error(resp).await);
+    }
+    let created: AzurePr = resp.json().await.context("failed to parse Azure DevOps response")?;
+    Ok(format!(
+        "https://dev.azure.com/{}/{}/_git/{}/pullrequest/{}",
+        organization, project, repo, created.pull_request_id
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forge_is_picked_from_remote_url() {
+        let bitbucket = Forge::Bitbucket {
+            workspace: "acme".to_string(),
+            repo: "api".to_string(),
+        };
+        assert_eq!(Forge::from_remote_url("git@bitbucket.org:acme/api.git"), bitbucket);
+        assert_eq!(Forge::from_remote_url("https://dev@bitbucket.org/acme/api.git"), bitbucket);
+        assert_eq!(Forge::from_remote_url("https://bitbucket.org/acme/api"), bitbucket);
+        assert_eq!(Forge::from_remote_url("git@github.com:acme/api.git"), Forge::GitHub);
+        assert_eq!(Forge::from_remote_url("https://bitbucket.org/acme"), Forge::GitHub);
+
+        let azure = Forge::AzureDevOps {
+            organization: "acme".to_string(),
+            project: "Web".to_string(),
+            repo: "api".to_string(),
+        };
+        assert_eq!(Forge::from_remote_url("https://acme@dev.azure.com/acme/Web/_git/api"), azure);
+        assert_eq!(Forge::from_remote_url("git@ssh.dev.azure.com:v3/acme/Web/api"), azure);
+        assert_eq!(Forge::from_remote_url("https://acme.visualstudio.com/DefaultCollection/Web/_git/api"), azure);
+    }
+
+    #[test]
+    fn gh_repo_is_read_from_remote_url() {
+        let acme = Some(("acme/api".to_string(), "acme".to_string()));
+        assert_eq!(gh_repo("git@github.com:acme/api.git"), acme);
+        assert_eq!(gh_repo("https://github.com/acme/api"), acme);
+        assert_eq!(gh_repo("https://me@github.com/acme/api.git/"), acme);
+        assert_eq!(
+            gh_repo("git@ghe.corp.com:alice/api.git"),
+            Some(("ghe.corp.com/alice/api".to_string(), "alice".to_string()))
+        );
+        assert_eq!(gh_repo("https://github.com/acme"), None);
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use anyhow::{bail, Context, Result};
+
+use crate::parallel;
+use crate::timing::{self, TimedOutput};
+use crate::worktree;
+
+/// Branch and working-tree state of a checkout, from a single
+/// `git status --porcelain=v2 --branch` call.
+#[derive(Debug, Default, PartialEq)]
+pub struct RepoStatus {
+    /// `None` when HEAD is detached.
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    /// Commits ahead of / behind `upstream`.
+    pub upstream_ahead_behind: Option<(u32, u32)>,
+    /// Changed and untracked files in `git status --short` form.
+    pub changes: Vec<String>,
+}
+
+impl RepoStatus {
+    pub fn is_dirty(&self) -> bool {
+        !self.changes.is_empty()
+    }
+}
+
+/// Results of read-only git queries, shared for the rest of the command so
+/// each repo is asked at most once. Cleared by [`invalidate`] whenever sesh
+/// runs git in a way that may change them.
+#[derive(Default)]
+struct Cache {
+    status: HashMap<PathBuf, Arc<RepoStatus>>,
+    remote_refs: HashMap<PathBuf, Arc<BTreeSet<String>>>,
+    worktrees: HashMap<PathBuf, Arc<Vec<String>>>,
+    ahead_behind: HashMap<(PathBuf, String), (u32, u32)>,
+}
+
+static CACHE: LazyLock<Mutex<Cache>> = LazyLock::new(Default::default);
+
+/// Look `key` up in one of the cache's maps, running `load` on a miss. The
+/// lock isn't held while loading, so loaders may query the cache themselves.
+fn cached<K: Eq + Hash, V: Clone>(
+    map: fn(&mut Cache) -> &mut HashMap<K, V>,
+    key: K,
+    load: impl FnOnce() -> Result<V>,
+) -> Result<V> {
+    if let Some(v) = CACHE.lock().ok().and_then(|mut c| map(&mut c).get(&key).cloned()) {
+        return Ok(v);
+    }
+    let value = load()?;
+    if let Ok(mut c) = CACHE.lock() {
+        map(&mut c).insert(key, value.clone());
+    }
+    Ok(value)
+}
+
+/// Forget everything cached; call after git commands that change refs,
+/// worktrees or files.
+pub fn invalidate() {
+    if let Ok(mut c) = CACHE.lock() {
+        *c = Cache::default();
+    }
+}
+
+const STATUS_ARGS: &[&str] = &["status", "--porcelain=v2", "--branch", "-z"];
+const REMOTE_REFS_ARGS: &[&str] = &["for-each-ref", "--format=%(refname:short)", "refs/remotes"];
+const WORKTREES_ARGS: &[&str] = &["worktree", "list", "--porcelain", "-z"];
+
+pub fn status(path: &Path) -> Result<Arc<RepoStatus>> {
+    cached(|c| &mut c.status, path.to_path_buf(), || {
+        Ok(Arc::new(parse_status(&git(path, STATUS_ARGS)?)))
+    })
+}
+
+/// Short names (`origin/main`) of every remote-tracking branch.
+pub fn remote_refs(path: &Path) -> Result<Arc<BTreeSet<String>>> {
+    cached(|c| &mut c.remote_refs, path.to_path_buf(), || {
+        Ok(Arc::new(parse_lines(&git(path, REMOTE_REFS_ARGS)?)))
+    })
+}
+
+pub fn has_remote_ref(path: &Path, short_name: &str) -> Result<bool> {
+    Ok(remote_refs(path)?.contains(short_name))
+}
+
+/// Commits HEAD has that `other` lacks, and the reverse. Free when `other` is
+/// the branch's upstream, since `status` already reports those counts.
+pub fn ahead_behind(path: &Path, other: &str) -> Result<(u32, u32)> {
+    let status = status(path)?;
+    if status.upstream.as_deref() == Some(other)
+        && let Some(counts) = status.upstream_ahead_behind
+    {
+        return Ok(counts);
+    }
+    cached(|c| &mut c.ahead_behind, (path.to_path_buf(), other.to_string()), || {
+        worktree::ahead_behind(path, other)
+    })
+}
+
+/// Records of `git worktree list --porcelain -z`.
+pub fn worktree_records(repo_path: &Path) -> Result<Arc<Vec<String>>> {
+    cached(|c| &mut c.worktrees, repo_path.to_path_buf(), || {
+        Ok(Arc::new(parse_records(&git(repo_path, WORKTREES_ARGS)?)))
+    })
+}
+
+/// Query [`status`] for many checkouts concurrently, so later calls are
+/// answered from the cache. Failures are left for the synchronous call to
+/// report.
+pub async fn prefetch_status(paths: &[PathBuf]) {
+    prefetch(|c| &mut c.status, paths, STATUS_ARGS, |out| Arc::new(parse_status(out))).await
+}
+
+/// Concurrent counterpart of [`remote_refs`]; see [`prefetch_status`].
+pub async fn prefetch_remote_refs(paths: &[PathBuf]) {
+    prefetch(|c| &mut c.remote_refs, paths, REMOTE_REFS_ARGS, |out| Arc::new(parse_lines(out))).await
+}
+
+/// Concurrent counterpart of [`worktree_records`]; see [`prefetch_status`].
+pub async fn prefetch_worktrees(repo_paths: &[PathBuf]) {
+    prefetch(|c| &mut c.worktrees, repo_paths, WORKTREES_ARGS, |out| Arc::new(parse_records(out))).await
+}
+
+async fn prefetch<V: Send + 'static>(
+    map: fn(&mut Cache) -> &mut HashMap<PathBuf, V>,
+    paths: &[PathBuf],
+    args: &'static [&'static str],
+    parse: fn(&str) -> V,
+) {
+    let missing: Vec<PathBuf> = match CACHE.lock() {
+        Ok(mut c) => paths.iter().filter(|p| !map(&mut c).contains_key(*p)).cloned().collect(),
+        Err(_) => return,
+    };
+    let results = parallel::map_bounded(missing, |path| async move {
+        let output = git_async(&path, args).await;
+        (path, output)
+    })
+    .await;
+    if let Ok(mut c) = CACHE.lock() {
+        for (path, output) in results {
+            if let Ok(output) = output {
+                map(&mut c).insert(path, parse(&output));
+            }
+        }
+    }
+}
+
+fn git(path: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(args)
+        .timed_output()
+        .with_context(|| format!("failed to run git {}", args.join(" ")))?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// [`git`] on tokio's process API, for running many repos at once.
+pub async fn git_async(path: &Path, args: &[&str]) -> Result<String> {
+    let _t = timing::timer("git", || format!("git {} {}", path.display(), args.join(" ")));
+    let output = tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(args)
+        .output()
+        .await
+        .with_context(|| format!("failed to run git {}", args.join(" ")))?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn parse_lines(output: &str) -> BTreeSet<String> {
+    output.lines().map(str::to_string).collect()
+}
+
+fn parse_records(output: &str) -> Vec<String> {
+    output.split('\0').map(str::to_string).collect()
+}
+
+/// Parse `git status --porcelain=v2 --branch -z` output.
+fn parse_status(output: &str) -> RepoStatus {
+    let mut status = RepoStatus::default();
+    let mut records = output.split('\0').filter(|r| !r.is_empty());
+    while let Some(record) = records.next() {
+        if let Some(header) = record.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.head" if value != "(detached)" => status.branch = Some(value.to_string()),
+                "branch.upstream" => status.upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    let mut counts = value
+                        .split(' ')
+                        .map(|n| n.trim_start_matches(['+', '-']).parse::<u32>());
+                    if let (Some(Ok(ahead)), Some(Ok(behind))) = (counts.next(), counts.next()) {
+                        status.upstream_ahead_behind = Some((ahead, behind));
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        // Fields before the path: ordinary 8, renamed/copied 9, unmerged 10
+        let (kind, rest) = record.split_at(1);
+        let fields = match kind {
+            "1" => 9,
+            "2" => 10,
+            "u" => 11,
+            "?" => {
+                status.changes.push(format!("?? {}", &rest[1..]));
+                continue;
+            }
+            _ => continue,
+        };
+        let parts: Vec<&str> = record.splitn(fields, ' ').collect();
+        let (Some(xy), Some(path)) = (parts.get(1), parts.last()) else {
+            continue;
+        };
+        let xy = xy.replace('.', " ");
+        if kind == "2" {
+            let orig = records.next().unwrap_or_default();
+            status.changes.push(format!("{} {} -> {}", xy, orig, path));
+        } else {
+            status.changes.push(format!("{} {}", xy, path));
+        }
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_porcelain_v2_status() {
+        let output = [
+            "# branch.oid 15f498bd4e0e5d96d0d8eff00bf7785a2f66cae0",
+            "# branch.head feat/x",
+            "# branch.upstream origin/main",
+            "# branch.ab +3 -1",
+            "1 .M N... 100644 100644 100644 abc abc src/main.rs",
+            "2 R. N... 100644 100644 100644 abc abc R100 new name.rs",
+            "old name.rs",
+            "u UU N... 100644 100644 100644 100644 a b c conflict.rs",
+            "? notes.txt",
+            "",
+        ]
+        .join("\0");
+        assert_eq!(
+            parse_status(&output),
+            RepoStatus {
+                branch: Some("feat/x".into()),
+                upstream: Some("origin/main".into()),
+                upstream_ahead_behind: Some((3, 1)),
+                changes: vec![
+                    " M src/main.rs".into(),
+                    "R  old name.rs -> new name.rs".into(),
+                    "UU conflict.rs".into(),
+                    "?? notes.txt".into(),
+                ],
+            }
+        );
+
+        let detached = parse_status("# branch.oid abc\0# branch.head (detached)\0");
+        assert_eq!(detached.branch, None);
+        assert!(!detached.is_dirty());
+    }
+}
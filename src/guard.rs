@@ -0,0 +1,80 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+use crate::hooks;
+
+const GUARD_HOOK: &str = "pre-commit";
+
+/// Build a matcher for the configured protected path globs.
+pub fn build_matcher(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .with_context(|| format!("invalid guard.protected pattern: {}", pattern))?;
+        builder.add(glob);
+    }
+    builder.build().context("failed to build protected path matcher")
+}
+
+/// Return the files that match a protected pattern.
+pub fn violations<'a>(matcher: &GlobSet, files: &'a [String]) -> Vec<&'a str> {
+    files
+        .iter()
+        .filter(|f| matcher.is_match(f.as_str()))
+        .map(|f| f.as_str())
+        .collect()
+}
+
+/// Install a `pre-commit` hook that runs `sesh guard check --staged` for the
+/// repo before chaining to the repo's own pre-commit hook.
+pub fn install_guard_hook(
+    parent_dir: &Path,
+    sess_dir: &Path,
+    session_name: &str,
+    repo_name: &str,
+    repo_path: &Path,
+    worktree_path: &Path,
+) -> Result<()> {
+    let hooks_dir = hooks::ensure_hooks_dir(sess_dir, repo_name, repo_path, worktree_path)?;
+    let original = hooks::original_hook(repo_path, GUARD_HOOK)?;
+    let exe = std::env::current_exe().context("failed to locate sesh executable")?;
+    let script = format!(
+        "#!/bin/sh\n\
+         # Installed by sesh: blocks commits that touch protected paths\n\
+         {exe} -d {parent} guard check {session} --repo {repo} --staged || exit 1\n\
+         ORIG={orig}\n\
+         if [ -x \"$ORIG\" ]; then exec \"$ORIG\" \"$@\"; fi\n",
+        exe = hooks::shell_quote(&exe.to_string_lossy()),
+        parent = hooks::shell_quote(&parent_dir.to_string_lossy()),
+        session = hooks::shell_quote(session_name),
+        repo = hooks::shell_quote(repo_name),
+        orig = hooks::shell_quote(&original.to_string_lossy()),
+    );
+    hooks::write_hook(&hooks_dir, GUARD_HOOK, &script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_violations() {
+        let matcher = build_matcher(&[
+            "**/migrations/**".to_string(),
+            ".github/workflows/**".to_string(),
+        ])
+        .unwrap();
+        let files = vec![
+            "src/main.rs".to_string(),
+            "db/migrations/001_init.sql".to_string(),
+            ".github/workflows/ci.yml".to_string(),
+            "migrations.md".to_string(),
+        ];
+        assert_eq!(
+            violations(&matcher, &files),
+            vec!["db/migrations/001_init.sql", ".github/workflows/ci.yml"]
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A state transition in a session's life, appended to its history file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    Created {
+        branch: String,
+        repos: Vec<String>,
+    },
+    /// The session finished setting up; `duration_secs` runs from when its
+    /// session file was written to the end of its setup scripts.
+    Ready {
+        duration_secs: f64,
+    },
+    Script {
+        phase: String,
+        script: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        repo: Option<String>,
+        outcome: String,
+    },
+    Activated,
+    /// Exclusive locks were moved to another session by `sesh activate`.
+    Deactivated {
+        to_session: String,
+    },
+    Archived,
+    Synced {
+        strategy: String,
+    },
+    Stopped,
+}
+
+impl Event {
+    pub fn script(phase: &str, script: &str, repo: Option<&str>, outcome: String) -> Self {
+        Event::Script {
+            phase: phase.to_string(),
+            script: script.to_string(),
+            repo: repo.map(str::to_string),
+            outcome,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Event::Created { branch, repos } => {
+                format!("created on '{}' with {}", branch, repos.join(", "))
+            }
+            Event::Ready { duration_secs } => format!("ready after {:.1}s", duration_secs),
+            Event::Script { phase, script, repo, outcome } => match repo {
+                Some(repo) => format!("{} {} for {}: {}", phase, script, repo, outcome),
+                None => format!("{} {}: {}", phase, script, outcome),
+            },
+            Event::Activated => "activated".to_string(),
+            Event::Deactivated { to_session } => format!("locks moved to '{}'", to_session),
+            Event::Archived => "archived".to_string(),
+            Event::Synced { strategy } => format!("synced with base ({})", strategy),
+            Event::Stopped => "stopped".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+impl Entry {
+    pub fn describe(&self) -> String {
+        self.event.describe()
+    }
+}
+
+/// History lives outside the session directory so it survives `sesh stop`;
+/// a reused session name keeps appending after a fresh `created` entry.
+pub fn history_path(parent_dir: &Path, session_name: &str) -> PathBuf {
+    parent_dir
+        .join(".sesh/history")
+        .join(format!("{}.jsonl", session_name))
+}
+
+/// `"ok"` or the error, for `Event::Script` outcomes.
+pub fn outcome(result: &Result<()>) -> String {
+    match result {
+        Ok(()) => "ok".to_string(),
+        Err(e) => format!("failed: {}", e),
+    }
+}
+
+/// Append an event to a session's history. Best-effort: a failure to record
+/// history is reported but never fails the command that triggered it.
+pub fn record(parent_dir: &Path, session_name: &str, event: Event) {
+    if let Err(e) = append(parent_dir, session_name, event) {
+        eprintln!("  Warning: failed to record session history: {:#}", e);
+    }
+}
+
+fn append(parent_dir: &Path, session_name: &str, event: Event) -> Result<()> {
+    let path = history_path(parent_dir, session_name);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    let entry = Entry {
+        at: Utc::now(),
+        user: ["USER", "USERNAME"]
+            .iter()
+            .find_map(|v| std::env::var(v).ok().filter(|u| !u.is_empty())),
+        event,
+    };
+    let mut line = serde_json::to_string(&entry).context("failed to serialize history entry")?;
+    line.push('\n');
+    // A single O_APPEND write keeps concurrent writers from interleaving lines
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    file.write_all(line.as_bytes())
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Read a session's history. Lines that don't parse (e.g. cut short by a
+/// crash) are skipped and counted.
+pub fn load(parent_dir: &Path, session_name: &str) -> Result<(Vec<Entry>, usize)> {
+    let path = history_path(parent_dir, session_name);
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("no history for session '{}' ({})", session_name, path.display()))?;
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+        match serde_json::from_str(line) {
+            Ok(entry) => entries.push(entry),
+            Err(_) => skipped += 1,
+        }
+    }
+    Ok((entries, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_round_trip_as_flat_json_lines() {
+        let entry = Entry {
+            at: Utc::now(),
+            user: Some("dev".to_string()),
+            event: Event::script("setup", "scripts/db.sh", Some("api"), "ok".to_string()),
+        };
+        let line = serde_json::to_string(&entry).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["event"], "script");
+        assert_eq!(value["repo"], "api");
+
+        let parsed: Entry = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.describe(), "setup scripts/db.sh for api: ok");
+        let stopped: Entry = serde_json::from_str(r#"{"at":"2025-01-01T00:00:00Z","event":"stopped"}"#).unwrap();
+        assert_eq!(stopped.describe(), "stopped");
+    }
+}
@@ -0,0 +1,96 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::worktree;
+
+/// Per-repo hooks directory inside the session.
+pub fn session_hooks_dir(sess_dir: &Path, repo_name: &str) -> PathBuf {
+    sess_dir.join("hooks").join(repo_name)
+}
+
+/// Point a worktree's `core.hooksPath` at its session hooks dir, linking in the
+/// repo's existing hooks so they keep running. Returns the hooks dir.
+pub fn ensure_hooks_dir(
+    sess_dir: &Path,
+    repo_name: &str,
+    repo_path: &Path,
+    worktree_path: &Path,
+) -> Result<PathBuf> {
+    let hooks_dir = session_hooks_dir(sess_dir, repo_name);
+    std::fs::create_dir_all(&hooks_dir)
+        .with_context(|| format!("failed to create {}", hooks_dir.display()))?;
+
+    let original_hooks = worktree::hooks_dir(repo_path)?;
+    if let Ok(entries) = std::fs::read_dir(&original_hooks) {
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            if name.to_string_lossy().ends_with(".sample") {
+                continue;
+            }
+            let link = hooks_dir.join(&name);
+            if link.symlink_metadata().is_err() {
+                std::os::unix::fs::symlink(entry.path(), &link)
+                    .with_context(|| format!("failed to link hook {}", link.display()))?;
+            }
+        }
+    }
+
+    worktree::set_worktree_config(
+        repo_path,
+        worktree_path,
+        "core.hooksPath",
+        &hooks_dir.to_string_lossy(),
+    )?;
+
+    Ok(hooks_dir)
+}
+
+/// Quote `value` as a single POSIX shell word, for paths and names embedded in
+/// generated hook scripts.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Write an executable hook script, replacing any linked original (never
+/// writing through the link).
+pub fn write_hook(hooks_dir: &Path, name: &str, script: &str) -> Result<()> {
+    let path = hooks_dir.join(name);
+    if path.symlink_metadata().is_ok() {
+        std::fs::remove_file(&path)
+            .with_context(|| format!("failed to replace {}", path.display()))?;
+    }
+    std::fs::write(&path, script)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+        .with_context(|| format!("failed to chmod {}", path.display()))?;
+
+    Ok(())
+}
+
+/// The repo's own hook of the given name, for chaining from a sesh hook.
+pub fn original_hook(repo_path: &Path, name: &str) -> Result<PathBuf> {
+    Ok(worktree::hooks_dir(repo_path)?.join(name))
+}
+
+/// Detach a worktree from its session hooks dir.
+pub fn remove_hooks(worktree_path: &Path) -> Result<()> {
+    worktree::unset_worktree_config(worktree_path, "core.hooksPath")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_quote_survives_the_shell() {
+        let value = "it's a \"dir\" with $HOME, `cmd` and \\ ü";
+        let output = std::process::Command::new("sh")
+            .args(["-c", &format!("printf %s {}", shell_quote(value))])
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), value);
+    }
+}
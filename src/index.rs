@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+
+use crate::session::{self, SessionInfo};
+
+// Optional SQLite cache of `session.json` files. Saving or deleting a session
+// writes through to it, so queries read rows without touching the session
+// directories. The JSON files stay the source of truth: `sesh doctor` compares
+// the two (by mtime and size) and rebuilds the index when they drift, e.g.
+// after a session file was edited by hand.
+
+const INDEX_FILE: &str = "index.db";
+
+pub fn index_path(parent_dir: &Path) -> PathBuf {
+    parent_dir.join(".sesh").join(INDEX_FILE)
+}
+
+/// Whether the index has been built; `sesh doctor` creates or removes it to
+/// match `[index] enabled`.
+pub fn exists(parent_dir: &Path) -> bool {
+    index_path(parent_dir).exists()
+}
+
+fn open(parent_dir: &Path) -> Result<Connection> {
+    let path = index_path(parent_dir);
+    let conn = Connection::open(&path)
+        .with_context(|| format!("failed to open session index: {}", path.display()))?;
+    conn.busy_timeout(Duration::from_secs(5))?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sessions (
+             name  TEXT PRIMARY KEY,
+             stamp TEXT NOT NULL,
+             info  TEXT NOT NULL
+         );
+         CREATE TABLE IF NOT EXISTS branches (
+             branch  TEXT NOT NULL,
+             session TEXT NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS branches_by_branch ON branches(branch);",
+    )
+    .context("failed to initialize session index")?;
+    Ok(conn)
+}
+
+/// Modification time and size of a session file, used to spot changes.
+fn file_stamp(path: &Path) -> Option<String> {
+    let meta = fs::metadata(path).ok()?;
+    let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(format!("{}:{}", modified.as_nanos(), meta.len()))
+}
+
+/// Session files on disk by session name, with their directory and stamp.
+fn on_disk(parent_dir: &Path) -> HashMap<String, (PathBuf, String)> {
+    let mut sessions = HashMap::new();
+    if let Ok(entries) = fs::read_dir(parent_dir.join(".sesh/sessions")) {
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            if let Some(stamp) = file_stamp(&dir.join("session.json")) {
+                sessions.insert(entry.file_name().to_string_lossy().to_string(), (dir, stamp));
+            }
+        }
+    }
+    sessions
+}
+
+/// Indexed sessions by name, with the stamp of the file they were read from.
+fn indexed(conn: &Connection) -> Result<HashMap<String, String>> {
+    let mut stmt = conn.prepare("SELECT name, stamp FROM sessions")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(rows)
+}
+
+/// Bring the index in line with the session directories on disk.
+fn sync(conn: &mut Connection, parent_dir: &Path) -> Result<()> {
+    let on_disk = on_disk(parent_dir);
+    let indexed = indexed(conn)?;
+
+    let tx = conn.transaction()?;
+    for name in indexed.keys().filter(|n| !on_disk.contains_key(*n)) {
+        remove_row(&tx, name)?;
+    }
+    for (name, (dir, stamp)) in &on_disk {
+        if indexed.get(name) == Some(stamp) {
+            continue;
+        }
+        remove_row(&tx, name)?;
+        if let Some(info) = session::load_listed_session(dir) {
+            insert_row(&tx, name, stamp, &info)?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Write a just-saved session through to the index.
+pub fn record(parent_dir: &Path, session_dir: &Path, info: &SessionInfo) -> Result<()> {
+    let mut conn = open(parent_dir)?;
+    let name = session_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let stamp = file_stamp(&session_dir.join("session.json")).unwrap_or_default();
+    let tx = conn.transaction()?;
+    remove_row(&tx, &name)?;
+    insert_row(&tx, &name, &stamp, info)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Drop a deleted session from the index.
+pub fn forget(parent_dir: &Path, name: &str) -> Result<()> {
+    remove_row(&open(parent_dir)?, name)
+}
+
+fn remove_row(conn: &Connection, name: &str) -> Result<()> {
+    conn.execute("DELETE FROM sessions WHERE name = ?1", params![name])?;
+    conn.execute("DELETE FROM branches WHERE session = ?1", params![name])?;
+    Ok(())
+}
+
+fn insert_row(conn: &Connection, name: &str, stamp: &str, info: &SessionInfo) -> Result<()> {
+    let json = serde_json::to_string(info).context("failed to serialize session info")?;
+    conn.execute(
+        "INSERT INTO sessions (name, stamp, info) VALUES (?1, ?2, ?3)",
+        params![name, stamp, json],
+    )?;
+    let mut branches = vec![info.branch.as_str()];
+    branches.extend(info.repos.iter().map(|r| info.branch_for(r)));
+    branches.sort();
+    branches.dedup();
+    for branch in branches {
+        conn.execute(
+            "INSERT INTO branches (branch, session) VALUES (?1, ?2)",
+            params![branch, name],
+        )?;
+    }
+    Ok(())
+}
+
+fn parse_rows(rows: Vec<String>) -> Result<Vec<SessionInfo>> {
+    rows.iter()
+        .map(|json| serde_json::from_str(json).context("session index holds an unreadable entry"))
+        .collect()
+}
+
+/// All sessions, unsorted.
+pub fn list(parent_dir: &Path) -> Result<Vec<SessionInfo>> {
+    let conn = open(parent_dir)?;
+    let mut stmt = conn.prepare("SELECT info FROM sessions")?;
+    let rows = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    parse_rows(rows)
+}
+
+/// The session whose branch (or one of whose composed repo branches) is `branch`.
+pub fn find_by_branch(parent_dir: &Path, branch: &str) -> Result<Option<SessionInfo>> {
+    let conn = open(parent_dir)?;
+    let mut stmt = conn.prepare(
+        "SELECT s.info FROM sessions s JOIN branches b ON b.session = s.name
+         WHERE b.branch = ?1 ORDER BY s.name LIMIT 1",
+    )?;
+    let rows = stmt
+        .query_map(params![branch], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(parse_rows(rows)?.into_iter().next())
+}
+
+/// Run SQLite's integrity check, compare the rows with the session files, and
+/// do a full read, returning the first problem found.
+pub fn check(parent_dir: &Path) -> Result<()> {
+    let conn = open(parent_dir)?;
+    let result: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+    if result != "ok" {
+        anyhow::bail!("integrity check failed: {}", result);
+    }
+    let on_disk: HashMap<String, String> = on_disk(parent_dir)
+        .into_iter()
+        .map(|(name, (_, stamp))| (name, stamp))
+        .collect();
+    if indexed(&conn)? != on_disk {
+        anyhow::bail!("out of date with the session files");
+    }
+    list(parent_dir).map(|_| ())
+}
+
+/// Drop the index and rebuild it from the session files. Returns how many
+/// sessions were indexed.
+pub fn rebuild(parent_dir: &Path) -> Result<usize> {
+    remove(parent_dir)?;
+    fs::create_dir_all(parent_dir.join(".sesh"))?;
+    let mut conn = open(parent_dir)?;
+    sync(&mut conn, parent_dir)?;
+    Ok(list(parent_dir)?.len())
+}
+
+pub fn remove(parent_dir: &Path) -> Result<()> {
+    let path = index_path(parent_dir);
+    for file in [path.clone(), path.with_extension("db-journal")] {
+        if file.exists() {
+            fs::remove_file(&file).with_context(|| format!("failed to remove {}", file.display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{SessionRepo, SESSION_SCHEMA_VERSION};
+
+    fn session(name: &str, branch: &str, parent_dir: &Path) -> SessionInfo {
+        SessionInfo {
+            version: SESSION_SCHEMA_VERSION,
+            name: name.to_string(),
+            branch: branch.to_string(),
+            repos: vec![SessionRepo {
+                name: "api".to_string(),
+                worktree_path: PathBuf::from("/nonexistent"),
+                original_repo_path: PathBuf::from("/nonexistent"),
+                pinned: false,
+                branch: Some("shared/api".to_string()),
+                prefixed_branch: None,
+            }],
+            created_at: chrono::Utc::now(),
+            parent_dir: parent_dir.to_path_buf(),
+            issue: None,
+            base_branch: None,
+            ci_runs: Vec::new(),
+            urls: Default::default(),
+            cleanup: None,
+            archived_at: None,
+            tags: Vec::new(),
+            metadata: Default::default(),
+            pinned_at: None,
+            ports: Default::default(),
+            pull_requests: Default::default(),
+        }
+    }
+
+    #[test]
+    fn index_follows_session_files() {
+        let parent = std::env::temp_dir().join(format!("sesh-index-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&parent);
+        let dir_a = session::session_dir(&parent, "a");
+        session::save_session(&dir_a, &session("a", "feat/a", &parent)).unwrap();
+
+        assert_eq!(rebuild(&parent).unwrap(), 1);
+        assert_eq!(find_by_branch(&parent, "shared/api").unwrap().unwrap().name, "a");
+
+        // Saves and deletes are written through without a rebuild
+        let dir_b = session::session_dir(&parent, "b");
+        session::save_session(&dir_b, &session("b", "feat/b", &parent)).unwrap();
+        session::delete_session_dir(&dir_a).unwrap();
+        let names: Vec<String> = list(&parent).unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["b"]);
+        assert!(find_by_branch(&parent, "feat/a").unwrap().is_none());
+        check(&parent).unwrap();
+
+        // A hand-edited file is caught by the check
+        let mut edited = session("b", "feat/edited", &parent);
+        edited.repos.clear();
+        fs::write(dir_b.join("session.json"), serde_json::to_string(&edited).unwrap()).unwrap();
+        assert!(check(&parent).is_err());
+        rebuild(&parent).unwrap();
+        assert_eq!(find_by_branch(&parent, "feat/edited").unwrap().unwrap().name, "b");
+
+        fs::remove_dir_all(&parent).unwrap();
+    }
+}
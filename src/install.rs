@@ -0,0 +1,67 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Lockfiles and the command that installs from each. Only the first match
+/// per ecosystem counts, so a repo with both `pnpm-lock.yaml` and a stray
+/// `package-lock.json` gets `pnpm install`.
+const INSTALLERS: &[(&str, &str, &str)] = &[
+    ("node", "pnpm-lock.yaml", "pnpm install --frozen-lockfile"),
+    ("node", "yarn.lock", "yarn install --frozen-lockfile"),
+    ("node", "bun.lockb", "bun install --frozen-lockfile"),
+    ("node", "package-lock.json", "npm ci"),
+    ("rust", "Cargo.lock", "cargo fetch"),
+    ("python", "uv.lock", "uv sync"),
+    ("python", "poetry.lock", "poetry install"),
+    ("ruby", "Gemfile.lock", "bundle install"),
+    ("go", "go.sum", "go mod download"),
+];
+
+/// Install commands for the lockfiles at the root of `worktree`. Node
+/// installs are skipped when `node_modules` is already there, e.g. symlinked
+/// or cloned from the original checkout.
+pub fn detect(worktree: &Path) -> Vec<&'static str> {
+    let mut seen = Vec::new();
+    let mut commands = Vec::new();
+    for &(ecosystem, lockfile, command) in INSTALLERS {
+        if seen.contains(&ecosystem) || !worktree.join(lockfile).is_file() {
+            continue;
+        }
+        seen.push(ecosystem);
+        if ecosystem == "node" && worktree.join("node_modules").exists() {
+            continue;
+        }
+        commands.push(command);
+    }
+    commands
+}
+
+/// Whether the program `command` starts with can be found on `PATH`.
+pub fn is_available(command: &str) -> bool {
+    let program = command.split_whitespace().next().unwrap_or_default();
+    Command::new("which")
+        .arg(program)
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_one_install_per_ecosystem() {
+        let root = std::env::temp_dir().join(format!("sesh-install-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        for file in ["pnpm-lock.yaml", "package-lock.json", "Cargo.lock"] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+        let detected = detect(&root);
+
+        std::fs::create_dir(root.join("node_modules")).unwrap();
+        let with_modules = detect(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(detected, vec!["pnpm install --frozen-lockfile", "cargo fetch"]);
+        assert_eq!(with_modules, vec!["cargo fetch"]);
+    }
+}
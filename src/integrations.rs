@@ -4,8 +4,9 @@ use anyhow::{bail, Context, Result};
 use reqwest::Client;
 use serde::Deserialize;
 
-use crate::config::SeshConfig;
+use crate::config::{JiraConfig, SeshConfig};
 use crate::session::IssueContext;
+use crate::timing::{TimedOutput, TimedSend};
 
 // ---------------------------------------------------------------------------
 // Public entry point
@@ -16,7 +17,8 @@ pub struct BranchResolution {
     pub issue: Option<IssueContext>,
 }
 
-/// Resolve user input that may be a Linear ticket, Sentry URL, or plain branch name.
+/// Resolve user input that may be a Linear ticket, Jira issue, Azure DevOps work
+/// item, Sentry URL, or plain branch name.
 pub async fn resolve_branch_input(
     input: &str,
     config: &SeshConfig,
@@ -35,8 +37,32 @@ pub async fn resolve_branch_input(
         return branch_from_sentry(&org, &issue_id, parent_dir).await;
     }
 
-    // Linear ID pattern: TEAM-123
+    // Jira URL: https://{site}/browse/{KEY-123}
+    if let Some(jira) = &config.jira
+        && let Some(key) = parse_jira_url(input, &jira.base_url)
+    {
+        return branch_from_jira(jira, &key, parent_dir).await;
+    }
+
+    // Azure DevOps work item: https://dev.azure.com/{org}/{project}/_workitems/edit/{id}, or AB#123
+    if let Some((org, id)) = parse_azure_work_item_url(input) {
+        return branch_from_azure(&org, id, parent_dir).await;
+    }
+    if let Some(id) = parse_azure_work_item_id(input) {
+        let azure = config
+            .azure
+            .as_ref()
+            .context("no [azure] section in sesh.toml (set organization to resolve AB# work items)")?;
+        return branch_from_azure(&azure.organization, id, parent_dir).await;
+    }
+
+    // Issue key pattern: TEAM-123 (Linear, or Jira for configured projects)
     if is_linear_id(input) {
+        if let Some(jira) = &config.jira
+            && key_routes_to_jira(jira, input, parent_dir)
+        {
+            return branch_from_jira(jira, input, parent_dir).await;
+        }
         return branch_from_linear(input, parent_dir).await;
     }
 
@@ -47,6 +73,120 @@ pub async fn resolve_branch_input(
     })
 }
 
+/// Issue providers accepted by `sesh start --issue <provider>:<id>`.
+const ISSUE_PROVIDERS: [&str; 5] = ["linear", "jira", "github", "azure", "sentry"];
+
+/// Resolve an explicit `<provider>:<id>` issue reference, e.g. `linear:ENG-123`,
+/// `jira:PROJ-9`, `github:org/repo#55`, `azure:123` or `sentry:4567`. Unlike
+/// `resolve_branch_input` nothing is guessed, so scripts get the same behavior
+/// whichever trackers are configured.
+pub async fn resolve_issue(
+    spec: &str,
+    config: &SeshConfig,
+    parent_dir: &Path,
+) -> Result<BranchResolution> {
+    let (provider, id) = parse_issue_spec(spec)?;
+    match provider {
+        "linear" => {
+            let id = id.to_uppercase();
+            if !is_linear_id(&id) {
+                bail!("invalid Linear issue '{}' (expected e.g. linear:ENG-123)", id);
+            }
+            branch_from_linear(&id, parent_dir).await
+        }
+        "jira" => {
+            let jira = config
+                .jira
+                .as_ref()
+                .context("no [jira] section in sesh.toml (set base_url to use jira: issues)")?;
+            let key = id.to_uppercase();
+            if !is_linear_id(&key) {
+                bail!("invalid Jira issue '{}' (expected e.g. jira:PROJ-9)", id);
+            }
+            branch_from_jira(jira, &key, parent_dir).await
+        }
+        "github" => {
+            let (repo, number) = parse_github_issue(id)
+                .with_context(|| format!("invalid GitHub issue '{}' (expected e.g. github:org/repo#55)", id))?;
+            branch_from_github(&repo, number)
+        }
+        "azure" => {
+            let azure = config
+                .azure
+                .as_ref()
+                .context("no [azure] section in sesh.toml (set organization to use azure: issues)")?;
+            let number = id.strip_prefix("AB#").unwrap_or(id);
+            let number = number
+                .parse()
+                .with_context(|| format!("invalid Azure DevOps work item '{}' (expected e.g. azure:123)", id))?;
+            branch_from_azure(&azure.organization, number, parent_dir).await
+        }
+        "sentry" => {
+            let org = resolve_sentry_org(config, None);
+            if org.is_empty() {
+                bail!("no [sentry] org in sesh.toml (needed for sentry: issues)");
+            }
+            if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+                bail!("invalid Sentry issue '{}' (expected e.g. sentry:12345)", id);
+            }
+            branch_from_sentry(&org, id, parent_dir).await
+        }
+        _ => unreachable!("parse_issue_spec only returns known providers"),
+    }
+}
+
+/// The token (`linear` or `sentry`) resolving `input` will call an API with,
+/// so it can be checked before the flow starts.
+pub fn token_for_input(input: &str, config: &SeshConfig, parent_dir: &Path) -> Option<&'static str> {
+    let input = input.trim();
+    if parse_linear_url(input).is_some() {
+        return Some("linear");
+    }
+    if parse_sentry_url(input).is_some() {
+        return Some("sentry");
+    }
+    let jira_key = config.jira.as_ref().is_some_and(|jira| key_routes_to_jira(jira, input, parent_dir));
+    (is_linear_id(input) && !jira_key).then_some("linear")
+}
+
+/// Like `token_for_input`, for an explicit `<provider>:<id>` issue reference.
+pub fn token_for_issue(spec: &str) -> Option<&'static str> {
+    match parse_issue_spec(spec).ok()?.0 {
+        provider @ ("linear" | "sentry") => Some(provider),
+        _ => None,
+    }
+}
+
+fn parse_issue_spec(spec: &str) -> Result<(&'static str, &str)> {
+    let (provider, id) = spec
+        .trim()
+        .split_once(':')
+        .with_context(|| format!("invalid issue '{}' (expected <provider>:<id>, e.g. linear:ENG-123)", spec))?;
+    let provider = provider.to_lowercase();
+    let Some(known) = ISSUE_PROVIDERS.iter().find(|p| **p == provider) else {
+        bail!(
+            "unknown issue provider '{}' (supported: {})",
+            provider,
+            ISSUE_PROVIDERS.join(", ")
+        );
+    };
+    let id = id.trim();
+    if id.is_empty() {
+        bail!("missing issue ID in '{}'", spec);
+    }
+    Ok((known, id))
+}
+
+fn parse_github_issue(id: &str) -> Option<(String, u64)> {
+    // org/repo#55
+    let (repo, number) = id.rsplit_once('#')?;
+    let (owner, name) = repo.split_once('/')?;
+    if owner.is_empty() || name.is_empty() || name.contains('/') {
+        return None;
+    }
+    Some((repo.to_string(), number.parse().ok()?))
+}
+
 // ---------------------------------------------------------------------------
 // URL / ID parsing
 // ---------------------------------------------------------------------------
@@ -81,6 +221,54 @@ fn parse_sentry_url(input: &str) -> Option<(String, String)> {
     None
 }
 
+fn parse_jira_url(input: &str, base_url: &str) -> Option<String> {
+    // {base_url}/browse/{KEY-123}, or any Jira Cloud site
+    let base = base_url.trim_end_matches('/');
+    let path = match input.strip_prefix(base) {
+        Some(rest) => rest.strip_prefix('/')?,
+        None => {
+            let rest = input.strip_prefix("https://")?;
+            let (host, path) = rest.split_once('/')?;
+            if !host.ends_with(".atlassian.net") {
+                return None;
+            }
+            path
+        }
+    };
+    let key = path.strip_prefix("browse/")?.split(['/', '?', '#']).next()?;
+    is_linear_id(key).then(|| key.to_string())
+}
+
+fn parse_azure_work_item_url(input: &str) -> Option<(String, u64)> {
+    // https://dev.azure.com/{org}/{project}/_workitems/edit/{id}
+    // https://{org}.visualstudio.com/{project}/_workitems/edit/{id}
+    let rest = input.strip_prefix("https://")?;
+    let (host, path) = rest.split_once('/')?;
+    let (org, path) = if host == "dev.azure.com" {
+        path.split_once('/')?
+    } else {
+        (host.strip_suffix(".visualstudio.com")?, path)
+    };
+    let (_, id) = path.split_once("/_workitems/edit/")?;
+    let id = id.split(['/', '?', '#']).next()?.parse().ok()?;
+    (!org.is_empty()).then(|| (org.to_string(), id))
+}
+
+fn parse_azure_work_item_id(input: &str) -> Option<u64> {
+    // AB#123, the Azure Boards mention syntax
+    input.strip_prefix("AB#")?.parse().ok()
+}
+
+/// Whether a bare `KEY-123` should be looked up in Jira rather than Linear.
+fn key_routes_to_jira(jira: &JiraConfig, key: &str, parent_dir: &Path) -> bool {
+    let prefix = key.split_once('-').map(|(p, _)| p).unwrap_or(key);
+    if jira.projects.is_empty() {
+        !parent_dir.join(".sesh/secrets/linear_token").exists()
+    } else {
+        jira.projects.iter().any(|p| p == prefix)
+    }
+}
+
 fn is_linear_id(input: &str) -> bool {
     // Pattern: one or more uppercase letters, a dash, one or more digits (e.g. ENG-123)
     let Some((prefix, suffix)) = input.split_once('-') else {
@@ -186,7 +374,7 @@ async fn branch_from_linear(id: &str, parent_dir: &Path) -> Result<BranchResolut
         .header("Authorization", &token)
         .header("Content-Type", "application/json")
         .body(query)
-        .send()
+        .timed_send()
         .await
         .context("failed to call Linear API")?;
 
@@ -237,7 +425,7 @@ async fn branch_from_sentry(org: &str, issue_id: &str, parent_dir: &Path) -> Res
     let resp = client
         .get(&url)
         .header("Authorization", format!("Bearer {}", token))
-        .send()
+        .timed_send()
         .await
         .context("failed to call Sentry API")?;
 
@@ -276,7 +464,7 @@ pub async fn list_linear_issues(parent_dir: &Path) -> Result<Vec<LinearIssueSumm
         .post("https://api.linear.app/graphql")
         .header("Authorization", &token)
         .json(&body)
-        .send()
+        .timed_send()
         .await
         .context("failed to call Linear API")?;
 
@@ -378,7 +566,416 @@ fn state_sort_key(state_type: &str) -> u8 {
     }
 }
 
-fn load_token(parent_dir: &Path, filename: &str) -> Result<String> {
+#[derive(Deserialize)]
+struct JiraIssue {
+    key: String,
+    fields: JiraFields,
+}
+
+#[derive(Deserialize)]
+struct JiraFields {
+    summary: String,
+    #[serde(default)]
+    status: Option<JiraStatus>,
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct JiraStatus {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct JiraSearchResponse {
+    issues: Vec<JiraIssue>,
+}
+
+pub struct JiraIssueSummary {
+    pub key: String,
+    pub title: String,
+    pub status: String,
+    pub labels: Vec<String>,
+}
+
+fn jira_request(jira: &JiraConfig, token: &str, url: &str) -> reqwest::RequestBuilder {
+    let req = Client::new().get(url).header("Accept", "application/json");
+    match &jira.email {
+        Some(email) => req.basic_auth(email, Some(token)),
+        None => req.bearer_auth(token),
+    }
+}
+
+async fn branch_from_jira(jira: &JiraConfig, key: &str, parent_dir: &Path) -> Result<BranchResolution> {
+    let token = load_token(parent_dir, "jira_token")?;
+    let url = format!(
+        "{}/rest/api/2/issue/{}?fields=summary,status,labels",
+        jira.base_url.trim_end_matches('/'),
+        key
+    );
+
+    let resp = jira_request(jira, &token, &url)
+        .timed_send()
+        .await
+        .context("failed to call Jira API")?;
+
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        bail!("Jira issue '{}' not found", key);
+    }
+    if !resp.status().is_success() {
+        bail!("Jira API returned status {}", resp.status());
+    }
+
+    let issue: JiraIssue = resp.json().await.context("failed to parse Jira response")?;
+    let summary = jira_summary(issue);
+
+    Ok(BranchResolution {
+        branch: branch_name_from_jira_issue(&summary),
+        issue: Some(issue_context_from_jira_summary(&summary)),
+    })
+}
+
+fn jira_summary(issue: JiraIssue) -> JiraIssueSummary {
+    JiraIssueSummary {
+        key: issue.key,
+        title: issue.fields.summary,
+        status: issue
+            .fields
+            .status
+            .map(|s| s.name)
+            .unwrap_or_else(|| "Unknown".to_string()),
+        labels: issue.fields.labels,
+    }
+}
+
+/// Fetch the authenticated user's unresolved Jira issues, most recently updated first.
+pub async fn list_jira_issues(config: &SeshConfig, parent_dir: &Path) -> Result<Vec<JiraIssueSummary>> {
+    let jira = config
+        .jira
+        .as_ref()
+        .context("no [jira] section in sesh.toml (set base_url to use --jira)")?;
+    let token = load_token(parent_dir, "jira_token")?;
+    let url = format!(
+        "{}/rest/api/2/search?jql={}&fields=summary,status,labels&maxResults=50",
+        jira.base_url.trim_end_matches('/'),
+        "assignee%20%3D%20currentUser()%20AND%20statusCategory%20!%3D%20Done%20ORDER%20BY%20updated%20DESC"
+    );
+
+    let resp = jira_request(jira, &token, &url)
+        .timed_send()
+        .await
+        .context("failed to call Jira API")?;
+
+    if !resp.status().is_success() {
+        bail!("Jira API returned status {}", resp.status());
+    }
+
+    let body: JiraSearchResponse = resp.json().await.context("failed to parse Jira response")?;
+    Ok(body.issues.into_iter().map(jira_summary).collect())
+}
+
+/// Generate a branch name from a Jira issue.
+pub fn branch_name_from_jira_issue(issue: &JiraIssueSummary) -> String {
+    let branch = format!("{}-{}", issue.key.to_lowercase(), slugify(&issue.title));
+    truncate(&branch, 60)
+}
+
+/// Build an IssueContext from a JiraIssueSummary.
+pub fn issue_context_from_jira_summary(summary: &JiraIssueSummary) -> IssueContext {
+    IssueContext {
+        provider: "jira".to_string(),
+        identifier: summary.key.clone(),
+        title: summary.title.clone(),
+        state: Some(summary.status.clone()),
+        labels: summary.labels.clone(),
+    }
+}
+
+#[derive(Deserialize)]
+struct AzureWorkItem {
+    id: u64,
+    fields: AzureWorkItemFields,
+}
+
+#[derive(Deserialize)]
+struct AzureWorkItemFields {
+    #[serde(rename = "System.Title")]
+    title: String,
+    #[serde(rename = "System.State", default)]
+    state: Option<String>,
+    /// Semicolon-separated, e.g. `"backend; urgent"`.
+    #[serde(rename = "System.Tags", default)]
+    tags: Option<String>,
+}
+
+async fn branch_from_azure(org: &str, id: u64, parent_dir: &Path) -> Result<BranchResolution> {
+    let token = load_token(parent_dir, "azure_token")?;
+    let url = format!(
+        "https://dev.azure.com/{}/_apis/wit/workitems/{}?fields=System.Title,System.State,System.Tags&api-version=7.1",
+        org, id
+    );
+
+    let resp = Client::new()
+        .get(&url)
+        .basic_auth("", Some(&token))
+        .header("Accept", "application/json")
+        .timed_send()
+        .await
+        .context("failed to call Azure DevOps API")?;
+
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        bail!("Azure DevOps work item {} not found in '{}'", id, org);
+    }
+    if !resp.status().is_success() {
+        bail!("Azure DevOps API returned status {}", resp.status());
+    }
+
+    let item: AzureWorkItem = resp.json().await.context("failed to parse Azure DevOps response")?;
+    let branch = format!("ab-{}-{}", item.id, slugify(&item.fields.title));
+    let labels = item
+        .fields
+        .tags
+        .as_deref()
+        .unwrap_or_default()
+        .split(';')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    Ok(BranchResolution {
+        branch: truncate(&branch, 60),
+        issue: Some(IssueContext {
+            provider: "azure".to_string(),
+            identifier: format!("AB#{}", item.id),
+            title: item.fields.title,
+            state: item.fields.state,
+            labels,
+        }),
+    })
+}
+
+#[derive(Deserialize)]
+struct GhIssue {
+    number: u64,
+    title: String,
+    state: String,
+    #[serde(default)]
+    labels: Vec<GhLabel>,
+}
+
+#[derive(Deserialize)]
+struct GhLabel {
+    name: String,
+}
+
+/// GitHub issues are fetched through `gh`, which already holds the user's auth.
+fn branch_from_github(repo: &str, number: u64) -> Result<BranchResolution> {
+    crate::forge::require_gh()?;
+    let output = std::process::Command::new("gh")
+        .args([
+            "issue", "view", &number.to_string(),
+            "--repo", repo,
+            "--json", "number,title,state,labels",
+        ])
+        .timed_output()
+        .context("failed to run gh issue view")?;
+    if !output.status.success() {
+        bail!(
+            "failed to fetch GitHub issue {}#{}: {}",
+            repo,
+            number,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let issue: GhIssue = serde_json::from_slice(&output.stdout).context("failed to parse gh issue output")?;
+    let branch = format!("gh-{}-{}", issue.number, slugify(&issue.title));
+
+    Ok(BranchResolution {
+        branch: truncate(&branch, 60),
+        issue: Some(IssueContext {
+            provider: "github".to_string(),
+            identifier: format!("{}#{}", repo, issue.number),
+            title: issue.title,
+            state: Some(issue.state),
+            labels: issue.labels.into_iter().map(|l| l.name).collect(),
+        }),
+    })
+}
+
+/// Current workflow state and assignee of a Linear issue.
+pub struct LinearIssueStatus {
+    /// State type, e.g. "started", "completed", "canceled".
+    pub state_type: Option<String>,
+    pub assignee_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LinearStatusResponse {
+    data: Option<LinearStatusData>,
+}
+
+#[derive(Deserialize)]
+struct LinearStatusData {
+    issue: Option<LinearStatusIssue>,
+    viewer: Option<LinearUser>,
+}
+
+#[derive(Deserialize)]
+struct LinearStatusIssue {
+    #[serde(default)]
+    state: Option<LinearState>,
+    #[serde(default)]
+    assignee: Option<LinearUser>,
+}
+
+#[derive(Deserialize)]
+struct LinearUser {
+    id: String,
+}
+
+/// Fetch a Linear issue's state and assignee, plus the authenticated user's ID.
+pub async fn linear_issue_status(
+    parent_dir: &Path,
+    identifier: &str,
+) -> Result<(Option<LinearIssueStatus>, Option<String>)> {
+    let token = load_token(parent_dir, "linear_token")?;
+    let client = Client::builder()
+        .timeout(std::time::Duration::from_secs(5))
+        .build()
+        .context("failed to build HTTP client")?;
+
+    let query = format!(
+        r#"{{"query":"{{ viewer {{ id }} issue(id: \"{}\") {{ state {{ name type }} assignee {{ id }} }} }}"}}"#,
+        identifier
+    );
+
+    let resp = client
+        .post("https://api.linear.app/graphql")
+        .header("Authorization", &token)
+        .header("Content-Type", "application/json")
+        .body(query)
+        .timed_send()
+        .await
+        .context("failed to call Linear API")?;
+
+    if !resp.status().is_success() {
+        bail!("Linear API returned status {}", resp.status());
+    }
+
+    let body: LinearStatusResponse = resp.json().await.context("failed to parse Linear response")?;
+    let Some(data) = body.data else {
+        return Ok((None, None));
+    };
+    let status = data.issue.map(|i| LinearIssueStatus {
+        state_type: i.state.map(|s| s.state_type),
+        assignee_id: i.assignee.map(|a| a.id),
+    });
+    Ok((status, data.viewer.map(|v| v.id)))
+}
+
+/// Attach `url` to a Linear issue as a link titled `title`. Linear keeps one
+/// attachment per URL, so linking the same PR again just updates it.
+pub async fn link_linear_attachment(parent_dir: &Path, identifier: &str, url: &str, title: &str) -> Result<()> {
+    let token = load_token(parent_dir, "linear_token")?;
+    let body = serde_json::json!({
+        "query": "mutation($issue: String!, $url: String!, $title: String) { attachmentLinkURL(issueId: $issue, url: $url, title: $title) { success } }",
+        "variables": { "issue": identifier, "url": url, "title": title },
+    });
+
+    let resp = Client::new()
+        .post("https://api.linear.app/graphql")
+        .header("Authorization", &token)
+        .json(&body)
+        .timed_send()
+        .await
+        .context("failed to call Linear API")?;
+    if !resp.status().is_success() {
+        bail!("Linear API returned status {}", resp.status());
+    }
+
+    let body: serde_json::Value = resp.json().await.context("failed to parse Linear response")?;
+    if let Some(message) = body["errors"][0]["message"].as_str() {
+        bail!("Linear API error: {}", message);
+    }
+    if body["data"]["attachmentLinkURL"]["success"].as_bool() != Some(true) {
+        bail!("Linear did not accept the attachment");
+    }
+    Ok(())
+}
+
+/// Post a markdown comment on a Linear issue.
+pub async fn comment_on_linear_issue(parent_dir: &Path, identifier: &str, body: &str) -> Result<()> {
+    let token = load_token(parent_dir, "linear_token")?;
+    let request = serde_json::json!({
+        "query": "mutation($issue: String!, $body: String!) { commentCreate(input: { issueId: $issue, body: $body }) { success } }",
+        "variables": { "issue": identifier, "body": body },
+    });
+
+    let resp = Client::new()
+        .post("https://api.linear.app/graphql")
+        .header("Authorization", &token)
+        .json(&request)
+        .timed_send()
+        .await
+        .context("failed to call Linear API")?;
+    if !resp.status().is_success() {
+        bail!("Linear API returned status {}", resp.status());
+    }
+    let body: serde_json::Value = resp.json().await.context("failed to parse Linear response")?;
+    if let Some(message) = body["errors"][0]["message"].as_str() {
+        bail!("Linear API error: {}", message);
+    }
+    if body["data"]["commentCreate"]["success"].as_bool() != Some(true) {
+        bail!("Linear did not accept the comment");
+    }
+    Ok(())
+}
+
+pub enum TokenHealth {
+    Valid,
+    Missing,
+    Rejected(reqwest::StatusCode),
+    /// The check itself failed (offline, API down); the flow reports its own error
+    Unknown,
+}
+
+/// Verify the saved `linear` or `sentry` token with a cheap authenticated call.
+pub async fn token_health(parent_dir: &Path, provider: &str) -> TokenHealth {
+    let Ok(token) = load_token(parent_dir, &format!("{}_token", provider)) else {
+        return TokenHealth::Missing;
+    };
+    let client = Client::new();
+    let request = match provider {
+        "linear" => client
+            .post("https://api.linear.app/graphql")
+            .header("Authorization", &token)
+            .json(&serde_json::json!({ "query": "{ viewer { id } }" })),
+        "sentry" => client
+            .get("https://sentry.io/api/0/")
+            .header("Authorization", format!("Bearer {}", token)),
+        _ => return TokenHealth::Unknown,
+    };
+    let Ok(resp) = request.timeout(std::time::Duration::from_secs(10)).timed_send().await else {
+        return TokenHealth::Unknown;
+    };
+    let status = resp.status();
+    if matches!(status, reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN) {
+        return TokenHealth::Rejected(status);
+    }
+    if status.is_success() {
+        return TokenHealth::Valid;
+    }
+    // Linear reports a bad key as a 400 with an AUTHENTICATION_ERROR code
+    let body: serde_json::Value = resp.json().await.unwrap_or_default();
+    if body["errors"][0]["extensions"]["code"].as_str() == Some("AUTHENTICATION_ERROR") {
+        return TokenHealth::Rejected(status);
+    }
+    TokenHealth::Unknown
+}
+
+pub fn load_token(parent_dir: &Path, filename: &str) -> Result<String> {
     let path = parent_dir.join(".sesh/secrets").join(filename);
     let token = std::fs::read_to_string(&path).with_context(|| {
         format!(
@@ -403,7 +1000,7 @@ fn resolve_sentry_org(config: &SeshConfig, url_org: Option<&str>) -> String {
         .unwrap_or_default()
 }
 
-fn slugify(s: &str) -> String {
+pub fn slugify(s: &str) -> String {
     s.to_lowercase()
         .chars()
         .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
@@ -414,7 +1011,7 @@ fn slugify(s: &str) -> String {
         .join("-")
 }
 
-fn truncate(s: &str, max: usize) -> String {
+pub fn truncate(s: &str, max: usize) -> String {
     if s.len() <= max {
         return s.to_string();
     }
@@ -427,10 +1024,40 @@ fn truncate(s: &str, max: usize) -> String {
     }
 }
 
+/// Fill `{placeholder}`s in a branch template, then tidy the separators an
+/// empty value leaves behind (`alice/-fix` becomes `alice/fix`).
+pub fn render_branch_template(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut branch = template.to_string();
+    for (name, value) in vars {
+        branch = branch.replace(&format!("{{{}}}", name), value);
+    }
+    let mut tidy = String::with_capacity(branch.len());
+    for c in branch.chars() {
+        match (tidy.chars().last(), c) {
+            (Some('-'), '-') | (Some('/'), '/') | (Some('/'), '-') | (None, '-' | '/') => {}
+            (Some('-'), '/') => {
+                tidy.pop();
+                tidy.push('/');
+            }
+            _ => tidy.push(c),
+        }
+    }
+    tidy.trim_end_matches(['-', '/']).to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn branch_templates_drop_dangling_separators() {
+        let vars = [("user", "alice"), ("ticket", "eng-123"), ("slug", "fix-login")];
+        assert_eq!(render_branch_template("{user}/{ticket}-{slug}", &vars), "alice/eng-123-fix-login");
+        let vars = [("user", "alice"), ("ticket", ""), ("slug", "fix-login")];
+        assert_eq!(render_branch_template("{user}/{ticket}-{slug}", &vars), "alice/fix-login");
+        assert_eq!(render_branch_template("{slug}-{ticket}/", &vars), "fix-login");
+    }
+
     #[test]
     fn test_parse_linear_url() {
         assert_eq!(
@@ -459,6 +1086,53 @@ mod tests {
         assert_eq!(parse_sentry_url("https://myorg.sentry.io/settings/"), None);
     }
 
+    #[test]
+    fn test_parse_jira_url() {
+        assert_eq!(
+            parse_jira_url("https://acme.atlassian.net/browse/PROJ-42", "https://acme.atlassian.net"),
+            Some("PROJ-42".to_string())
+        );
+        assert_eq!(
+            parse_jira_url("https://jira.corp.example/browse/OPS-7?focused=1", "https://jira.corp.example/"),
+            Some("OPS-7".to_string())
+        );
+        assert_eq!(
+            parse_jira_url("https://other.atlassian.net/browse/X-1/", "https://acme.atlassian.net"),
+            Some("X-1".to_string())
+        );
+        assert_eq!(parse_jira_url("https://example.com/browse/X-1", "https://acme.atlassian.net"), None);
+        assert_eq!(parse_jira_url("https://acme.atlassian.net/browse/", "https://acme.atlassian.net"), None);
+    }
+
+    #[test]
+    fn test_parse_azure_work_item() {
+        assert_eq!(
+            parse_azure_work_item_url("https://dev.azure.com/acme/Web%20App/_workitems/edit/42"),
+            Some(("acme".to_string(), 42))
+        );
+        assert_eq!(
+            parse_azure_work_item_url("https://acme.visualstudio.com/Web/_workitems/edit/7/?view=x"),
+            Some(("acme".to_string(), 7))
+        );
+        assert_eq!(parse_azure_work_item_url("https://dev.azure.com/acme/Web/_git/api"), None);
+        assert_eq!(parse_azure_work_item_id("AB#123"), Some(123));
+        assert_eq!(parse_azure_work_item_id("AB#"), None);
+        assert_eq!(parse_azure_work_item_id("ENG-123"), None);
+    }
+
+    #[test]
+    fn test_parse_issue_spec() {
+        assert_eq!(parse_issue_spec("linear:ENG-123").unwrap(), ("linear", "ENG-123"));
+        assert_eq!(parse_issue_spec("GitHub:org/repo#55").unwrap(), ("github", "org/repo#55"));
+        assert!(parse_issue_spec("ENG-123").is_err());
+        assert!(parse_issue_spec("gitlab:1").is_err());
+        assert!(parse_issue_spec("jira:").is_err());
+
+        assert_eq!(parse_github_issue("org/repo#55"), Some(("org/repo".to_string(), 55)));
+        assert_eq!(parse_github_issue("repo#55"), None);
+        assert_eq!(parse_github_issue("org/repo#x"), None);
+    }
+
     #[test]
     fn test_is_linear_id() {
         assert!(is_linear_id("ENG-123"));
@@ -478,6 +1152,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::needless_borrow)]
     fn test_truncate() {
         let short = "abc-def";
         assert_eq!(truncate(short, 60), "abc-def");
@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::config::{ScriptEntry, SeshConfig};
+use crate::session::{self, SessionInfo};
+use crate::state;
+
+/// Landing page written at the root of every session directory.
+pub const README_FILE: &str = "README.sesh.md";
+
+/// (Re)write the session's `README.sesh.md`.
+pub fn write(session: &SessionInfo, config: &SeshConfig) -> Result<PathBuf> {
+    let path = session::session_dir(&session.parent_dir, &session.name).join(README_FILE);
+    state::write_atomic(&path, render(session, config).as_bytes())
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Summarize the ticket, repos, services, ports and the commands a teammate
+/// (or agent) opening the session folder is most likely to need.
+fn render(session: &SessionInfo, config: &SeshConfig) -> String {
+    let name = &session.name;
+    let mut out = format!("# Session: {}\n\n", name);
+    out.push_str(&format!("Branch `{}`", session.branch));
+    if let Some(base) = &session.base_branch {
+        out.push_str(&format!(" off `{}`", base));
+    }
+    out.push_str(&format!(
+        ", created {}.\n",
+        session.created_at.format("%Y-%m-%d %H:%M UTC")
+    ));
+
+    if let Some(issue) = &session.issue {
+        out.push_str("\n## Ticket\n\n");
+        out.push_str(&format!("**{}**: {}", issue.identifier, issue.title));
+        let mut details = vec![issue.provider.clone()];
+        details.extend(issue.state.clone());
+        details.extend(issue.labels.iter().cloned());
+        out.push_str(&format!(" ({})\n", details.join(", ")));
+    }
+
+    out.push_str("\n## Repositories\n\n| Repo | Branch | Worktree |\n| --- | --- | --- |\n");
+    for repo in &session.repos {
+        let branch = if repo.pinned {
+            "pinned (detached)".to_string()
+        } else {
+            format!("`{}`", session.branch_for(repo))
+        };
+        out.push_str(&format!(
+            "| {} | {} | `{}` |\n",
+            repo.name,
+            branch,
+            relative(session, &repo.worktree_path).display()
+        ));
+    }
+
+    let mut services = Vec::new();
+    let describe = |scope: &str, entry: &ScriptEntry| {
+        let mode = if entry.background { "background, logs in `logs/`" } else { "runs to completion" };
+        format!("- {}: `{}` ({})\n", scope, entry.name(), mode)
+    };
+    for entry in &config.scripts.setup {
+        services.push(describe("session", entry));
+    }
+    for repo in &session.repos {
+        if let Some(rc) = config.repos.get(&repo.name) {
+            services.extend(rc.setup.iter().map(|e| describe(&repo.name, e)));
+        }
+    }
+    if !services.is_empty() {
+        out.push_str("\n## Services\n\nStarted by setup scripts when the session was created:\n\n");
+        out.extend(services);
+        out.push_str(&format!(
+            "\nFollow their output with `sesh log -s {} --follow --all`.\n",
+            name
+        ));
+    }
+
+    if !config.tunnel.services.is_empty() || !session.ports.is_empty() || !session.urls.is_empty() {
+        out.push_str("\n## Ports & URLs\n\n");
+        for (service, port) in &session.ports {
+            out.push_str(&format!("- {}: http://localhost:{} (this session)\n", service, port));
+        }
+        for (service, port) in &config.tunnel.services {
+            out.push_str(&format!("- {}: http://localhost:{}\n", service, port));
+        }
+        for (label, url) in &session.urls {
+            out.push_str(&format!("- {}: {}\n", label, url));
+        }
+    }
+
+    out.push_str("\n## Useful Commands\n\n```bash\n");
+    for (cmd, what) in [
+        (format!("sesh status {}", name), "git state of every repo"),
+        (format!("sesh exec -s {} \"git log --oneline -5\"", name), "run a command in each repo"),
+        (format!("sesh sync {}", name), "rebase onto the latest base"),
+        (format!("sesh verify {}", name), "run the [verify] checklist"),
+        (format!("sesh pr {}", name), "push and open PRs"),
+        (format!("sesh stop {}", name), "tear the session down"),
+    ] {
+        out.push_str(&format!("{:<48} # {}\n", cmd, what));
+    }
+    out.push_str("```\n\nSession context for agents: `context/.sesh-context.md`.\n");
+    out
+}
+
+fn relative<'a>(session: &SessionInfo, path: &'a Path) -> &'a Path {
+    let session_dir = session::session_dir(&session.parent_dir, &session.name);
+    path.strip_prefix(&session_dir).unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_ticket_repos_and_services() {
+        let config: SeshConfig = toml::from_str(
+            r#"
+            [scripts]
+            setup = [{ path = "dev.sh", background = true }]
+
+            [tunnel.services]
+            web = 3000
+            "#,
+        )
+        .unwrap();
+        let session: SessionInfo = serde_json::from_value(serde_json::json!({
+            "version": session::SESSION_SCHEMA_VERSION,
+            "name": "eng-42",
+            "branch": "eng-42-login",
+            "repos": [{
+                "name": "api",
+                "worktree_path": "/work/.sesh/sessions/eng-42/api",
+                "original_repo_path": "/work/api"
+            }],
+            "created_at": "2025-01-01T00:00:00Z",
+            "parent_dir": "/work",
+            "issue": { "provider": "linear", "identifier": "ENG-42", "title": "Fix login" }
+        }))
+        .unwrap();
+
+        let readme = render(&session, &config);
+        assert!(readme.contains("**ENG-42**: Fix login (linear)"));
+        assert!(readme.contains("| api | `eng-42-login` | `api` |"));
+        assert!(readme.contains("- session: `dev.sh` (background, logs in `logs/`)"));
+        assert!(readme.contains("- web: http://localhost:3000"));
+        assert!(readme.contains("sesh stop eng-42"));
+    }
+}
@@ -5,10 +5,17 @@ use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::config;
+use crate::state;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LockInfo {
     pub session: String,
     pub locked_at: DateTime<Utc>,
+    /// Repo the lock belongs to; the file name may be a hashed form of it.
+    /// Empty in locks written before this was recorded.
+    #[serde(default)]
+    pub repo: String,
 }
 
 fn locks_dir(parent_dir: &Path) -> PathBuf {
@@ -16,7 +23,50 @@ fn locks_dir(parent_dir: &Path) -> PathBuf {
 }
 
 fn lock_path(parent_dir: &Path, repo_name: &str) -> PathBuf {
-    locks_dir(parent_dir).join(format!("{}.lock", repo_name))
+    locks_dir(parent_dir).join(lock_file_name(repo_name))
+}
+
+/// Where the lock of a repo with an unusual name was kept before such names
+/// were hashed (`api.v2.lock`); `None` for plain names, which never moved.
+fn legacy_lock_path(parent_dir: &Path, repo_name: &str) -> Option<PathBuf> {
+    let legacy = format!("{}.lock", repo_name);
+    (!config::is_plain_name(repo_name)).then(|| locks_dir(parent_dir).join(legacy))
+}
+
+/// Move a lock from its legacy file name to the current one, so locks held
+/// across an upgrade keep protecting the repo.
+fn migrate_legacy_lock(parent_dir: &Path, repo_name: &str) -> Result<()> {
+    let path = lock_path(parent_dir, repo_name);
+    if let Some(legacy) = legacy_lock_path(parent_dir, repo_name)
+        && legacy.exists()
+        && !path.exists()
+    {
+        fs::rename(&legacy, &path)
+            .with_context(|| format!("failed to move lock file {} to {}", legacy.display(), path.display()))?;
+    }
+    Ok(())
+}
+
+/// Lock file name for a repo. Plain names map to `<name>.lock` as they always
+/// have; anything else (dots, spaces, non-ASCII) gets a sanitized stem plus a
+/// hash of the full name, so distinct repos never share a lock file.
+fn lock_file_name(repo_name: &str) -> String {
+    if config::is_plain_name(repo_name) {
+        return format!("{}.lock", repo_name);
+    }
+    let stem: String = repo_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}-{:016x}.lock", stem, fnv1a(repo_name.as_bytes()))
+}
+
+/// FNV-1a; unlike `DefaultHasher` its output is stable across Rust releases,
+/// which matters for names persisted on disk.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
 }
 
 pub fn acquire_lock(parent_dir: &Path, repo_name: &str, session_name: &str) -> Result<()> {
@@ -27,26 +77,27 @@ pub fn acquire_lock(parent_dir: &Path, repo_name: &str, session_name: &str) -> R
     let info = LockInfo {
         session: session_name.to_string(),
         locked_at: Utc::now(),
+        repo: repo_name.to_string(),
     };
 
     let path = lock_path(parent_dir, repo_name);
-    let json = serde_json::to_string_pretty(&info).context("failed to serialize lock info")?;
-    fs::write(&path, json)
-        .with_context(|| format!("failed to write lock file: {}", path.display()))?;
-
-    Ok(())
+    state::write_json(&path, &info)
+        .with_context(|| format!("failed to write lock file: {}", path.display()))
 }
 
 pub fn release_lock(parent_dir: &Path, repo_name: &str) -> Result<()> {
-    let path = lock_path(parent_dir, repo_name);
-    if path.exists() {
-        fs::remove_file(&path)
-            .with_context(|| format!("failed to remove lock file: {}", path.display()))?;
+    let legacy = legacy_lock_path(parent_dir, repo_name);
+    for path in std::iter::once(lock_path(parent_dir, repo_name)).chain(legacy) {
+        if path.exists() {
+            fs::remove_file(&path)
+                .with_context(|| format!("failed to remove lock file: {}", path.display()))?;
+        }
     }
     Ok(())
 }
 
 pub fn check_lock(parent_dir: &Path, repo_name: &str) -> Result<Option<LockInfo>> {
+    migrate_legacy_lock(parent_dir, repo_name)?;
     let path = lock_path(parent_dir, repo_name);
     if !path.exists() {
         return Ok(None);
@@ -54,12 +105,18 @@ pub fn check_lock(parent_dir: &Path, repo_name: &str) -> Result<Option<LockInfo>
 
     let contents = fs::read_to_string(&path)
         .with_context(|| format!("failed to read lock file: {}", path.display()))?;
-    let info: LockInfo =
-        serde_json::from_str(&contents).context("failed to parse lock file")?;
-    Ok(Some(info))
+    match serde_json::from_str::<LockInfo>(&contents) {
+        Ok(info) => Ok(Some(info)),
+        // A lock nobody can read protects nothing; treat the repo as unlocked
+        Err(e) => {
+            state::quarantine_with_warning(&path, &e.into());
+            Ok(None)
+        }
+    }
 }
 
-/// List all lock files and their contents.
+/// List all lock files and their contents, keyed by repo name.
+#[allow(clippy::collapsible_if)]
 pub fn list_locks(parent_dir: &Path) -> Result<Vec<(String, LockInfo)>> {
     let dir = locks_dir(parent_dir);
     if !dir.exists() {
@@ -70,10 +127,15 @@ pub fn list_locks(parent_dir: &Path) -> Result<Vec<(String, LockInfo)>> {
     for entry in fs::read_dir(&dir)?.flatten() {
         let path = entry.path();
         if path.extension().and_then(|e| e.to_str()) == Some("lock") {
-            if let Some(repo_name) = path.file_stem().and_then(|s| s.to_str()) {
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
                 if let Ok(contents) = fs::read_to_string(&path) {
                     if let Ok(info) = serde_json::from_str::<LockInfo>(&contents) {
-                        locks.push((repo_name.to_string(), info));
+                        let repo_name = if info.repo.is_empty() {
+                            stem.to_string()
+                        } else {
+                            info.repo.clone()
+                        };
+                        locks.push((repo_name, info));
                     }
                 }
             }
@@ -82,3 +144,36 @@ pub fn list_locks(parent_dir: &Path) -> Result<Vec<(String, LockInfo)>> {
 
     Ok(locks)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_file_names_are_unambiguous() {
+        assert_eq!(lock_file_name("api"), "api.lock");
+        assert_eq!(lock_file_name("web_app-2"), "web_app-2.lock");
+
+        let dotted = lock_file_name("api.v2");
+        assert!(dotted.starts_with("api_v2-") && dotted.ends_with(".lock"));
+        assert_ne!(dotted, lock_file_name("api_v2"));
+        assert_ne!(dotted, lock_file_name("api v2"));
+        assert_eq!(dotted, lock_file_name("api.v2"));
+    }
+
+    #[test]
+    fn legacy_lock_files_are_migrated() {
+        let dir = std::env::temp_dir().join(format!("sesh-lock-test-{}", std::process::id()));
+        fs::create_dir_all(locks_dir(&dir)).unwrap();
+        let legacy = locks_dir(&dir).join("api.v2.lock");
+        fs::write(&legacy, r#"{"session":"feat","locked_at":"2026-01-01T00:00:00Z"}"#).unwrap();
+
+        let info = check_lock(&dir, "api.v2").unwrap().unwrap();
+        assert_eq!(info.session, "feat");
+        assert!(!legacy.exists());
+        assert!(lock_path(&dir, "api.v2").exists());
+        assert!(legacy_lock_path(&dir, "api").is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,17 +1,44 @@
+mod ci;
 mod cli;
 mod commands;
+mod commit_msg;
 mod config;
 mod context;
 mod discovery;
+mod fileset;
+mod forge;
+mod gitstate;
+mod guard;
+mod history;
+mod hooks;
+mod index;
+mod install;
 mod integrations;
+mod landing;
 mod lock;
+mod manifest;
 mod mcp;
+mod metrics;
+mod remote_config;
+mod notify;
+mod parallel;
+mod pool;
+mod plugins;
+mod registry;
+mod reminders;
+mod rollback;
+mod scripting;
 mod scripts;
 mod session;
+mod state;
+mod template;
+mod timing;
+mod tunnel;
 mod vscode;
 mod worktree;
 
 use std::env;
+use std::time::Instant;
 
 use anyhow::Result;
 use clap::Parser;
@@ -20,30 +47,155 @@ use cli::{Cli, Command};
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let started = Instant::now();
     let cli = Cli::parse();
-    let parent_dir = cli.dir.unwrap_or_else(|| env::current_dir().expect("cannot determine current directory"));
+    if cli.profile_startup || env::var_os(timing::ENV_VAR).is_some_and(|v| !v.is_empty() && v != "0") {
+        timing::enable();
+    }
+    let parent_dir = cli.dir.unwrap_or_else(|| {
+        let cwd = env::current_dir().expect("cannot determine current directory");
+        // `sesh init` sets up the directory it is run in
+        let cwd = match cli.command {
+            Command::Init { .. } => cwd,
+            _ => discovery::workspace_root(&cwd),
+        };
+        registry::resolve(cwd, cli.command.session_name())
+    });
+    let config = config::SeshConfig::load(&parent_dir.join("sesh.toml")).ok();
+    if let Some(config) = &config {
+        scripts::set_env_policy(&config.scripts);
+        notify::set_desktop(&config.notify);
+        session::set_index_enabled(&config.index);
+    }
+    if let Some(jobs) = cli.jobs.or_else(|| config.as_ref().and_then(|c| c.performance.jobs)) {
+        parallel::set_jobs(jobs);
+    }
 
-    match cli.command {
-        Command::Start { branch, from, all, preset, no_setup, no_vscode, linear } => {
-            commands::start::run(&parent_dir, branch, from, all, preset, no_setup, no_vscode, linear).await
+    if !matches!(
+        cli.command,
+        Command::LogPrefix { .. } | Command::WatchExit { .. } | Command::Completions { .. } | Command::Guard { .. }
+    ) {
+        reminders::maybe_remind(&parent_dir).await;
+    }
+
+    let result = match cli.command {
+        Command::Start { branch, from, all, preset, no_setup, no_vscode, linear, jira, issue, keep_on_failure } => {
+            commands::start::run(
+                &parent_dir, branch, from, all, preset, no_setup, no_vscode, linear, jira, issue, keep_on_failure,
+            )
+            .await
+        }
+        Command::List { active, tag, long, global } => {
+            commands::list::run(&parent_dir, active, tag, long, global).await
+        }
+        Command::Switch { name } => commands::switch::run(&parent_dir, name),
+        Command::Stop { name, keep_branches, prune_remote, force, keep_scratch } => {
+            commands::stop::run(&parent_dir, name, keep_branches, prune_remote, force, keep_scratch).await
         }
-        Command::List { active } => commands::list::run(&parent_dir, active),
-        Command::Stop { name, keep_branches } => commands::stop::run(&parent_dir, name, keep_branches),
+        Command::Archive { name } => commands::archive::run(&parent_dir, name).await,
+        Command::Pin { name } => commands::pin::run(&parent_dir, name, true),
+        Command::Setup { name } => commands::lifecycle::setup(&parent_dir, name),
+        Command::Teardown { name } => commands::lifecycle::teardown(&parent_dir, name).await,
+        Command::Unpin { name } => commands::pin::run(&parent_dir, name, false),
+        Command::Prune { merged, yes, keep_branches, prune_remote, force } => {
+            commands::prune::run(&parent_dir, merged, yes, keep_branches, prune_remote, force).await
+        }
+        Command::History { name } => commands::history::run(&parent_dir, name),
+        Command::Metrics { textfile, write } => commands::metrics::run(&parent_dir, textfile, write),
         Command::Resume { name } => commands::resume::run(&parent_dir, name),
-        Command::Status { name } => commands::status::run(&parent_dir, name),
-        Command::Pr { name, base } => commands::pr::run(&parent_dir, name, base),
-        Command::Checkout { branch, pr, all, preset, no_setup, no_vscode } => {
-            commands::checkout::run(&parent_dir, branch, pr, all, preset, no_setup, no_vscode).await
+        Command::Status { name, changes, watch } => commands::status::run(&parent_dir, name, changes, watch).await,
+        Command::Du { name, enforce } => commands::du::run(&parent_dir, name, enforce),
+        Command::Pr { name, base, repos, only_changed, all, force } => {
+            commands::pr::run(&parent_dir, name, base, repos, only_changed, all, force).await
+        }
+        Command::Sync { name, merge, rebase } => commands::sync::run(&parent_dir, name, merge, rebase),
+        Command::Verify { name } => commands::verify::run(&parent_dir, name),
+        Command::Matrix { action } => match action {
+            cli::MatrixAction::Run { task, sessions, repo } => {
+                commands::matrix::run(&parent_dir, &task, sessions, repo)
+            }
+        },
+        Command::Commit { session, message, all } => {
+            commands::commit::run(&parent_dir, session, message, all)
+        }
+        Command::Guard { action } => match action {
+            cli::GuardAction::Check { session, repo, staged } => {
+                commands::guard::check(&parent_dir, session, repo, staged)
+            }
+            cli::GuardAction::Install { session } => commands::guard::install(&parent_dir, session),
+        },
+        Command::Watch { session, command } => commands::watch::run(&parent_dir, session, command),
+        Command::Url { action } => match action {
+            cli::UrlAction::Add { name, url, session } => {
+                commands::url::add(&parent_dir, session, name, url)
+            }
+            cli::UrlAction::Remove { name, session } => {
+                commands::url::remove(&parent_dir, session, name)
+            }
+            cli::UrlAction::List { session } => commands::url::list(&parent_dir, session),
+        },
+        Command::Context { action } => match action {
+            cli::ContextAction::Show { name, path } => commands::context::show(&parent_dir, name, path),
+        },
+        Command::Pool { action } => match action {
+            cli::PoolAction::Warm { repos } => commands::pool::warm(&parent_dir, repos),
+            cli::PoolAction::Status => commands::pool::status(&parent_dir),
+            cli::PoolAction::Clear => commands::pool::clear(&parent_dir),
+        },
+        Command::Tag { action } => match action {
+            cli::TagAction::Add { name, tags } => commands::tag::add(&parent_dir, name, tags),
+            cli::TagAction::Remove { name, tags } => commands::tag::remove(&parent_dir, name, tags),
+        },
+        Command::Meta { action } => match action {
+            cli::MetaAction::Set { name, fields } => commands::tag::set_meta(&parent_dir, name, fields),
+            cli::MetaAction::Unset { name, keys } => commands::tag::unset_meta(&parent_dir, name, keys),
+        },
+        Command::Listen { linear, interval, once } => {
+            commands::listen::run(&parent_dir, linear, interval, once).await
         }
-        Command::Init => commands::init::run(&parent_dir),
-        Command::Doctor => commands::doctor::run(&parent_dir),
-        Command::Activate { name } => commands::activate::run(&parent_dir, name),
-        Command::Log { session, script, follow } => {
-            commands::log::run(&parent_dir, session, script, follow)
+        Command::Tunnel { action } => match action {
+            cli::TunnelAction::Start { services, session } => {
+                commands::tunnel::start(&parent_dir, session, services)
+            }
+            cli::TunnelAction::Stop { services, session } => {
+                commands::tunnel::stop(&parent_dir, session, services)
+            }
+        },
+        Command::Checkout { branch, branch_filter, pr, all, preset, no_setup, no_vscode, keep_on_failure } => {
+            let branch_mode = branch.is_some() || branch_filter.is_some();
+            commands::checkout::run(
+                &parent_dir, branch_mode, branch.flatten(), branch_filter, pr, all, preset, no_setup, no_vscode,
+                keep_on_failure,
+            )
+            .await
         }
-        Command::Exec { session, command } => {
-            commands::exec::run(&parent_dir, session, &command)
+        Command::Compose { name, no_setup, no_vscode, keep_on_failure } => {
+            commands::compose::run(&parent_dir, name, no_setup, no_vscode, keep_on_failure).await
         }
+        Command::Init { from_url } => match from_url {
+            Some(url) => commands::init::from_url(&parent_dir, &url).await,
+            None => commands::init::run(&parent_dir).await,
+        },
+        Command::Config { action } => match action {
+            cli::ConfigAction::Update => commands::config::update(&parent_dir).await,
+            cli::ConfigAction::Show => commands::config::show(&parent_dir),
+            cli::ConfigAction::Get { key } => commands::config::get(&parent_dir, &key),
+            cli::ConfigAction::Set { key, value } => commands::config::set(&parent_dir, &key, &value),
+            cli::ConfigAction::Validate => commands::config::validate(&parent_dir),
+        },
+        Command::Doctor { fix, json } => commands::doctor::run(&parent_dir, fix, json).await,
+        Command::Activate { name, dry_run } => commands::activate::run(&parent_dir, name, dry_run).await,
+        Command::Log { session, script, follow, all, grep, since, export } => {
+            let filter = commands::log::LogFilter { grep, since };
+            commands::log::run(&parent_dir, session, script, follow, all, filter, export)
+        }
+        Command::Ps { name, restart } => commands::ps::run(&parent_dir, name, restart),
+        Command::Restart { session, label } => commands::ps::restart(&parent_dir, session, label),
+        Command::Exec { session, repos, sequential, fail_fast, command } => {
+            commands::exec::run(&parent_dir, session, &repos, sequential, fail_fast, &command).await
+        }
+        Command::LogPrefix { label, log_file } => scripts::run_log_prefixer(&label, &log_file),
+        Command::WatchExit { session_dir, label, pid } => scripts::watch_exit(&session_dir, &label, pid),
         Command::Completions { shell } => {
             commands::completions::run(shell);
             Ok(())
@@ -52,8 +204,17 @@ async fn main() -> Result<()> {
             let provider_name = match provider {
                 cli::AuthProvider::Linear => "linear",
                 cli::AuthProvider::Sentry => "sentry",
+                cli::AuthProvider::Jira => "jira",
+                cli::AuthProvider::Bitbucket => "bitbucket",
+                cli::AuthProvider::Azure => "azure",
             };
             commands::auth::run(&parent_dir, provider_name)
         }
-    }
+        Command::Plugins => commands::plugins::list(),
+        Command::External(args) => plugins::run(&parent_dir, &args),
+    };
+    // Best effort: a registry we can't write only costs `--global` lookups
+    let _ = registry::record(&parent_dir);
+    timing::report(started.elapsed());
+    result
 }
@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::worktree;
+
+/// Files sesh generated inside each worktree (relative paths), keyed by repo name.
+pub type GeneratedFiles = BTreeMap<String, Vec<String>>;
+
+const MANIFEST_FILE: &str = "generated_files.json";
+
+pub fn load(sess_dir: &Path) -> GeneratedFiles {
+    std::fs::read_to_string(sess_dir.join(MANIFEST_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save(sess_dir: &Path, files: &GeneratedFiles) -> Result<()> {
+    let path = sess_dir.join(MANIFEST_FILE);
+    crate::state::write_json(&path, files).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Record that sesh generated `rel_path` in a repo's worktree and exclude it
+/// from git locally so it can never be accidentally committed.
+pub fn record(sess_dir: &Path, repo_name: &str, original_repo_path: &Path, rel_path: &str) -> Result<()> {
+    let mut files = load(sess_dir);
+    let entry = files.entry(repo_name.to_string()).or_default();
+    if !entry.iter().any(|f| f == rel_path) {
+        entry.push(rel_path.to_string());
+        save(sess_dir, &files)?;
+    }
+
+    add_to_git_exclude(original_repo_path, rel_path)
+}
+
+/// Generated files that are staged, or committed on the branch since `base_ref`.
+pub fn leaked_files(worktree_path: &Path, generated: &[String], base_ref: &str) -> Result<Vec<String>> {
+    let mut touched = worktree::changed_files(worktree_path, base_ref, true)?;
+    touched.extend(worktree::committed_files(worktree_path, base_ref)?);
+
+    let mut leaked: Vec<String> = touched
+        .into_iter()
+        .filter(|f| {
+            generated
+                .iter()
+                .any(|g| f == g || f.starts_with(&format!("{}/", g.trim_end_matches('/'))))
+        })
+        .collect();
+    leaked.sort();
+    leaked.dedup();
+    Ok(leaked)
+}
+
+/// Appends an entry to the repo's `.git/info/exclude` if not already present.
+/// This is a local-only exclude mechanism that is never committed.
+pub fn add_to_git_exclude(repo_path: &Path, pattern: &str) -> Result<()> {
+    let exclude_dir = repo_path.join(".git/info");
+    std::fs::create_dir_all(&exclude_dir)
+        .with_context(|| format!("failed to create {}", exclude_dir.display()))?;
+
+    let exclude_path = exclude_dir.join("exclude");
+    let contents = std::fs::read_to_string(&exclude_path).unwrap_or_default();
+
+    if contents.lines().any(|line| line.trim() == pattern) {
+        return Ok(());
+    }
+
+    use std::io::Write;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&exclude_path)
+        .with_context(|| format!("failed to open {}", exclude_path.display()))?;
+
+    // Ensure we start on a new line
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        writeln!(file)?;
+    }
+    writeln!(file, "{}", pattern)?;
+
+    Ok(())
+}
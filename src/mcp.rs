@@ -1,25 +1,49 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
 use anyhow::{Context, Result};
 use serde::Serialize;
 
-use crate::config::McpServer;
+use crate::config::{McpServer, McpTransport};
+use crate::manifest;
 
 #[derive(Serialize)]
-struct McpServerEntry {
-    #[serde(rename = "type")]
-    kind: String,
-    url: String,
+#[serde(tag = "type", rename_all = "lowercase")]
+enum McpServerEntry<'a> {
+    Http {
+        url: &'a str,
+    },
+    Sse {
+        url: &'a str,
+    },
+    Stdio {
+        command: &'a str,
+        #[serde(skip_serializing_if = "<[String]>::is_empty")]
+        args: &'a [String],
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        env: &'a BTreeMap<String, String>,
+    },
+}
+
+impl<'a> From<&'a McpTransport> for McpServerEntry<'a> {
+    fn from(transport: &'a McpTransport) -> Self {
+        match transport {
+            McpTransport::Http { url } => McpServerEntry::Http { url },
+            McpTransport::Sse { url } => McpServerEntry::Sse { url },
+            McpTransport::Stdio { command, args, env } => McpServerEntry::Stdio { command, args, env },
+        }
+    }
 }
 
 #[derive(Serialize)]
-struct McpConfigFile {
+struct McpConfigFile<'a> {
     #[serde(rename = "mcpServers")]
-    mcp_servers: HashMap<String, McpServerEntry>,
+    mcp_servers: HashMap<&'a str, McpServerEntry<'a>>,
 }
 
 pub fn write_mcp_config(
+    sess_dir: &Path,
+    repo_name: &str,
     worktree_path: &Path,
     original_repo_path: &Path,
     servers: &[McpServer],
@@ -28,17 +52,9 @@ pub fn write_mcp_config(
         return Ok(());
     }
 
-    let mcp_servers: HashMap<String, McpServerEntry> = servers
+    let mcp_servers: HashMap<&str, McpServerEntry> = servers
         .iter()
-        .map(|s| {
-            (
-                s.name.clone(),
-                McpServerEntry {
-                    kind: s.kind.clone(),
-                    url: s.url.clone(),
-                },
-            )
-        })
+        .map(|s| (s.name.as_str(), McpServerEntry::from(&s.transport)))
         .collect();
 
     let config = McpConfigFile { mcp_servers };
@@ -49,39 +65,53 @@ pub fn write_mcp_config(
     std::fs::write(&dest, json)
         .with_context(|| format!("failed to write MCP config to {}", dest.display()))?;
 
-    // Ensure .mcp.json is excluded from git in the original repo so it can
-    // never be accidentally committed from any worktree.
-    add_to_git_exclude(original_repo_path, ".mcp.json")?;
+    // Ensure .mcp.json is tracked as generated and excluded from git in the
+    // original repo so it can never be accidentally committed from any worktree.
+    manifest::record(sess_dir, repo_name, original_repo_path, ".mcp.json")?;
 
     Ok(())
 }
 
-/// Appends an entry to the repo's `.git/info/exclude` if not already present.
-/// This is a local-only exclude mechanism that is never committed.
-fn add_to_git_exclude(repo_path: &Path, pattern: &str) -> Result<()> {
-    let exclude_dir = repo_path.join(".git/info");
-    std::fs::create_dir_all(&exclude_dir)
-        .with_context(|| format!("failed to create {}", exclude_dir.display()))?;
-
-    let exclude_path = exclude_dir.join("exclude");
-    let contents = std::fs::read_to_string(&exclude_path).unwrap_or_default();
-
-    if contents.lines().any(|line| line.trim() == pattern) {
-        return Ok(());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_http_and_stdio_servers() {
+        let config: crate::config::McpConfig = toml::from_str(
+            r#"
+            [[servers]]
+            name = "sentry"
+            type = "http"
+            url = "https://mcp.sentry.dev/mcp"
+
+            [[servers]]
+            name = "db"
+            type = "stdio"
+            command = "npx"
+            args = ["-y", "@bytebase/dbhub"]
+            env = { DSN = "postgres://localhost/dev" }
+            "#,
+        )
+        .unwrap();
+
+        let entries: Vec<serde_json::Value> = config
+            .servers
+            .iter()
+            .map(|s| serde_json::to_value(McpServerEntry::from(&s.transport)).unwrap())
+            .collect();
+        assert_eq!(
+            entries[0],
+            serde_json::json!({ "type": "http", "url": "https://mcp.sentry.dev/mcp" })
+        );
+        assert_eq!(
+            entries[1],
+            serde_json::json!({
+                "type": "stdio",
+                "command": "npx",
+                "args": ["-y", "@bytebase/dbhub"],
+                "env": { "DSN": "postgres://localhost/dev" }
+            })
+        );
     }
-
-    use std::io::Write;
-    let mut file = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&exclude_path)
-        .with_context(|| format!("failed to open {}", exclude_path.display()))?;
-
-    // Ensure we start on a new line
-    if !contents.is_empty() && !contents.ends_with('\n') {
-        writeln!(file)?;
-    }
-    writeln!(file, "{}", pattern)?;
-
-    Ok(())
 }
@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::history::{self, Event};
+use crate::lock;
+use crate::scripts;
+use crate::session::{self, SessionInfo};
+use crate::state;
+
+/// A session's disk usage as last measured, kept in `.sesh/metrics-disk.json`
+/// so frequent scrapes don't walk every worktree each time.
+#[derive(Serialize, Deserialize)]
+struct DiskSample {
+    bytes: u64,
+    measured_at: DateTime<Utc>,
+}
+
+/// Render the current state of a sesh workspace in the Prometheus text
+/// exposition format. Disk usage measured less than `disk_ttl_secs` ago is
+/// reused.
+pub fn render(parent_dir: &Path, disk_ttl_secs: u64) -> Result<String> {
+    let sessions = session::list_sessions(parent_dir)?;
+    let mut out = String::new();
+
+    let archived = sessions.iter().filter(|s| s.archived_at.is_some()).count();
+    let active = sessions
+        .iter()
+        .filter(|s| s.archived_at.is_none() && s.repos.iter().any(|r| r.worktree_path.exists()))
+        .count();
+    header(&mut out, "sesh_sessions", "gauge", "Sessions by state.");
+    let _ = writeln!(out, "sesh_sessions{{state=\"active\"}} {}", active);
+    let _ = writeln!(out, "sesh_sessions{{state=\"archived\"}} {}", archived);
+    let _ = writeln!(
+        out,
+        "sesh_sessions{{state=\"stale\"}} {}",
+        sessions.len() - active - archived
+    );
+
+    header(
+        &mut out,
+        "sesh_background_processes",
+        "gauge",
+        "Background processes still alive, per session.",
+    );
+    for s in &sessions {
+        let alive = session::load_background_pids(&session::session_dir(parent_dir, &s.name))
+            .iter()
+            .filter(|p| !p.dead && scripts::is_process_alive(p.pid))
+            .count();
+        let _ = writeln!(out, "sesh_background_processes{{session=\"{}\"}} {}", escape_label(&s.name), alive);
+    }
+
+    header(
+        &mut out,
+        "sesh_session_disk_bytes",
+        "gauge",
+        "Disk used by each session directory, worktrees included.",
+    );
+    let now = Utc::now();
+    let cache_path = parent_dir.join(".sesh/metrics-disk.json");
+    let mut cached: BTreeMap<String, DiskSample> = std::fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default();
+    let mut disk = BTreeMap::new();
+    let mut measured = false;
+    for s in &sessions {
+        let sample = match cached.remove(&s.name) {
+            Some(sample) if (now - sample.measured_at).num_seconds() < disk_ttl_secs as i64 => sample,
+            _ => {
+                measured = true;
+                DiskSample {
+                    bytes: session::disk_usage(&session::session_dir(parent_dir, &s.name)),
+                    measured_at: now,
+                }
+            }
+        };
+        let _ = writeln!(out, "sesh_session_disk_bytes{{session=\"{}\"}} {}", escape_label(&s.name), sample.bytes);
+        disk.insert(s.name.clone(), sample);
+    }
+    // Entries for stopped sessions are left behind in `cached` and dropped here
+    if measured || !cached.is_empty() {
+        state::write_json(&cache_path, &disk)?;
+    }
+
+    header(
+        &mut out,
+        "sesh_lock_held_seconds",
+        "gauge",
+        "How long each exclusive repo lock has been held.",
+    );
+    for (repo, info) in lock::list_locks(parent_dir).unwrap_or_default() {
+        let held = now.signed_duration_since(info.locked_at).num_seconds().max(0);
+        let _ = writeln!(
+            out,
+            "sesh_lock_held_seconds{{repo=\"{}\",session=\"{}\"}} {}",
+            escape_label(&repo),
+            escape_label(&info.session),
+            held
+        );
+    }
+
+    header(
+        &mut out,
+        "sesh_start_duration_seconds",
+        "gauge",
+        "Time the last start of each session took to become ready (setup scripts included).",
+    );
+    for s in &sessions {
+        if let Some(secs) = start_duration(parent_dir, s) {
+            let _ = writeln!(out, "sesh_start_duration_seconds{{session=\"{}\"}} {}", escape_label(&s.name), secs);
+        }
+    }
+
+    header(
+        &mut out,
+        "sesh_metrics_generated_timestamp_seconds",
+        "gauge",
+        "When these metrics were generated.",
+    );
+    let _ = writeln!(out, "sesh_metrics_generated_timestamp_seconds {}", now.timestamp());
+
+    Ok(out)
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// The `ready` duration recorded after the session's most recent `created` event.
+fn start_duration(parent_dir: &Path, s: &SessionInfo) -> Option<f64> {
+    let (entries, _) = history::load(parent_dir, &s.name).ok()?;
+    let created = entries.iter().rposition(|e| matches!(e.event, Event::Created { .. }))?;
+    entries[created..].iter().find_map(|e| match e.event {
+        Event::Ready { duration_secs } => Some(duration_secs),
+        _ => None,
+    })
+}
+
+/// Escape a label value per the exposition format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!(escape_label("feat-x"), "feat-x");
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use console::style;
+use notify_rust::Notification;
+use reqwest::Client;
+
+use crate::config::NotifyConfig;
+use crate::timing::TimedSend;
+
+/// Set from `[notify]` when `desktop = true`: how long an operation must
+/// take before its completion is announced.
+static DESKTOP_MIN: OnceLock<Duration> = OnceLock::new();
+
+pub fn set_desktop(config: &NotifyConfig) {
+    if config.desktop {
+        let _ = DESKTOP_MIN.set(Duration::from_secs(config.desktop_min_secs));
+    }
+}
+
+pub fn desktop_enabled() -> bool {
+    DESKTOP_MIN.get().is_some()
+}
+
+/// Announce an operation that took `elapsed`, if desktop notifications are
+/// on and it took at least `desktop_min_secs`.
+pub fn desktop_after(elapsed: Duration, summary: &str, body: &str) {
+    if DESKTOP_MIN.get().is_some_and(|min| elapsed >= *min) {
+        show_desktop(summary, body);
+    }
+}
+
+/// Show a desktop notification. Failing to (no notification daemon, e.g. over
+/// SSH) is only a warning.
+pub fn show_desktop(summary: &str, body: &str) {
+    if let Err(e) = Notification::new().appname("sesh").summary(summary).body(body).show() {
+        eprintln!("  {} Desktop notification failed: {}", style("!").yellow(), e);
+    }
+}
+
+/// Send `message` to the configured notification channel, if any.
+pub async fn send(config: &NotifyConfig, message: &str) -> Result<()> {
+    let Some(url) = &config.webhook_url else {
+        return Ok(());
+    };
+
+    let resp = Client::new()
+        .post(url)
+        .json(&serde_json::json!({ "text": message }))
+        .timed_send()
+        .await
+        .context("failed to call notification webhook")?;
+
+    if !resp.status().is_success() {
+        bail!("notification webhook returned status {}", resp.status());
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Set by `--jobs` / `[performance] jobs`; 0 means the default.
+static JOBS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn set_jobs(jobs: usize) {
+    JOBS.store(jobs, Ordering::Relaxed);
+}
+
+/// How many repos multi-repo operations work on at once.
+pub fn limit() -> usize {
+    match JOBS.load(Ordering::Relaxed) {
+        0 => std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4)
+            .clamp(4, 16),
+        jobs => jobs,
+    }
+}
+
+/// Run `f` on every item concurrently, at most [`limit`] at a time, and return
+/// the results in the order of `items`.
+pub async fn map_bounded<T, R, F, Fut>(items: Vec<T>, f: F) -> Vec<R>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = R> + Send + 'static,
+{
+    let permits = Arc::new(Semaphore::new(limit()));
+    let mut tasks = JoinSet::new();
+    for (i, item) in items.into_iter().enumerate() {
+        let permits = permits.clone();
+        let fut = f(item);
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            (i, fut.await)
+        });
+    }
+
+    let mut results = Vec::with_capacity(tasks.len());
+    while let Some(joined) = tasks.join_next().await {
+        results.push(joined.expect("task panicked"));
+    }
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, r)| r).collect()
+}
+
+/// [`map_bounded`] for blocking work (std::process, git via `worktree`), run on
+/// tokio's blocking thread pool.
+pub async fn map_blocking<T, R, F>(items: Vec<T>, f: F) -> Vec<R>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    F: Fn(T) -> R + Send + Sync + 'static,
+{
+    let f = Arc::new(f);
+    map_bounded(items, |item| {
+        let f = f.clone();
+        async move {
+            tokio::task::spawn_blocking(move || f(item))
+                .await
+                .expect("task panicked")
+        }
+    })
+    .await
+}
@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+use crate::session::{self, SessionInfo};
+use crate::state;
+
+/// Executables named `sesh-<name>` on PATH become `sesh <name>`.
+const PLUGIN_PREFIX: &str = "sesh-";
+
+/// Bumped when the context handed to plugins changes incompatibly.
+pub const PLUGIN_API_VERSION: u32 = 1;
+
+/// Written to the file named by `$SESH_CONTEXT` before a plugin starts.
+#[derive(Serialize)]
+struct PluginContext<'a> {
+    api_version: u32,
+    sesh_version: &'static str,
+    parent_dir: &'a Path,
+    config_path: PathBuf,
+    session_dir: Option<PathBuf>,
+    session: Option<&'a SessionInfo>,
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+/// Plugins on PATH, by subcommand name. Earlier PATH entries win, as in a shell.
+pub fn discover() -> BTreeMap<String, PathBuf> {
+    let mut found = BTreeMap::new();
+    let Some(path) = env::var_os("PATH") else {
+        return found;
+    };
+    for dir in env::split_paths(&path) {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let Some(name) = file_name.strip_prefix(PLUGIN_PREFIX) else {
+                continue;
+            };
+            let name = name.strip_suffix(".exe").unwrap_or(name);
+            if !name.is_empty() && is_executable(&entry.path()) {
+                found.entry(name.to_string()).or_insert_with(|| entry.path());
+            }
+        }
+    }
+    found
+}
+
+/// Run `sesh-<name>` with the remaining arguments and exit with its status.
+pub fn run(parent_dir: &Path, args: &[String]) -> Result<()> {
+    let Some((name, rest)) = args.split_first() else {
+        bail!("no subcommand given");
+    };
+    let Some(path) = discover().remove(name.as_str()) else {
+        bail!(
+            "unknown command '{}' (no `{}{}` plugin found on PATH; see `sesh plugins`)",
+            name,
+            PLUGIN_PREFIX,
+            name
+        );
+    };
+
+    let session = session::current_session(parent_dir);
+    let session_dir = session.as_ref().map(|s| session::session_dir(parent_dir, &s.name));
+    let context = PluginContext {
+        api_version: PLUGIN_API_VERSION,
+        sesh_version: env!("CARGO_PKG_VERSION"),
+        parent_dir,
+        config_path: parent_dir.join("sesh.toml"),
+        session_dir: session_dir.clone(),
+        session: session.as_ref(),
+    };
+    let tmp_dir = parent_dir.join(".sesh/tmp");
+    fs::create_dir_all(&tmp_dir)
+        .with_context(|| format!("failed to create {}", tmp_dir.display()))?;
+    let context_path = tmp_dir.join(format!("plugin-{}.json", std::process::id()));
+    state::write_json(&context_path, &context)?;
+
+    let mut cmd = Command::new(&path);
+    cmd.args(rest)
+        .env("SESH_PLUGIN_API", PLUGIN_API_VERSION.to_string())
+        .env("SESH_CONTEXT", &context_path)
+        .env("SESH_PARENT_DIR", parent_dir);
+    if let Ok(exe) = env::current_exe() {
+        cmd.env("SESH_BIN", exe);
+    }
+    if let (Some(sess), Some(dir)) = (&session, &session_dir) {
+        let repo_names: Vec<&str> = sess.repos.iter().map(|r| r.name.as_str()).collect();
+        cmd.env("SESH_SESSION", &sess.name)
+            .env("SESH_BRANCH", &sess.branch)
+            .env("SESH_SESSION_DIR", dir)
+            .env("SESH_REPOS", repo_names.join(","));
+    }
+
+    let status = cmd.status();
+    let _ = fs::remove_file(&context_path);
+    let status = status.with_context(|| format!("failed to run plugin {}", path.display()))?;
+    std::process::exit(status.code().unwrap_or(1));
+}
@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+
+use crate::install;
+use crate::scripts;
+use crate::worktree;
+
+/// Warm worktrees live in `.sesh/pool/<repo>/<id>/`, detached at the repo's
+/// base branch. Each is claimable once `<id>.ready` (holding the ref it was
+/// created from) exists; a claim renames that marker first, so two sessions
+/// never take the same worktree.
+pub fn pool_dir(parent_dir: &Path) -> PathBuf {
+    parent_dir.join(".sesh/pool")
+}
+
+pub struct Entry {
+    pub path: PathBuf,
+    /// Base ref the worktree was created from; `None` while it's still warming.
+    pub base_ref: Option<String>,
+}
+
+fn ready_marker(path: &Path) -> PathBuf {
+    path.with_extension("ready")
+}
+
+/// `repo`'s pooled worktrees, oldest first.
+pub fn entries(parent_dir: &Path, repo: &str) -> Vec<Entry> {
+    let Ok(dir) = fs::read_dir(pool_dir(parent_dir).join(repo)) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = dir.flatten().map(|e| e.path()).filter(|p| p.is_dir()).collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let base_ref = fs::read_to_string(ready_marker(&path)).ok().map(|s| s.trim().to_string());
+            Entry { path, base_ref }
+        })
+        .collect()
+}
+
+/// Create one pooled worktree of `repo_path` at `base_ref` and mark it ready.
+/// With `install`, the detected dependency installs run in it first.
+pub fn warm_one(parent_dir: &Path, repo: &str, repo_path: &Path, base_ref: &str, install: bool) -> Result<PathBuf> {
+    let dir = pool_dir(parent_dir).join(repo);
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let path = dir.join(format!("{}-{}", Utc::now().format("%Y%m%d%H%M%S%3f"), std::process::id()));
+    worktree::create_detached_worktree(repo_path, &path, base_ref)?;
+
+    if install {
+        for command in install::detect(&path).into_iter().filter(|c| install::is_available(c)) {
+            let status = scripts::shell_command(command, &path, "", base_ref, &[repo.to_string()])
+                .status()
+                .with_context(|| format!("failed to run `{}`", command))?;
+            if !status.success() {
+                let _ = worktree::remove_worktree(repo_path, &path);
+                anyhow::bail!("`{}` exited with {}", command, status);
+            }
+        }
+    }
+
+    fs::write(ready_marker(&path), base_ref)
+        .with_context(|| format!("failed to mark {} ready", path.display()))?;
+    Ok(path)
+}
+
+/// Take a ready worktree of `repo` created from `base_ref`, move it to
+/// `worktree_path` and check out a new `branch` at `base_ref` there.
+/// Returns `false` when none is ready.
+pub fn claim(
+    parent_dir: &Path,
+    repo: &str,
+    repo_path: &Path,
+    base_ref: &str,
+    worktree_path: &Path,
+    branch: &str,
+) -> Result<bool> {
+    for entry in entries(parent_dir, repo) {
+        if entry.base_ref.as_deref() != Some(base_ref) {
+            continue;
+        }
+        let claimed = entry.path.with_extension("claimed");
+        if fs::rename(ready_marker(&entry.path), &claimed).is_err() {
+            continue;
+        }
+        // Unlike `worktree add`, `worktree move` doesn't create missing parents
+        let moved = worktree_path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| worktree::move_worktree(repo_path, &entry.path, worktree_path));
+        let result = moved.and_then(|_| worktree::checkout_new_branch(worktree_path, branch, base_ref));
+        let _ = fs::remove_file(&claimed);
+        if let Err(e) = result {
+            let left = if worktree_path.exists() { worktree_path } else { &entry.path };
+            let _ = worktree::remove_worktree(repo_path, left);
+            return Err(e.context(format!("failed to claim pooled worktree for {}", repo)));
+        }
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+/// Remove every pooled worktree of `repo`. Returns how many were removed.
+pub fn clear(parent_dir: &Path, repo: &str, repo_path: &Path) -> Result<usize> {
+    let entries = entries(parent_dir, repo);
+    for entry in &entries {
+        // A worktree whose warm-up was interrupted may not be registered yet
+        if worktree::remove_worktree(repo_path, &entry.path).is_err() {
+            fs::remove_dir_all(&entry.path)
+                .with_context(|| format!("failed to remove {}", entry.path.display()))?;
+            worktree::prune_worktrees(repo_path)?;
+        }
+        let _ = fs::remove_file(ready_marker(&entry.path));
+    }
+    let _ = fs::remove_dir(pool_dir(parent_dir).join(repo));
+    Ok(entries.len())
+}
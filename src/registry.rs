@@ -0,0 +1,99 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use console::style;
+use serde::{Deserialize, Serialize};
+
+use crate::session;
+use crate::state;
+
+/// Every parent directory sesh has seen sessions in, so sessions can be found
+/// by name from anywhere.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Registry {
+    parent_dirs: Vec<PathBuf>,
+}
+
+/// `$XDG_DATA_HOME/sesh/registry.json`, defaulting to `~/.local/share`.
+fn registry_path() -> Option<PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/share")))?;
+    Some(data_home.join("sesh/registry.json"))
+}
+
+fn load() -> Registry {
+    registry_path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Registered parent directories that still have a sessions directory.
+pub fn parent_dirs() -> Vec<PathBuf> {
+    load()
+        .parent_dirs
+        .into_iter()
+        .filter(|d| d.join(".sesh/sessions").is_dir())
+        .collect()
+}
+
+/// Add `parent_dir` to the registry if it has sessions and isn't listed yet.
+pub fn record(parent_dir: &Path) -> Result<()> {
+    if !parent_dir.join(".sesh/sessions").is_dir() {
+        return Ok(());
+    }
+    let Some(path) = registry_path() else {
+        return Ok(());
+    };
+    let parent_dir = parent_dir.canonicalize().unwrap_or_else(|_| parent_dir.to_path_buf());
+    let mut registry = load();
+    if registry.parent_dirs.contains(&parent_dir) {
+        return Ok(());
+    }
+    registry.parent_dirs.push(parent_dir);
+    // Forget directories whose sessions are gone
+    registry.parent_dirs.retain(|d| d.join(".sesh/sessions").is_dir());
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    state::write_json(&path, &registry)
+}
+
+/// The parent directory to run a command for session `name` in: `cwd` when
+/// the session is there (or no session was named), otherwise the one
+/// registered directory that has it.
+pub fn resolve(cwd: PathBuf, name: Option<&str>) -> PathBuf {
+    let Some(name) = name else {
+        return cwd;
+    };
+    if session::session_exists(&cwd, name) {
+        return cwd;
+    }
+    let found: Vec<PathBuf> = parent_dirs()
+        .into_iter()
+        .filter(|d| session::session_exists(d, name))
+        .collect();
+    match found.as_slice() {
+        [dir] => {
+            eprintln!(
+                "{}",
+                style(format!("Using session '{}' in {}", name, dir.display())).dim()
+            );
+            dir.clone()
+        }
+        [] => cwd,
+        dirs => {
+            eprintln!(
+                "{} Session '{}' exists in several workspaces; pass --dir to pick one:",
+                style("note:").yellow(),
+                name
+            );
+            for dir in dirs {
+                eprintln!("  {}", dir.display());
+            }
+            cwd
+        }
+    }
+}
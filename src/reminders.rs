@@ -0,0 +1,93 @@
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+use console::{style, Term};
+
+use crate::config::SeshConfig;
+use crate::integrations;
+use crate::session::{self, SessionInfo};
+use crate::worktree;
+
+/// Print a nudge about idle sessions and sessions whose Linear ticket is done.
+/// Opt-in via `[reminders] enabled`, throttled to once per `interval_hours`,
+/// and silent on any error so it never gets in the way of the actual command.
+pub async fn maybe_remind(parent_dir: &Path) {
+    if !Term::stderr().is_term() {
+        return;
+    }
+    let Ok(config) = SeshConfig::load(&parent_dir.join("sesh.toml")) else {
+        return;
+    };
+    let rc = &config.reminders;
+    if !rc.enabled {
+        return;
+    }
+
+    let stamp = parent_dir.join(".sesh/reminded_at");
+    let now = Utc::now();
+    if let Some(last) = fs::read_to_string(&stamp)
+        .ok()
+        .and_then(|s| DateTime::parse_from_rfc3339(s.trim()).ok())
+        && now.signed_duration_since(last) < Duration::hours(rc.interval_hours.into())
+    {
+        return;
+    }
+
+    let Ok(sessions) = session::list_sessions(parent_dir) else {
+        return;
+    };
+    if sessions.is_empty() {
+        return;
+    }
+
+    let mut nudges: Vec<(String, String)> = Vec::new();
+    for s in &sessions {
+        if let Some(mark) = &s.cleanup {
+            nudges.push((s.name.clone(), mark.reason.clone()));
+            continue;
+        }
+        if rc.check_linear
+            && let Some(issue) = s.issue.as_ref().filter(|i| i.provider == "linear")
+            && let Ok((Some(status), _)) = integrations::linear_issue_status(parent_dir, &issue.identifier).await
+            && let Some(state) = status.state_type.filter(|t| t == "completed" || t == "canceled")
+        {
+            nudges.push((s.name.clone(), format!("{} is {}", issue.identifier, state)));
+            continue;
+        }
+
+        // Archiving is a deliberate pause, not a forgotten session
+        if s.archived_at.is_some() {
+            continue;
+        }
+        let idle = now.signed_duration_since(last_activity(s));
+        if idle >= Duration::days(rc.idle_days.into()) {
+            nudges.push((s.name.clone(), format!("idle for {} days", idle.num_days())));
+        }
+    }
+
+    let _ = crate::state::write_atomic(&stamp, now.to_rfc3339().as_bytes());
+    if nudges.is_empty() {
+        return;
+    }
+
+    eprintln!("{} Sessions that may be ready to clean up:", style("ℹ").cyan());
+    for (name, reason) in &nudges {
+        eprintln!(
+            "  {} ({}) — run `{}`",
+            style(name).green(),
+            reason,
+            style(format!("sesh stop {}", name)).bold()
+        );
+    }
+    eprintln!();
+}
+
+/// Latest of the session's creation time and the newest HEAD commit in its worktrees.
+fn last_activity(s: &SessionInfo) -> DateTime<Utc> {
+    s.repos
+        .iter()
+        .filter(|r| !r.pinned)
+        .filter_map(|r| worktree::head_commit_time(&r.worktree_path))
+        .fold(s.created_at, |acc, t| acc.max(t))
+}
@@ -0,0 +1,130 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use reqwest::Client;
+
+use crate::config::SeshConfig;
+use crate::timing::TimedSend;
+
+/// Whether a config source refers to a git repository rather than a plain file URL.
+fn is_git_source(source: &str) -> bool {
+    source.starts_with("git@") || source.starts_with("ssh://") || source.ends_with(".git")
+}
+
+/// Fetch the team config from `source` (an http(s) URL or a git repo) and
+/// check that it parses as a sesh config.
+pub async fn fetch(
+    parent_dir: &Path,
+    source: &str,
+    path: Option<&str>,
+    git_ref: Option<&str>,
+) -> Result<String> {
+    let contents = if is_git_source(source) {
+        fetch_from_git(parent_dir, source, path.unwrap_or("sesh.toml"), git_ref)?
+    } else if source.starts_with("https://") || source.starts_with("http://") {
+        fetch_from_url(source).await?
+    } else {
+        bail!("unsupported config source '{}' (expected an http(s) URL or a git repo)", source);
+    };
+
+    toml::from_str::<SeshConfig>(&contents)
+        .with_context(|| format!("config fetched from {} is not a valid sesh.toml", source))?;
+    Ok(contents)
+}
+
+async fn fetch_from_url(url: &str) -> Result<String> {
+    let resp = Client::new()
+        .get(url)
+        .timed_send()
+        .await
+        .with_context(|| format!("failed to fetch {}", url))?;
+    if !resp.status().is_success() {
+        bail!("fetching {} returned status {}", url, resp.status());
+    }
+    resp.text().await.context("failed to read config response")
+}
+
+fn fetch_from_git(parent_dir: &Path, repo: &str, path: &str, git_ref: Option<&str>) -> Result<String> {
+    let checkout = parent_dir.join(".sesh/tmp/config-source");
+    if checkout.exists() {
+        std::fs::remove_dir_all(&checkout)
+            .with_context(|| format!("failed to clear {}", checkout.display()))?;
+    }
+
+    let mut cmd = Command::new("git");
+    cmd.args(["clone", "--quiet", "--depth", "1"]);
+    if let Some(r) = git_ref {
+        cmd.args(["--branch", r]);
+    }
+    let output = cmd
+        .arg(repo)
+        .arg(&checkout)
+        .output()
+        .context("failed to run git clone")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git clone {} failed: {}", repo, stderr.trim());
+    }
+
+    let file = checkout.join(path);
+    let contents = std::fs::read_to_string(&file)
+        .with_context(|| format!("{} not found in {}", path, repo));
+    let _ = std::fs::remove_dir_all(&checkout);
+    contents
+}
+
+/// Make sure the fetched config remembers where it came from, so later
+/// `sesh config update` runs know what to pull.
+pub fn record_source(contents: &str, source: &str) -> Result<String> {
+    let table: toml::Table = toml::from_str(contents).context("failed to parse fetched config")?;
+    let meta = table.get("meta").and_then(|m| m.as_table());
+    if meta.is_some_and(|m| m.contains_key("config_source")) {
+        return Ok(contents.to_string());
+    }
+
+    let line = format!("config_source = {}\n", toml::Value::String(source.to_string()));
+    if meta.is_some() {
+        // Insert under the existing [meta] header
+        let mut out = String::new();
+        let mut inserted = false;
+        for l in contents.lines() {
+            out.push_str(l);
+            out.push('\n');
+            if !inserted && l.trim() == "[meta]" {
+                out.push_str(&line);
+                inserted = true;
+            }
+        }
+        if inserted {
+            return Ok(out);
+        }
+        bail!("fetched config has an inline `meta` table; add config_source to it manually");
+    }
+
+    let mut out = format!("[meta]\n{}\n", line);
+    out.push_str(contents);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_source_once() {
+        let src = "https://example.com/sesh.toml";
+
+        let added = record_source("[session]\nbase_branch = \"main\"\n", src).unwrap();
+        assert!(added.starts_with("[meta]\nconfig_source = \"https://example.com/sesh.toml\"\n"));
+
+        let under_meta = record_source("[meta]\nconfig_path = \"x.toml\"\n", src).unwrap();
+        assert_eq!(
+            under_meta,
+            "[meta]\nconfig_source = \"https://example.com/sesh.toml\"\nconfig_path = \"x.toml\"\n"
+        );
+
+        let existing = "[meta]\nconfig_source = \"git@host:team/cfg.git\"\n";
+        assert_eq!(record_source(existing, src).unwrap(), existing);
+    }
+}
@@ -0,0 +1,122 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use console::style;
+
+use crate::worktree;
+
+type Undo = Box<dyn FnOnce() -> Result<()>>;
+
+/// Undo actions registered while a multi-step operation makes changes, run
+/// newest first if it fails part-way. On success just drop it.
+pub struct Rollback {
+    steps: Vec<(String, Undo)>,
+    keep_on_failure: bool,
+}
+
+impl Rollback {
+    /// With `keep_on_failure`, [`Rollback::run`] only reports what was left in
+    /// place, for debugging a failed setup.
+    pub fn new(keep_on_failure: bool) -> Self {
+        Self { steps: Vec::new(), keep_on_failure }
+    }
+
+    pub fn add(&mut self, description: impl Into<String>, undo: impl FnOnce() -> Result<()> + 'static) {
+        self.steps.push((description.into(), Box::new(undo)));
+    }
+
+    pub fn add_worktree(&mut self, repo_path: PathBuf, worktree_path: PathBuf) {
+        self.add(format!("worktree {}", worktree_path.display()), move || {
+            worktree::remove_worktree(&repo_path, &worktree_path)
+        });
+    }
+
+    /// Remove `sess_dir` on rollback if this operation is about to create it.
+    /// Only an empty directory is removed, so a worktree that failed to roll
+    /// back keeps its files.
+    pub fn add_session_dir(&mut self, sess_dir: &Path) {
+        if sess_dir.exists() {
+            return;
+        }
+        let dir = sess_dir.to_path_buf();
+        self.add(format!("session directory {}", dir.display()), move || {
+            if dir.exists() {
+                std::fs::remove_dir(&dir)?;
+            }
+            Ok(())
+        });
+    }
+
+    /// Undo every registered step, newest first, continuing past failures.
+    /// Returns the descriptions of the steps that could not be undone.
+    pub fn run(self) -> Vec<String> {
+        if self.steps.is_empty() {
+            return Vec::new();
+        }
+        if self.keep_on_failure {
+            eprintln!(
+                "\n  {} Left in place (--keep-on-failure):",
+                style("!").yellow()
+            );
+            for (description, _) in &self.steps {
+                eprintln!("    {}", description);
+            }
+            return Vec::new();
+        }
+
+        eprintln!("\n  {} Rolling back...", style("✗").red());
+        let mut failed = Vec::new();
+        for (description, undo) in self.steps.into_iter().rev() {
+            if let Err(e) = undo() {
+                eprintln!("    Failed to remove {}: {}", description, e);
+                failed.push(description);
+            }
+        }
+        failed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use anyhow::bail;
+
+    use super::*;
+
+    fn recording(keep_on_failure: bool) -> (Rollback, Rc<RefCell<Vec<&'static str>>>) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut rollback = Rollback::new(keep_on_failure);
+        for name in ["first", "second", "third"] {
+            let log = log.clone();
+            rollback.add(name, move || {
+                log.borrow_mut().push(name);
+                if name == "second" {
+                    bail!("simulated failure");
+                }
+                Ok(())
+            });
+        }
+        (rollback, log)
+    }
+
+    #[test]
+    fn undoes_newest_first_and_continues_past_failures() {
+        let (rollback, log) = recording(false);
+        assert_eq!(rollback.run(), vec!["second".to_string()]);
+        assert_eq!(*log.borrow(), vec!["third", "second", "first"]);
+    }
+
+    #[test]
+    fn keep_on_failure_leaves_everything_in_place() {
+        let (rollback, log) = recording(true);
+        assert!(rollback.run().is_empty());
+        assert!(log.borrow().is_empty());
+
+        // Dropping without `run` (the success path) undoes nothing either
+        let (rollback, log) = recording(false);
+        drop(rollback);
+        assert!(log.borrow().is_empty());
+    }
+}
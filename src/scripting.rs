@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+
+use crate::session::IssueContext;
+
+/// Directory holding `*.rhai` hook scripts.
+pub const PLUGINS_DIR: &str = ".sesh/plugins";
+
+/// Keeps a runaway script from hanging sesh.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Rhai scripts from `.sesh/plugins/` that customize sesh at fixed hook points.
+/// Each script may define any of:
+///
+/// - `fn branch_name(name, issue)`: returns the branch to use (`issue` is a
+///   map, or `()` when the input wasn't a ticket)
+/// - `fn default_repos(selected, repos)`: returns the repo names pre-selected
+///   in the repo picker
+/// - `fn context(content, session)`: returns the `.sesh-context.md` content
+///
+/// Scripts run in file-name order, each receiving the previous one's result.
+pub struct ScriptHooks {
+    engine: Engine,
+    scripts: Vec<(PathBuf, AST)>,
+}
+
+impl ScriptHooks {
+    pub fn load(parent_dir: &Path) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(parent_dir.join(PLUGINS_DIR))
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("rhai"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        paths.sort();
+
+        let mut scripts = Vec::new();
+        for path in paths {
+            let ast = engine
+                .compile_file(path.clone())
+                .map_err(|e| anyhow!("{}", e))
+                .with_context(|| format!("failed to compile hook script {}", path.display()))?;
+            scripts.push((path, ast));
+        }
+        Ok(Self { engine, scripts })
+    }
+
+    /// Thread `value` through every script defining `name`, passing `extra`
+    /// as the remaining arguments.
+    fn chain(&self, name: &str, value: Dynamic, extra: &[Dynamic]) -> Result<Dynamic> {
+        let arity = extra.len() + 1;
+        let mut value = value;
+        for (path, ast) in &self.scripts {
+            if !ast.iter_functions().any(|f| f.name == name && f.params.len() == arity) {
+                continue;
+            }
+            let mut args = vec![value];
+            args.extend(extra.iter().cloned());
+            value = self
+                .engine
+                .call_fn::<Dynamic>(&mut Scope::new(), ast, name, args)
+                .map_err(|e| anyhow!("{}", e))
+                .with_context(|| format!("hook `{}` in {} failed", name, path.display()))?;
+        }
+        Ok(value)
+    }
+
+    pub fn branch_name(&self, name: &str, issue: Option<&IssueContext>) -> Result<String> {
+        let issue = match issue {
+            Some(i) => {
+                let mut map = Map::new();
+                map.insert("provider".into(), i.provider.clone().into());
+                map.insert("identifier".into(), i.identifier.clone().into());
+                map.insert("title".into(), i.title.clone().into());
+                map.insert("state".into(), i.state.clone().map(Dynamic::from).unwrap_or(Dynamic::UNIT));
+                map.insert("labels".into(), strings(&i.labels).into());
+                Dynamic::from_map(map)
+            }
+            None => Dynamic::UNIT,
+        };
+        self.chain("branch_name", name.into(), &[issue])?
+            .into_string()
+            .map_err(|t| anyhow!("hook `branch_name` must return a string, got {}", t))
+    }
+
+    /// Apply `default_repos` hooks to the picker's pre-selection.
+    pub fn default_repos(&self, repos: &[String], defaults: &[bool]) -> Result<Vec<bool>> {
+        let selected: Vec<String> = repos
+            .iter()
+            .zip(defaults)
+            .filter(|(_, d)| **d)
+            .map(|(r, _)| r.clone())
+            .collect();
+        let result = self.chain("default_repos", strings(&selected).into(), &[strings(repos).into()])?;
+        let names: Vec<String> = result
+            .into_typed_array::<String>()
+            .map_err(|t| anyhow!("hook `default_repos` must return an array of repo names, got {}", t))?;
+        Ok(repos.iter().map(|r| names.contains(r)).collect())
+    }
+
+    pub fn context(&self, content: &str, session_name: &str, branch: &str, repos: &[String]) -> Result<String> {
+        let mut session = Map::new();
+        session.insert("name".into(), session_name.into());
+        session.insert("branch".into(), branch.into());
+        session.insert("repos".into(), strings(repos).into());
+        self.chain("context", content.into(), &[Dynamic::from_map(session)])?
+            .into_string()
+            .map_err(|t| anyhow!("hook `context` must return a string, got {}", t))
+    }
+}
+
+fn strings(items: &[String]) -> Array {
+    items.iter().cloned().map(Dynamic::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hooks(source: &str) -> ScriptHooks {
+        let engine = Engine::new();
+        let ast = engine.compile(source).unwrap();
+        ScriptHooks { engine, scripts: vec![(PathBuf::from("test.rhai"), ast)] }
+    }
+
+    #[test]
+    fn hooks_transform_values_and_skip_missing_functions() {
+        let h = hooks(
+            r#"
+            fn branch_name(name, issue) {
+                if issue == () { name } else { issue.identifier.to_lower() + "/" + name }
+            }
+            fn default_repos(selected, repos) { selected.filter(|r| r != "docs") }
+            "#,
+        );
+        assert_eq!(h.branch_name("fix-login", None).unwrap(), "fix-login");
+        let issue = IssueContext {
+            provider: "linear".into(),
+            identifier: "ENG-1".into(),
+            title: "Fix login".into(),
+            ..Default::default()
+        };
+        assert_eq!(h.branch_name("fix-login", Some(&issue)).unwrap(), "eng-1/fix-login");
+
+        let repos = vec!["api".to_string(), "docs".to_string(), "web".to_string()];
+        assert_eq!(
+            h.default_repos(&repos, &[true, true, false]).unwrap(),
+            vec![true, false, false]
+        );
+        // No `context` hook defined: content passes through unchanged
+        assert_eq!(h.context("# Session", "s", "b", &repos).unwrap(), "# Session");
+    }
+}
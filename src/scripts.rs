@@ -1,16 +1,69 @@
 use std::fs::{self, File};
+use std::io::{IsTerminal, Read, Write};
+use std::os::unix::process::CommandExt;
 use std::path::Path;
 use std::process::Command;
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
 use std::thread;
 use std::time::Duration;
 
 use anyhow::{bail, Context, Result};
 
-use crate::config::ScriptEntry;
-use crate::session::BackgroundPid;
+use chrono::{DateTime, Utc};
 
-/// Build a Command with standard sesh env vars set.
+use crate::config::{EnvMode, HealthCheck, ScriptEntry, ScriptsConfig};
+use crate::notify;
+use crate::session::{self, BackgroundPid};
+
+/// Set from `[scripts] env_mode` / `pass` / `deny` at startup. When unset,
+/// because sesh.toml couldn't be read, scripts get only [`CLEAN_BASELINE`]
+/// rather than everything sesh has.
+static ENV_POLICY: OnceLock<EnvPolicy> = OnceLock::new();
+
+static CLOSED_POLICY: EnvPolicy = EnvPolicy {
+    mode: EnvMode::Clean,
+    pass: Vec::new(),
+    deny: Vec::new(),
+};
+
+/// Variables every script gets in `clean` mode so shells and toolchains work.
+const CLEAN_BASELINE: &[&str] = &["PATH", "HOME", "USER", "LOGNAME", "SHELL", "TERM", "LANG", "TMPDIR"];
+
+struct EnvPolicy {
+    mode: EnvMode,
+    pass: Vec<String>,
+    deny: Vec<String>,
+}
+
+pub fn set_env_policy(config: &ScriptsConfig) {
+    let _ = ENV_POLICY.set(EnvPolicy {
+        mode: config.env_mode,
+        pass: config.pass.clone(),
+        deny: config.deny.clone(),
+    });
+}
+
+impl EnvPolicy {
+    /// Whether `name` from sesh's own environment reaches scripts.
+    fn passes(&self, name: &str) -> bool {
+        let matches = |pattern: &String| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == pattern,
+        };
+        if self.deny.iter().any(matches) {
+            return false;
+        }
+        match self.mode {
+            EnvMode::Inherit => true,
+            EnvMode::Clean => CLEAN_BASELINE.contains(&name) || self.pass.iter().any(matches),
+        }
+    }
+}
+
+/// Build a Command for `entry` with its interpreter, working directory and
+/// env, plus the standard sesh env vars.
 fn base_command(
+    entry: &ScriptEntry,
     script_path: &Path,
     cwd: &Path,
     session_name: &str,
@@ -18,15 +71,66 @@ fn base_command(
     repo_names: &[String],
 ) -> Command {
     let repos_csv = repo_names.join(",");
-    let mut cmd = Command::new(script_path);
+    let mut interpreter = entry.interpreter.as_deref().unwrap_or_default().split_whitespace();
+    let mut cmd = match (&entry.command, interpreter.next()) {
+        (Some(command), program) => {
+            let mut cmd = Command::new(program.unwrap_or("sh"));
+            cmd.args(interpreter).arg("-c").arg(command);
+            cmd
+        }
+        (None, Some(program)) => {
+            let mut cmd = Command::new(program);
+            cmd.args(interpreter).arg(script_path);
+            cmd
+        }
+        (None, None) => Command::new(script_path),
+    };
+    let policy = ENV_POLICY.get().unwrap_or(&CLOSED_POLICY);
+    if policy.mode == EnvMode::Clean {
+        cmd.env_clear();
+    }
+    for (key, val) in std::env::vars_os() {
+        if !key.to_str().is_some_and(|name| policy.passes(name)) {
+            cmd.env_remove(&key);
+        } else if policy.mode == EnvMode::Clean {
+            cmd.env(&key, &val);
+        }
+    }
+    let cwd = match &entry.cwd {
+        Some(dir) => cwd.join(dir),
+        None => cwd.to_path_buf(),
+    };
     cmd.current_dir(cwd)
+        .envs(&entry.env)
         .env("SESH_SESSION", session_name)
         .env("SESH_BRANCH", branch)
         .env("SESH_REPOS", &repos_csv);
     cmd
 }
 
-/// Run a script entry as a foreground process (blocking).
+/// A command sesh runs on its own, such as a dependency install, built like an
+/// inline `command` entry so it gets the SESH_* env and `[scripts] env_mode`.
+pub fn shell_command(command: &str, cwd: &Path, session_name: &str, branch: &str, repo_names: &[String]) -> Command {
+    let entry = ScriptEntry {
+        command: Some(command.to_string()),
+        ..Default::default()
+    };
+    base_command(&entry, cwd, cwd, session_name, branch, repo_names)
+}
+
+/// Where a foreground script's output is copied while it streams to the
+/// terminal: `<session>/logs/<label>.log`, like a background script's.
+pub struct ScriptLog<'a> {
+    pub log_dir: &'a Path,
+    pub label: &'a str,
+    pub timestamps: bool,
+}
+
+/// Run a script entry as a foreground process (blocking). With `log`, its
+/// stdout and stderr are teed into the log file while still streaming to the
+/// terminal. When sesh's stdout is a terminal they go through a pty rather
+/// than pipes, so colors, progress bars and TTY prompts keep working.
+#[allow(clippy::too_many_arguments)]
 pub fn run_script_entry(
     label: &str,
     entry: &ScriptEntry,
@@ -36,28 +140,82 @@ pub fn run_script_entry(
     branch: &str,
     repo_names: &[String],
     extra_env: &[(&str, &str)],
+    log: Option<&ScriptLog>,
 ) -> Result<()> {
-    if !script_path.exists() {
+    if entry.command.is_none() && !script_path.exists() {
         bail!("{} script not found: {}", label, script_path.display());
     }
 
-    let mut cmd = base_command(script_path, cwd, session_name, branch, repo_names);
+    let mut cmd = base_command(entry, script_path, cwd, session_name, branch, repo_names);
     for &(key, val) in extra_env {
         cmd.env(key, val);
     }
-    cmd.stdin(std::process::Stdio::inherit())
-        .stdout(std::process::Stdio::inherit())
-        .stderr(std::process::Stdio::inherit());
+    let log_file = log.map(|log| open_log(log.log_dir, log.label)).transpose()?;
+    let (primary, replica) = match &log_file {
+        Some(_) if std::io::stdout().is_terminal() => open_pty().unzip(),
+        _ => (None, None),
+    };
+    cmd.stdin(std::process::Stdio::inherit());
+    match &replica {
+        Some(replica) => {
+            cmd.stdout(replica.try_clone()?).stderr(replica.try_clone()?);
+        }
+        None => {
+            let output = if log_file.is_some() { std::process::Stdio::piped } else { std::process::Stdio::inherit };
+            cmd.stdout(output()).stderr(output());
+        }
+    }
+    // A timeout has to take down what the script started too (`pnpm install`'s
+    // workers, say), so such scripts get their own process group. Without
+    // one they stay in sesh's group, keeping the terminal's Ctrl-C.
+    if entry.timeout_secs.is_some() {
+        cmd.process_group(0);
+    }
 
-    let status = cmd
-        .status()
+    let mut child = cmd
+        .spawn()
         .with_context(|| format!("failed to execute {} script: {}", label, script_path.display()))?;
+    // Only the script may hold the pty's replica side, so reading the primary
+    // side ends once it exits
+    drop(cmd);
+    drop(replica);
+    let mut copiers = Vec::new();
+    if let (Some(file), Some(log)) = (log_file, log) {
+        let file = Arc::new(Mutex::new(file));
+        let prefix = log.timestamps.then(|| log.label.to_string());
+        if let Some(primary) = primary {
+            copiers.push(thread::spawn(move || tee(primary, std::io::stdout(), &file, prefix.as_deref())));
+        } else {
+            if let Some(out) = child.stdout.take() {
+                let (file, prefix) = (file.clone(), prefix.clone());
+                copiers.push(thread::spawn(move || tee(out, std::io::stdout(), &file, prefix.as_deref())));
+            }
+            if let Some(err) = child.stderr.take() {
+                copiers.push(thread::spawn(move || tee(err, std::io::stderr(), &file, prefix.as_deref())));
+            }
+        }
+    }
+    let status = match entry.timeout_secs {
+        Some(secs) => match wait_timeout(&mut child, Duration::from_secs(secs))? {
+            Some(status) => status,
+            None => {
+                kill_process_group(&mut child);
+                bail!("{} script '{}' timed out after {}s and was killed", label, entry.name(), secs);
+            }
+        },
+        None => child.wait()?,
+    };
+    // Anything the script left running may hold the pipes open; don't wait on it
+    let deadline = std::time::Instant::now() + Duration::from_secs(1);
+    while copiers.iter().any(|c| !c.is_finished()) && std::time::Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
 
     if !status.success() {
         bail!(
             "{} script '{}' exited with status: {}",
             label,
-            entry.path,
+            entry.name(),
             status.code().map(|c| c.to_string()).unwrap_or_else(|| "signal".to_string())
         );
     }
@@ -65,8 +223,290 @@ pub fn run_script_entry(
     Ok(())
 }
 
-/// Spawn a script as a background process. Returns the PID.
-/// stdout/stderr are redirected to `<log_dir>/<label>.log`.
+/// Open a pty sized like sesh's terminal, returning its primary and replica
+/// sides. Output post-processing is turned off so the log gets the script's
+/// own line endings; the real terminal still translates them.
+fn open_pty() -> Option<(File, File)> {
+    use std::os::fd::FromRawFd;
+
+    let (mut primary, mut replica) = (-1, -1);
+    // SAFETY: openpty only writes the two fds; the winsize and termios
+    // pointers are to initialized locals or null
+    unsafe {
+        let mut size: libc::winsize = std::mem::zeroed();
+        let size_ptr = if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) == 0 {
+            &size as *const libc::winsize
+        } else {
+            std::ptr::null()
+        };
+        if libc::openpty(&mut primary, &mut replica, std::ptr::null_mut(), std::ptr::null(), size_ptr) != 0 {
+            return None;
+        }
+        let mut termios: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(replica, &mut termios) == 0 {
+            termios.c_oflag &= !libc::OPOST;
+            libc::tcsetattr(replica, libc::TCSANOW, &termios);
+        }
+        Some((File::from_raw_fd(primary), File::from_raw_fd(replica)))
+    }
+}
+
+/// Open `<log_dir>/<label>.log` for appending. A rerun (`sesh restart`,
+/// `sesh setup`) keeps the earlier output above a marker.
+fn open_log(log_dir: &Path, label: &str) -> Result<File> {
+    fs::create_dir_all(log_dir)
+        .with_context(|| format!("failed to create log dir: {}", log_dir.display()))?;
+    let log_path = log_dir.join(format!("{}.log", label));
+    let mut log_file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .with_context(|| format!("failed to open log file: {}", log_path.display()))?;
+    if log_file.metadata().is_ok_and(|m| m.len() > 0) {
+        let stamp = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        writeln!(log_file, "--- restarted at {} ---", stamp)?;
+    }
+    Ok(log_file)
+}
+
+/// Copy `from` to `to` as it arrives, so prompts without a newline still
+/// show, and append each complete line to `log`, prefixed like
+/// [`run_log_prefixer`] when `prefix` is set.
+fn tee(mut from: impl Read, mut to: impl Write, log: &Mutex<File>, prefix: Option<&str>) {
+    let mut buf = [0u8; 8192];
+    let mut pending = Vec::new();
+    loop {
+        let n = match from.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        let _ = to.write_all(&buf[..n]);
+        let _ = to.flush();
+        pending.extend_from_slice(&buf[..n]);
+        if let Some(end) = pending.iter().rposition(|&b| b == b'\n') {
+            let lines: Vec<u8> = pending.drain(..=end).collect();
+            write_log_lines(log, &lines, prefix);
+        }
+    }
+    if !pending.is_empty() {
+        pending.push(b'\n');
+        write_log_lines(log, &pending, prefix);
+    }
+}
+
+fn write_log_lines(log: &Mutex<File>, lines: &[u8], prefix: Option<&str>) {
+    let Ok(mut file) = log.lock() else { return };
+    let _ = match prefix {
+        Some(label) => String::from_utf8_lossy(lines)
+            .lines()
+            .try_for_each(|line| writeln!(file, "{}", prefixed_line(label, line))),
+        None => file.write_all(lines),
+    };
+}
+
+/// A log line as `timestamp_logs` writes it: RFC 3339 timestamp, label, text.
+fn prefixed_line(label: &str, line: &str) -> String {
+    let stamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    format!("{} [{}] {}", stamp, label, line.trim_end_matches(['\n', '\r']))
+}
+
+/// SIGKILL a timed-out child spawned with `process_group(0)`, along with
+/// everything it started, and reap it.
+fn kill_process_group(child: &mut std::process::Child) {
+    if !signal_pid(child.id(), true, "-KILL") {
+        let _ = child.kill();
+    }
+    let _ = child.wait();
+}
+
+/// Wait for `child` to exit, giving up (with `None`) after `timeout`.
+fn wait_timeout(child: &mut std::process::Child, timeout: Duration) -> Result<Option<std::process::ExitStatus>> {
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if std::time::Instant::now() >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Run `run(i)` for each of `entries`, each tagged with the list it was
+/// declared in (`None` for the global one, which comes first). Without any
+/// `group` or `depends_on` they run one at a time, in order. Otherwise each
+/// starts as soon as its dependencies have finished, up to `jobs` at once:
+/// the entries in the groups it `depends_on`, or without `depends_on` the
+/// entry declared before it in its list (a repo's first entry waits for the
+/// global list). Groups no entry here belongs to are already satisfied.
+/// After a failure nothing new starts, and the first error is returned once
+/// the running entries are done.
+pub fn run_scheduled(
+    entries: &[(Option<&str>, &ScriptEntry)],
+    jobs: usize,
+    run: &(dyn Fn(usize) -> Result<()> + Sync),
+) -> Result<()> {
+    if entries.iter().all(|(_, e)| e.group.is_none() && e.depends_on.is_empty()) {
+        return (0..entries.len()).try_for_each(run);
+    }
+    let deps = dependencies(entries)?;
+    let mut started = vec![false; entries.len()];
+    let mut done = vec![false; entries.len()];
+    let (tx, rx) = mpsc::channel();
+    thread::scope(|scope| {
+        let mut running = 0;
+        let mut failure = None;
+        loop {
+            for i in 0..entries.len() {
+                if failure.is_some() || running >= jobs.max(1) {
+                    break;
+                }
+                if !started[i] && deps[i].iter().all(|&d| done[d]) {
+                    started[i] = true;
+                    running += 1;
+                    let tx = tx.clone();
+                    scope.spawn(move || {
+                        let _ = tx.send((i, run(i)));
+                    });
+                }
+            }
+            if running == 0 {
+                break;
+            }
+            let (i, result) = rx.recv().expect("a running step always reports back");
+            running -= 1;
+            match result {
+                Ok(()) => done[i] = true,
+                Err(e) => {
+                    failure.get_or_insert(e);
+                }
+            }
+        }
+        failure.map_or(Ok(()), Err)
+    })
+}
+
+/// For each entry, the entries it waits for, rejecting circular `depends_on`.
+fn dependencies(entries: &[(Option<&str>, &ScriptEntry)]) -> Result<Vec<Vec<usize>>> {
+    let deps: Vec<Vec<usize>> = entries
+        .iter()
+        .enumerate()
+        .map(|(i, (list, entry))| {
+            if !entry.depends_on.is_empty() {
+                return (0..entries.len())
+                    .filter(|&j| j != i && entries[j].1.group.as_ref().is_some_and(|g| entry.depends_on.contains(g)))
+                    .collect();
+            }
+            match (0..i).rev().find(|&j| entries[j].0 == *list) {
+                Some(previous) => vec![previous],
+                None if list.is_some() => (0..entries.len()).filter(|&j| entries[j].0.is_none()).collect(),
+                None => Vec::new(),
+            }
+        })
+        .collect();
+
+    // Peel off entries whose dependencies are all peeled; any left form a cycle
+    let mut resolved = vec![false; entries.len()];
+    while let Some(i) = (0..entries.len()).find(|&i| !resolved[i] && deps[i].iter().all(|&d| resolved[d])) {
+        resolved[i] = true;
+    }
+    let stuck: Vec<&str> = (0..entries.len()).filter(|&i| !resolved[i]).map(|i| entries[i].1.name()).collect();
+    if !stuck.is_empty() {
+        bail!("setup scripts depend on each other in a cycle: {}", stuck.join(", "));
+    }
+    Ok(deps)
+}
+
+/// Probe `check` (with its `url` already rendered) every second until it
+/// passes, failing once its timeout has gone by. Command checks run like the
+/// entry itself would, with its env and working directory.
+#[allow(clippy::too_many_arguments)]
+pub fn wait_healthy(
+    check: &HealthCheck,
+    entry: &ScriptEntry,
+    cwd: &Path,
+    session_name: &str,
+    branch: &str,
+    repo_names: &[String],
+    extra_env: &[(&str, &str)],
+) -> Result<()> {
+    let secs = check.timeout_secs.unwrap_or(HealthCheck::DEFAULT_TIMEOUT_SECS);
+    let deadline = std::time::Instant::now() + Duration::from_secs(secs);
+    loop {
+        let healthy = match (&check.url, &check.command) {
+            (Some(url), _) => http_ok(url),
+            (None, Some(command)) => {
+                let probe = ScriptEntry {
+                    command: Some(command.clone()),
+                    interpreter: None,
+                    ..entry.clone()
+                };
+                let mut cmd = base_command(&probe, Path::new(""), cwd, session_name, branch, repo_names);
+                for &(key, val) in extra_env {
+                    cmd.env(key, val);
+                }
+                cmd.stdin(std::process::Stdio::null())
+                    .stdout(std::process::Stdio::null())
+                    .stderr(std::process::Stdio::null())
+                    .process_group(0);
+                let mut child = cmd
+                    .spawn()
+                    .with_context(|| format!("failed to run health check for '{}'", entry.name()))?;
+                let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+                match wait_timeout(&mut child, remaining.max(Duration::from_secs(1)))? {
+                    Some(status) => status.success(),
+                    None => {
+                        kill_process_group(&mut child);
+                        false
+                    }
+                }
+            }
+            (None, None) => bail!("health_check for '{}' needs a `url` or a `command`", entry.name()),
+        };
+        if healthy {
+            return Ok(());
+        }
+        if std::time::Instant::now() >= deadline {
+            bail!("background script '{}' did not become healthy within {}s", entry.name(), secs);
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+/// Whether a GET of `url` answers with a 2xx or 3xx status within a few
+/// seconds. Runs on its own thread with a private runtime, since callers may
+/// be on a tokio worker or one of [`run_scheduled`]'s threads.
+fn http_ok(url: &str) -> bool {
+    let url = url.to_string();
+    thread::spawn(move || {
+        let Ok(runtime) = tokio::runtime::Builder::new_current_thread().enable_all().build() else {
+            return false;
+        };
+        runtime.block_on(async {
+            let Ok(client) = reqwest::Client::builder()
+                .connect_timeout(Duration::from_secs(2))
+                .timeout(Duration::from_secs(5))
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+            else {
+                return false;
+            };
+            client.get(&url).send().await.is_ok_and(|resp| {
+                let status = resp.status();
+                status.is_success() || status.is_redirection()
+            })
+        })
+    })
+    .join()
+    .unwrap_or(false)
+}
+
+/// Spawn a script as a background process in its own process group. Returns
+/// the PID, which is also the process group ID. stdout/stderr are redirected
+/// to `<log_dir>/<label>.log`, optionally through a `sesh __log-prefix`
+/// process that stamps each line with time and label.
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_background_script(
     entry: &ScriptEntry,
     script_path: &Path,
@@ -77,52 +517,163 @@ pub fn spawn_background_script(
     branch: &str,
     repo_names: &[String],
     extra_env: &[(&str, &str)],
+    timestamps: bool,
 ) -> Result<u32> {
-    if !script_path.exists() {
+    if entry.command.is_none() && !script_path.exists() {
         bail!("background script not found: {}", script_path.display());
     }
 
-    fs::create_dir_all(log_dir)
-        .with_context(|| format!("failed to create log dir: {}", log_dir.display()))?;
-
     let log_path = log_dir.join(format!("{}.log", label));
-    let log_file = File::create(&log_path)
-        .with_context(|| format!("failed to create log file: {}", log_path.display()))?;
+    let log_file = open_log(log_dir, label)?;
     let log_stderr = log_file
         .try_clone()
         .context("failed to clone log file handle")?;
 
-    let mut cmd = base_command(script_path, cwd, session_name, branch, repo_names);
+    // The log dir is always <session>/logs
+    let session_dir = log_dir.parent().unwrap_or(log_dir);
+    let pidfile = session::pidfile(session_dir, label);
+    let portfile = session::portfile(session_dir, label);
+    if let Some(run_dir) = pidfile.parent() {
+        fs::create_dir_all(run_dir)
+            .with_context(|| format!("failed to create run dir: {}", run_dir.display()))?;
+    }
+    // Leftovers from an earlier run would point at the wrong process
+    let _ = fs::remove_file(&pidfile);
+    let _ = fs::remove_file(&portfile);
+
+    let mut cmd = base_command(entry, script_path, cwd, session_name, branch, repo_names);
     for &(key, val) in extra_env {
         cmd.env(key, val);
     }
-    cmd.stdin(std::process::Stdio::null())
-        .stdout(log_file)
-        .stderr(log_stderr);
+    cmd.env("SESH_PIDFILE", &pidfile).env("SESH_PORTFILE", &portfile);
+    cmd.stdin(std::process::Stdio::null()).process_group(0);
+
+    if !timestamps {
+        cmd.stdout(log_file).stderr(log_stderr);
+        let child = cmd
+            .spawn()
+            .with_context(|| format!("failed to spawn background script: {}", entry.name()))?;
+        spawn_exit_watcher(session_dir, label, child.id());
+        return Ok(child.id());
+    }
+
+    // Route both streams through one pipe into the prefixer
+    let (reader, writer) = std::io::pipe().context("failed to create log pipe")?;
+    let writer_err = writer.try_clone().context("failed to clone log pipe")?;
+    cmd.stdout(writer).stderr(writer_err);
 
     let child = cmd
         .spawn()
-        .with_context(|| format!("failed to spawn background script: {}", entry.path))?;
+        .with_context(|| format!("failed to spawn background script: {}", entry.name()))?;
+    // Release our copies of the write end so the prefixer sees EOF when the script exits
+    drop(cmd);
 
+    let exe = std::env::current_exe().context("failed to locate sesh executable")?;
+    Command::new(exe)
+        .args(["__log-prefix", label])
+        .arg(&log_path)
+        .stdin(reader)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to spawn log prefixer for {}", label))?;
+
+    spawn_exit_watcher(session_dir, label, child.id());
     Ok(child.id())
 }
 
+/// With desktop notifications on, start a detached `sesh __watch-exit` for a
+/// freshly spawned background script. Best effort: a script without a watcher
+/// just exits silently.
+fn spawn_exit_watcher(session_dir: &Path, label: &str, pid: u32) {
+    if !notify::desktop_enabled() {
+        return;
+    }
+    let Ok(exe) = std::env::current_exe() else {
+        return;
+    };
+    let _ = Command::new(exe)
+        .arg("__watch-exit")
+        .arg(session_dir)
+        .args([label, &pid.to_string()])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .process_group(0)
+        .spawn();
+}
+
+/// Wait for background process `pid` to exit, then show a desktop
+/// notification unless sesh stopped it: `sesh stop` and restarts drop or
+/// replace its `background_pids.json` entry right after killing it, and a
+/// script that handed off to the PID in its `$SESH_PIDFILE` is adopted, not
+/// dead. Runs as the hidden `sesh __watch-exit` subcommand.
+pub fn watch_exit(session_dir: &Path, label: &str, pid: u32) -> Result<()> {
+    while is_process_alive(pid) {
+        thread::sleep(Duration::from_secs(2));
+    }
+    thread::sleep(Duration::from_secs(3));
+
+    let mut pids = session::load_background_pids(session_dir);
+    let Some(bp) = pids.iter_mut().find(|bp| bp.label == label && bp.pid == pid && !bp.dead) else {
+        return Ok(());
+    };
+    if reconcile_background_pids(session_dir, std::slice::from_mut(bp)) != [ProcessState::Dead] {
+        return Ok(());
+    }
+    let session_name = session_dir.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    notify::show_desktop(
+        &format!("{} exited", label),
+        &format!(
+            "Background script in session {} stopped unexpectedly. See `sesh log -s {} {}`.",
+            session_name, session_name, label
+        ),
+    );
+    Ok(())
+}
+
+/// Copy stdin to `log_path`, prefixing each line with an RFC 3339 timestamp and label.
+/// Runs as the hidden `sesh __log-prefix` subcommand.
+pub fn run_log_prefixer(label: &str, log_path: &Path) -> Result<()> {
+    use std::io::{BufRead, Write};
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .with_context(|| format!("failed to open log file: {}", log_path.display()))?;
+
+    let mut stdin = std::io::stdin().lock();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        if stdin.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        writeln!(file, "{}", prefixed_line(label, &String::from_utf8_lossy(&buf)))?;
+        file.flush()?;
+    }
+
+    Ok(())
+}
+
 /// Kill background processes: SIGTERM first, wait up to 5s, then SIGKILL stragglers.
+/// Processes that lead their own group are killed along with everything they started.
+/// Entries already marked dead are left alone.
 pub fn kill_background_pids(pids: &[BackgroundPid]) {
-    use std::process::Command as Cmd;
-
-    // Send SIGTERM to all
-    for bp in pids {
-        let _ = Cmd::new("kill")
-            .arg("-TERM")
-            .arg(bp.pid.to_string())
-            .output();
+    let pids: Vec<&BackgroundPid> = pids.iter().filter(|bp| !bp.dead).collect();
+    // Send SIGTERM to all; a paused process only acts on it once continued
+    for bp in &pids {
+        signal(bp, "-TERM");
+        if bp.paused {
+            signal(bp, "-CONT");
+        }
     }
 
     // Wait up to 5 seconds for processes to exit
     let deadline = std::time::Instant::now() + Duration::from_secs(5);
     loop {
-        let any_alive = pids.iter().any(|bp| is_process_alive(bp.pid));
+        let any_alive = pids.iter().any(|bp| signal(bp, "-0"));
         if !any_alive || std::time::Instant::now() >= deadline {
             break;
         }
@@ -130,17 +681,44 @@ pub fn kill_background_pids(pids: &[BackgroundPid]) {
     }
 
     // SIGKILL any survivors
-    for bp in pids {
-        if is_process_alive(bp.pid) {
-            let _ = Cmd::new("kill")
-                .arg("-KILL")
-                .arg(bp.pid.to_string())
-                .output();
+    for bp in &pids {
+        if signal(bp, "-0") {
+            signal(bp, "-KILL");
+        }
+    }
+}
+
+/// Pause (`SIGSTOP`) or resume (`SIGCONT`) live background processes, marking
+/// them accordingly. Returns how many changed.
+pub fn set_paused(pids: &mut [BackgroundPid], paused: bool) -> usize {
+    let sig = if paused { "-STOP" } else { "-CONT" };
+    let mut changed = 0;
+    for bp in pids.iter_mut().filter(|bp| !bp.dead && bp.paused != paused) {
+        if signal(bp, sig) {
+            bp.paused = paused;
+            changed += 1;
         }
     }
+    changed
+}
+
+/// Send `sig` to a background process, or to its whole group when it leads
+/// one. Returns whether the signal was delivered (with `-0`: whether anything
+/// is still alive).
+fn signal(bp: &BackgroundPid, sig: &str) -> bool {
+    signal_pid(bp.pid, bp.process_group, sig)
+}
+
+fn signal_pid(pid: u32, process_group: bool, sig: &str) -> bool {
+    let target = if process_group { format!("-{}", pid) } else { pid.to_string() };
+    Command::new("kill")
+        .args([sig, "--", &target])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
 }
 
-fn is_process_alive(pid: u32) -> bool {
+pub fn is_process_alive(pid: u32) -> bool {
     // kill -0 checks if process exists without sending a signal
     Command::new("kill")
         .arg("-0")
@@ -149,3 +727,206 @@ fn is_process_alive(pid: u32) -> bool {
         .map(|o| o.status.success())
         .unwrap_or(false)
 }
+
+/// How a background process entry checked out against the running system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessState {
+    Running,
+    /// The original process is gone, but the PID its script wrote to
+    /// `$SESH_PIDFILE` is alive and is now tracked instead.
+    Adopted,
+    Dead,
+}
+
+/// Bring `pids` in line with reality, e.g. after sesh was killed or the machine
+/// rebooted. A live entry stays; a gone one adopts the live PID its script wrote
+/// to `$SESH_PIDFILE`, or else is marked dead. Entries started before the last
+/// boot count as gone even if their PID has been reused since.
+pub fn reconcile_background_pids(session_dir: &Path, pids: &mut [BackgroundPid]) -> Vec<ProcessState> {
+    let boot = boot_time();
+    let since_boot = |t: Option<DateTime<Utc>>| match (t, boot) {
+        (Some(t), Some(boot)) => t >= boot,
+        _ => true,
+    };
+
+    pids.iter_mut()
+        .map(|bp| {
+            if bp.dead {
+                return ProcessState::Dead;
+            }
+            if since_boot(bp.started_at) && signal(bp, "-0") {
+                return ProcessState::Running;
+            }
+            let pidfile = session::pidfile(session_dir, &bp.label);
+            let written = fs::metadata(&pidfile)
+                .and_then(|m| m.modified())
+                .ok()
+                .map(DateTime::<Utc>::from)
+                .filter(|&t| since_boot(Some(t)));
+            let adopted = written.and_then(|t| {
+                let pid = fs::read_to_string(&pidfile).ok()?.trim().parse::<u32>().ok()?;
+                is_process_alive(pid).then_some((pid, t))
+            });
+            match adopted {
+                Some((pid, written)) => {
+                    bp.pid = pid;
+                    bp.process_group = false;
+                    bp.started_at = Some(written);
+                    ProcessState::Adopted
+                }
+                None => {
+                    bp.dead = true;
+                    ProcessState::Dead
+                }
+            }
+        })
+        .collect()
+}
+
+/// The port a background script recorded in `$SESH_PORTFILE`, if any.
+pub fn recorded_port(session_dir: &Path, label: &str) -> Option<u16> {
+    fs::read_to_string(session::portfile(session_dir, label))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// When the machine booted: `btime` in `/proc/stat` on Linux, `kern.boottime` on macOS.
+fn boot_time() -> Option<DateTime<Utc>> {
+    if let Ok(stat) = fs::read_to_string("/proc/stat") {
+        let secs = stat.lines().find_map(|l| l.strip_prefix("btime "))?.trim().parse().ok()?;
+        return DateTime::from_timestamp(secs, 0);
+    }
+    // "{ sec = 1700000000, usec = 0 } Tue Nov 14 22:13:20 2023"
+    let out = Command::new("sysctl").args(["-n", "kern.boottime"]).output().ok()?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    let secs = text
+        .split("sec = ")
+        .nth(1)?
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()?;
+    DateTime::from_timestamp(secs, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_policy_filters_variables() {
+        let policy = EnvPolicy {
+            mode: EnvMode::Clean,
+            pass: vec!["NODE_ENV".into(), "AWS_*".into()],
+            deny: vec!["AWS_SECRET_ACCESS_KEY".into()],
+        };
+        assert!(policy.passes("PATH"));
+        assert!(policy.passes("NODE_ENV"));
+        assert!(policy.passes("AWS_REGION"));
+        assert!(!policy.passes("AWS_SECRET_ACCESS_KEY"));
+        assert!(!policy.passes("GITHUB_TOKEN"));
+
+        let policy = EnvPolicy { mode: EnvMode::Inherit, ..policy };
+        assert!(policy.passes("GITHUB_TOKEN"));
+        assert!(!policy.passes("AWS_SECRET_ACCESS_KEY"));
+    }
+
+    #[test]
+    fn scheduled_entries_wait_for_their_groups() {
+        let entry = |command: &str, group: Option<&str>, depends_on: &[&str]| ScriptEntry {
+            command: Some(command.to_string()),
+            group: group.map(String::from),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            ..toml::from_str("").unwrap()
+        };
+        let migrate = entry("migrate", Some("db"), &[]);
+        let api = entry("api", None, &["db", "absent"]);
+        let web = entry("web", None, &["db"]);
+        let entries = [(Some("api"), &api), (Some("web"), &web), (None, &migrate)];
+
+        let order = std::sync::Mutex::new(Vec::new());
+        run_scheduled(&entries, 4, &|i| {
+            order.lock().unwrap().push(entries[i].1.name().to_string());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(order.into_inner().unwrap()[0], "migrate");
+
+        let schema = entry("schema", Some("db"), &["cache"]);
+        let redis = entry("redis", Some("cache"), &["db"]);
+        let cycle = [(None, &schema), (None, &redis)];
+        assert!(run_scheduled(&cycle, 4, &|_| Ok(())).is_err());
+    }
+
+    #[test]
+    fn command_entries_run_through_the_shell() {
+        let entry: ScriptEntry = toml::from_str(r#"command = "pnpm dev --port 3000""#).unwrap();
+        let args = |cmd: &Command| cmd.get_args().map(|a| a.to_string_lossy().to_string()).collect::<Vec<_>>();
+
+        let cmd = base_command(&entry, Path::new("/work"), Path::new("/work/api"), "s", "b", &[]);
+        assert_eq!(cmd.get_program(), "sh");
+        assert_eq!(args(&cmd), ["-c", "pnpm dev --port 3000"]);
+        assert_eq!(cmd.get_current_dir(), Some(Path::new("/work/api")));
+
+        let entry = ScriptEntry { interpreter: Some("bash -e".to_string()), ..entry };
+        let cmd = base_command(&entry, Path::new("/work"), Path::new("/work/api"), "s", "b", &[]);
+        assert_eq!(cmd.get_program(), "bash");
+        assert_eq!(args(&cmd), ["-e", "-c", "pnpm dev --port 3000"]);
+    }
+
+    #[test]
+    fn tee_copies_output_and_logs_whole_lines() {
+        let dir = std::env::temp_dir().join(format!("sesh-tee-test-{}", std::process::id()));
+        let log = Mutex::new(open_log(&dir, "setup").unwrap());
+        let mut shown = Vec::new();
+        tee(&b"building\nPassword: "[..], &mut shown, &log, None);
+        assert_eq!(shown, b"building\nPassword: ");
+        let logged = fs::read_to_string(dir.join("setup.log")).unwrap();
+        assert_eq!(logged, "building\nPassword: \n");
+
+        write_log_lines(&log, b"one\ntwo\n", Some("api"));
+        let logged = fs::read_to_string(dir.join("setup.log")).unwrap();
+        let stamped: Vec<&str> = logged.lines().skip(2).collect();
+        assert_eq!(stamped.len(), 2);
+        assert!(stamped[0].ends_with(" [api] one") && stamped[1].ends_with(" [api] two"));
+
+        // A rerun appends below a marker instead of truncating
+        drop(open_log(&dir, "setup").unwrap());
+        let logged = fs::read_to_string(dir.join("setup.log")).unwrap();
+        assert!(logged.starts_with("building\n"));
+        assert!(logged.lines().last().unwrap().starts_with("--- restarted at "));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn ungrouped_entries_keep_declaration_order() {
+        let entry = |command: &str, group: Option<&str>| ScriptEntry {
+            command: Some(command.to_string()),
+            group: group.map(String::from),
+            ..toml::from_str("").unwrap()
+        };
+        let global = entry("global", None);
+        let install = entry("install", None);
+        let build = entry("build", None);
+        let migrate = entry("migrate", Some("db"));
+        let entries = [(None, &global), (Some("api"), &install), (Some("api"), &build), (Some("web"), &migrate)];
+
+        let order = std::sync::Mutex::new(Vec::new());
+        run_scheduled(&entries, 4, &|i| {
+            // The slower `install` would finish last if `build` didn't wait for it
+            if entries[i].1.name() == "install" {
+                thread::sleep(Duration::from_millis(50));
+            }
+            order.lock().unwrap().push(entries[i].1.name().to_string());
+            Ok(())
+        })
+        .unwrap();
+        let order = order.into_inner().unwrap();
+        let position = |name: &str| order.iter().position(|n| n == name).unwrap();
+        assert!(position("global") < position("install"));
+        assert!(position("install") < position("build"));
+        assert!(position("global") < position("migrate"));
+    }
+}
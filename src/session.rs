@@ -1,10 +1,15 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
 
+use crate::config::IndexConfig;
+use crate::index;
+use crate::state::{self, CorruptStateFile};
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct IssueContext {
     pub provider: String,
@@ -16,8 +21,14 @@ pub struct IssueContext {
     pub labels: Vec<String>,
 }
 
+/// Current `session.json` schema version. Bump it and add a step to
+/// `migrate_session_value` whenever a change needs more than serde defaults.
+pub const SESSION_SCHEMA_VERSION: u32 = 2;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionInfo {
+    /// Schema version of this file (see `SESSION_SCHEMA_VERSION`).
+    pub version: u32,
     pub name: String,
     pub branch: String,
     pub repos: Vec<SessionRepo>,
@@ -27,6 +38,50 @@ pub struct SessionInfo {
     pub issue: Option<IssueContext>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub base_branch: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ci_runs: Vec<CiRun>,
+    /// Named URLs (preview deploys, tunnels) registered with `sesh url add`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub urls: BTreeMap<String, String>,
+    /// Set by `sesh listen` when the session's ticket is closed or reassigned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cleanup: Option<CleanupMark>,
+    /// Set by `sesh archive`: services are stopped but worktrees are kept.
+    /// Cleared when `sesh activate` brings the session back.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Free-form labels added with `sesh tag add`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Key/value fields set with `sesh meta set`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    /// Set by `sesh pin`: `sesh prune` skips the session and `sesh stop`
+    /// requires `--force`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Ports allocated for `[session] ports`, by service name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub ports: BTreeMap<String, u16>,
+    /// URLs of PRs opened by `sesh pr`, by repo.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pull_requests: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanupMark {
+    pub reason: String,
+    pub marked_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A workflow run dispatched for a repo by `sesh pr`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CiRun {
+    pub repo: String,
+    pub workflow: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub dispatched_at: chrono::DateTime<chrono::Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,31 +89,223 @@ pub struct SessionRepo {
     pub name: String,
     pub worktree_path: PathBuf,
     pub original_repo_path: PathBuf,
+    /// Read-only repo checked out detached at the base branch (no session branch).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// Existing branch checked out for this repo when it differs from the
+    /// session branch (composed sessions). Never created or deleted by sesh.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// The session branch under this repo's `branch_template` or
+    /// `branch_prefix`, when that changes it.
+    /// Created and deleted by sesh like the plain session branch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefixed_branch: Option<String>,
+}
+
+impl SessionInfo {
+    /// The branch checked out in `repo`'s worktree.
+    pub fn branch_for<'a>(&'a self, repo: &'a SessionRepo) -> &'a str {
+        repo.branch
+            .as_deref()
+            .or(repo.prefixed_branch.as_deref())
+            .unwrap_or(&self.branch)
+    }
+
+    /// Environment for the session's scripts: `SESH_SCRATCH`, `SESH_TAGS`
+    /// (comma-separated), `SESH_META_<KEY>` per metadata field and
+    /// `SESH_PORT_<NAME>` per allocated port, the key upper-cased with `-` as `_`.
+    pub fn script_env(&self) -> Vec<(String, String)> {
+        let scratch = scratch_dir(&session_dir(&self.parent_dir, &self.name));
+        let mut env = vec![("SESH_SCRATCH".to_string(), scratch.display().to_string())];
+        if !self.tags.is_empty() {
+            env.push(("SESH_TAGS".to_string(), self.tags.join(",")));
+        }
+        for (key, value) in &self.metadata {
+            env.push((meta_env_var(key), value.clone()));
+        }
+        for (name, port) in &self.ports {
+            env.push((format!("SESH_PORT_{}", env_key(name)), port.to_string()));
+        }
+        env
+    }
+}
+
+fn meta_env_var(key: &str) -> String {
+    format!("SESH_META_{}", env_key(key))
+}
+
+fn env_key(key: &str) -> String {
+    key.to_ascii_uppercase().replace('-', "_")
+}
+
+/// Pick a port for each of `wanted` (service → first port to try): the first
+/// one at or above it that no other session holds and nothing is listening on.
+pub fn allocate_ports(parent_dir: &Path, wanted: &BTreeMap<String, u16>) -> BTreeMap<String, u16> {
+    let mut taken: HashSet<u16> = list_sessions(parent_dir)
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|s| s.ports.values().copied())
+        .collect();
+    let mut ports = BTreeMap::new();
+    for (name, &base) in wanted {
+        let free = (base..=u16::MAX).find(|&port| {
+            !taken.contains(&port) && std::net::TcpListener::bind(("127.0.0.1", port)).is_ok()
+        });
+        if let Some(port) = free {
+            taken.insert(port);
+            ports.insert(name.clone(), port);
+        }
+    }
+    ports
 }
 
 pub fn session_dir(parent_dir: &Path, session_name: &str) -> PathBuf {
     parent_dir.join(".sesh/sessions").join(session_name)
 }
 
+/// The session's `scratch/` directory for temporary artifacts, outside every
+/// worktree (`$SESH_SCRATCH`).
+pub fn scratch_dir(session_dir: &Path) -> PathBuf {
+    session_dir.join("scratch")
+}
+
+/// Create the session's `scratch/`, with a `.gitignore` that ignores all of it
+/// in case the session directory sits inside a git repository.
+pub fn ensure_scratch_dir(session_dir: &Path) -> anyhow::Result<PathBuf> {
+    let dir = scratch_dir(session_dir);
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(&gitignore, "*\n").with_context(|| format!("Failed to write {}", gitignore.display()))?;
+    }
+    Ok(dir)
+}
+
+/// The session being worked in: `$SESH_SESSION` (set for scripts and
+/// plugins), or the session whose directory contains the current directory.
+pub fn current_session(parent_dir: &Path) -> Option<SessionInfo> {
+    let name = std::env::var("SESH_SESSION").ok().filter(|s| !s.is_empty()).or_else(|| {
+        let cwd = std::env::current_dir().ok()?;
+        let rel = cwd.strip_prefix(parent_dir.join(".sesh/sessions")).ok()?;
+        rel.components()
+            .next()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+    })?;
+    load_session(&session_dir(parent_dir, &name)).ok()
+}
+
+/// Fail unless `path` lies strictly inside `parent_dir/.sesh/sessions`, so a
+/// corrupted session.json can't point `git worktree remove --force` (or a
+/// directory delete) at an arbitrary directory. Symlinks are resolved when
+/// both paths exist; otherwise the check is lexical and rejects `..`.
+pub fn ensure_managed_path(parent_dir: &Path, path: &Path) -> anyhow::Result<()> {
+    let root = parent_dir.join(".sesh/sessions");
+    let inside = |root: &Path, path: &Path| {
+        path != root
+            && path.starts_with(root)
+            && !path.components().any(|c| c == std::path::Component::ParentDir)
+    };
+    let managed = match (root.canonicalize(), path.canonicalize()) {
+        (Ok(root), Ok(path)) => inside(&root, &path),
+        _ => inside(&root, path),
+    };
+    if !managed {
+        anyhow::bail!(
+            "{} is not inside the sesh-managed directory {}",
+            path.display(),
+            root.display()
+        );
+    }
+    Ok(())
+}
+
 pub fn save_session(session_dir: &Path, info: &SessionInfo) -> anyhow::Result<()> {
     fs::create_dir_all(session_dir)
         .with_context(|| format!("Failed to create session directory: {}", session_dir.display()))?;
 
-    let json = serde_json::to_string_pretty(info).context("Failed to serialize session info")?;
     let path = session_dir.join("session.json");
-    fs::write(&path, json)
+    state::write_json(&path, info)
         .with_context(|| format!("Failed to write session file: {}", path.display()))?;
-
+    if let Some(parent_dir) = parent_of(session_dir)
+        && index::exists(parent_dir)
+        && let Err(e) = index::record(parent_dir, session_dir, info)
+    {
+        warn_index_unusable(&e);
+    }
     Ok(())
 }
 
+/// The parent dir a `.sesh/sessions/<name>` directory belongs to.
+fn parent_of(session_dir: &Path) -> Option<&Path> {
+    session_dir.ancestors().nth(3)
+}
+
 pub fn load_session(session_dir: &Path) -> anyhow::Result<SessionInfo> {
+    let (info, _) = load_session_versioned(session_dir)?;
+    Ok(info)
+}
+
+/// Load a session, migrating older schemas in memory. Also returns the version
+/// found on disk so callers can tell whether the file needs upgrading.
+/// Unparseable files fail with a `CorruptStateFile` context.
+pub fn load_session_versioned(session_dir: &Path) -> anyhow::Result<(SessionInfo, u32)> {
     let path = session_dir.join("session.json");
     let contents = fs::read_to_string(&path)
         .with_context(|| format!("Failed to read session file: {}", path.display()))?;
+    let mut value: serde_json::Value =
+        serde_json::from_str(&contents).context(CorruptStateFile(path.clone()))?;
+    let on_disk = migrate_session_value(&mut value)
+        .with_context(|| format!("Failed to migrate session file: {}", path.display()))?;
     let info: SessionInfo =
-        serde_json::from_str(&contents).context("Failed to parse session.json")?;
-    Ok(info)
+        serde_json::from_value(value).context(CorruptStateFile(path.clone()))?;
+    Ok((info, on_disk))
+}
+
+/// Rewrite a session file in the current schema. Returns the previous version
+/// if the file was upgraded.
+pub fn upgrade_session_file(session_dir: &Path) -> anyhow::Result<Option<u32>> {
+    let (info, on_disk) = load_session_versioned(session_dir)?;
+    if on_disk == SESSION_SCHEMA_VERSION {
+        return Ok(None);
+    }
+    save_session(session_dir, &info)?;
+    Ok(Some(on_disk))
+}
+
+/// Upgrade a raw `session.json` value step by step to `SESSION_SCHEMA_VERSION`.
+/// Returns the version the value started at.
+fn migrate_session_value(value: &mut serde_json::Value) -> anyhow::Result<u32> {
+    let obj = value
+        .as_object_mut()
+        .context("session.json is not a JSON object")?;
+    // Files written before versioning carry no `version` field
+    let start = obj
+        .get("version")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32)
+        .unwrap_or(1);
+    if start > SESSION_SCHEMA_VERSION {
+        anyhow::bail!(
+            "session.json has schema version {}, but this sesh only understands up to {}; upgrade sesh",
+            start,
+            SESSION_SCHEMA_VERSION
+        );
+    }
+
+    let mut version = start;
+    while version < SESSION_SCHEMA_VERSION {
+        match version {
+            // v1 → v2: introduces the `version` field; every other field added
+            // since the unversioned format has a serde default.
+            1 => {}
+            _ => unreachable!("no migration from session schema v{}", version),
+        }
+        version += 1;
+    }
+    obj.insert("version".to_string(), version.into());
+
+    Ok(start)
 }
 
 pub fn list_sessions(parent_dir: &Path) -> anyhow::Result<Vec<SessionInfo>> {
@@ -67,10 +314,26 @@ pub fn list_sessions(parent_dir: &Path) -> anyhow::Result<Vec<SessionInfo>> {
         return Ok(Vec::new());
     }
 
-    let mut sessions = Vec::new();
-    let entries = fs::read_dir(&sessions_dir)
+    let mut sessions = match index_enabled(parent_dir).then(|| index::list(parent_dir)) {
+        Some(Ok(sessions)) => sessions,
+        Some(Err(e)) => {
+            warn_index_unusable(&e);
+            scan_sessions(&sessions_dir)?
+        }
+        None => scan_sessions(&sessions_dir)?,
+    };
+
+    #[allow(clippy::unnecessary_sort_by)]
+    sessions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(sessions)
+}
+
+#[allow(clippy::collapsible_if)]
+fn scan_sessions(sessions_dir: &Path) -> anyhow::Result<Vec<SessionInfo>> {
+    let entries = fs::read_dir(sessions_dir)
         .with_context(|| format!("Failed to read sessions directory: {}", sessions_dir.display()))?;
 
+    let mut sessions = Vec::new();
     for entry in entries {
         let entry = match entry {
             Ok(e) => e,
@@ -78,19 +341,79 @@ pub fn list_sessions(parent_dir: &Path) -> anyhow::Result<Vec<SessionInfo>> {
         };
         let path = entry.path();
         if path.is_dir() {
-            if let Ok(info) = load_session(&path) {
+            if let Some(info) = load_listed_session(&path) {
                 sessions.push(info);
             }
         }
     }
-
-    sessions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
     Ok(sessions)
 }
 
+/// Load a session for listing. Corrupt files are left in place with a warning
+/// rather than silently hiding the session (`sesh doctor` moves them aside and
+/// rebuilds them); sessions from a newer sesh are skipped.
+pub fn load_listed_session(session_dir: &Path) -> Option<SessionInfo> {
+    match load_session(session_dir) {
+        Ok(info) => Some(info),
+        Err(e) => {
+            if e.downcast_ref::<CorruptStateFile>().is_some() {
+                eprintln!(
+                    "warning: {} is corrupt ({}); run `sesh doctor` to rebuild it",
+                    session_dir.join("session.json").display(),
+                    e.root_cause()
+                );
+            }
+            None
+        }
+    }
+}
+
+/// Set from `[index] enabled` at startup, so session queries don't reload
+/// the config to find out.
+static INDEX_ENABLED: OnceLock<bool> = OnceLock::new();
+
+pub fn set_index_enabled(config: &IndexConfig) {
+    let _ = INDEX_ENABLED.set(config.enabled);
+}
+
+/// Whether queries go through the session index: `[index] enabled` is set and
+/// `sesh doctor` has built it.
+fn index_enabled(parent_dir: &Path) -> bool {
+    INDEX_ENABLED.get().copied().unwrap_or(false) && index::exists(parent_dir)
+}
+
+fn warn_index_unusable(e: &anyhow::Error) {
+    eprintln!(
+        "warning: session index is unusable ({:#}); run `sesh doctor` to rebuild it",
+        e
+    );
+}
+
+/// Total size of regular files under `path`, without following symlinks.
+pub fn disk_usage(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(ft) if ft.is_dir() => disk_usage(&entry.path()),
+            Ok(ft) if ft.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
 pub fn delete_session_dir(session_dir: &Path) -> anyhow::Result<()> {
     fs::remove_dir_all(session_dir)
         .with_context(|| format!("Failed to remove session directory: {}", session_dir.display()))?;
+    if let Some(parent_dir) = parent_of(session_dir)
+        && index::exists(parent_dir)
+        && let Some(name) = session_dir.file_name()
+        && let Err(e) = index::forget(parent_dir, &name.to_string_lossy())
+    {
+        warn_index_unusable(&e);
+    }
     Ok(())
 }
 
@@ -101,9 +424,14 @@ pub fn session_exists(parent_dir: &Path, session_name: &str) -> bool {
 }
 
 /// Sanitize a branch name into a flat folder name suitable for use as a session directory.
-/// Replaces `/` with `-`, strips leading `.` and `..`, and appends `-2`, `-3`, etc. on collision.
+/// Replaces `/` and anything else but letters, digits, `.`, `-` and `_` with `-`, strips
+/// leading `.` and `..`, and appends `-2`, `-3`, etc. on collision.
+#[allow(clippy::collapsible_if)]
 pub fn sanitize_session_name(branch: &str, parent_dir: &Path) -> String {
-    let mut name = branch.replace('/', "-");
+    let mut name: String = branch
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '-' })
+        .collect();
 
     // Strip leading dots
     name = name.trim_start_matches('.').to_string();
@@ -143,8 +471,90 @@ pub fn sanitize_session_name(branch: &str, parent_dir: &Path) -> String {
 
 /// Check if any existing session already uses the given branch name.
 pub fn find_session_by_branch(parent_dir: &Path, branch: &str) -> Option<SessionInfo> {
+    if index_enabled(parent_dir) {
+        match index::find_by_branch(parent_dir, branch) {
+            Ok(found) => return found,
+            Err(e) => warn_index_unusable(&e),
+        }
+    }
     let sessions = list_sessions(parent_dir).ok()?;
-    sessions.into_iter().find(|s| s.branch == branch)
+    sessions.into_iter().find(|s| {
+        s.branch == branch || s.repos.iter().any(|r| s.branch_for(r) == branch)
+    })
+}
+
+/// Per-repo commit each repo was last reviewed at via `sesh status --changes`.
+pub type ReviewMarkers = std::collections::HashMap<String, String>;
+
+pub fn load_review_markers(session_dir: &Path) -> ReviewMarkers {
+    fs::read_to_string(session_dir.join("review_marker.json"))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_review_markers(session_dir: &Path, markers: &ReviewMarkers) -> anyhow::Result<()> {
+    let path = session_dir.join("review_marker.json");
+    state::write_json(&path, markers)
+        .with_context(|| format!("Failed to write review markers: {}", path.display()))
+}
+
+const ARCHIVE_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+fn logs_archive_dir(parent_dir: &Path) -> PathBuf {
+    parent_dir.join(".sesh/archive/logs")
+}
+
+/// Move a session's `logs/` into `.sesh/archive/logs/<session>-<timestamp>/`.
+/// Returns the archive path, or `None` if the session has no logs.
+pub fn archive_logs(parent_dir: &Path, session_dir: &Path, session_name: &str) -> anyhow::Result<Option<PathBuf>> {
+    archive_into(&session_dir.join("logs"), &logs_archive_dir(parent_dir), session_name)
+}
+
+/// Move a session's `scratch/` into `.sesh/archive/scratch/<session>-<timestamp>/`.
+/// Returns the archive path, or `None` if the session has no scratch directory.
+pub fn archive_scratch(parent_dir: &Path, session_dir: &Path, session_name: &str) -> anyhow::Result<Option<PathBuf>> {
+    archive_into(&scratch_dir(session_dir), &parent_dir.join(".sesh/archive/scratch"), session_name)
+}
+
+fn archive_into(dir: &Path, archive_root: &Path, session_name: &str) -> anyhow::Result<Option<PathBuf>> {
+    if !dir.exists() {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(archive_root)
+        .with_context(|| format!("Failed to create archive directory: {}", archive_root.display()))?;
+
+    let stamp = chrono::Utc::now().format(ARCHIVE_TIMESTAMP_FORMAT);
+    let dest = archive_root.join(format!("{}-{}", session_name, stamp));
+    fs::rename(dir, &dest)
+        .with_context(|| format!("Failed to archive {} to {}", dir.display(), dest.display()))?;
+
+    Ok(Some(dest))
+}
+
+/// Delete archived log directories older than `keep_days`. Returns how many were removed.
+pub fn prune_log_archives(parent_dir: &Path, keep_days: u32) -> usize {
+    let Ok(entries) = fs::read_dir(logs_archive_dir(parent_dir)) else {
+        return 0;
+    };
+    let cutoff = chrono::Utc::now().naive_utc() - chrono::Duration::days(keep_days as i64);
+
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // Names end with the fixed-width timestamp, e.g. `feature-auth-20250101-120000`
+        let Some(stamp) = name.len().checked_sub(15).and_then(|i| name.get(i..)) else {
+            continue;
+        };
+        let Ok(archived_at) = chrono::NaiveDateTime::parse_from_str(stamp, ARCHIVE_TIMESTAMP_FORMAT) else {
+            continue;
+        };
+        if archived_at < cutoff && fs::remove_dir_all(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -152,14 +562,49 @@ pub struct BackgroundPid {
     pub pid: u32,
     pub label: String,
     pub script: String,
+    /// The process leads its own process group, so teardown signals the whole
+    /// group (servers and containers the script started, not just the script).
+    /// False for entries written before scripts got their own group.
+    #[serde(default)]
+    pub process_group: bool,
+    /// When sesh spawned (or adopted) the process. Entries from before the
+    /// last boot can't be alive, whatever their PID now points at.
+    #[serde(default)]
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Found gone by `sesh ps` or `sesh doctor`. Never signalled again, since
+    /// its PID may belong to an unrelated process by now.
+    #[serde(default)]
+    pub dead: bool,
+    /// Stopped with SIGSTOP by `sesh du --enforce` for going over the disk quota.
+    #[serde(default)]
+    pub paused: bool,
+    /// Outcome of the script's `health_check` when it was started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<Health>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Health {
+    Healthy,
+    Unhealthy,
+}
+
+/// Where a background script may record the PID of the long-lived process it
+/// hands off to (`$SESH_PIDFILE`), so sesh can find it again after a restart.
+pub fn pidfile(session_dir: &Path, label: &str) -> PathBuf {
+    session_dir.join("run").join(format!("{}.pid", label))
+}
+
+/// Where a background script may record the port it serves on (`$SESH_PORTFILE`).
+pub fn portfile(session_dir: &Path, label: &str) -> PathBuf {
+    session_dir.join("run").join(format!("{}.port", label))
 }
 
 pub fn save_background_pids(session_dir: &Path, pids: &[BackgroundPid]) -> anyhow::Result<()> {
     let path = session_dir.join("background_pids.json");
-    let json = serde_json::to_string_pretty(pids).context("Failed to serialize background PIDs")?;
-    fs::write(&path, json)
-        .with_context(|| format!("Failed to write background PIDs: {}", path.display()))?;
-    Ok(())
+    state::write_json(&path, pids)
+        .with_context(|| format!("Failed to write background PIDs: {}", path.display()))
 }
 
 pub fn load_background_pids(session_dir: &Path) -> Vec<BackgroundPid> {
@@ -171,5 +616,90 @@ pub fn load_background_pids(session_dir: &Path) -> Vec<BackgroundPid> {
         Ok(c) => c,
         Err(_) => return Vec::new(),
     };
-    serde_json::from_str(&contents).unwrap_or_default()
+    match serde_json::from_str(&contents) {
+        Ok(pids) => pids,
+        Err(e) => {
+            state::quarantine_with_warning(&path, &e.into());
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn managed_paths_stay_inside_sessions_dir() {
+        let parent = Path::new("/nonexistent-sesh-parent");
+        assert!(ensure_managed_path(parent, &parent.join(".sesh/sessions/feat/api")).is_ok());
+        assert!(ensure_managed_path(parent, &parent.join(".sesh/sessions")).is_err());
+        assert!(ensure_managed_path(parent, &parent.join("api")).is_err());
+        assert!(ensure_managed_path(parent, &parent.join(".sesh/sessions/../../api")).is_err());
+        assert!(ensure_managed_path(parent, Path::new("/etc")).is_err());
+    }
+
+    #[test]
+    fn session_names_are_flat_folder_names() {
+        let parent = Path::new("/nonexistent-sesh-parent");
+        assert_eq!(sanitize_session_name("feat/login", parent), "feat-login");
+        assert_eq!(sanitize_session_name("../fix it:now", parent), "-fix-it-now");
+        assert_eq!(sanitize_session_name("v1.2_rc", parent), "v1.2_rc");
+    }
+
+    #[test]
+    fn migrates_unversioned_session_and_rejects_newer() {
+        let mut legacy = serde_json::json!({
+            "name": "feat-x",
+            "branch": "feat/x",
+            "repos": [],
+            "created_at": "2025-01-01T00:00:00Z",
+            "parent_dir": "/work"
+        });
+        assert_eq!(migrate_session_value(&mut legacy).unwrap(), 1);
+        let info: SessionInfo = serde_json::from_value(legacy).unwrap();
+        assert_eq!(info.version, SESSION_SCHEMA_VERSION);
+
+        let mut newer = serde_json::json!({ "version": SESSION_SCHEMA_VERSION + 1 });
+        assert!(migrate_session_value(&mut newer).is_err());
+    }
+
+    #[test]
+    fn script_env_exposes_tags_and_metadata() {
+        let info: SessionInfo = serde_json::from_value(serde_json::json!({
+            "version": SESSION_SCHEMA_VERSION,
+            "name": "feat-x",
+            "branch": "feat/x",
+            "repos": [],
+            "created_at": "2025-01-01T00:00:00Z",
+            "parent_dir": "/work",
+            "tags": ["urgent", "backend"],
+            "metadata": { "ticket-url": "https://x", "owner": "alice" }
+        }))
+        .unwrap();
+        assert_eq!(
+            info.script_env(),
+            vec![
+                ("SESH_SCRATCH".to_string(), "/work/.sesh/sessions/feat-x/scratch".to_string()),
+                ("SESH_TAGS".to_string(), "urgent,backend".to_string()),
+                ("SESH_META_OWNER".to_string(), "alice".to_string()),
+                ("SESH_META_TICKET_URL".to_string(), "https://x".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn script_env_exposes_ports() {
+        let info: SessionInfo = serde_json::from_value(serde_json::json!({
+            "version": SESSION_SCHEMA_VERSION,
+            "name": "feat-x",
+            "branch": "feat/x",
+            "repos": [],
+            "created_at": "2025-01-01T00:00:00Z",
+            "parent_dir": "/work",
+            "ports": { "web-app": 3001 }
+        }))
+        .unwrap();
+        assert!(info.script_env().contains(&("SESH_PORT_WEB_APP".to_string(), "3001".to_string())));
+    }
 }
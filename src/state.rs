@@ -0,0 +1,126 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+/// Marker attached to errors from state files that exist but can't be parsed,
+/// so callers can quarantine them instead of treating them as missing.
+#[derive(Debug, thiserror::Error)]
+#[error("corrupt state file: {}", .0.display())]
+pub struct CorruptStateFile(pub PathBuf);
+
+const QUARANTINE_MARKER: &str = ".corrupt-";
+
+/// Write `contents` to `path` so readers only ever see the old or the new file:
+/// write a temp file in the same directory, fsync it, then rename over `path`.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let dir = path
+        .parent()
+        .with_context(|| format!("no parent directory for {}", path.display()))?;
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("invalid state file path: {}", path.display()))?
+        .to_string_lossy();
+    let tmp = dir.join(format!(".{}.tmp-{}", file_name, std::process::id()));
+
+    let result = (|| {
+        let mut file = fs::File::create(&tmp)
+            .with_context(|| format!("failed to create {}", tmp.display()))?;
+        file.write_all(contents)
+            .with_context(|| format!("failed to write {}", tmp.display()))?;
+        file.sync_all()
+            .with_context(|| format!("failed to sync {}", tmp.display()))?;
+        fs::rename(&tmp, path)
+            .with_context(|| format!("failed to replace {}", path.display()))
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result?;
+
+    // Persist the rename itself; not every platform lets a directory be fsync'd.
+    if let Ok(d) = fs::File::open(dir) {
+        let _ = d.sync_all();
+    }
+    Ok(())
+}
+
+/// Serialize `value` as pretty JSON and write it with `write_atomic`.
+pub fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<()> {
+    let json = serde_json::to_string_pretty(value)
+        .with_context(|| format!("failed to serialize {}", path.display()))?;
+    write_atomic(path, json.as_bytes())
+}
+
+/// Move a corrupt state file aside as `<name>.corrupt-<timestamp>` so it stops
+/// breaking commands but is kept for inspection. Returns the new path.
+pub fn quarantine(path: &Path) -> Result<PathBuf> {
+    let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("invalid state file path: {}", path.display()))?
+        .to_string_lossy();
+    let dest = path.with_file_name(format!("{}{}{}", file_name, QUARANTINE_MARKER, stamp));
+    fs::rename(path, &dest)
+        .with_context(|| format!("failed to quarantine {}", path.display()))?;
+    Ok(dest)
+}
+
+/// Quarantine `path` and warn on stderr. Used where the caller can carry on
+/// without the file (it is treated as missing from then on).
+pub fn quarantine_with_warning(path: &Path, err: &anyhow::Error) {
+    match quarantine(path) {
+        Ok(dest) => eprintln!(
+            "warning: {} is corrupt ({}); moved it to {} (see `sesh doctor`)",
+            path.display(),
+            err.root_cause(),
+            dest.display()
+        ),
+        Err(e) => eprintln!("warning: {} is corrupt and could not be moved aside: {:#}", path.display(), e),
+    }
+}
+
+/// Quarantined files directly inside `dir`.
+pub fn quarantined_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.contains(QUARANTINE_MARKER))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atomic_write_replaces_and_quarantine_moves_aside() {
+        let dir = std::env::temp_dir().join(format!("sesh-state-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("session.json");
+
+        write_atomic(&path, b"{\"a\":1}").unwrap();
+        write_json(&path, &serde_json::json!({ "a": 2 })).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(value["a"], 2);
+
+        let moved = quarantine(&path).unwrap();
+        assert!(!path.exists());
+        assert_eq!(quarantined_files(&dir), vec![moved]);
+        // No temp files left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,93 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Render a small handlebars/mustache-style template:
+///
+/// - `{{ name }}` is replaced by the variable's value (empty if unknown)
+/// - `{{#name}}...{{/name}}` is kept only when `name` is non-empty
+/// - `{{^name}}...{{/name}}` is kept only when `name` is empty
+///
+/// A section tag alone on its line takes the line break with it, so sections
+/// don't leave blank lines behind.
+pub fn render<K: Borrow<str> + Hash + Eq>(template: &str, vars: &HashMap<K, String>) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    // Whether output is currently suppressed by each open section
+    let mut hidden: Vec<(String, bool)> = Vec::new();
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let tag = rest[start + 2..start + len].trim();
+        let before = &rest[..start];
+        let mut after = &rest[start + len + 2..];
+        let suppressed = hidden.iter().any(|(_, h)| *h);
+
+        let section = tag.starts_with(['#', '^', '/']);
+        let mut before = before;
+        if section
+            && let Some(line_start) = standalone_line(out.as_str(), before, after)
+        {
+            before = &before[..line_start];
+            after = after.strip_prefix("\r\n").or_else(|| after.strip_prefix('\n')).unwrap_or(after);
+        }
+        if !suppressed {
+            out.push_str(before);
+        }
+
+        let is_set = |name: &str| vars.get(name.trim()).is_some_and(|v| !v.is_empty());
+        if let Some(name) = tag.strip_prefix('#') {
+            hidden.push((name.trim().to_string(), !is_set(name)));
+        } else if let Some(name) = tag.strip_prefix('^') {
+            hidden.push((name.trim().to_string(), is_set(name)));
+        } else if let Some(name) = tag.strip_prefix('/') {
+            if let Some(pos) = hidden.iter().rposition(|(n, _)| n == name.trim()) {
+                hidden.truncate(pos);
+            }
+        } else if !suppressed {
+            out.push_str(vars.get(tag).map(String::as_str).unwrap_or_default());
+        }
+        rest = after;
+    }
+
+    if !hidden.iter().any(|(_, h)| *h) {
+        out.push_str(rest);
+    }
+    out
+}
+
+/// If a tag preceded by `before` and followed by `after` sits alone on its line
+/// (only whitespace around it), return where that line starts in `before`.
+fn standalone_line(out: &str, before: &str, after: &str) -> Option<usize> {
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let at_line_start = line_start > 0 || out.is_empty() || out.ends_with('\n');
+    let rest_of_line = after.split('\n').next().unwrap_or(after);
+    (at_line_start
+        && before[line_start..].trim().is_empty()
+        && rest_of_line.trim().is_empty())
+    .then_some(line_start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_variables_and_sections() {
+        let template = "{{#issue}}\n## {{ issue.identifier }}: {{issue.title}}\n\n{{ fixes }}\n{{/issue}}\n{{^issue}}\nNo linked issue.\n{{/issue}}\nBranch `{{branch}}`{{missing}}\n";
+        let mut vars = HashMap::new();
+        vars.insert("branch", "eng-1-login".to_string());
+        assert_eq!(render(template, &vars), "No linked issue.\nBranch `eng-1-login`\n");
+
+        vars.insert("issue", "ENG-1".to_string());
+        vars.insert("issue.identifier", "ENG-1".to_string());
+        vars.insert("issue.title", "Fix login".to_string());
+        vars.insert("fixes", "Fixes ENG-1".to_string());
+        assert_eq!(
+            render(template, &vars),
+            "## ENG-1: Fix login\n\nFixes ENG-1\nBranch `eng-1-login`\n"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::future::Future;
+use std::process::{Command, Output};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use console::style;
+
+/// Environment variable that turns timing on, same as `--profile-startup`.
+pub const ENV_VAR: &str = "SESH_PROFILE";
+
+/// How many of the slowest individual calls the report lists.
+const SLOWEST: usize = 10;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SPANS: Mutex<Vec<Span>> = Mutex::new(Vec::new());
+
+struct Span {
+    category: &'static str,
+    label: String,
+    elapsed: Duration,
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Records the time until it is dropped under `category`. Does nothing (and
+/// never builds the label) unless timing is enabled.
+pub struct Timer(Option<(&'static str, String, Instant)>);
+
+pub fn timer(category: &'static str, label: impl FnOnce() -> String) -> Timer {
+    Timer(enabled().then(|| (category, label(), Instant::now())))
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some((category, label, start)) = self.0.take()
+            && let Ok(mut spans) = SPANS.lock()
+        {
+            spans.push(Span { category, label, elapsed: start.elapsed() });
+        }
+    }
+}
+
+/// `Command::output`, timed under the program's name (`git`, `gh`, ...).
+pub trait TimedOutput {
+    fn timed_output(&mut self) -> std::io::Result<Output>;
+}
+
+impl TimedOutput for Command {
+    fn timed_output(&mut self) -> std::io::Result<Output> {
+        let category = match self.get_program().to_str() {
+            Some("git") => "git",
+            Some("gh") => "gh",
+            _ => "process",
+        };
+        let _t = timer(category, || {
+            std::iter::once(self.get_program())
+                .chain(self.get_args().filter(|a| *a != OsStr::new("-C")))
+                .map(|a| a.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" ")
+        });
+        self.output()
+    }
+}
+
+/// `RequestBuilder::send`, timed under `api` as `<METHOD> <host><path>`.
+pub trait TimedSend {
+    fn timed_send(self) -> impl Future<Output = reqwest::Result<reqwest::Response>>;
+}
+
+impl TimedSend for reqwest::RequestBuilder {
+    async fn timed_send(self) -> reqwest::Result<reqwest::Response> {
+        let (client, request) = self.build_split();
+        let request = request?;
+        // Query strings are left out; some APIs take secrets there
+        let _t = timer("api", || {
+            let url = request.url();
+            format!("{} {}{}", request.method(), url.host_str().unwrap_or(""), url.path())
+        });
+        client.execute(request).await
+    }
+}
+
+/// Print per-category totals and the slowest calls to stderr.
+pub fn report(total: Duration) {
+    if !enabled() {
+        return;
+    }
+    let spans = SPANS.lock().map(|mut s| std::mem::take(&mut *s)).unwrap_or_default();
+
+    let mut totals: BTreeMap<&str, (usize, Duration)> = BTreeMap::new();
+    for span in &spans {
+        let entry = totals.entry(span.category).or_default();
+        entry.0 += 1;
+        entry.1 += span.elapsed;
+    }
+
+    eprintln!("\n{} {}", style("⏱").bold(), style(format!("Total {}", ms(total))).bold());
+    for (category, (count, elapsed)) in &totals {
+        eprintln!("  {:<10} {:>10}  ({} call(s))", category, ms(*elapsed), count);
+    }
+
+    let mut slowest: Vec<&Span> = spans.iter().collect();
+    slowest.sort_by_key(|s| std::cmp::Reverse(s.elapsed));
+    if !slowest.is_empty() {
+        eprintln!("  Slowest:");
+    }
+    for span in slowest.into_iter().take(SLOWEST) {
+        eprintln!(
+            "  {:>10}  {} {}",
+            ms(span.elapsed),
+            style(format!("[{}]", span.category)).dim(),
+            span.label
+        );
+    }
+}
+
+fn ms(d: Duration) -> String {
+    format!("{:.1}ms", d.as_secs_f64() * 1000.0)
+}
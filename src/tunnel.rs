@@ -0,0 +1,103 @@
+use std::fs::{self, File};
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::config::TunnelTool;
+
+/// Spawn the tunneling tool for `port`, logging to `log_path`. Returns the PID.
+pub fn spawn(tool: TunnelTool, port: u16, log_path: &Path) -> Result<u32> {
+    if let Some(dir) = log_path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create log dir: {}", dir.display()))?;
+    }
+    let log_file = File::create(log_path)
+        .with_context(|| format!("failed to create log file: {}", log_path.display()))?;
+    let log_stderr = log_file
+        .try_clone()
+        .context("failed to clone log file handle")?;
+
+    let mut cmd = match tool {
+        TunnelTool::Cloudflared => {
+            let mut c = Command::new("cloudflared");
+            c.args(["tunnel", "--no-autoupdate", "--url"])
+                .arg(format!("http://localhost:{}", port));
+            c
+        }
+        TunnelTool::Ngrok => {
+            let mut c = Command::new("ngrok");
+            c.args(["http", &port.to_string(), "--log", "stdout", "--log-format", "logfmt"]);
+            c
+        }
+    };
+    let child = cmd
+        .process_group(0)
+        .stdin(Stdio::null())
+        .stdout(log_file)
+        .stderr(log_stderr)
+        .spawn()
+        .with_context(|| format!("failed to run {} (is it installed?)", tool.binary()))?;
+    Ok(child.id())
+}
+
+/// Extract the public URL a tunneling tool printed to its log.
+pub fn parse_url(tool: TunnelTool, log: &str) -> Option<String> {
+    let pattern = match tool {
+        TunnelTool::Cloudflared => r"https://[a-z0-9-]+\.trycloudflare\.com",
+        TunnelTool::Ngrok => r"url=(https://\S+)",
+    };
+    let re = Regex::new(pattern).expect("valid tunnel URL regex");
+    let caps = re.captures(log)?;
+    let m = caps.get(1).or_else(|| caps.get(0))?;
+    Some(m.as_str().to_string())
+}
+
+/// Poll the tunnel's log until it reports a public URL or `timeout` elapses.
+pub fn wait_for_url(tool: TunnelTool, log_path: &Path, timeout: Duration) -> Option<String> {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if let Ok(log) = fs::read_to_string(log_path)
+            && let Some(url) = parse_url(tool, &log)
+        {
+            return Some(url);
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+    None
+}
+
+impl TunnelTool {
+    pub fn binary(self) -> &'static str {
+        match self {
+            TunnelTool::Cloudflared => "cloudflared",
+            TunnelTool::Ngrok => "ngrok",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tool_urls() {
+        let cf = "2024-01-01T00:00:00Z INF |  https://quiet-fox-12.trycloudflare.com  |\n";
+        assert_eq!(
+            parse_url(TunnelTool::Cloudflared, cf).as_deref(),
+            Some("https://quiet-fox-12.trycloudflare.com")
+        );
+
+        let ngrok = "t=2024 lvl=info msg=\"started tunnel\" name=command_line addr=http://localhost:3000 url=https://ab12.ngrok-free.app\n";
+        assert_eq!(
+            parse_url(TunnelTool::Ngrok, ngrok).as_deref(),
+            Some("https://ab12.ngrok-free.app")
+        );
+
+        assert_eq!(parse_url(TunnelTool::Cloudflared, "starting tunnel\n"), None);
+    }
+}
@@ -1,25 +1,164 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::Serialize;
 
-/// Open VS Code with the appropriate strategy:
-/// - 1 repo: open the single worktree path directly
-/// - 2+ repos: open the session directory so all repos appear as folders in one window
-pub fn open_session_in_vscode(session_dir: &Path, worktree_paths: &[PathBuf]) -> Result<()> {
-    if worktree_paths.is_empty() {
+use crate::config::{VscodeConfig, VscodeWindow};
+use crate::manifest;
+use crate::session::{self, SessionInfo};
+use crate::state;
+
+#[derive(Serialize)]
+struct WorkspaceFolder {
+    name: String,
+    path: PathBuf,
+}
+
+#[derive(Serialize)]
+struct WorkspaceExtensions<'a> {
+    recommendations: &'a [String],
+}
+
+#[derive(Serialize)]
+struct WorkspaceFile<'a> {
+    folders: Vec<WorkspaceFolder>,
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    settings: &'a serde_json::Map<String, serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extensions: Option<WorkspaceExtensions<'a>>,
+}
+
+/// Write `<session>.code-workspace` in the session directory with one named
+/// folder per worktree, one for the session directory itself (README.sesh.md,
+/// URLS.md, context/), plus the `[vscode]` settings and recommendations.
+pub fn write_workspace_file(session: &SessionInfo, config: &VscodeConfig) -> Result<PathBuf> {
+    let session_dir = session::session_dir(&session.parent_dir, &session.name);
+    let mut folders: Vec<WorkspaceFolder> = session
+        .repos
+        .iter()
+        .map(|r| WorkspaceFolder {
+            name: r.name.clone(),
+            // Relative paths keep the workspace valid if the directory moves
+            path: r
+                .worktree_path
+                .strip_prefix(&session_dir)
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|_| r.worktree_path.clone()),
+        })
+        .collect();
+    folders.push(WorkspaceFolder {
+        name: format!("{} (session)", session.name),
+        path: PathBuf::from("."),
+    });
+    let workspace = WorkspaceFile {
+        folders,
+        settings: &config.settings,
+        extensions: (!config.extensions.is_empty()).then_some(WorkspaceExtensions {
+            recommendations: &config.extensions,
+        }),
+    };
+
+    let path = session_dir.join(format!("{}.code-workspace", session.name));
+    let json = serde_json::to_string_pretty(&workspace).context("failed to serialize VS Code workspace")?;
+    state::write_atomic(&path, json.as_bytes())?;
+    Ok(path)
+}
+
+/// Open VS Code for a session:
+/// - 1 repo and no `[vscode]` config: open the single worktree directly
+/// - otherwise: (re)generate the session's `.code-workspace` file and open it
+///
+/// `window`, `open_files` and `wait` from `[vscode]` shape the `code` call.
+pub fn open_session_in_vscode(session: &SessionInfo, config: &VscodeConfig) -> Result<()> {
+    if session.repos.is_empty() {
         return Ok(());
     }
 
-    let path = if worktree_paths.len() == 1 {
-        worktree_paths[0].clone()
+    let path = if session.repos.len() == 1 && config.settings.is_empty() && config.extensions.is_empty() {
+        session.repos[0].worktree_path.clone()
     } else {
-        session_dir.to_path_buf()
+        write_workspace_file(session, config)?
     };
 
-    if let Err(e) = Command::new("code").arg(&path).spawn() {
+    let mut cmd = Command::new("code");
+    match config.window {
+        VscodeWindow::Default => {}
+        VscodeWindow::New => {
+            cmd.arg("--new-window");
+        }
+        VscodeWindow::Reuse => {
+            cmd.arg("--reuse-window");
+        }
+    }
+    if config.wait {
+        cmd.arg("--wait");
+    }
+    cmd.arg(&path);
+    let session_dir = session::session_dir(&session.parent_dir, &session.name);
+    for file in &config.open_files {
+        let file = session_dir.join(file);
+        if file.exists() {
+            cmd.arg(file);
+        } else {
+            eprintln!("warning: [vscode] open_files: {} not found", file.display());
+        }
+    }
+
+    if config.wait {
+        println!("Waiting for the VS Code window to close...");
+        match cmd.status() {
+            Ok(status) if !status.success() => {
+                eprintln!("warning: VS Code exited with {}: {}", status, path.display());
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("warning: VS Code launch failed: {}: {}", path.display(), e),
+        }
+    } else if let Err(e) = cmd.spawn() {
         eprintln!("warning: VS Code launch failed: {}: {}", path.display(), e);
     }
 
     Ok(())
 }
+
+/// Write per-repo settings to `<worktree>/.vscode/settings.json`, recorded as a
+/// generated file. Returns false (and leaves it alone) when the repo already
+/// has that file, so tracked settings are never overwritten.
+pub fn write_repo_settings(
+    sess_dir: &Path,
+    repo_name: &str,
+    worktree_path: &Path,
+    original_repo_path: &Path,
+    settings: &serde_json::Map<String, serde_json::Value>,
+) -> Result<bool> {
+    let path = worktree_path.join(".vscode/settings.json");
+    if path.exists() {
+        return Ok(false);
+    }
+    std::fs::create_dir_all(worktree_path.join(".vscode"))
+        .with_context(|| format!("failed to create .vscode in {}", worktree_path.display()))?;
+    let json = serde_json::to_string_pretty(settings).context("failed to serialize VS Code settings")?;
+    std::fs::write(&path, json).with_context(|| format!("failed to write {}", path.display()))?;
+    manifest::record(sess_dir, repo_name, original_repo_path, ".vscode/settings.json")?;
+    Ok(true)
+}
+
+/// Write the session's registered URLs to `URLS.md` in the session directory so
+/// they are one click away in the VS Code window. Removes the file when empty.
+pub fn write_urls_file(session_dir: &Path, urls: &BTreeMap<String, String>) -> Result<()> {
+    let path = session_dir.join("URLS.md");
+    if urls.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("failed to remove {}", path.display()))?;
+        }
+        return Ok(());
+    }
+
+    let mut content = String::from("# Session URLs\n\n");
+    for (name, url) in urls {
+        content.push_str(&format!("- [{}]({})\n", name, url));
+    }
+    state::write_atomic(&path, content.as_bytes())
+}
@@ -1,22 +1,42 @@
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::Arc;
 
 use anyhow::{bail, Context, Result};
 
-fn run_git(repo_path: &Path, args: &[&str]) -> Result<String> {
+use crate::gitstate;
+use crate::timing::TimedOutput;
+
+/// Git subcommands `run_git` may issue without invalidating [`gitstate`]'s cache.
+const READ_ONLY_COMMANDS: &[&str] = &[
+    "diff", "for-each-ref", "log", "ls-files", "ls-remote", "merge-base", "rev-list", "rev-parse", "status", "symbolic-ref",
+];
+
+/// Run git in `repo_path`. Arguments are passed straight to the process (never
+/// through a shell), so paths may hold spaces, quotes or non-UTF-8 bytes.
+fn run_git<S: AsRef<OsStr>>(repo_path: &Path, args: &[S]) -> Result<String> {
+    let read_only = args
+        .first()
+        .and_then(|a| a.as_ref().to_str())
+        .is_some_and(|a| READ_ONLY_COMMANDS.contains(&a));
+    if !read_only {
+        gitstate::invalidate();
+    }
     let output = Command::new("git")
         .arg("-C")
         .arg(repo_path)
         .args(args)
-        .output()
-        .with_context(|| format!("failed to run git {}", args.join(" ")))?;
+        .timed_output()
+        .with_context(|| format!("failed to run git {}", describe_args(args)))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         let code = output.status.code().unwrap_or(-1);
         bail!(
             "git {} failed (exit code {}): {}",
-            args.join(" "),
+            describe_args(args),
             code,
             stderr.trim()
         );
@@ -26,16 +46,29 @@ fn run_git(repo_path: &Path, args: &[&str]) -> Result<String> {
     Ok(stdout)
 }
 
+fn describe_args<S: AsRef<OsStr>>(args: &[S]) -> String {
+    args.iter()
+        .map(|a| a.as_ref().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 pub fn create_worktree(
     repo_path: &Path,
     worktree_path: &Path,
     branch_name: &str,
     base_ref: &str,
 ) -> Result<()> {
-    let wt = worktree_path.to_string_lossy();
     let result = run_git(
         repo_path,
-        &["worktree", "add", &wt, "-b", branch_name, base_ref],
+        &[
+            OsStr::new("worktree"),
+            OsStr::new("add"),
+            worktree_path.as_os_str(),
+            OsStr::new("-b"),
+            OsStr::new(branch_name),
+            OsStr::new(base_ref),
+        ],
     );
 
     if let Err(e) = result {
@@ -49,9 +82,55 @@ pub fn create_worktree(
     Ok(())
 }
 
+/// Create a detached worktree at `base_ref` (used for pinned, read-only repos).
+pub fn create_detached_worktree(repo_path: &Path, worktree_path: &Path, base_ref: &str) -> Result<()> {
+    let result = run_git(
+        repo_path,
+        &[
+            OsStr::new("worktree"),
+            OsStr::new("add"),
+            OsStr::new("--detach"),
+            worktree_path.as_os_str(),
+            OsStr::new(base_ref),
+        ],
+    );
+
+    if let Err(e) = result {
+        let repo_name = repo_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| repo_path.display().to_string());
+        bail!("failed to create pinned worktree for repo '{}': {}", repo_name, e);
+    }
+
+    Ok(())
+}
+
+/// Move a worktree of `repo_path` from `from` to `to` (`git worktree move`).
+pub fn move_worktree(repo_path: &Path, from: &Path, to: &Path) -> Result<()> {
+    run_git(
+        repo_path,
+        &[OsStr::new("worktree"), OsStr::new("move"), from.as_os_str(), to.as_os_str()],
+    )?;
+    Ok(())
+}
+
+/// Create `branch_name` at `base_ref` and check it out in an existing worktree.
+pub fn checkout_new_branch(worktree_path: &Path, branch_name: &str, base_ref: &str) -> Result<()> {
+    run_git(worktree_path, &["checkout", "-q", "-b", branch_name, base_ref])?;
+    Ok(())
+}
+
 pub fn remove_worktree(repo_path: &Path, worktree_path: &Path) -> Result<()> {
-    let wt = worktree_path.to_string_lossy();
-    run_git(repo_path, &["worktree", "remove", &wt, "--force"])?;
+    run_git(
+        repo_path,
+        &[
+            OsStr::new("worktree"),
+            OsStr::new("remove"),
+            worktree_path.as_os_str(),
+            OsStr::new("--force"),
+        ],
+    )?;
     Ok(())
 }
 
@@ -60,13 +139,22 @@ pub fn prune_worktrees(repo_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Delete remote-tracking refs whose branches are gone from `remote`, via
+/// `fetch --prune`, falling back to `remote prune` if the fetch fails.
+pub fn prune_remote(repo_path: &Path, remote: &str) -> Result<()> {
+    if run_git(repo_path, &["fetch", "--prune", remote]).is_err() {
+        run_git(repo_path, &["remote", "prune", remote])?;
+    }
+    Ok(())
+}
+
 pub fn branch_exists(repo_path: &Path, branch_name: &str) -> Result<bool> {
     let ref_name = format!("refs/heads/{}", branch_name);
     let output = Command::new("git")
         .arg("-C")
         .arg(repo_path)
         .args(["rev-parse", "--verify", &ref_name])
-        .output()
+        .timed_output()
         .with_context(|| format!("failed to run git rev-parse for branch '{}'", branch_name))?;
 
     Ok(output.status.success())
@@ -77,16 +165,268 @@ pub fn fetch_branch(repo_path: &Path, remote: &str, branch: &str) -> Result<()>
     Ok(())
 }
 
+/// Count commits on HEAD that are not reachable from `base_ref`.
+pub fn commits_ahead(worktree_path: &Path, base_ref: &str) -> Result<u32> {
+    let range = format!("{}..HEAD", base_ref);
+    let output = run_git(worktree_path, &["rev-list", "--count", &range])?;
+    output
+        .trim()
+        .parse()
+        .with_context(|| format!("unexpected rev-list output: {}", output.trim()))
+}
+
+/// Count commits on `base_ref` that HEAD does not contain yet.
+pub fn commits_behind(worktree_path: &Path, base_ref: &str) -> Result<u32> {
+    let range = format!("HEAD..{}", base_ref);
+    let output = run_git(worktree_path, &["rev-list", "--count", &range])?;
+    output
+        .trim()
+        .parse()
+        .with_context(|| format!("unexpected rev-list output: {}", output.trim()))
+}
+
+/// Count commits HEAD has that `other` lacks, and commits `other` has that
+/// HEAD lacks, in one `rev-list --left-right` call.
+pub fn ahead_behind(worktree_path: &Path, other: &str) -> Result<(u32, u32)> {
+    let range = format!("HEAD...{}", other);
+    let output = run_git(worktree_path, &["rev-list", "--left-right", "--count", &range])?;
+    let mut counts = output.split_whitespace().map(str::parse::<u32>);
+    match (counts.next(), counts.next()) {
+        (Some(Ok(ahead)), Some(Ok(behind))) => Ok((ahead, behind)),
+        _ => bail!("unexpected rev-list output: {}", output.trim()),
+    }
+}
+
+/// Rebase (or merge) the worktree's branch onto `base_ref`. On conflicts the
+/// operation is aborted, leaving the worktree as it was, and the conflicted
+/// paths are returned.
+pub fn integrate_base(worktree_path: &Path, base_ref: &str, merge: bool) -> Result<Vec<String>> {
+    let result = if merge {
+        run_git(worktree_path, &["merge", "--no-edit", base_ref])
+    } else {
+        run_git(worktree_path, &["rebase", base_ref])
+    };
+    let Err(e) = result else {
+        return Ok(Vec::new());
+    };
+
+    let conflicts: Vec<String> = run_git(worktree_path, &["diff", "--name-only", "--diff-filter=U"])
+        .map(|out| out.lines().map(str::to_string).collect())
+        .unwrap_or_default();
+    let abort = if merge { "merge" } else { "rebase" };
+    let _ = run_git(worktree_path, &[abort, "--abort"]);
+
+    if conflicts.is_empty() {
+        return Err(e);
+    }
+    Ok(conflicts)
+}
+
+/// Move a detached (pinned) worktree to `base_ref`.
+pub fn checkout_detached(worktree_path: &Path, base_ref: &str) -> Result<()> {
+    run_git(worktree_path, &["checkout", "--quiet", "--detach", base_ref])?;
+    Ok(())
+}
+
+/// Whether a worktree has staged changes (or, with `include_unstaged`, any tracked changes).
+pub fn has_changes_to_commit(worktree_path: &Path, include_unstaged: bool) -> Result<bool> {
+    let output = if include_unstaged {
+        run_git(worktree_path, &["status", "--porcelain", "--untracked-files=no"])?
+    } else {
+        run_git(worktree_path, &["diff", "--cached", "--name-only"])?
+    };
+    Ok(!output.trim().is_empty())
+}
+
+/// Uncommitted paths in a worktree (staged, unstaged and untracked), as
+/// `git status --porcelain` lines.
+pub fn uncommitted_files(worktree_path: &Path) -> Result<Vec<String>> {
+    let output = run_git(worktree_path, &["status", "--porcelain"])?;
+    Ok(output.lines().map(|l| l.trim_end().to_string()).filter(|l| !l.is_empty()).collect())
+}
+
+/// Count commits on HEAD that are not on any remote-tracking branch.
+/// Commits on HEAD that no remote-tracking branch has. Without any remote
+/// refs there's nothing to compare against, so this is 0 rather than the
+/// whole history.
+pub fn unpushed_commits(worktree_path: &Path) -> Result<u32> {
+    if run_git(worktree_path, &["for-each-ref", "--count=1", "refs/remotes"])?.trim().is_empty() {
+        return Ok(0);
+    }
+    let output = run_git(worktree_path, &["rev-list", "--count", "HEAD", "--not", "--remotes"])?;
+    output
+        .trim()
+        .parse()
+        .with_context(|| format!("unexpected rev-list output: {}", output.trim()))
+}
+
+/// Set a config value for a single worktree, enabling `extensions.worktreeConfig`
+/// on the repo so the value doesn't leak into the main checkout.
+pub fn set_worktree_config(repo_path: &Path, worktree_path: &Path, key: &str, value: &str) -> Result<()> {
+    run_git(repo_path, &["config", "extensions.worktreeConfig", "true"])?;
+    run_git(worktree_path, &["config", "--worktree", key, value])?;
+    Ok(())
+}
+
+/// Remove a worktree-scoped config value (no-op if it isn't set).
+pub fn unset_worktree_config(worktree_path: &Path, key: &str) -> Result<()> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(worktree_path)
+        .args(["config", "--worktree", "--unset", key])
+        .timed_output()
+        .with_context(|| format!("failed to run git config --unset {}", key))?;
+
+    // Exit code 5 means the key wasn't set
+    if !output.status.success() && output.status.code() != Some(5) {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git config --unset {} failed: {}", key, stderr.trim());
+    }
+
+    Ok(())
+}
+
+/// Resolve the hooks directory git uses for a repo (honours `core.hooksPath`).
+pub fn hooks_dir(repo_path: &Path) -> Result<PathBuf> {
+    let output = run_git(repo_path, &["rev-parse", "--git-path", "hooks"])?;
+    let path = PathBuf::from(output.trim());
+    if path.is_absolute() {
+        Ok(path)
+    } else {
+        Ok(repo_path.join(path))
+    }
+}
+
+/// Files changed relative to `base_ref` (committed, uncommitted and untracked),
+/// or only the staged files when `staged` is set.
+pub fn changed_files(worktree_path: &Path, base_ref: &str, staged: bool) -> Result<Vec<String>> {
+    let mut files: Vec<String> = if staged {
+        run_git(worktree_path, &["diff", "--cached", "--name-only"])?
+            .lines()
+            .map(|s| s.to_string())
+            .collect()
+    } else {
+        let base = run_git(worktree_path, &["merge-base", base_ref, "HEAD"])?;
+        let mut files: Vec<String> = run_git(worktree_path, &["diff", "--name-only", base.trim()])?
+            .lines()
+            .map(|s| s.to_string())
+            .collect();
+        files.extend(
+            run_git(worktree_path, &["ls-files", "--others", "--exclude-standard"])?
+                .lines()
+                .map(|s| s.to_string()),
+        );
+        files
+    };
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Files changed by commits on HEAD since it diverged from `base_ref`.
+pub fn committed_files(worktree_path: &Path, base_ref: &str) -> Result<Vec<String>> {
+    let range = format!("{}...HEAD", base_ref);
+    Ok(run_git(worktree_path, &["diff", "--name-only", &range])?
+        .lines()
+        .map(|s| s.to_string())
+        .collect())
+}
+
+pub fn head_sha(worktree_path: &Path) -> Result<String> {
+    Ok(run_git(worktree_path, &["rev-parse", "HEAD"])?.trim().to_string())
+}
+
+/// Branch checked out in a worktree, or `None` when HEAD is detached.
+pub fn current_branch(worktree_path: &Path) -> Option<String> {
+    let out = run_git(worktree_path, &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok()?;
+    Some(out.trim().to_string()).filter(|b| !b.is_empty())
+}
+
+/// Main checkout a linked worktree belongs to.
+pub fn main_repo_path(worktree_path: &Path) -> Result<PathBuf> {
+    let out = run_git(worktree_path, &["rev-parse", "--path-format=absolute", "--git-common-dir"])?;
+    let common = PathBuf::from(out.trim());
+    common
+        .parent()
+        .map(Path::to_path_buf)
+        .with_context(|| format!("unexpected git dir for {}", worktree_path.display()))
+}
+
+/// Commit time of HEAD, if the worktree has any commits.
+pub fn head_commit_time(worktree_path: &Path) -> Option<chrono::DateTime<chrono::Utc>> {
+    let out = run_git(worktree_path, &["log", "-1", "--format=%ct"]).ok()?;
+    let secs: i64 = out.trim().parse().ok()?;
+    chrono::DateTime::from_timestamp(secs, 0)
+}
+
+pub fn merge_base(worktree_path: &Path, a: &str, b: &str) -> Result<String> {
+    Ok(run_git(worktree_path, &["merge-base", a, b])?.trim().to_string())
+}
+
+/// Subject lines of the commits in `range`, oldest first.
+pub fn commit_subjects(worktree_path: &Path, range: &str) -> Result<Vec<String>> {
+    let output = run_git(worktree_path, &["log", "--reverse", "--format=%s", range])?;
+    Ok(output.lines().map(str::to_string).collect())
+}
+
+/// `git diff --stat` for `range` (e.g. `origin/main...HEAD`).
+pub fn diff_stat(worktree_path: &Path, range: &str) -> Result<String> {
+    Ok(run_git(worktree_path, &["diff", "--stat", range])?.trim_end().to_string())
+}
+
+/// Files touched by commits in `range`, grouped by author `(name, email)` in
+/// first-seen order.
+pub fn changed_files_by_author(worktree_path: &Path, range: &str) -> Result<Vec<(String, String, Vec<String>)>> {
+    let output = run_git(
+        worktree_path,
+        &["log", "--format=%x00%an%x00%ae", "--name-only", range],
+    )?;
+
+    let mut groups: Vec<(String, String, Vec<String>)> = Vec::new();
+    let mut current: Option<usize> = None;
+    for line in output.lines() {
+        if let Some(header) = line.strip_prefix('\0') {
+            let (name, email) = header.split_once('\0').unwrap_or((header, ""));
+            let idx = match groups.iter().position(|(_, e, _)| e == email) {
+                Some(i) => i,
+                None => {
+                    groups.push((name.to_string(), email.to_string(), Vec::new()));
+                    groups.len() - 1
+                }
+            };
+            current = Some(idx);
+        } else if !line.trim().is_empty()
+            && let Some(idx) = current
+            && !groups[idx].2.iter().any(|f| f == line)
+        {
+            groups[idx].2.push(line.to_string());
+        }
+    }
+
+    Ok(groups)
+}
+
+pub fn user_email(repo_path: &Path) -> Option<String> {
+    run_git(repo_path, &["config", "user.email"])
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
 pub fn delete_branch(repo_path: &Path, branch_name: &str) -> Result<()> {
     run_git(repo_path, &["branch", "-D", branch_name])?;
     Ok(())
 }
 
-pub fn get_worktree_list(repo_path: &Path) -> Result<Vec<String>> {
-    let output = run_git(repo_path, &["worktree", "list", "--porcelain"])?;
+/// Records of `git worktree list --porcelain -z`. NUL separation keeps paths
+/// with newlines or other unusual characters verbatim instead of C-quoted.
+fn worktree_list_records(repo_path: &Path) -> Result<Arc<Vec<String>>> {
+    gitstate::worktree_records(repo_path)
+}
 
-    let paths = output
-        .lines()
+pub fn get_worktree_list(repo_path: &Path) -> Result<Vec<String>> {
+    let paths = worktree_list_records(repo_path)?
+        .iter()
         .filter_map(|line| line.strip_prefix("worktree "))
         .map(|s| s.to_string())
         .collect();
@@ -99,8 +439,15 @@ pub fn checkout_existing_branch(
     worktree_path: &Path,
     branch_name: &str,
 ) -> Result<()> {
-    let wt = worktree_path.to_string_lossy();
-    let result = run_git(repo_path, &["worktree", "add", &wt, branch_name]);
+    let result = run_git(
+        repo_path,
+        &[
+            OsStr::new("worktree"),
+            OsStr::new("add"),
+            worktree_path.as_os_str(),
+            OsStr::new(branch_name),
+        ],
+    );
 
     if let Err(e) = result {
         let repo_name = repo_path
@@ -117,33 +464,45 @@ pub fn checkout_existing_branch(
     Ok(())
 }
 
-pub fn list_all_branches(repo_path: &Path) -> Result<Vec<String>> {
+/// Local branches plus `remote`'s branches (without the `<remote>/` prefix).
+pub fn list_all_branches(repo_path: &Path, remote: &str) -> Result<Vec<String>> {
+    Ok(branch_locations(repo_path, remote)?.into_keys().collect())
+}
+
+/// Local and remote-tracking branches (those on `remote` without their
+/// `<remote>/` prefix), mapped to whether a local branch of that name exists.
+pub fn branch_locations(repo_path: &Path, remote: &str) -> Result<BTreeMap<String, bool>> {
     let output = run_git(
         repo_path,
-        &["branch", "-a", "--format=%(refname:short)"],
+        &["for-each-ref", "--format=%(refname)", "refs/heads", "refs/remotes"],
     )?;
 
-    let mut seen = std::collections::BTreeSet::new();
+    let remote_prefix = format!("refs/remotes/{}/", remote);
+    let mut branches = BTreeMap::new();
     for line in output.lines() {
-        let branch = line.trim();
-        if branch.is_empty() || branch == "HEAD" || branch.ends_with("/HEAD") {
+        let refname = line.trim();
+        if refname.is_empty() || refname.ends_with("/HEAD") {
             continue;
         }
-        let stripped = branch
-            .strip_prefix("origin/")
-            .unwrap_or(branch);
-        seen.insert(stripped.to_string());
+        if let Some(local) = refname.strip_prefix("refs/heads/") {
+            branches.insert(local.to_string(), true);
+        } else {
+            let branch = refname
+                .strip_prefix(&remote_prefix)
+                .or_else(|| refname.strip_prefix("refs/remotes/"))
+                .unwrap_or(refname);
+            branches.entry(branch.to_string()).or_insert(false);
+        }
     }
 
-    Ok(seen.into_iter().collect())
+    Ok(branches)
 }
 
 pub fn is_branch_on_worktree(repo_path: &Path, branch_name: &str) -> Result<bool> {
-    let output = run_git(repo_path, &["worktree", "list", "--porcelain"])?;
     let target = format!("branch refs/heads/{}", branch_name);
 
-    for line in output.lines() {
-        if line.trim() == target {
+    for line in worktree_list_records(repo_path)?.iter() {
+        if *line == target {
             return Ok(true);
         }
     }
@@ -151,13 +510,13 @@ pub fn is_branch_on_worktree(repo_path: &Path, branch_name: &str) -> Result<bool
     Ok(false)
 }
 
-pub fn remote_branch_exists(repo_path: &Path, branch_name: &str) -> Result<bool> {
-    let ref_name = format!("refs/remotes/origin/{}", branch_name);
+pub fn remote_branch_exists(repo_path: &Path, remote: &str, branch_name: &str) -> Result<bool> {
+    let ref_name = format!("refs/remotes/{}/{}", remote, branch_name);
     let output = Command::new("git")
         .arg("-C")
         .arg(repo_path)
         .args(["rev-parse", "--verify", &ref_name])
-        .output()
+        .timed_output()
         .with_context(|| {
             format!(
                 "failed to run git rev-parse for remote branch '{}'",
@@ -168,10 +527,18 @@ pub fn remote_branch_exists(repo_path: &Path, branch_name: &str) -> Result<bool>
     Ok(output.status.success())
 }
 
+/// Ask `remote` itself whether it has `branch_name`, rather than trusting the
+/// remote-tracking refs from the last fetch.
+pub fn remote_has_branch(repo_path: &Path, remote: &str, branch_name: &str) -> Result<bool> {
+    let ref_name = format!("refs/heads/{}", branch_name);
+    let out = run_git(repo_path, &["ls-remote", "--heads", remote, &ref_name])?;
+    Ok(!out.trim().is_empty())
+}
+
 pub fn validate_branch_name(name: &str) -> Result<()> {
     let output = Command::new("git")
         .args(["check-ref-format", "--branch", name])
-        .output()
+        .timed_output()
         .context("failed to run git check-ref-format")?;
 
     if !output.status.success() {
@@ -180,3 +547,34 @@ pub fn validate_branch_name(name: &str) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worktrees_work_under_exotic_paths() {
+        let parent = std::env::temp_dir()
+            .join(format!("sesh wt test {}", std::process::id()))
+            .join("it's a \"dir\" $HOME #1 ü");
+        let _ = std::fs::remove_dir_all(&parent);
+        let repo = parent.join("my repo");
+        std::fs::create_dir_all(&repo).unwrap();
+        run_git(&repo, &["init", "--quiet", "--initial-branch=main"]).unwrap();
+        run_git(
+            &repo,
+            &["-c", "user.name=t", "-c", "user.email=t@t", "commit", "--quiet", "--allow-empty", "-m", "init"],
+        )
+        .unwrap();
+
+        let wt = parent.join(".sesh/sessions/feat x/my repo");
+        create_worktree(&repo, &wt, "feat/x", "main").unwrap();
+        assert!(get_worktree_list(&repo).unwrap().contains(&wt.to_string_lossy().to_string()));
+        assert!(is_branch_on_worktree(&repo, "feat/x").unwrap());
+        assert_eq!(current_branch(&wt).as_deref(), Some("feat/x"));
+
+        remove_worktree(&repo, &wt).unwrap();
+        assert!(!wt.exists());
+        std::fs::remove_dir_all(parent.parent().unwrap()).unwrap();
+    }
+}